@@ -0,0 +1,83 @@
+//! Acknowledged findings module
+//! `why ack <rule>` silences a finding until its metric moves significantly
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::cache_dir;
+
+const ACKS_FILE: &str = "acks.json";
+
+/// Minimum absolute change that re-arms an acked finding
+const ACK_REARM_DELTA: f32 = 5.0;
+/// Relative change (of the acked value) that re-arms an acked finding
+const ACK_REARM_RATIO: f32 = 0.1;
+
+/// Rule name at ack time plus the value of its trigger's metric, if numeric
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Ack {
+    pub value: Option<f32>,
+    pub acked_at: String,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct AckStore {
+    pub acks: BTreeMap<String, Ack>,
+}
+
+impl AckStore {
+    pub fn ack(&mut self, rule_name: &str, value: Option<f32>) {
+        self.acks.insert(
+            rule_name.to_string(),
+            Ack {
+                value,
+                acked_at: Utc::now().to_rfc3339(),
+            },
+        );
+    }
+
+    /// True while the rule is acked and its metric stayed close to the acked value
+    pub fn is_suppressed(&self, rule_name: &str, current: Option<f32>) -> bool {
+        let Some(ack) = self.acks.get(rule_name) else {
+            return false;
+        };
+        match (ack.value, current) {
+            (Some(acked), Some(current)) => {
+                let threshold = ACK_REARM_DELTA.max(acked.abs() * ACK_REARM_RATIO);
+                (current - acked).abs() <= threshold
+            }
+            // Non-numeric triggers stay silenced until --clear-acks
+            _ => true,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.acks.clear();
+    }
+}
+
+pub fn acks_path() -> PathBuf {
+    let mut path = cache_dir();
+    path.push(ACKS_FILE);
+    path
+}
+
+/// Load the ack store; a missing or unreadable file means no acks
+pub fn load(path: &Path) -> AckStore {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+pub fn save(path: &Path, store: &AckStore) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).context("Unable to create cache directory")?;
+    }
+    let json = serde_json::to_string_pretty(store).context("Failed to serialize acks")?;
+    fs::write(path, json).with_context(|| format!("Failed to write {}", path.display()))
+}
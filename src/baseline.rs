@@ -0,0 +1,98 @@
+//! Baseline module
+//! Known-good readings saved with `--save-baseline`, compared with `--compare`
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::cache_dir;
+
+const BASELINE_FILE: &str = "baseline.json";
+
+/// GPU utilization from which a reading counts as under load
+const GPU_LOAD_PERCENT: f32 = 30.0;
+
+/// GPU state worth comparing across time: same load, different temperature,
+/// clocks or power points at cooling or throttling
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct GpuBaseline {
+    pub saved_at: String,
+    pub vendor: String,
+    pub model: Option<String>,
+    pub temperature: Option<f32>,
+    pub utilization: Option<f32>,
+    pub fan_speed_percent: Option<f32>,
+    pub core_mhz: Option<f32>,
+    pub mem_mhz: Option<f32>,
+    #[serde(default)]
+    pub power_w: Option<f32>,
+}
+
+/// Idle and loaded readings are not comparable, so each gets its own baseline
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GpuLoadBand {
+    Idle,
+    Load,
+}
+
+impl GpuBaseline {
+    /// Unknown utilization counts as idle: nothing says the GPU was busy
+    pub fn band(&self) -> GpuLoadBand {
+        if self
+            .utilization
+            .is_some_and(|load| load >= GPU_LOAD_PERCENT)
+        {
+            GpuLoadBand::Load
+        } else {
+            GpuLoadBand::Idle
+        }
+    }
+}
+
+/// One optional baseline per scope and load band, so saving one keeps the
+/// others
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct BaselineStore {
+    pub gpu_idle: Option<GpuBaseline>,
+    pub gpu_load: Option<GpuBaseline>,
+}
+
+impl BaselineStore {
+    pub fn gpu(&self, band: GpuLoadBand) -> Option<&GpuBaseline> {
+        match band {
+            GpuLoadBand::Idle => self.gpu_idle.as_ref(),
+            GpuLoadBand::Load => self.gpu_load.as_ref(),
+        }
+    }
+
+    /// Store `reading` as the baseline of its own band
+    pub fn set_gpu(&mut self, reading: GpuBaseline) {
+        match reading.band() {
+            GpuLoadBand::Idle => self.gpu_idle = Some(reading),
+            GpuLoadBand::Load => self.gpu_load = Some(reading),
+        }
+    }
+}
+
+pub fn baseline_path() -> PathBuf {
+    let mut path = cache_dir();
+    path.push(BASELINE_FILE);
+    path
+}
+
+/// Load the baseline store; a missing or unreadable file means no baselines
+pub fn load(path: &Path) -> BaselineStore {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+pub fn save(path: &Path, store: &BaselineStore) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).context("Unable to create cache directory")?;
+    }
+    let json = serde_json::to_string_pretty(store).context("Failed to serialize baseline")?;
+    fs::write(path, json).with_context(|| format!("Failed to write {}", path.display()))
+}
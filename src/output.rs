@@ -0,0 +1,303 @@
+//! Output format module
+//! Renders findings and metrics for the `--format` modes
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use rust_i18n::t;
+use serde::Serialize;
+
+use crate::{Finding, InsightLevel, InsightLine, Metrics};
+
+/// Output selected with `--format`. `Table` is the colored human output
+/// produced by each subcommand; every other format goes through `render`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    Json,
+    Plain,
+    Prometheus,
+    Md,
+    Yaml,
+}
+
+#[derive(Serialize)]
+struct Report<'a> {
+    schema_version: u32,
+    findings: &'a [Finding],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    machine_id: Option<&'a str>,
+    metrics: &'a Metrics,
+    #[serde(skip_serializing_if = "<[Section]>::is_empty")]
+    sections: &'a [Section],
+}
+
+/// A subcommand section captured for every format but `table` and
+/// `prometheus`: its lines, or why it could not be gathered
+#[derive(Serialize)]
+pub struct Section {
+    pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub lines: Vec<InsightLine>,
+}
+
+/// Serialize as minified single-line JSON (`--compact`) or pretty-printed
+pub fn to_json<T: Serialize>(value: &T, compact: bool) -> Result<String> {
+    let json = if compact {
+        serde_json::to_string(value)
+    } else {
+        serde_json::to_string_pretty(value)
+    };
+    json.context("Failed to serialize JSON")
+}
+
+/// Render findings and metrics in a machine-friendly format; `sections`
+/// follow the findings in plain and Markdown and are a field in JSON and
+/// YAML (Prometheus has no place for them, see `check_format`); `machine_id`
+/// only appears in JSON and YAML; `compact` only affects JSON
+pub fn render(
+    format: OutputFormat,
+    findings: &[Finding],
+    metrics: &Metrics,
+    sections: &[Section],
+    machine_id: Option<&str>,
+    compact: bool,
+) -> Result<String> {
+    let report = Report {
+        schema_version: crate::schema::SCHEMA_VERSION,
+        findings,
+        machine_id,
+        metrics,
+        sections,
+    };
+    match format {
+        OutputFormat::Table | OutputFormat::Plain => Ok(render_plain(findings, sections)),
+        OutputFormat::Json => to_json(&report, compact),
+        OutputFormat::Yaml => serde_yaml::to_string(&report).context("Failed to serialize report"),
+        OutputFormat::Md => Ok(render_markdown(findings, sections)),
+        OutputFormat::Prometheus => Ok(render_prometheus(findings, metrics)),
+    }
+}
+
+/// One finding per line, no colors or emoji (grep/awk friendly), then each
+/// section as `== title ==` and one `[level] message` line per entry
+fn render_plain(findings: &[Finding], sections: &[Section]) -> String {
+    let mut out = if findings.is_empty() {
+        format!("{}\n", t!("all_good"))
+    } else {
+        findings
+            .iter()
+            .map(|f| {
+                format!(
+                    "[{}] {}: {} — {}\n",
+                    f.severity_value, f.rule_name, f.message, f.solution
+                )
+            })
+            .collect()
+    };
+    for section in sections {
+        out.push_str(&format!("\n== {} ==\n", section.title));
+        if let Some(error) = &section.error {
+            out.push_str(&format!("[error] {}\n", error.replace('\n', " ")));
+        }
+        for line in &section.lines {
+            out.push_str(&format!(
+                "[{}] {}\n",
+                level_label(line.level),
+                line.message.replace('\n', " ")
+            ));
+        }
+    }
+    out
+}
+
+fn render_markdown(findings: &[Finding], sections: &[Section]) -> String {
+    let mut out = if findings.is_empty() {
+        format!("{}\n", t!("all_good"))
+    } else {
+        let mut table = format!(
+            "| {} | {} | {} |\n|---|---|---|\n",
+            t!("severity_header"),
+            t!("diagnosis_header"),
+            t!("solution_header")
+        );
+        for f in findings {
+            table.push_str(&format!(
+                "| {} | {} | {} |\n",
+                f.severity_value,
+                escape_markdown_cell(&f.message),
+                escape_markdown_cell(&f.solution)
+            ));
+        }
+        table
+    };
+    for section in sections {
+        out.push_str(&format!("\n## {}\n\n", section.title));
+        if let Some(error) = &section.error {
+            out.push_str(&format!("_{}_\n", error.replace('\n', " ")));
+        }
+        for line in &section.lines {
+            out.push_str(&format!(
+                "- **{}** {}\n",
+                level_label(line.level),
+                line.message.replace('\n', " ")
+            ));
+        }
+    }
+    out
+}
+
+/// Same words as the JSON `level` field
+fn level_label(level: InsightLevel) -> &'static str {
+    match level {
+        InsightLevel::Info => "info",
+        InsightLevel::Good => "good",
+        InsightLevel::Warning => "warning",
+        InsightLevel::Critical => "critical",
+    }
+}
+
+fn escape_markdown_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+fn render_prometheus(findings: &[Finding], metrics: &Metrics) -> String {
+    let mut out = String::new();
+    let unlabeled = |value: Option<f64>| -> Vec<(String, f64)> {
+        value.map(|v| (String::new(), v)).into_iter().collect()
+    };
+    let optional = |value: Option<f32>| unlabeled(value.map(f64::from));
+
+    gauge(
+        &mut out,
+        "why_cpu_usage_percent",
+        "Global CPU usage",
+        &optional(metrics.cpu_usage),
+    );
+    gauge(
+        &mut out,
+        "why_mem_usage_percent",
+        "RAM usage",
+        &unlabeled(Some(metrics.mem_usage as f64)),
+    );
+    gauge(
+        &mut out,
+        "why_swap_usage_percent",
+        "Swap usage (0 without swap)",
+        &unlabeled(Some(metrics.swap_usage_percent as f64)),
+    );
+    gauge(
+        &mut out,
+        "why_disk_full_percent",
+        "Root filesystem usage",
+        &unlabeled(Some(metrics.disk_full_percent as f64)),
+    );
+    gauge(
+        &mut out,
+        "why_temperature_celsius",
+        "Hottest sensor",
+        &optional(metrics.temperature_c),
+    );
+    gauge(
+        &mut out,
+        "why_fan_speed_rpm",
+        "Fastest fan",
+        &optional(metrics.fan_speed_rpm),
+    );
+    gauge(
+        &mut out,
+        "why_iowait_percent",
+        "CPU time waiting on I/O",
+        &optional(metrics.iowait_percent),
+    );
+    gauge(
+        &mut out,
+        "why_tmpfs_full_percent",
+        "Fullest tmpfs mount",
+        &optional(metrics.tmpfs_full_percent),
+    );
+    gauge(
+        &mut out,
+        "why_battery_drain_watts",
+        "Battery discharge rate",
+        &optional(metrics.battery_drain_w),
+    );
+    gauge(
+        &mut out,
+        "why_wifi_signal_dbm",
+        "Wi-Fi signal strength",
+        &optional(metrics.wifi_signal_dbm),
+    );
+
+    if let Some(gpu) = &metrics.gpu {
+        let labels = format!("{{vendor=\"{}\"}}", escape_label(&gpu.vendor));
+        let with_vendor = |value: Option<f32>| -> Vec<(String, f64)> {
+            value
+                .map(|v| (labels.clone(), f64::from(v)))
+                .into_iter()
+                .collect()
+        };
+        gauge(
+            &mut out,
+            "why_gpu_temperature_celsius",
+            "GPU temperature",
+            &with_vendor(gpu.temperature),
+        );
+        gauge(
+            &mut out,
+            "why_gpu_utilization_percent",
+            "GPU utilization",
+            &with_vendor(gpu.utilization),
+        );
+        gauge(
+            &mut out,
+            "why_gpu_memory_used_megabytes",
+            "VRAM in use",
+            &with_vendor(gpu.memory_used_mb),
+        );
+    }
+
+    gauge(
+        &mut out,
+        "why_findings_total",
+        "Number of findings",
+        &unlabeled(Some(findings.len() as f64)),
+    );
+    // Every severity, so a series drops to 0 instead of disappearing
+    let by_severity: Vec<(String, f64)> = (1..=10u8)
+        .map(|severity| {
+            let count = findings
+                .iter()
+                .filter(|f| f.severity_value == severity)
+                .count();
+            (format!("{{severity=\"{severity}\"}}"), count as f64)
+        })
+        .collect();
+    gauge(
+        &mut out,
+        "why_finding_count",
+        "Findings per rule severity (1-10)",
+        &by_severity,
+    );
+    out
+}
+
+/// Append a gauge with its HELP/TYPE header, one line per `(labels, value)`
+/// sample; without samples (metric not gathered) nothing is written
+fn gauge(out: &mut String, name: &str, help: &str, samples: &[(String, f64)]) {
+    if samples.is_empty() {
+        return;
+    }
+    out.push_str(&format!("# HELP {name} {help}\n# TYPE {name} gauge\n"));
+    for (labels, value) in samples {
+        out.push_str(&format!("{name}{labels} {value}\n"));
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
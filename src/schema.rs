@@ -0,0 +1,128 @@
+//! JSON schema module
+//! Hand-written JSON Schema for snapshots and `--format json` reports
+
+use serde_json::{json, Value};
+
+/// Version of the serialized layout. Bump it whenever a field of
+/// `SnapshotData`, `Finding` or `Metrics` is added, renamed or removed.
+pub const SCHEMA_VERSION: u32 = 18;
+
+fn nullable(kind: &str) -> Value {
+    json!({ "type": [kind, "null"] })
+}
+
+fn metrics_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "cpu_usage": nullable("number"),
+            "mem_usage": { "type": "number" },
+            "total_ram_mb": { "type": "integer" },
+            "swap_usage_percent": { "type": "number" },
+            "swap_total_mb": { "type": "integer" },
+            "load_avg_1": nullable("number"),
+            "load_avg_5": nullable("number"),
+            "load_avg_15": nullable("number"),
+            "disk_full_percent": { "type": "number" },
+            "inode_full_percent": nullable("number"),
+            "filesystem": nullable("string"),
+            "snap_loops": nullable("integer"),
+            "flatpak_unused": nullable("integer"),
+            "battery_drain_w": nullable("number"),
+            "wifi_channel_count": nullable("integer"),
+            "wifi_signal_dbm": nullable("number"),
+            "fan_speed_rpm": nullable("number"),
+            "temperature_c": nullable("number"),
+            "wayland_vs_x11": nullable("string"),
+            "docker_dangling": nullable("integer"),
+            "process_names": { "type": "array", "items": { "type": "string" } },
+            "process_count": { "type": "integer" },
+            "process_memory_mb": { "type": "object", "additionalProperties": { "type": "integer" } },
+            "pipewire_latency_ms": nullable("number"),
+            "firefox_soft_render": nullable("boolean"),
+            "zfs_arc_full_percent": nullable("number"),
+            "luks_device_count": nullable("integer"),
+            "gpu": { "anyOf": [{ "$ref": "#/$defs/gpu" }, { "type": "null" }] },
+            "prime_offload_enabled": nullable("boolean"),
+            "gamescope_running": nullable("boolean"),
+            "steam_running": nullable("boolean"),
+            "proton_failure_detected": nullable("boolean"),
+            "vulkan_loader_missing": nullable("boolean"),
+            "conntrack_usage_percent": nullable("number"),
+            "tmpfs_full_percent": nullable("number"),
+            "vulkan_device_count": nullable("integer"),
+            "iowait_percent": nullable("number"),
+            "stale_mounts": { "type": ["array", "null"], "items": { "type": "string" } },
+            "firmware_updates": nullable("integer"),
+            "coredump_size_mb": nullable("number"),
+            "hw_encoder_missing": nullable("boolean"),
+            "unit_restarts": nullable("integer"),
+            "failed_units": nullable("integer"),
+            "gpu_driver_mismatch": nullable("boolean"),
+            "gpu_underclocked": nullable("boolean")
+        }
+    })
+}
+
+fn gpu_schema() -> Value {
+    json!({
+        "type": "object",
+        "required": ["vendor"],
+        "properties": {
+            "vendor": { "type": "string" },
+            "model": nullable("string"),
+            "driver": nullable("string"),
+            "temperature": nullable("number"),
+            "utilization": nullable("number"),
+            "memory_total_mb": nullable("number"),
+            "memory_used_mb": nullable("number"),
+            "fan_speed_percent": nullable("number")
+        }
+    })
+}
+
+fn finding_schema() -> Value {
+    json!({
+        "type": "object",
+        "required": ["id", "severity", "severity_value", "message", "solution", "rule_name", "actionable"],
+        "properties": {
+            "id": { "type": "string" },
+            "severity": { "type": "string" },
+            "severity_value": { "type": "integer", "minimum": 0, "maximum": 10 },
+            "message": { "type": "string" },
+            "solution": { "type": "string" },
+            "auto_fix": nullable("string"),
+            "rule_name": { "type": "string" },
+            "actionable": { "type": "boolean" }
+        }
+    })
+}
+
+/// Schema of `why --snapshot` files. `--format json` reports carry the same
+/// `schema_version`, `metrics` and `findings` properties.
+pub fn json_schema() -> Value {
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "why snapshot",
+        "type": "object",
+        "required": ["schema_version", "metrics", "findings"],
+        "properties": {
+            "schema_version": { "type": "integer", "const": SCHEMA_VERSION },
+            "timestamp": { "type": "string", "format": "date-time" },
+            "hostname": { "type": "string" },
+            "kernel": { "type": "string" },
+            "distro": { "type": "string" },
+            "uptime_seconds": { "type": "integer" },
+            "machine_id": { "type": "string" },
+            "metrics": { "$ref": "#/$defs/metrics" },
+            "findings": { "type": "array", "items": { "$ref": "#/$defs/finding" } },
+            "recent_dmesg": { "type": ["array", "null"], "items": { "type": "string" } },
+            "recent_journal": { "type": ["array", "null"], "items": { "type": "string" } }
+        },
+        "$defs": {
+            "metrics": metrics_schema(),
+            "gpu": gpu_schema(),
+            "finding": finding_schema()
+        }
+    })
+}
@@ -0,0 +1,84 @@
+//! Color theme module
+//! Palettes for insight lines and the `--watch` TUI (`--theme` or `theme = "..."`)
+
+use clap::ValueEnum;
+use colored::{Color, ColoredString, Colorize};
+use ratatui::style::Color as TuiColor;
+use serde::Deserialize;
+use std::sync::OnceLock;
+
+use crate::{InsightLevel, InsightLine};
+
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Theme {
+    /// Original palette, for dark terminals
+    #[default]
+    Default,
+    /// No dim or yellow text, which vanish on white backgrounds
+    Light,
+    /// Bright bold colors only
+    HighContrast,
+    /// Okabe-Ito blue/orange/vermillion instead of green/yellow/red
+    ColorblindSafe,
+}
+
+/// Okabe-Ito palette
+const OKABE_BLUE: (u8, u8, u8) = (0, 114, 178);
+const OKABE_SKY: (u8, u8, u8) = (86, 180, 233);
+const OKABE_ORANGE: (u8, u8, u8) = (230, 159, 0);
+const OKABE_VERMILLION: (u8, u8, u8) = (213, 94, 0);
+
+fn truecolor((r, g, b): (u8, u8, u8)) -> Color {
+    Color::TrueColor { r, g, b }
+}
+
+impl Theme {
+    pub fn paint(self, line: &InsightLine) -> ColoredString {
+        let text = line.message.as_str();
+        match (self, line.level) {
+            (Theme::Default, InsightLevel::Info) => text.dimmed(),
+            (Theme::Default, InsightLevel::Good) => text.green(),
+            (Theme::Default, InsightLevel::Warning) => text.yellow(),
+            (Theme::Default, InsightLevel::Critical) => text.red().bold(),
+            (Theme::Light, InsightLevel::Info) => text.blue(),
+            (Theme::Light, InsightLevel::Good) => text.green(),
+            (Theme::Light, InsightLevel::Warning) => text.magenta(),
+            (Theme::Light, InsightLevel::Critical) => text.red().bold(),
+            (Theme::HighContrast, InsightLevel::Info) => text.bright_white(),
+            (Theme::HighContrast, InsightLevel::Good) => text.bright_green().bold(),
+            (Theme::HighContrast, InsightLevel::Warning) => text.bright_yellow().bold(),
+            (Theme::HighContrast, InsightLevel::Critical) => text.bright_red().bold().underline(),
+            (Theme::ColorblindSafe, InsightLevel::Info) => text.dimmed(),
+            (Theme::ColorblindSafe, InsightLevel::Good) => text.color(truecolor(OKABE_BLUE)),
+            (Theme::ColorblindSafe, InsightLevel::Warning) => text.color(truecolor(OKABE_ORANGE)),
+            (Theme::ColorblindSafe, InsightLevel::Critical) => {
+                text.color(truecolor(OKABE_VERMILLION)).bold()
+            }
+        }
+    }
+
+    /// Colors of the CPU and RAM sparklines
+    pub fn sparklines(self) -> (TuiColor, TuiColor) {
+        match self {
+            Theme::Default => (TuiColor::Green, TuiColor::Cyan),
+            Theme::Light => (TuiColor::Blue, TuiColor::Magenta),
+            Theme::HighContrast => (TuiColor::White, TuiColor::LightYellow),
+            Theme::ColorblindSafe => {
+                let rgb = |(r, g, b): (u8, u8, u8)| TuiColor::Rgb(r, g, b);
+                (rgb(OKABE_BLUE), rgb(OKABE_SKY))
+            }
+        }
+    }
+}
+
+/// Select the theme once at startup; later calls are ignored
+pub fn set(theme: Theme) {
+    let _ = THEME.set(theme);
+}
+
+pub fn current() -> Theme {
+    THEME.get().copied().unwrap_or_default()
+}